@@ -1,6 +1,6 @@
-use crate::cal_checksum;
 use crate::icmp::{Code, Kind};
 use crate::ip::ipv4::packet::IpV4Packet;
+use crate::{cal_checksum, incremental_checksum};
 use byteorder::{BigEndian, ReadBytesExt};
 use std::{fmt, io};
 
@@ -39,6 +39,15 @@ impl<B: AsRef<[u8]> + AsMut<[u8]>> IcmpPacket<B> {
     pub fn set_kind(&mut self, kind: Kind) {
         self.buffer.as_mut()[0] = kind.into();
     }
+    /// 修改类型，并增量更新校验和
+    pub fn replace_kind(&mut self, kind: Kind) {
+        let buffer = self.buffer.as_mut();
+        let old = [buffer[0], buffer[1]];
+        buffer[0] = kind.into();
+        let checksum = u16::from_be_bytes([buffer[2], buffer[3]]);
+        let checksum = incremental_checksum(checksum, &old, &buffer[0..2]);
+        buffer[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
     pub fn update_checksum(&mut self) {
         self.buffer.as_mut()[2..4].copy_from_slice(&[0, 0]);
         let checksum = cal_checksum(self.buffer.as_ref());
@@ -1,8 +1,8 @@
 use std::net::Ipv4Addr;
 use std::{fmt, io};
 
-use crate::cal_checksum;
 use crate::ip::ipv4::protocol::Protocol;
+use crate::{cal_checksum, incremental_checksum};
 
 /// ip协议
 /*
@@ -69,6 +69,19 @@ impl<B: AsRef<[u8]> + AsMut<[u8]>> IpV4Packet<B> {
         let len = self.header_len() as usize * 4;
         &mut self.buffer.as_mut()[len..]
     }
+    /// 设置版本号为4及头部长度，头部长度以4字节为单位
+    pub fn set_version_and_header_len(&mut self, header_len: u8) {
+        self.buffer.as_mut()[0] = (4 << 4) | (header_len & 0b1111);
+    }
+    pub fn set_length(&mut self, value: u16) {
+        self.buffer.as_mut()[2..4].copy_from_slice(&value.to_be_bytes())
+    }
+    pub fn set_id(&mut self, value: u16) {
+        self.buffer.as_mut()[4..6].copy_from_slice(&value.to_be_bytes())
+    }
+    pub fn set_ttl(&mut self, value: u8) {
+        self.buffer.as_mut()[8] = value;
+    }
     pub fn set_protocol(&mut self, value: Protocol) {
         self.header_mut()[9] = value.into();
     }
@@ -78,8 +91,29 @@ impl<B: AsRef<[u8]> + AsMut<[u8]>> IpV4Packet<B> {
     pub fn set_destination_ip(&mut self, value: Ipv4Addr) {
         self.header_mut()[16..20].copy_from_slice(&value.octets());
     }
+    /// 修改源地址，并增量更新首部校验和，上层协议的校验和需要另外更新
+    pub fn replace_source_ip(&mut self, value: Ipv4Addr) {
+        self.replace_header(12, &value.octets());
+    }
+    /// 修改目的地址，并增量更新首部校验和，上层协议的校验和需要另外更新
+    pub fn replace_destination_ip(&mut self, value: Ipv4Addr) {
+        self.replace_header(16, &value.octets());
+    }
+    fn replace_header(&mut self, offset: usize, value: &[u8]) {
+        let header = self.header_mut();
+        let checksum = u16::from_be_bytes([header[10], header[11]]);
+        let checksum = incremental_checksum(checksum, &header[offset..offset + value.len()], value);
+        header[offset..offset + value.len()].copy_from_slice(value);
+        header[10..12].copy_from_slice(&checksum.to_be_bytes());
+    }
     pub fn set_flags(&mut self, flags: u8) {
-        self.buffer.as_mut()[6] = (self.buffer.as_ref()[6] & 0b11100000) | (flags << 5)
+        self.buffer.as_mut()[6] = (self.buffer.as_ref()[6] & 0b00011111) | (flags << 5)
+    }
+    /// 设置片偏移，单位为8字节
+    pub fn set_offset(&mut self, offset: u16) {
+        let flags = self.buffer.as_ref()[6] & 0b11100000;
+        self.buffer.as_mut()[6..8].copy_from_slice(&(offset & 0x1fff).to_be_bytes());
+        self.buffer.as_mut()[6] |= flags;
     }
     fn set_checksum(&mut self, value: u16) {
         self.header_mut()[10..12].copy_from_slice(&value.to_be_bytes())
@@ -1,8 +1,5 @@
 use std::net::Ipv4Addr;
 
-use byteorder::BigEndian;
-use byteorder::ReadBytesExt;
-
 pub mod arp;
 pub mod ethernet;
 pub mod icmp;
@@ -69,21 +66,50 @@ unsigned short getChecksum(unsigned short * iphead, int count)
 }
  */
 pub fn cal_checksum(buffer: &[u8]) -> u16 {
-    use std::io::Cursor;
-    let mut sum = 0;
-    let length = buffer.len();
-    let mut buffer = Cursor::new(buffer);
-    while let Ok(value) = buffer.read_u16::<BigEndian>() {
-        sum += u32::from(value);
+    !fold(sum_words(buffer))
+}
+
+/// 对数据按16位求和，未折叠
+///
+/// 一次累加8字节，65536对65535取模为1，所以按32位累加后再折叠和按16位累加的结果相同，
+/// 循环体简单，编译器可以向量化
+fn sum_words(buffer: &[u8]) -> u64 {
+    let mut sum = 0u64;
+    let mut chunks = buffer.chunks_exact(8);
+    for chunk in &mut chunks {
+        sum += u64::from(u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+        sum += u64::from(u32::from_be_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]));
     }
-    if length & 1 == 1 {
+    for word in chunks.remainder().chunks(2) {
         //奇数,说明还有一位,不足的补0
-        sum += u32c(buffer.read_u8().unwrap(), 0);
+        sum += u64::from(u32c(word[0], word.get(1).copied().unwrap_or(0)));
     }
+    sum
+}
+
+/// 持续取高16位加到低16位，直到高16位全为0
+fn fold(mut sum: u64) -> u16 {
     while sum >> 16 != 0 {
         sum = (sum & 0xffff) + (sum >> 16);
     }
-    !sum as u16
+    sum as u16
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc1624 第3节
+///
+/// 修改了部分字段后增量更新校验和，不需要重新计算整个数据
+/// HC' = ~(~HC + ~m + m')
+/// old和new是修改前后的字段，长度相同，并且从偶数偏移开始
+pub fn incremental_checksum(checksum: u16, old: &[u8], new: &[u8]) -> u16 {
+    debug_assert_eq!(old.len(), new.len());
+    let mut sum = u64::from(!checksum);
+    for (old, new) in old.chunks(2).zip(new.chunks(2)) {
+        let old = u32c(old[0], old.get(1).copied().unwrap_or(0));
+        let new = u32c(new[0], new.get(1).copied().unwrap_or(0));
+        sum += u64::from(!old & 0xffff);
+        sum += u64::from(new);
+    }
+    !fold(sum)
 }
 
 /// ipv4上层协议校验和计算方式
@@ -104,7 +130,6 @@ pub fn ipv4_cal_checksum(
     dest_ip: &Ipv4Addr,
     protocol: u8,
 ) -> u16 {
-    use std::io::Cursor;
     let length = buffer.len();
     let mut sum = 0;
     let src_ip = src_ip.octets();
@@ -115,18 +140,7 @@ pub fn ipv4_cal_checksum(
     sum += u32c(dest_ip[2], dest_ip[3]);
     sum += u32c(0, protocol);
     sum += length as u32;
-    let mut buffer = Cursor::new(buffer);
-    while let Ok(value) = buffer.read_u16::<BigEndian>() {
-        sum += u32::from(value);
-    }
-    if length & 1 == 1 {
-        //奇数,说明还有一位
-        sum += u32c(buffer.read_u8().unwrap(), 0);
-    }
-    while sum >> 16 != 0 {
-        sum = (sum & 0xffff) + (sum >> 16);
-    }
-    !sum as u16
+    !fold(u64::from(sum) + sum_words(buffer))
 }
 
 #[inline]
@@ -143,4 +157,48 @@ mod tests {
         let sum = cal_checksum(&[255, 255]);
         println!("{:?}", sum);
     }
+
+    /// 逐个16位累加的校验和，用于对比
+    fn simple_checksum(buffer: &[u8]) -> u16 {
+        let mut sum = 0u32;
+        for word in buffer.chunks(2) {
+            sum += u32c(word[0], word.get(1).copied().unwrap_or(0));
+        }
+        while sum >> 16 != 0 {
+            sum = (sum & 0xffff) + (sum >> 16);
+        }
+        !sum as u16
+    }
+
+    #[test]
+    fn checksum_matches_simple() {
+        let data: Vec<u8> = (0..1500u32).map(|v| (v * 7 + v / 3) as u8).collect();
+        for len in 0..64 {
+            assert_eq!(cal_checksum(&data[..len]), simple_checksum(&data[..len]));
+        }
+        assert_eq!(cal_checksum(&data), simple_checksum(&data));
+        assert_eq!(cal_checksum(&[0xff; 1499]), simple_checksum(&[0xff; 1499]));
+    }
+
+    #[test]
+    fn incremental_matches_full() {
+        // icmp echo request，校验和位置在2..4
+        let mut packet = vec![8u8, 0, 0, 0, 0x12, 0x34, 0, 1, 0xab, 0xcd, 0xef];
+        let checksum = cal_checksum(&packet);
+        packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+        let checksum = incremental_checksum(checksum, &[8, 0], &[0, 0]);
+        packet[0] = 0;
+        packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+        assert_eq!(cal_checksum(&packet), 0);
+
+        let mut header = [
+            0x45u8, 0, 0, 0x54, 0, 0, 0x40, 0, 0x40, 1, 0, 0, 10, 26, 0, 2, 10, 26, 0, 1,
+        ];
+        let checksum = cal_checksum(&header);
+        header[10..12].copy_from_slice(&checksum.to_be_bytes());
+        let checksum = incremental_checksum(checksum, &[10, 26, 0, 2], &[192, 168, 1, 7]);
+        header[12..16].copy_from_slice(&[192, 168, 1, 7]);
+        header[10..12].copy_from_slice(&checksum.to_be_bytes());
+        assert_eq!(cal_checksum(&header), 0);
+    }
 }
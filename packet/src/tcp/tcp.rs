@@ -87,6 +87,29 @@ impl<B: AsRef<[u8]> + AsMut<[u8]>> TcpPacket<B> {
     pub fn set_destination_port(&mut self, value: u16) {
         self.buffer.as_mut()[2..4].copy_from_slice(&value.to_be_bytes())
     }
+    pub fn set_sequence(&mut self, value: u32) {
+        self.buffer.as_mut()[4..8].copy_from_slice(&value.to_be_bytes())
+    }
+    pub fn set_acknowledgment(&mut self, value: u32) {
+        self.buffer.as_mut()[8..12].copy_from_slice(&value.to_be_bytes())
+    }
+    /// 数据偏移 4字节为单位
+    pub fn set_data_offset(&mut self, value: u8) {
+        self.buffer.as_mut()[12] = (value << 4) | (self.buffer.as_ref()[12] & 0x0F)
+    }
+    pub fn set_flags(&mut self, flags: u8) {
+        self.buffer.as_mut()[13] = flags
+    }
+    pub fn set_window(&mut self, value: u16) {
+        self.buffer.as_mut()[14..16].copy_from_slice(&value.to_be_bytes())
+    }
+    pub fn set_urgent_pointer(&mut self, value: u16) {
+        self.buffer.as_mut()[18..20].copy_from_slice(&value.to_be_bytes())
+    }
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        let offset = self.data_offset() as usize * 4;
+        &mut self.buffer.as_mut()[offset..]
+    }
     /// 更新校验和
     pub fn update_checksum(&mut self) {
         //先将校验和置0
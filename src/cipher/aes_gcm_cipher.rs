@@ -1,32 +1,82 @@
 use std::io;
 
-use aes_gcm::aead::consts::{U12, U16};
+use aes_gcm::aead::consts::U16;
 use aes_gcm::aead::generic_array::GenericArray;
 use aes_gcm::{AeadInPlace, Aes256Gcm, Key, KeyInit, Nonce, Tag};
-use rand::RngCore;
 
 use crate::cipher::finger::Finger;
+use crate::cipher::nonce::{self, Direction, NonceState};
 use crate::protocol::{body::SecretBody, body::AES_GCM_ENCRYPTION_RESERVED, NetPacket};
 
 #[derive(Clone)]
 pub struct Aes256GcmCipher {
     cipher: Aes256Gcm,
     finger: Finger,
+    nonce: NonceState,
 }
 
 impl Aes256GcmCipher {
+    /// 计数器模式，nonce由数据方向和加密计数器生成
     pub fn new(key: [u8; 32], finger: Finger) -> Self {
+        Self::with_nonce(key, finger, NonceState::counter())
+    }
+    /// 兼容不支持计数器nonce的旧客户端，nonce由协议头生成
+    pub fn with_header_nonce(key: [u8; 32], finger: Finger) -> Self {
+        Self::with_nonce(key, finger, NonceState::header())
+    }
+    fn with_nonce(key: [u8; 32], finger: Finger, nonce: NonceState) -> Self {
         let key: &Key<Aes256Gcm> = &key.into();
         Self {
             cipher: Aes256Gcm::new(key),
             finger,
+            nonce,
         }
     }
+    pub fn finger(&self) -> &Finger {
+        &self.finger
+    }
+    /// 加密计数器已经用完，需要重新握手更换密钥
+    pub fn exhausted(&self) -> bool {
+        self.nonce.exhausted()
+    }
 
+    /// 是否使用计数器生成nonce，只有计数器模式才能识别重放的数据
+    pub fn counter_nonce(&self) -> bool {
+        self.nonce.is_counter()
+    }
+
+    /// 解密客户端发来的数据
     pub fn decrypt_ipv4<B: AsRef<[u8]> + AsMut<[u8]>>(
         &self,
         net_packet: &mut NetPacket<B>,
     ) -> io::Result<()> {
+        if let Some(counter) = self.decrypt_from(net_packet, Direction::ToServer)? {
+            self.nonce.record(counter);
+        }
+        Ok(())
+    }
+    /// 解密客户端发来的数据，并且要求计数器大于之前解密过的所有数据，重放的数据返回错误
+    pub fn decrypt_fresh<B: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        net_packet: &mut NetPacket<B>,
+    ) -> io::Result<()> {
+        let Some(counter) = self.decrypt_from(net_packet, Direction::ToServer)? else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "counter nonce required",
+            ));
+        };
+        if !self.nonce.record(counter) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "replayed"));
+        }
+        Ok(())
+    }
+    /// 解密数据，计数器模式下返回数据中的计数器
+    pub(crate) fn decrypt_from<B: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        net_packet: &mut NetPacket<B>,
+        direction: Direction,
+    ) -> io::Result<Option<u32>> {
         if !net_packet.is_encrypt() {
             //未加密的数据直接丢弃
             return Err(io::Error::new(io::ErrorKind::Other, "not encrypt"));
@@ -35,29 +85,24 @@ impl Aes256GcmCipher {
             log::error!("数据异常,长度小于{}", AES_GCM_ENCRYPTION_RESERVED);
             return Err(io::Error::new(io::ErrorKind::Other, "data err"));
         }
-        let mut nonce_raw = [0; 12];
-        nonce_raw[0..4].copy_from_slice(&net_packet.source().octets());
-        nonce_raw[4..8].copy_from_slice(&net_packet.destination().octets());
-        nonce_raw[8] = net_packet.protocol().into();
-        nonce_raw[9] = net_packet.transport_protocol();
-        nonce_raw[10] = net_packet.is_gateway() as u8;
-        nonce_raw[11] = net_packet.source_ttl();
-        let nonce: &GenericArray<u8, U12> = Nonce::from_slice(&nonce_raw);
+        let header = nonce::header(net_packet);
 
         let mut secret_body = SecretBody::new(net_packet.payload_mut(), true)?;
-        let tag = secret_body.tag();
-        let finger = self
-            .finger
-            .calculate_finger(&nonce_raw, secret_body.en_body());
+        let finger = self.finger.calculate_finger(&header, secret_body.en_body());
         if finger != secret_body.finger() {
             return Err(io::Error::new(io::ErrorKind::Other, "finger err"));
         }
 
-        let tag: GenericArray<u8, U16> = Tag::clone_from_slice(tag);
-        if let Err(e) =
-            self.cipher
-                .decrypt_in_place_detached(nonce, &[], secret_body.body_mut(), &tag)
-        {
+        let tag: GenericArray<u8, U16> = Tag::clone_from_slice(secret_body.tag());
+        let packet_nonce = self.nonce.open(direction, &header, secret_body.random());
+        let body = secret_body.body_mut();
+        let len = packet_nonce.sealed_len(body.len());
+        if let Err(e) = self.cipher.decrypt_in_place_detached(
+            Nonce::from_slice(&packet_nonce.nonce),
+            packet_nonce.aad(&header),
+            &mut body[..len],
+            &tag,
+        ) {
             return Err(io::Error::new(
                 io::ErrorKind::Other,
                 format!("解密失败:{}", e),
@@ -65,38 +110,41 @@ impl Aes256GcmCipher {
         }
         net_packet.set_encrypt_flag(false);
         net_packet.set_data_len(net_packet.data_len() - AES_GCM_ENCRYPTION_RESERVED)?;
-        Ok(())
+        Ok(packet_nonce.counter)
     }
+    /// 加密发往客户端的数据
     /// net_packet 必须预留足够长度
     /// data_len是有效载荷的长度
-    /// 返回加密后载荷的长度
     pub fn encrypt_ipv4<B: AsRef<[u8]> + AsMut<[u8]>>(
         &self,
         net_packet: &mut NetPacket<B>,
+    ) -> io::Result<()> {
+        self.encrypt_to(net_packet, Direction::ToClient)
+    }
+    pub(crate) fn encrypt_to<B: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        net_packet: &mut NetPacket<B>,
+        direction: Direction,
     ) -> io::Result<()> {
         if net_packet.reserve() < AES_GCM_ENCRYPTION_RESERVED {
             return Err(io::Error::new(io::ErrorKind::Other, "too short"));
         }
-        let mut nonce_raw = [0; 12];
-        nonce_raw[0..4].copy_from_slice(&net_packet.source().octets());
-        nonce_raw[4..8].copy_from_slice(&net_packet.destination().octets());
-        nonce_raw[8] = net_packet.protocol().into();
-        nonce_raw[9] = net_packet.transport_protocol();
-        nonce_raw[10] = net_packet.is_gateway() as u8;
-        nonce_raw[11] = net_packet.source_ttl();
-        let nonce: &GenericArray<u8, U12> = Nonce::from_slice(&nonce_raw);
+        let header = nonce::header(net_packet);
+        let packet_nonce = self.nonce.seal(direction, &header)?;
         net_packet.set_data_len(net_packet.data_len() + AES_GCM_ENCRYPTION_RESERVED)?;
         let mut secret_body = SecretBody::new(net_packet.payload_mut(), true)?;
-        secret_body.set_random(rand::thread_rng().next_u32());
-        return match self
-            .cipher
-            .encrypt_in_place_detached(nonce, &[], secret_body.body_mut())
-        {
+        secret_body.set_random(packet_nonce.random);
+        let body = secret_body.body_mut();
+        let len = packet_nonce.sealed_len(body.len());
+        let rs = self.cipher.encrypt_in_place_detached(
+            Nonce::from_slice(&packet_nonce.nonce),
+            packet_nonce.aad(&header),
+            &mut body[..len],
+        );
+        match rs {
             Ok(tag) => {
                 secret_body.set_tag(tag.as_slice())?;
-                let finger = self
-                    .finger
-                    .calculate_finger(&nonce_raw, secret_body.en_body());
+                let finger = self.finger.calculate_finger(&header, secret_body.en_body());
                 secret_body.set_finger(&finger)?;
                 net_packet.set_encrypt_flag(true);
                 Ok(())
@@ -105,6 +153,122 @@ impl Aes256GcmCipher {
                 io::ErrorKind::Other,
                 format!("加密失败:{}", e),
             )),
-        };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cipher::KeyUsage;
+    use crate::protocol::body::ENCRYPTION_RESERVED;
+
+    const PAYLOAD: &[u8] = b"same header, same payload";
+
+    fn packet() -> NetPacket<Vec<u8>> {
+        let mut packet =
+            NetPacket::new_encrypt(vec![0u8; 12 + PAYLOAD.len() + ENCRYPTION_RESERVED]).unwrap();
+        packet.set_default_version();
+        packet.set_source([10, 26, 0, 1].into());
+        packet.set_destination([10, 26, 0, 2].into());
+        packet.set_payload(PAYLOAD).unwrap();
+        packet
+    }
+
+    fn cipher() -> Aes256GcmCipher {
+        Aes256GcmCipher::new([7; 32], Finger::new("test"))
+    }
+
+    #[test]
+    fn counter_increments() {
+        let cipher = cipher();
+        for expected in 0..3 {
+            let mut packet = packet();
+            cipher.encrypt_ipv4(&mut packet).unwrap();
+            let secret_body = SecretBody::new(packet.payload(), true).unwrap();
+            assert_eq!(secret_body.random(), expected);
+        }
+    }
+
+    #[test]
+    fn identical_headers_get_distinct_nonces() {
+        let cipher = cipher();
+        let mut first = packet();
+        let mut second = packet();
+        cipher.encrypt_ipv4(&mut first).unwrap();
+        cipher.encrypt_ipv4(&mut second).unwrap();
+        assert_eq!(nonce::header(&first), nonce::header(&second));
+        let first_counter = SecretBody::new(first.payload(), true).unwrap().random();
+        let second_counter = SecretBody::new(second.payload(), true).unwrap().random();
+        assert_ne!(
+            nonce::counter_nonce(Direction::ToClient, first_counter),
+            nonce::counter_nonce(Direction::ToClient, second_counter)
+        );
+        assert_ne!(first.payload(), second.payload());
+        for mut packet in [first, second] {
+            cipher
+                .decrypt_from(&mut packet, Direction::ToClient)
+                .unwrap();
+            assert_eq!(packet.payload(), PAYLOAD);
+        }
+    }
+
+    #[test]
+    fn replayed_packets_are_not_fresh() {
+        let cipher = cipher();
+        let mut first = packet();
+        let mut second = packet();
+        cipher.encrypt_to(&mut first, Direction::ToServer).unwrap();
+        cipher.encrypt_to(&mut second, Direction::ToServer).unwrap();
+        let replays = [&first, &second].map(|p| NetPacket::new(p.buffer().to_vec()).unwrap());
+        // 后发出的数据包先到达，之后到达的更早的数据包和重放的数据包都不是新的
+        cipher.decrypt_fresh(&mut second).unwrap();
+        cipher.decrypt_ipv4(&mut first).unwrap();
+        for mut packet in replays {
+            let err = cipher.decrypt_fresh(&mut packet).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn directions_do_not_share_nonces() {
+        // 发往客户端的数据不能被当作客户端发来的数据解密
+        let cipher = cipher();
+        let mut packet = packet();
+        cipher.encrypt_ipv4(&mut packet).unwrap();
+        assert!(cipher.decrypt_ipv4(&mut packet).is_err());
+    }
+
+    #[test]
+    fn header_nonce_round_trip() {
+        let cipher = Aes256GcmCipher::with_header_nonce([7; 32], Finger::new("test"));
+        let mut first = packet();
+        cipher.encrypt_to(&mut first, Direction::ToServer).unwrap();
+        cipher.decrypt_ipv4(&mut first).unwrap();
+        assert_eq!(first.payload(), PAYLOAD);
+        // 旧客户端没有计数器，无法识别重放
+        let mut second = packet();
+        cipher.encrypt_to(&mut second, Direction::ToServer).unwrap();
+        let err = cipher.decrypt_fresh(&mut second).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn fails_at_wrap_limit() {
+        let mut cipher = cipher();
+        cipher.nonce = NonceState::counter().with_usage(KeyUsage::starting_at(u32::MAX as u64));
+        let mut last = packet();
+        cipher.encrypt_ipv4(&mut last).unwrap();
+        assert_eq!(
+            SecretBody::new(last.payload(), true).unwrap().random(),
+            u32::MAX
+        );
+        assert!(cipher.exhausted());
+        let mut rejected = packet();
+        let err = cipher.encrypt_ipv4(&mut rejected).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        // 失败时数据包保持原样
+        assert!(!rejected.is_encrypt());
+        assert_eq!(rejected.payload(), PAYLOAD);
     }
 }
@@ -0,0 +1,150 @@
+use std::fmt;
+use std::io;
+use std::time::{Duration, Instant};
+
+use rand::RngCore;
+use rsa::{PaddingScheme, PublicKey, RsaPrivateKey, RsaPublicKey};
+
+use crate::cipher::{Aes256GcmCipher, Direction, Finger};
+use crate::protocol::body::ENCRYPTION_RESERVED;
+use crate::protocol::NetPacket;
+
+/// 测试使用的数据包大小，接近常见的mtu
+const PACKET_LEN: usize = 1400;
+/// 测试使用的rsa密钥长度，和服务端默认生成的密钥相同
+const RSA_BITS: usize = 2048;
+
+/// cpu是否支持aes硬件加速，无法检测的平台返回None
+pub fn aes_acceleration() -> Option<bool> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        Some(std::is_x86_feature_detected!("aes") && std::is_x86_feature_detected!("pclmulqdq"))
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        Some(
+            std::arch::is_aarch64_feature_detected!("aes")
+                && std::arch::is_aarch64_feature_detected!("pmull"),
+        )
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        None
+    }
+}
+
+/// 启动时检查aes硬件加速，没有时中继和服务端加密的数据会占用较多cpu
+pub fn check_aes_acceleration() {
+    if aes_acceleration() == Some(false) {
+        log::warn!("cpu不支持aes硬件加速，和服务端加密的设备较多时会占用较多cpu，可以用'vnts bench-crypto'测试性能");
+        println!("warning: cpu不支持aes硬件加速，可以用'vnts bench-crypto'测试加密性能");
+    }
+}
+
+/// 加密性能测试结果
+pub struct BenchReport {
+    aes_acceleration: Option<bool>,
+    // aes-256-gcm加密速度，MB/s
+    aes_encrypt: f64,
+    // aes-256-gcm解密速度，MB/s
+    aes_decrypt: f64,
+    // rsa私钥解密次数，每秒
+    rsa_decrypt: f64,
+}
+
+/// 在当前主机上测试各加密算法的单核性能，每项测试持续duration
+pub fn run(duration: Duration) -> io::Result<BenchReport> {
+    let (aes_encrypt, aes_decrypt) = bench_aes(duration)?;
+    let rsa_decrypt = bench_rsa(duration)?;
+    Ok(BenchReport {
+        aes_acceleration: aes_acceleration(),
+        aes_encrypt,
+        aes_decrypt,
+        rsa_decrypt,
+    })
+}
+
+fn bench_aes(duration: Duration) -> io::Result<(f64, f64)> {
+    let mut rng = rand::thread_rng();
+    let mut key = [0u8; 32];
+    rng.fill_bytes(&mut key);
+    let cipher = Aes256GcmCipher::new(key, Finger::new("bench"));
+    let mut buf = vec![0u8; 12 + PACKET_LEN + ENCRYPTION_RESERVED];
+    rng.fill_bytes(&mut buf[12..12 + PACKET_LEN]);
+    let mut packet = NetPacket::new_encrypt(buf)?;
+    let mut encrypt_time = Duration::ZERO;
+    let mut decrypt_time = Duration::ZERO;
+    let mut count = 0u64;
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        let time = Instant::now();
+        cipher.encrypt_ipv4(&mut packet)?;
+        let encrypted = Instant::now();
+        cipher.decrypt_from(&mut packet, Direction::ToClient)?;
+        encrypt_time += encrypted - time;
+        decrypt_time += encrypted.elapsed();
+        count += 1;
+    }
+    let mb = (count * PACKET_LEN as u64) as f64 / 1024.0 / 1024.0;
+    Ok((
+        mb / encrypt_time.as_secs_f64(),
+        mb / decrypt_time.as_secs_f64(),
+    ))
+}
+
+fn bench_rsa(duration: Duration) -> io::Result<f64> {
+    let mut rng = rand::thread_rng();
+    let private_key = RsaPrivateKey::new(&mut rng, RSA_BITS).map_err(io::Error::other)?;
+    let public_key = RsaPublicKey::from(&private_key);
+    // 和握手时一样加密会话密钥
+    let mut secret = [0u8; 64];
+    rng.fill_bytes(&mut secret);
+    let data = public_key
+        .encrypt(&mut rng, PaddingScheme::new_pkcs1v15_encrypt(), &secret)
+        .map_err(io::Error::other)?;
+    let mut count = 0u64;
+    let start = Instant::now();
+    while count == 0 || start.elapsed() < duration {
+        private_key
+            .decrypt(PaddingScheme::new_pkcs1v15_encrypt(), &data)
+            .map_err(io::Error::other)?;
+        count += 1;
+    }
+    Ok(count as f64 / start.elapsed().as_secs_f64())
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let acceleration = match self.aes_acceleration {
+            Some(true) => "支持",
+            Some(false) => "不支持",
+            None => "未知",
+        };
+        writeln!(f, "aes硬件加速: {}", acceleration)?;
+        writeln!(f, "aes-256-gcm 加密: {:.1} MB/s", self.aes_encrypt)?;
+        writeln!(f, "aes-256-gcm 解密: {:.1} MB/s", self.aes_decrypt)?;
+        writeln!(f, "chacha20-poly1305: 暂不支持")?;
+        writeln!(f, "rsa-{} 解密: {:.0} 次/s", RSA_BITS, self.rsa_decrypt)?;
+        writeln!(f)?;
+        writeln!(f, "建议(单核估算):")?;
+        // 中继加密需要先解密再加密
+        let relay = 1.0 / (1.0 / self.aes_encrypt + 1.0 / self.aes_decrypt);
+        writeln!(
+            f,
+            "  和服务端加密的数据约 {:.0} Mbps，开启--relay-encryption后中继约 {:.0} Mbps",
+            self.aes_decrypt * 8.0,
+            relay * 8.0
+        )?;
+        if self.aes_acceleration == Some(false) {
+            writeln!(
+                f,
+                "  cpu不支持aes硬件加速，中继流量较大时建议只使用客户端加密，不开启--relay-encryption"
+            )?;
+        }
+        write!(
+            f,
+            "  1000台设备同时重连时加密握手约需要 {:.1} 秒",
+            1000.0 / self.rsa_decrypt
+        )
+    }
+}
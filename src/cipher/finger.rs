@@ -5,11 +5,19 @@ use sha2::Digest;
 
 use crate::protocol::NetPacket;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Finger {
     pub(crate) hash: [u8; 32],
 }
 
+/// 握手时的token证明，服务端不需要知道是哪个token，只和白名单中的token比较
+pub fn handshake_proof(token: &str) -> [u8; 32] {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(b"vnts-handshake");
+    hasher.update(token.as_bytes());
+    hasher.finalize().into()
+}
+
 impl Finger {
     pub fn new(str: &str) -> Self {
         let mut hasher = sha2::Sha256::new();
@@ -0,0 +1,65 @@
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// 同一个会话密钥最多加密的数据包数量，等于nonce中32位计数器的取值个数
+///
+/// 计数器写在数据包中参与生成nonce，用完所有取值后拒绝加密，客户端需要重新握手更换密钥，
+/// 这样同一个密钥下的计数器不会回绕，nonce也就不会重复
+const MAX_ENCRYPTIONS: u64 = 1 << 32;
+
+/// 会话密钥的加密计数器，clone后共享计数
+#[derive(Clone, Default)]
+pub struct KeyUsage(Arc<AtomicU64>);
+
+impl KeyUsage {
+    /// 取下一个计数器的值，计数器用完后返回错误
+    pub fn acquire(&self) -> io::Result<u32> {
+        let count = self.0.fetch_add(1, Ordering::Relaxed);
+        if count >= MAX_ENCRYPTIONS {
+            // 保持在上限，避免一直递增导致回绕
+            self.0.store(MAX_ENCRYPTIONS, Ordering::Relaxed);
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "session key exhausted, rekey required",
+            ));
+        }
+        Ok(count as u32)
+    }
+    /// 计数器是否已经用完
+    pub fn exhausted(&self) -> bool {
+        self.0.load(Ordering::Relaxed) >= MAX_ENCRYPTIONS
+    }
+    #[cfg(test)]
+    pub(crate) fn starting_at(count: u64) -> KeyUsage {
+        KeyUsage(Arc::new(AtomicU64::new(count)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acquire_increments() {
+        let usage = KeyUsage::default();
+        assert_eq!(usage.acquire().unwrap(), 0);
+        assert_eq!(usage.acquire().unwrap(), 1);
+        // clone后共享计数
+        assert_eq!(usage.clone().acquire().unwrap(), 2);
+        assert_eq!(usage.acquire().unwrap(), 3);
+    }
+
+    #[test]
+    fn fails_at_wrap_limit() {
+        let usage = KeyUsage::starting_at(MAX_ENCRYPTIONS - 1);
+        assert!(!usage.exhausted());
+        assert_eq!(usage.acquire().unwrap(), u32::MAX);
+        assert!(usage.exhausted());
+        for _ in 0..3 {
+            let err = usage.acquire().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        }
+        assert!(usage.exhausted());
+    }
+}
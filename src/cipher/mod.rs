@@ -1,13 +1,18 @@
 #[cfg(not(feature = "ring-cipher"))]
 mod aes_gcm_cipher;
+pub mod bench;
 mod finger;
+mod key_usage;
+mod nonce;
 #[cfg(feature = "ring-cipher")]
 mod ring_aes_gcm_cipher;
 mod rsa_cipher;
 
 #[cfg(not(feature = "ring-cipher"))]
 pub use aes_gcm_cipher::Aes256GcmCipher;
-pub use finger::Finger;
+pub use finger::{handshake_proof, Finger};
+pub use key_usage::KeyUsage;
+pub use nonce::Direction;
 #[cfg(feature = "ring-cipher")]
 pub use ring_aes_gcm_cipher::Aes256GcmCipher;
-pub use rsa_cipher::RsaCipher;
+pub use rsa_cipher::{RsaCipher, RsaOptions, RsaPadding, MIN_SAFE_BITS};
@@ -0,0 +1,223 @@
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use rand::RngCore;
+
+use crate::cipher::KeyUsage;
+use crate::protocol::NetPacket;
+
+/// 数据的方向，写入计数器模式的nonce，服务端和客户端各自计数，使用同一个密钥也不会产生相同的nonce
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// 客户端发往服务端
+    ToServer = 0,
+    /// 服务端发往客户端
+    ToClient = 1,
+}
+
+/// 计数器模式的nonce，由方向和加密计数器组成，计数器以明文附在数据包中
+pub fn counter_nonce(direction: Direction, counter: u32) -> [u8; 12] {
+    let mut nonce_raw = [0; 12];
+    nonce_raw[0] = direction as u8;
+    nonce_raw[8..12].copy_from_slice(&counter.to_be_bytes());
+    nonce_raw
+}
+
+/// 协议头中的地址和协议字段，用于计算指纹，计数器模式下同时作为附加数据参与认证
+///
+/// 旧客户端直接用它作为nonce，相同协议头的数据包nonce相同
+pub fn header<B: AsRef<[u8]>>(net_packet: &NetPacket<B>) -> [u8; 12] {
+    let mut header = [0; 12];
+    header[0..4].copy_from_slice(&net_packet.source().octets());
+    header[4..8].copy_from_slice(&net_packet.destination().octets());
+    header[8] = net_packet.protocol().into();
+    header[9] = net_packet.transport_protocol();
+    header[10] = net_packet.is_gateway() as u8;
+    header[11] = net_packet.source_ttl();
+    header
+}
+
+/// 一个数据包使用的nonce
+#[derive(Debug, PartialEq, Eq)]
+pub struct PacketNonce {
+    pub nonce: [u8; 12],
+    // 写在数据包random位置的值，计数器模式下是计数器
+    pub random: u32,
+    // 计数器模式下的计数器，计数器以明文放在random位置，不参与加密，协议头作为附加数据
+    pub counter: Option<u32>,
+}
+
+impl PacketNonce {
+    /// 数据部分(包含random)中需要加密的长度
+    pub fn sealed_len(&self, body_len: usize) -> usize {
+        if self.counter.is_some() {
+            body_len - 4
+        } else {
+            body_len
+        }
+    }
+    /// 附加数据，旧客户端没有附加数据
+    pub fn aad<'a>(&self, header: &'a [u8; 12]) -> &'a [u8] {
+        if self.counter.is_some() {
+            header
+        } else {
+            &[]
+        }
+    }
+}
+
+/// 会话密钥的nonce管理，两种aes实现共用，clone后共享计数
+#[derive(Clone)]
+pub struct NonceState {
+    usage: KeyUsage,
+    // 使用计数器生成nonce，旧客户端使用协议头生成nonce
+    counter: bool,
+    // 已解密的对端数据中最大的计数器+1
+    received: Arc<AtomicU64>,
+}
+
+impl NonceState {
+    /// 计数器模式，nonce由数据方向和加密计数器生成
+    pub fn counter() -> Self {
+        Self {
+            usage: KeyUsage::default(),
+            counter: true,
+            received: Arc::new(AtomicU64::new(0)),
+        }
+    }
+    /// 兼容不支持计数器nonce的旧客户端，nonce由协议头生成
+    pub fn header() -> Self {
+        Self {
+            counter: false,
+            ..Self::counter()
+        }
+    }
+    pub fn is_counter(&self) -> bool {
+        self.counter
+    }
+    /// 加密计数器已经用完，需要重新握手更换密钥
+    pub fn exhausted(&self) -> bool {
+        self.usage.exhausted()
+    }
+    /// 加密前取nonce，计数器用完后返回错误
+    pub fn seal(&self, direction: Direction, header: &[u8; 12]) -> io::Result<PacketNonce> {
+        let counter = self.usage.acquire()?;
+        Ok(if self.counter {
+            PacketNonce {
+                nonce: counter_nonce(direction, counter),
+                random: counter,
+                counter: Some(counter),
+            }
+        } else {
+            PacketNonce {
+                nonce: *header,
+                random: rand::thread_rng().next_u32(),
+                counter: None,
+            }
+        })
+    }
+    /// 解密时按数据包的random位置还原nonce
+    pub fn open(&self, direction: Direction, header: &[u8; 12], random: u32) -> PacketNonce {
+        if self.counter {
+            PacketNonce {
+                nonce: counter_nonce(direction, random),
+                random,
+                counter: Some(random),
+            }
+        } else {
+            PacketNonce {
+                nonce: *header,
+                random,
+                counter: None,
+            }
+        }
+    }
+    /// 记录解密成功的计数器，返回计数器是否大于之前记录过的所有计数器
+    pub fn record(&self, counter: u32) -> bool {
+        let previous = self
+            .received
+            .fetch_max(counter as u64 + 1, Ordering::Relaxed);
+        previous <= counter as u64
+    }
+    #[cfg(test)]
+    pub(crate) fn with_usage(mut self, usage: KeyUsage) -> Self {
+        self.usage = usage;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: [u8; 12] = [10, 26, 0, 1, 10, 26, 0, 2, 4, 1, 1, 64];
+
+    #[test]
+    fn counter_increments() {
+        let state = NonceState::counter();
+        for expected in 0..3 {
+            let nonce = state.seal(Direction::ToClient, &HEADER).unwrap();
+            assert_eq!(nonce.counter, Some(expected));
+            assert_eq!(nonce.random, expected);
+        }
+        // clone后共享计数
+        let nonce = state.clone().seal(Direction::ToClient, &HEADER).unwrap();
+        assert_eq!(nonce.counter, Some(3));
+    }
+
+    #[test]
+    fn identical_headers_get_distinct_nonces() {
+        let state = NonceState::counter();
+        let first = state.seal(Direction::ToClient, &HEADER).unwrap();
+        let second = state.seal(Direction::ToClient, &HEADER).unwrap();
+        assert_ne!(first.nonce, second.nonce);
+        // 旧客户端的nonce只由协议头决定
+        let legacy = NonceState::header();
+        let first = legacy.seal(Direction::ToClient, &HEADER).unwrap();
+        let second = legacy.seal(Direction::ToClient, &HEADER).unwrap();
+        assert_eq!(first.nonce, second.nonce);
+    }
+
+    #[test]
+    fn open_matches_seal() {
+        let state = NonceState::counter();
+        let sealed = state.seal(Direction::ToServer, &HEADER).unwrap();
+        let opened = NonceState::counter().open(Direction::ToServer, &HEADER, sealed.random);
+        assert_eq!(sealed, opened);
+        assert_eq!(opened.sealed_len(100), 96);
+        assert_eq!(opened.aad(&HEADER), &HEADER);
+        let legacy = NonceState::header().open(Direction::ToServer, &HEADER, 7);
+        assert_eq!(legacy.nonce, HEADER);
+        assert_eq!(legacy.sealed_len(100), 100);
+        assert!(legacy.aad(&HEADER).is_empty());
+    }
+
+    #[test]
+    fn directions_do_not_share_nonces() {
+        assert_ne!(
+            counter_nonce(Direction::ToServer, 5),
+            counter_nonce(Direction::ToClient, 5)
+        );
+    }
+
+    #[test]
+    fn fails_at_wrap_limit() {
+        let state = NonceState::counter().with_usage(KeyUsage::starting_at(u32::MAX as u64));
+        let last = state.seal(Direction::ToClient, &HEADER).unwrap();
+        assert_eq!(last.counter, Some(u32::MAX));
+        assert!(state.exhausted());
+        let err = state.seal(Direction::ToClient, &HEADER).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn record_rejects_replays() {
+        let state = NonceState::counter();
+        assert!(state.record(0));
+        assert!(state.record(5));
+        assert!(!state.record(5));
+        assert!(!state.record(3));
+        assert!(state.record(6));
+    }
+}
@@ -1,5 +1,5 @@
+use crate::cipher::nonce::{self, Direction, NonceState};
 use crate::cipher::Finger;
-use rand::RngCore;
 use ring::aead;
 use ring::aead::{LessSafeKey, UnboundKey};
 use std::io;
@@ -11,6 +11,7 @@ use crate::protocol::NetPacket;
 pub struct Aes256GcmCipher {
     pub(crate) cipher: AesGcmEnum,
     pub(crate) finger: Finger,
+    nonce: NonceState,
 }
 
 pub enum AesGcmEnum {
@@ -36,17 +37,66 @@ impl Clone for AesGcmEnum {
 }
 
 impl Aes256GcmCipher {
+    /// 计数器模式，nonce由数据方向和加密计数器生成
     pub fn new(key: [u8; 32], finger: Finger) -> Self {
+        Self::with_nonce(key, finger, NonceState::counter())
+    }
+    /// 兼容不支持计数器nonce的旧客户端，nonce由协议头生成
+    pub fn with_header_nonce(key: [u8; 32], finger: Finger) -> Self {
+        Self::with_nonce(key, finger, NonceState::header())
+    }
+    fn with_nonce(key: [u8; 32], finger: Finger, nonce: NonceState) -> Self {
         let cipher = LessSafeKey::new(UnboundKey::new(&aead::AES_256_GCM, &key).unwrap());
         Self {
             cipher: AesGcmEnum::AesGCM256(cipher, key),
             finger,
+            nonce,
         }
     }
+    pub fn finger(&self) -> &Finger {
+        &self.finger
+    }
+    /// 加密计数器已经用完，需要重新握手更换密钥
+    pub fn exhausted(&self) -> bool {
+        self.nonce.exhausted()
+    }
+    /// 是否使用计数器生成nonce，只有计数器模式才能识别重放的数据
+    pub fn counter_nonce(&self) -> bool {
+        self.nonce.is_counter()
+    }
+
+    /// 解密客户端发来的数据
     pub fn decrypt_ipv4<B: AsRef<[u8]> + AsMut<[u8]>>(
         &self,
         net_packet: &mut NetPacket<B>,
     ) -> io::Result<()> {
+        if let Some(counter) = self.decrypt_from(net_packet, Direction::ToServer)? {
+            self.nonce.record(counter);
+        }
+        Ok(())
+    }
+    /// 解密客户端发来的数据，并且要求计数器大于之前解密过的所有数据，重放的数据返回错误
+    pub fn decrypt_fresh<B: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        net_packet: &mut NetPacket<B>,
+    ) -> io::Result<()> {
+        let Some(counter) = self.decrypt_from(net_packet, Direction::ToServer)? else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "counter nonce required",
+            ));
+        };
+        if !self.nonce.record(counter) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "replayed"));
+        }
+        Ok(())
+    }
+    /// 解密数据，计数器模式下返回数据中的计数器
+    pub(crate) fn decrypt_from<B: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        net_packet: &mut NetPacket<B>,
+        direction: Direction,
+    ) -> io::Result<Option<u32>> {
         if !net_packet.is_encrypt() {
             //未加密的数据直接丢弃
             return Err(io::Error::new(io::ErrorKind::Other, "not encrypt"));
@@ -55,31 +105,25 @@ impl Aes256GcmCipher {
             log::error!("数据异常,长度小于{}", AES_GCM_ENCRYPTION_RESERVED);
             return Err(io::Error::new(io::ErrorKind::Other, "data err"));
         }
-        let mut nonce_raw = [0; 12];
-        nonce_raw[0..4].copy_from_slice(&net_packet.source().octets());
-        nonce_raw[4..8].copy_from_slice(&net_packet.destination().octets());
-        nonce_raw[8] = net_packet.protocol().into();
-        nonce_raw[9] = net_packet.transport_protocol();
-        nonce_raw[10] = net_packet.is_gateway() as u8;
-        nonce_raw[11] = net_packet.source_ttl();
-        let nonce = aead::Nonce::assume_unique_for_key(nonce_raw);
+        let header = nonce::header(net_packet);
         let mut secret_body = SecretBody::new(net_packet.payload_mut(), true)?;
-        let tag = secret_body.tag();
-        let finger = self
-            .finger
-            .calculate_finger(&nonce_raw, secret_body.en_body());
+        let finger = self.finger.calculate_finger(&header, secret_body.en_body());
         if &finger != secret_body.finger() {
             return Err(io::Error::new(io::ErrorKind::Other, "finger err"));
         }
-
-        let rs = match &self.cipher {
-            AesGcmEnum::AesGCM128(cipher, _) => {
-                cipher.open_in_place(nonce, aead::Aad::empty(), secret_body.en_body_mut())
-            }
-            AesGcmEnum::AesGCM256(cipher, _) => {
-                cipher.open_in_place(nonce, aead::Aad::empty(), secret_body.en_body_mut())
-            }
+        let cipher = match &self.cipher {
+            AesGcmEnum::AesGCM128(cipher, _) => cipher,
+            AesGcmEnum::AesGCM256(cipher, _) => cipher,
         };
+        let packet_nonce = self.nonce.open(direction, &header, secret_body.random());
+        let sealed_len = packet_nonce.sealed_len(secret_body.body().len());
+        let nonce = aead::Nonce::assume_unique_for_key(packet_nonce.nonce);
+        let aad = aead::Aad::from(packet_nonce.aad(&header));
+        // 计数器模式下计数器以明文附在数据和tag之间，把tag移到加密的数据之后再解密
+        let en_body = secret_body.en_body_mut();
+        let len = en_body.len();
+        en_body.copy_within(len - 16..len, sealed_len);
+        let rs = cipher.open_in_place(nonce, aad, &mut en_body[..sealed_len + 16]);
         if let Err(e) = rs {
             return Err(io::Error::new(
                 io::ErrorKind::Other,
@@ -88,36 +132,37 @@ impl Aes256GcmCipher {
         }
         net_packet.set_encrypt_flag(false);
         net_packet.set_data_len(net_packet.data_len() - AES_GCM_ENCRYPTION_RESERVED)?;
-        return Ok(());
+        return Ok(packet_nonce.counter);
     }
+    /// 加密发往客户端的数据
     /// net_packet 必须预留足够长度
     /// data_len是有效载荷的长度
-    /// 返回加密后载荷的长度
     pub fn encrypt_ipv4<B: AsRef<[u8]> + AsMut<[u8]>>(
         &self,
         net_packet: &mut NetPacket<B>,
     ) -> io::Result<()> {
-        let mut nonce_raw = [0; 12];
-        nonce_raw[0..4].copy_from_slice(&net_packet.source().octets());
-        nonce_raw[4..8].copy_from_slice(&net_packet.destination().octets());
-        nonce_raw[8] = net_packet.protocol().into();
-        nonce_raw[9] = net_packet.transport_protocol();
-        nonce_raw[10] = net_packet.is_gateway() as u8;
-        nonce_raw[11] = net_packet.source_ttl();
-        let nonce = aead::Nonce::assume_unique_for_key(nonce_raw);
+        self.encrypt_to(net_packet, Direction::ToClient)
+    }
+    pub(crate) fn encrypt_to<B: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        net_packet: &mut NetPacket<B>,
+        direction: Direction,
+    ) -> io::Result<()> {
+        let header = nonce::header(net_packet);
+        let packet_nonce = self.nonce.seal(direction, &header)?;
         let data_len = net_packet.data_len() + AES_GCM_ENCRYPTION_RESERVED;
         net_packet.set_data_len(data_len)?;
         let mut secret_body = SecretBody::new(net_packet.payload_mut(), true)?;
-        secret_body.set_random(rand::thread_rng().next_u32());
-
-        let rs = match &self.cipher {
-            AesGcmEnum::AesGCM128(cipher, _) => {
-                cipher.seal_in_place_separate_tag(nonce, aead::Aad::empty(), secret_body.body_mut())
-            }
-            AesGcmEnum::AesGCM256(cipher, _) => {
-                cipher.seal_in_place_separate_tag(nonce, aead::Aad::empty(), secret_body.body_mut())
-            }
+        let cipher = match &self.cipher {
+            AesGcmEnum::AesGCM128(cipher, _) => cipher,
+            AesGcmEnum::AesGCM256(cipher, _) => cipher,
         };
+        secret_body.set_random(packet_nonce.random);
+        let nonce = aead::Nonce::assume_unique_for_key(packet_nonce.nonce);
+        let aad = aead::Aad::from(packet_nonce.aad(&header));
+        let body = secret_body.body_mut();
+        let len = packet_nonce.sealed_len(body.len());
+        let rs = cipher.seal_in_place_separate_tag(nonce, aad, &mut body[..len]);
         return match rs {
             Ok(tag) => {
                 let tag = tag.as_ref();
@@ -128,9 +173,7 @@ impl Aes256GcmCipher {
                     ));
                 }
                 secret_body.set_tag(tag)?;
-                let finger = self
-                    .finger
-                    .calculate_finger(&nonce_raw, secret_body.en_body());
+                let finger = self.finger.calculate_finger(&header, secret_body.en_body());
                 secret_body.set_finger(&finger)?;
                 net_packet.set_encrypt_flag(true);
                 Ok(())
@@ -1,14 +1,70 @@
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::protocol::body::RsaSecretBody;
 use crate::protocol::NetPacket;
 use rsa::pkcs8::der::Decode;
 use rsa::pkcs8::{DecodePrivateKey, EncodePrivateKey, EncodePublicKey, LineEnding};
-use rsa::{RsaPrivateKey, RsaPublicKey};
+use rsa::{PaddingScheme, PublicKeyParts, RsaPrivateKey, RsaPublicKey};
 use sha2::Digest;
 
+/// 低于该长度的密钥视为不安全
+pub const MIN_SAFE_BITS: usize = 2048;
+
+/// 握手时rsa加密会话密钥使用的填充方式，需要和客户端一致
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RsaPadding {
+    // 兼容现有客户端
+    #[default]
+    Pkcs1v15,
+    OaepSha256,
+}
+
+impl FromStr for RsaPadding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "pkcs1" | "pkcs1v15" => Ok(RsaPadding::Pkcs1v15),
+            "oaep" | "oaep-sha256" => Ok(RsaPadding::OaepSha256),
+            _ => Err(format!("'{}' must be pkcs1/oaep", s)),
+        }
+    }
+}
+
+impl RsaPadding {
+    fn scheme(&self) -> PaddingScheme {
+        match self {
+            RsaPadding::Pkcs1v15 => PaddingScheme::new_pkcs1v15_encrypt(),
+            RsaPadding::OaepSha256 => PaddingScheme::new_oaep::<sha2::Sha256>(),
+        }
+    }
+}
+
+/// rsa参数
+#[derive(Clone, Copy, Debug)]
+pub struct RsaOptions {
+    // 没有密钥文件时生成的密钥长度
+    pub bits: usize,
+    pub padding: RsaPadding,
+}
+
+impl Default for RsaOptions {
+    fn default() -> Self {
+        Self {
+            bits: MIN_SAFE_BITS,
+            padding: RsaPadding::default(),
+        }
+    }
+}
+
+impl RsaOptions {
+    /// 支持的密钥长度
+    pub const BITS: [usize; 4] = [1024, 2048, 3072, 4096];
+}
+
 #[derive(Clone)]
 pub struct RsaCipher {
     inner: Arc<Inner>,
@@ -18,10 +74,11 @@ struct Inner {
     private_key: RsaPrivateKey,
     public_key_der: Vec<u8>,
     finger: String,
+    padding: RsaPadding,
 }
 
 impl RsaCipher {
-    pub fn new(root_path: PathBuf) -> io::Result<Self> {
+    pub fn new(root_path: PathBuf, options: RsaOptions) -> io::Result<Self> {
         let priv_key_path = root_path.join("key/private_key.pem");
         let pub_key_path = root_path.join("key/public_key.pem");
         let private_key = if priv_key_path.exists() {
@@ -38,7 +95,7 @@ impl RsaCipher {
             }
         } else {
             let mut rng = rand::thread_rng();
-            let bits = 2048;
+            let bits = options.bits;
             let private_key = match RsaPrivateKey::new(&mut rng, bits) {
                 Ok(private_key) => private_key,
                 Err(e) => {
@@ -62,6 +119,21 @@ impl RsaCipher {
             };
             private_key
         };
+        let bits = private_key.size() * 8;
+        if bits != options.bits {
+            log::warn!(
+                "已有密钥长度为{}位，和配置的{}位不一致，继续使用已有密钥，删除'key/'目录后重启可以重新生成",
+                bits,
+                options.bits
+            );
+        }
+        if bits < MIN_SAFE_BITS {
+            log::warn!("rsa密钥长度{}位不安全，建议至少{}位", bits, MIN_SAFE_BITS);
+            println!(
+                "warning: rsa密钥长度{}位不安全，建议至少{}位",
+                bits, MIN_SAFE_BITS
+            );
+        }
         let public_key = RsaPublicKey::from(&private_key);
         match public_key.write_public_key_pem_file(pub_key_path, LineEnding::CRLF) {
             Ok(_) => {}
@@ -83,11 +155,37 @@ impl RsaCipher {
             private_key,
             public_key_der,
             finger,
+            padding: options.padding,
         };
         Ok(Self {
             inner: Arc::new(inner),
         })
     }
+    /// 检查已有的私钥文件，返回密钥长度和指纹，没有私钥文件时返回None，不会生成密钥
+    pub fn inspect(root_path: &Path) -> io::Result<Option<(usize, String)>> {
+        let priv_key_path = root_path.join("key/private_key.pem");
+        if !priv_key_path.exists() {
+            return Ok(None);
+        }
+        let key = std::fs::read_to_string(priv_key_path)?;
+        let private_key = RsaPrivateKey::from_pkcs8_pem(&key).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("'key/private_key.pem' content error {}", e),
+            )
+        })?;
+        private_key.validate().map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("'key/private_key.pem' invalid {}", e),
+            )
+        })?;
+        let public_key_der = RsaPublicKey::from(&private_key)
+            .to_public_key_der()
+            .map_err(|e| io::Error::other(format!("to_public_key_der failed {}", e)))?;
+        let finger = Self::finger_(public_key_der.as_ref())?;
+        Ok(Some((private_key.size() * 8, finger)))
+    }
     pub fn finger_(public_key_der: &[u8]) -> io::Result<String> {
         match rsa::pkcs8::SubjectPublicKeyInfo::from_der(public_key_der) {
             Ok(spki) => match spki.fingerprint_base64() {
@@ -120,7 +218,7 @@ impl RsaCipher {
         match self
             .inner
             .private_key
-            .decrypt(rsa::PaddingScheme::PKCS1v15Encrypt, net_packet.payload())
+            .decrypt(self.inner.padding.scheme(), net_packet.payload())
         {
             Ok(rs) => {
                 let mut nonce_raw = [0; 12];
@@ -0,0 +1,362 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::core::entity::{Bandwidth, HANDSHAKE_FAILURES, TOKEN_ERRORS};
+use crate::core::store::cache::AppCache;
+use crate::core::store::snapshot::parse_response;
+
+mod email;
+
+pub use email::{EmailConfig, EmailTemplate, SmtpServer};
+
+/// 告警规则的检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 告警规则，格式为 类型=阈值
+#[derive(Clone, Copy, Debug)]
+pub enum AlertRule {
+    /// 设备离线超过该时长，offline=分钟
+    Offline(Duration),
+    /// 组内已分配的ip占可分配ip的百分比达到该值，address=百分比
+    AddressUsage(u8),
+    /// 组内中继流量超过该速率，bandwidth=字节/秒，支持K、M、G后缀
+    RelayBandwidth(Bandwidth),
+    /// 每分钟加密握手失败次数超过该值，handshake=次数
+    HandshakeFailures(u64),
+    /// 每分钟token错误次数超过该值，auth=次数
+    AuthFailures(u64),
+}
+
+impl AlertRule {
+    fn name(&self) -> &'static str {
+        match self {
+            AlertRule::Offline(_) => "offline",
+            AlertRule::AddressUsage(_) => "address",
+            AlertRule::RelayBandwidth(_) => "bandwidth",
+            AlertRule::HandshakeFailures(_) => "handshake",
+            AlertRule::AuthFailures(_) => "auth",
+        }
+    }
+}
+
+impl FromStr for AlertRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' format: kind=threshold", s))?;
+        let value = value.trim();
+        let number = || value.parse::<u64>().map_err(|e| format!("'{}' {}", s, e));
+        match kind.trim() {
+            "offline" => Ok(AlertRule::Offline(Duration::from_secs(number()? * 60))),
+            "address" => match number()? {
+                percent @ 1..=100 => Ok(AlertRule::AddressUsage(percent as u8)),
+                _ => Err(format!("'{}' percent must be 1-100", s)),
+            },
+            "bandwidth" => Ok(AlertRule::RelayBandwidth(Bandwidth::from_str(value)?)),
+            "handshake" => Ok(AlertRule::HandshakeFailures(number()?)),
+            "auth" => Ok(AlertRule::AuthFailures(number()?)),
+            kind => Err(format!(
+                "'{}' unknown kind, kinds: offline/address/bandwidth/handshake/auth",
+                kind
+            )),
+        }
+    }
+}
+
+/// webhook地址，格式为 http://host:port/path
+#[derive(Clone, Debug)]
+pub struct WebhookUrl {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl FromStr for WebhookUrl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = match s.strip_prefix("http://") {
+            Some(rest) => rest,
+            None if s.starts_with("https://") => {
+                return Err("https is not supported, use http or a local tls proxy".into());
+            }
+            None => return Err(format!("'{}' must start with http://", s)),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>()
+                    .map_err(|e| format!("'{}' port {}", s, e))?,
+            ),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(format!("'{}' missing host", s));
+        }
+        Ok(WebhookUrl {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// 告警配置
+#[derive(Clone, Debug)]
+pub struct AlertConfig {
+    pub rules: Vec<AlertRule>,
+    // 告警触发和恢复时通知的地址，不配置时只输出日志
+    pub webhook: Option<WebhookUrl>,
+    // 重要告警和服务启动停止时发送邮件
+    pub email: Option<EmailConfig>,
+}
+
+/// 发给webhook的告警，规则触发和恢复时各发送一次
+#[derive(Clone, Debug, Serialize)]
+pub struct Alert {
+    // 规则类型，服务启动和停止时为server
+    pub rule: &'static str,
+    // firing：触发，resolved：恢复，start、stop：服务启动、停止
+    pub status: &'static str,
+    pub group: Option<String>,
+    // 告警对象，例如设备id
+    pub subject: String,
+    pub message: String,
+    pub time: String,
+}
+
+impl Alert {
+    /// 服务启动或停止
+    pub fn server(status: &'static str) -> Alert {
+        Alert {
+            rule: "server",
+            status,
+            group: None,
+            subject: "server".into(),
+            message: format!("vnts {} {}", env!("CARGO_PKG_VERSION"), status),
+            time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+    /// 地址耗尽、认证失败和服务启停需要发送邮件
+    fn critical(&self) -> bool {
+        matches!(self.rule, "address" | "handshake" | "auth" | "server")
+    }
+}
+
+/// 发送通知，webhook接收所有告警，邮件只接收重要告警
+pub async fn notify(config: &AlertConfig, alert: &Alert) {
+    match alert.status {
+        "firing" => log::warn!("告警 {:?}", alert),
+        _ => log::info!("告警 {:?}", alert),
+    }
+    if let Some(webhook) = &config.webhook {
+        if let Err(e) = post_json(webhook, alert).await {
+            log::error!("发送告警失败 {:?},{:?}", webhook, e);
+        }
+    }
+    if let Some(email) = config.email.as_ref().filter(|_| alert.critical()) {
+        if let Err(e) = email.send(alert).await {
+            log::error!("发送告警邮件失败 {:?},{:?}", email.server, e);
+        }
+    }
+}
+
+/// 定时检查告警规则
+pub async fn alert_task(cache: AppCache, config: AlertConfig) {
+    notify(&config, &Alert::server("start")).await;
+    if config.rules.is_empty() {
+        return;
+    }
+    let mut state = AlertState::default();
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        for alert in state.evaluate(&cache, &config.rules) {
+            notify(&config, &alert).await;
+        }
+    }
+}
+
+#[derive(Default)]
+struct AlertState {
+    // (组,设备id) -> 第一次检查到离线的时间
+    offline_since: HashMap<(String, String), Instant>,
+    // 组 -> (中继字节数,检查时间)
+    relay_bytes: HashMap<String, (u64, Instant)>,
+    // 上一次检查时加密握手失败的总数
+    handshake_failures: Option<u64>,
+    // 上一次检查时token错误的总数
+    auth_failures: Option<u64>,
+    // 正在触发的告警 (规则,组,对象)
+    firing: HashSet<(&'static str, Option<String>, String)>,
+}
+
+impl AlertState {
+    /// 检查所有规则，返回状态发生变化的告警
+    fn evaluate(&mut self, cache: &AppCache, rules: &[AlertRule]) -> Vec<Alert> {
+        let now = Instant::now();
+        let mut current: HashMap<(&'static str, Option<String>, String), String> = HashMap::new();
+        let mut offline_since = HashMap::new();
+        let mut relay_bytes = HashMap::new();
+        for (group, network_info) in cache.virtual_network.key_values() {
+            let guard = network_info.read();
+            let total = guard.relay_bytes.load(Ordering::Relaxed);
+            let rate = self.relay_bytes.get(&group).and_then(|(bytes, time)| {
+                let secs = now.duration_since(*time).as_secs_f64();
+                (secs > 0.0).then(|| (total.saturating_sub(*bytes) as f64 / secs) as u64)
+            });
+            relay_bytes.insert(group.clone(), (total, now));
+            for client in guard.clients.values().filter(|client| !client.online) {
+                let key = (group.clone(), client.device_id.clone());
+                let since = self.offline_since.get(&key).copied().unwrap_or(now);
+                offline_since.insert(key, since);
+            }
+            for rule in rules {
+                match rule {
+                    AlertRule::Offline(limit) => {
+                        for client in guard.clients.values().filter(|client| !client.online) {
+                            let since = offline_since[&(group.clone(), client.device_id.clone())];
+                            if now.duration_since(since) >= *limit {
+                                current.insert(
+                                    (rule.name(), Some(group.clone()), client.device_id.clone()),
+                                    format!(
+                                        "设备离线超过{}分钟 name={:?},virtual_ip={}",
+                                        limit.as_secs() / 60,
+                                        client.name,
+                                        std::net::Ipv4Addr::from(client.virtual_ip)
+                                    ),
+                                );
+                            }
+                        }
+                    }
+                    AlertRule::AddressUsage(percent) => {
+                        let capacity = guard.capacity();
+                        let used = guard.clients.len() as u64;
+                        if capacity > 0 && used * 100 >= capacity * *percent as u64 {
+                            current.insert(
+                                (rule.name(), Some(group.clone()), group.clone()),
+                                format!(
+                                    "ip使用率达到{}%,{}/{}",
+                                    used * 100 / capacity,
+                                    used,
+                                    capacity
+                                ),
+                            );
+                        }
+                    }
+                    AlertRule::RelayBandwidth(limit) => {
+                        if let Some(rate) = rate.filter(|rate| *rate > limit.0) {
+                            current.insert(
+                                (rule.name(), Some(group.clone()), group.clone()),
+                                format!("中继流量{}字节/秒,超过{}字节/秒", rate, limit.0),
+                            );
+                        }
+                    }
+                    AlertRule::HandshakeFailures(_) | AlertRule::AuthFailures(_) => {}
+                }
+            }
+        }
+        self.offline_since = offline_since;
+        self.relay_bytes = relay_bytes;
+        let handshake_failures = increase(
+            &mut self.handshake_failures,
+            cache.log_limiter.total(HANDSHAKE_FAILURES),
+        );
+        let auth_failures = increase(
+            &mut self.auth_failures,
+            cache.log_limiter.total(TOKEN_ERRORS),
+        );
+        for rule in rules {
+            let (limit, failures, text) = match rule {
+                AlertRule::HandshakeFailures(limit) => (limit, handshake_failures, "加密握手失败"),
+                AlertRule::AuthFailures(limit) => (limit, auth_failures, "token错误"),
+                _ => continue,
+            };
+            if let Some(failures) = failures.filter(|failures| failures > limit) {
+                current.insert(
+                    (rule.name(), None, "server".to_string()),
+                    format!("一分钟内{}{}次,超过{}次", text, failures, limit),
+                );
+            }
+        }
+        let time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let mut alerts = Vec::new();
+        for ((rule, group, subject), message) in &current {
+            if !self
+                .firing
+                .contains(&(*rule, group.clone(), subject.clone()))
+            {
+                alerts.push(Alert {
+                    rule,
+                    status: "firing",
+                    group: group.clone(),
+                    subject: subject.clone(),
+                    message: message.clone(),
+                    time: time.clone(),
+                });
+            }
+        }
+        for (rule, group, subject) in &self.firing {
+            if !current.contains_key(&(*rule, group.clone(), subject.clone())) {
+                alerts.push(Alert {
+                    rule,
+                    status: "resolved",
+                    group: group.clone(),
+                    subject: subject.clone(),
+                    message: String::new(),
+                    time: time.clone(),
+                });
+            }
+        }
+        self.firing = current.into_keys().collect();
+        alerts
+    }
+}
+
+/// 计数器相对上一次检查的增量，第一次检查时返回None
+fn increase(last: &mut Option<u64>, total: u64) -> Option<u64> {
+    let increase = last.map(|last| total.saturating_sub(last));
+    *last = Some(total);
+    increase
+}
+
+/// 以json格式post到webhook
+async fn post_json<T: Serialize>(url: &WebhookUrl, body: &T) -> io::Result<()> {
+    let body = serde_json::to_vec(body)?;
+    let host = if url.port == 80 {
+        url.host.clone()
+    } else {
+        format!("{}:{}", url.host, url.port)
+    };
+    let head = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n",
+        url.path,
+        host,
+        body.len()
+    );
+    let mut stream = tokio::time::timeout(
+        Duration::from_secs(10),
+        TcpStream::connect((url.host.as_str(), url.port)),
+    )
+    .await??;
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    let mut response = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), stream.read_to_end(&mut response)).await??;
+    parse_response(&response).map(|_| ())
+}
@@ -0,0 +1,158 @@
+use std::io;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::core::alert::WebhookUrl;
+use crate::core::store::snapshot::parse_response;
+
+/// 检查公网ip的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(300);
+/// 默认的公网ip查询地址，返回纯文本的ip
+pub const DEFAULT_IP_URL: &str = "http://api.ipify.org/";
+
+/// 动态域名服务商
+///
+/// duckdns://token@domain1,domain2 使用duckdns，
+/// http://host:port/path?ip={ip} 请求任意地址，{ip}替换为当前公网ip，可用于dyndns2兼容的服务商或者本地的tls代理
+#[derive(Clone)]
+pub enum DdnsProvider {
+    DuckDns { token: String, domains: String },
+    Http(WebhookUrl),
+}
+
+impl std::fmt::Debug for DdnsProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DdnsProvider::DuckDns { domains, .. } => write!(f, "duckdns({})", domains),
+            DdnsProvider::Http(url) => write!(f, "http({}:{})", url.host, url.port),
+        }
+    }
+}
+
+impl FromStr for DdnsProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = s.strip_prefix("duckdns://") {
+            let (token, domains) = rest
+                .split_once('@')
+                .ok_or_else(|| format!("'{}' format: duckdns://token@domain1,domain2", s))?;
+            if token.is_empty() || domains.is_empty() {
+                return Err(format!("'{}' missing token or domain", s));
+            }
+            return Ok(DdnsProvider::DuckDns {
+                token: token.to_string(),
+                domains: domains.trim_end_matches('/').to_string(),
+            });
+        }
+        if s.starts_with("cloudflare://") || s.starts_with("route53://") {
+            return Err(format!(
+                "'{}' requires https, use http://...{{ip}} through a local tls proxy",
+                s
+            ));
+        }
+        let url = WebhookUrl::from_str(s)?;
+        if !url.path.contains("{ip}") {
+            return Err(format!("'{}' missing {{ip}}", s));
+        }
+        Ok(DdnsProvider::Http(url))
+    }
+}
+
+impl DdnsProvider {
+    fn url(&self, ip: Ipv4Addr) -> WebhookUrl {
+        match self {
+            DdnsProvider::DuckDns { token, domains } => WebhookUrl {
+                host: "www.duckdns.org".into(),
+                port: 80,
+                path: format!("/update?domains={}&token={}&ip={}", domains, token, ip),
+            },
+            DdnsProvider::Http(url) => WebhookUrl {
+                host: url.host.clone(),
+                port: url.port,
+                path: url.path.replace("{ip}", &ip.to_string()),
+            },
+        }
+    }
+    async fn update(&self, ip: Ipv4Addr) -> io::Result<()> {
+        let body = get(&self.url(ip)).await?;
+        // duckdns失败时也返回200，内容为KO
+        if matches!(self, DdnsProvider::DuckDns { .. }) && body.trim() != "OK" {
+            return Err(io::Error::other(format!("duckdns {}", body.trim())));
+        }
+        Ok(())
+    }
+}
+
+/// 动态域名配置
+#[derive(Clone, Debug)]
+pub struct DdnsConfig {
+    pub providers: Vec<DdnsProvider>,
+    // 查询公网ip的地址
+    pub ip_url: WebhookUrl,
+}
+
+/// 定时查询公网ip，变化时更新域名记录，更新失败则下次检查时重试
+pub async fn ddns_task(config: DdnsConfig) {
+    let mut current: Option<Ipv4Addr> = None;
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let ip = match public_ip(&config.ip_url).await {
+            Ok(ip) => ip,
+            Err(e) => {
+                log::warn!("查询公网ip失败 {:?},{:?}", config.ip_url, e);
+                continue;
+            }
+        };
+        if current == Some(ip) {
+            continue;
+        }
+        log::info!("公网ip变化 {:?} -> {}", current, ip);
+        let mut success = true;
+        for provider in &config.providers {
+            match provider.update(ip).await {
+                Ok(_) => log::info!("更新动态域名 {:?},ip={}", provider, ip),
+                Err(e) => {
+                    success = false;
+                    log::error!("更新动态域名失败 {:?},ip={},{:?}", provider, ip, e);
+                }
+            }
+        }
+        if success {
+            current = Some(ip);
+        }
+    }
+}
+
+async fn public_ip(url: &WebhookUrl) -> io::Result<Ipv4Addr> {
+    let body = get(url).await?;
+    Ipv4Addr::from_str(body.trim())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?} {}", body, e)))
+}
+
+async fn get(url: &WebhookUrl) -> io::Result<String> {
+    let host = if url.port == 80 {
+        url.host.clone()
+    } else {
+        format!("{}:{}", url.host, url.port)
+    };
+    let head = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: vnts\r\nConnection: close\r\n\r\n",
+        url.path, host
+    );
+    let mut stream = tokio::time::timeout(
+        Duration::from_secs(10),
+        TcpStream::connect((url.host.as_str(), url.port)),
+    )
+    .await??;
+    stream.write_all(head.as_bytes()).await?;
+    let mut response = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), stream.read_to_end(&mut response)).await??;
+    let body = parse_response(&response)?;
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
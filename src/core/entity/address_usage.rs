@@ -0,0 +1,66 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// 统计ip分配和回收次数的时长
+const CHURN_WINDOW: Duration = Duration::from_secs(3600);
+/// 每个统计桶的时长
+const BUCKET: Duration = Duration::from_secs(60);
+
+/// 组内ip的分配和回收次数，按分钟分桶统计最近一小时
+pub struct AddressChurn {
+    start: Instant,
+    // (分钟序号，分配次数，回收次数)
+    buckets: VecDeque<(u64, u32, u32)>,
+    // ip使用率超过告警阈值后只输出一次日志，降到阈值以下后重新计算
+    pub warned: bool,
+}
+
+impl Default for AddressChurn {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            buckets: VecDeque::new(),
+            warned: false,
+        }
+    }
+}
+
+impl AddressChurn {
+    pub fn record_allocation(&mut self) {
+        self.bucket().1 += 1;
+    }
+    pub fn record_release(&mut self) {
+        self.bucket().2 += 1;
+    }
+    /// 最近一小时的分配和回收次数
+    #[cfg(feature = "web")]
+    pub fn last_hour(&self) -> (u64, u64) {
+        let oldest = self
+            .minute()
+            .saturating_sub(CHURN_WINDOW.as_secs() / BUCKET.as_secs());
+        self.buckets
+            .iter()
+            .filter(|(minute, _, _)| *minute > oldest)
+            .fold((0, 0), |(allocations, releases), (_, a, r)| {
+                (allocations + *a as u64, releases + *r as u64)
+            })
+    }
+    fn minute(&self) -> u64 {
+        self.start.elapsed().as_secs() / BUCKET.as_secs()
+    }
+    fn bucket(&mut self) -> &mut (u64, u32, u32) {
+        let minute = self.minute();
+        let oldest = minute.saturating_sub(CHURN_WINDOW.as_secs() / BUCKET.as_secs());
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(first, _, _)| *first <= oldest)
+        {
+            self.buckets.pop_front();
+        }
+        if self.buckets.back().map(|(last, _, _)| *last) != Some(minute) {
+            self.buckets.push_back((minute, 0, 0));
+        }
+        self.buckets.back_mut().unwrap()
+    }
+}
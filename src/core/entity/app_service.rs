@@ -0,0 +1,70 @@
+use crate::proto::message::ServiceEntry;
+
+/// 每台设备最多登记的服务数量
+const MAX_SERVICES: usize = 32;
+/// 服务名称的最大长度
+const MAX_SERVICE_NAME_LEN: usize = 64;
+
+/// 设备登记的应用服务，例如 nas:445
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AppService {
+    pub name: String,
+    pub port: u16,
+    // 为false时是tcp
+    pub udp: bool,
+}
+
+impl AppService {
+    pub fn protocol(&self) -> &'static str {
+        if self.udp {
+            "udp"
+        } else {
+            "tcp"
+        }
+    }
+    pub fn to_entry(&self) -> ServiceEntry {
+        let mut entry = ServiceEntry::new();
+        entry.name = self.name.clone();
+        entry.port = self.port as u32;
+        entry.protocol = self.protocol().to_string();
+        entry
+    }
+}
+
+/// 检查设备上报的服务列表，名称不能为空并且不能包含控制字符，端口不能为0
+pub fn parse_services(entries: &[ServiceEntry]) -> Result<Vec<AppService>, String> {
+    if entries.len() > MAX_SERVICES {
+        return Err(format!("at most {} services", MAX_SERVICES));
+    }
+    let mut services: Vec<AppService> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let name = entry.name.trim();
+        if name.is_empty()
+            || name.len() > MAX_SERVICE_NAME_LEN
+            || name.chars().any(char::is_control)
+        {
+            return Err(format!(
+                "invalid service name {:?}, 1-{} bytes",
+                entry.name, MAX_SERVICE_NAME_LEN
+            ));
+        }
+        let port = match u16::try_from(entry.port) {
+            Ok(port @ 1..) => port,
+            _ => return Err(format!("invalid service port {}", entry.port)),
+        };
+        let udp = match entry.protocol.trim().to_lowercase().as_str() {
+            "" | "tcp" => false,
+            "udp" => true,
+            _ => return Err(format!("invalid service protocol {:?}", entry.protocol)),
+        };
+        let service = AppService {
+            name: name.to_string(),
+            port,
+            udp,
+        };
+        if !services.contains(&service) {
+            services.push(service);
+        }
+    }
+    Ok(services)
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+
+use crate::proto::message;
+
+/// 配置的大小上限，键值和原始数据合计，字节
+pub const MAX_CLIENT_CONFIG_LEN: usize = 8192;
+
+/// 管理员下发给客户端的配置，服务端不解析内容
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ClientConfig {
+    // 修改时间，unix时间戳毫秒，客户端据此判断配置是否变化
+    pub version: i64,
+    pub values: BTreeMap<String, String>,
+    pub data: Vec<u8>,
+}
+
+impl ClientConfig {
+    pub fn check(&self) -> Result<(), String> {
+        let len = self.data.len()
+            + self
+                .values
+                .iter()
+                .map(|(k, v)| k.len() + v.len())
+                .sum::<usize>();
+        if len > MAX_CLIENT_CONFIG_LEN {
+            return Err(format!(
+                "config too large, {} > {} bytes",
+                len, MAX_CLIENT_CONFIG_LEN
+            ));
+        }
+        if self.values.keys().any(|k| k.is_empty()) {
+            return Err("config key must not be empty".into());
+        }
+        Ok(())
+    }
+}
+
+/// 按启动参数生成的默认配置，dns为逗号分隔的dns服务器，mtu为虚拟网卡的mtu，都没有配置时返回None
+pub fn default_config(dns: &[Ipv4Addr], mtu: Option<u16>, version: i64) -> Option<ClientConfig> {
+    let mut values = BTreeMap::new();
+    if !dns.is_empty() {
+        let dns: Vec<String> = dns.iter().map(|ip| ip.to_string()).collect();
+        values.insert("dns".to_string(), dns.join(","));
+    }
+    if let Some(mtu) = mtu {
+        values.insert("mtu".to_string(), mtu.to_string());
+    }
+    (!values.is_empty()).then(|| ClientConfig {
+        version,
+        values,
+        data: Vec::new(),
+    })
+}
+
+/// 设备实际生效的配置，依次合并默认配置、组的配置和设备的配置，后面的键值覆盖前面的同名键值，有原始数据时替换前面的原始数据
+pub fn effective_config(
+    defaults: Option<&ClientConfig>,
+    group: Option<&ClientConfig>,
+    device: Option<&ClientConfig>,
+) -> Option<message::ClientConfig> {
+    if defaults.is_none() && group.is_none() && device.is_none() {
+        return None;
+    }
+    let mut config = message::ClientConfig::new();
+    for blob in [defaults, group, device].into_iter().flatten() {
+        config.version = config.version.max(blob.version);
+        config
+            .values
+            .extend(blob.values.iter().map(|(k, v)| (k.clone(), v.clone())));
+        if !blob.data.is_empty() {
+            config.data = blob.data.clone();
+        }
+    }
+    Some(config)
+}
+
+/// 把按路径mtu推荐的mtu合并到配置中，配置中已经有更小的mtu时保留，version为推荐值变化的时间
+pub fn recommend_mtu(
+    config: Option<message::ClientConfig>,
+    mtu: Option<u16>,
+    version: i64,
+) -> Option<message::ClientConfig> {
+    let Some(mtu) = mtu else {
+        return config;
+    };
+    let mut config = config.unwrap_or_default();
+    let configured = config
+        .values
+        .get("mtu")
+        .and_then(|v| v.trim().parse::<u16>().ok());
+    if configured.is_none_or(|configured| configured > mtu) {
+        config.values.insert("mtu".to_string(), mtu.to_string());
+        config.version = config.version.max(version);
+    }
+    Some(config)
+}
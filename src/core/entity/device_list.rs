@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use protobuf::rt::WireType;
+use protobuf::{CodedOutputStream, Message};
+
+use crate::core::entity::ClientInfo;
+use crate::proto::message::DeviceInfo;
+
+/// 编码后的设备信息，不包含中继延迟
+struct EncodedDevice {
+    virtual_ip: u32,
+    bytes: Vec<u8>,
+}
+
+type Epochs = (u32, u32);
+
+/// 设备信息，不包含中继延迟
+pub fn device_info_of(client: &ClientInfo) -> DeviceInfo {
+    let mut dev = DeviceInfo::new();
+    dev.virtual_ip = client.virtual_ip;
+    dev.name = client.name.clone();
+    dev.device_status = if client.online { 0 } else { 1 };
+    dev.client_secret = client.client_secret;
+    dev.is_cone = client
+        .meta
+        .client_status
+        .as_ref()
+        .is_some_and(|status| status.is_cone);
+    dev.tags = client.tags.clone();
+    dev.local_addresses = client
+        .meta
+        .local_addrs
+        .iter()
+        .map(|v| v.to_address())
+        .collect();
+    dev.local_port = client.meta.local_port as u32;
+    dev
+}
+
+/// 按纪元号缓存编码后的设备信息，设备或设备状态变化时纪元号增加，缓存随之失效
+///
+/// 设备列表不包含请求方自己，中继延迟也和请求方有关，所以每个设备单独编码，
+/// 请求时跳过请求方并在各设备的编码后面追加中继延迟
+#[derive(Default)]
+pub struct DeviceListCache {
+    // ((纪元号,设备状态的纪元号),编码后的设备信息)
+    cache: Mutex<Option<(Epochs, Arc<Vec<EncodedDevice>>)>>,
+}
+
+impl DeviceListCache {
+    fn devices(
+        &self,
+        epoch: Epochs,
+        clients: &HashMap<u32, ClientInfo>,
+    ) -> protobuf::Result<Arc<Vec<EncodedDevice>>> {
+        // 并发请求时等待同一次编码
+        let mut cache = self.cache.lock();
+        if let Some((cached_epoch, devices)) = &*cache {
+            if *cached_epoch == epoch {
+                return Ok(devices.clone());
+            }
+        }
+        let mut devices = Vec::with_capacity(clients.len());
+        for device_info in clients.values() {
+            devices.push(EncodedDevice {
+                virtual_ip: device_info.virtual_ip,
+                bytes: device_info_of(device_info).write_to_bytes()?,
+            });
+        }
+        let devices = Arc::new(devices);
+        *cache = Some((epoch, devices.clone()));
+        Ok(devices)
+    }
+    /// 编码发给current_ip的DeviceList，isolated为true时不包含其他设备
+    pub fn encode(
+        &self,
+        epoch: u32,
+        status_epoch: u32,
+        clients: &HashMap<u32, ClientInfo>,
+        current_ip: u32,
+        isolated: bool,
+    ) -> protobuf::Result<Vec<u8>> {
+        let devices = self.devices((epoch, status_epoch), clients)?;
+        let current_rtt = clients.get(&current_ip).and_then(|v| v.meta.rtt);
+        let mut bytes = Vec::with_capacity(devices.iter().map(|v| v.bytes.len() + 8).sum());
+        let mut os = CodedOutputStream::vec(&mut bytes);
+        if epoch != 0 {
+            os.write_uint32(1, epoch)?;
+        }
+        for device in devices
+            .iter()
+            .filter(|v| !isolated && v.virtual_ip != current_ip)
+        {
+            let rtt = clients.get(&device.virtual_ip).and_then(|v| v.meta.rtt);
+            let relay_cost = match (current_rtt, rtt) {
+                (Some(current_rtt), Some(rtt)) => current_rtt + rtt,
+                _ => 0,
+            };
+            let mut len = device.bytes.len() as u64;
+            if relay_cost != 0 {
+                len += protobuf::rt::uint32_size(5, relay_cost);
+            }
+            os.write_tag(2, WireType::LengthDelimited)?;
+            os.write_raw_varint32(len as u32)?;
+            os.write_raw_bytes(&device.bytes)?;
+            if relay_cost != 0 {
+                os.write_uint32(5, relay_cost)?;
+            }
+        }
+        if status_epoch != 0 {
+            os.write_uint32(3, status_epoch)?;
+        }
+        os.flush()?;
+        drop(os);
+        Ok(bytes)
+    }
+}
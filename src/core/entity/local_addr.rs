@@ -0,0 +1,248 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use crate::core::entity::ClientInfo;
+use crate::proto::message::{LocalAddress, LocalPathReason};
+
+/// 每台设备最多上报的本地地址数量
+const MAX_LOCAL_ADDRS: usize = 16;
+
+/// 设备本地网卡的ipv4地址和网段的前缀长度
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LocalAddr {
+    pub ip: Ipv4Addr,
+    // 0表示未知
+    pub prefix_len: u8,
+}
+
+impl LocalAddr {
+    pub fn to_address(self) -> LocalAddress {
+        let mut address = LocalAddress::new();
+        address.ip = self.ip.into();
+        address.prefix_len = self.prefix_len as u32;
+        address
+    }
+    /// 是否和另一个地址在同一网段，前缀长度取双方已知的较短者，都未知时返回false
+    pub fn same_subnet(&self, other: &LocalAddr) -> bool {
+        let prefix_len = match (self.prefix_len, other.prefix_len) {
+            (0, 0) => return false,
+            (0, v) | (v, 0) => v,
+            (a, b) => a.min(b),
+        };
+        let mask = u32::MAX << (32 - prefix_len);
+        u32::from(self.ip) & mask == u32::from(other.ip) & mask
+    }
+}
+
+/// 是否是只在本地网络内有意义的地址：私有网段、链路本地地址和运营商级nat的共享地址(100.64.0.0/10)
+#[cfg(feature = "web")]
+pub fn is_local_scope(ip: Ipv4Addr) -> bool {
+    ip.is_private() || ip.is_link_local() || (ip.octets()[0] == 100 && ip.octets()[1] & 0xC0 == 64)
+}
+
+/// 按公网地址和上报的本地地址判断两台设备是否可能在同一局域网或同一nat后，只判断ipv4
+pub fn local_path(a: &ClientInfo, b: &ClientInfo) -> Option<LocalPathReason> {
+    match (a.address.ip(), b.address.ip()) {
+        (IpAddr::V4(x), IpAddr::V4(y)) if x == y => {}
+        _ => return None,
+    }
+    let same_lan = a
+        .meta
+        .local_addrs
+        .iter()
+        .any(|x| b.meta.local_addrs.iter().any(|y| x.same_subnet(y)));
+    Some(if same_lan {
+        LocalPathReason::SameLan
+    } else {
+        LocalPathReason::SameNat
+    })
+}
+
+/// 检查设备上报的本地地址，忽略回环、组播等不能用于直连的地址，重复的地址只保留一个
+pub fn parse_local_addrs(entries: &[LocalAddress]) -> Result<Vec<LocalAddr>, String> {
+    if entries.len() > MAX_LOCAL_ADDRS {
+        return Err(format!("at most {} local addresses", MAX_LOCAL_ADDRS));
+    }
+    let mut addrs: Vec<LocalAddr> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let prefix_len = match u8::try_from(entry.prefix_len) {
+            Ok(prefix_len @ 0..=32) => prefix_len,
+            _ => return Err(format!("invalid prefix length {}", entry.prefix_len)),
+        };
+        let ip = Ipv4Addr::from(entry.ip);
+        if ip.is_unspecified()
+            || ip.is_loopback()
+            || ip.is_multicast()
+            || ip.is_broadcast()
+            || addrs.iter().any(|v| v.ip == ip)
+        {
+            continue;
+        }
+        addrs.push(LocalAddr { ip, prefix_len });
+    }
+    Ok(addrs)
+}
+
+/// 按打洞结果推断设备的nat是否支持回环(hairpin)，没有可以判断的设备时返回None
+///
+/// 只看公网ip相同并且没有同一网段本地地址的设备，它们之间的p2p连接只能经过nat回环，
+/// 和其中任意一台建立了p2p连接即视为支持，否则和其中一台打洞失败视为不支持
+pub fn infer_hairpin<'a>(
+    client: &ClientInfo,
+    peers: impl Iterator<Item = &'a ClientInfo>,
+    p2p_list: &[Ipv4Addr],
+    punch_failed: &[u32],
+) -> Option<bool> {
+    let mut hairpin = None;
+    for peer in peers {
+        if peer.virtual_ip == client.virtual_ip
+            || local_path(client, peer) != Some(LocalPathReason::SameNat)
+        {
+            continue;
+        }
+        if p2p_list.contains(&peer.virtual_ip.into()) {
+            return Some(true);
+        }
+        if punch_failed.contains(&peer.virtual_ip) {
+            hairpin = Some(false);
+        }
+    }
+    hairpin
+}
+
+/// 两台设备中是否有一方的nat已知不支持回环
+pub fn no_hairpin(a: &ClientInfo, b: &ClientInfo) -> bool {
+    [a, b].iter().any(|client| {
+        client
+            .meta
+            .client_status
+            .as_ref()
+            .is_some_and(|status| status.hairpin == Some(false))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::entity::ClientStatusInfo;
+
+    fn local(ip: &str, prefix_len: u8) -> LocalAddr {
+        LocalAddr {
+            ip: ip.parse().unwrap(),
+            prefix_len,
+        }
+    }
+
+    fn entry(ip: &str, prefix_len: u32) -> LocalAddress {
+        let mut address = LocalAddress::new();
+        address.ip = ip.parse::<Ipv4Addr>().unwrap().into();
+        address.prefix_len = prefix_len;
+        address
+    }
+
+    fn client(virtual_ip: u32, address: &str, local_addrs: &[LocalAddr]) -> ClientInfo {
+        let mut client = ClientInfo {
+            virtual_ip,
+            address: address.parse().unwrap(),
+            ..Default::default()
+        };
+        client.meta.local_addrs = local_addrs.to_vec();
+        client
+    }
+
+    #[test]
+    fn same_subnet_uses_shorter_prefix() {
+        let a = local("192.168.1.5", 24);
+        assert!(a.same_subnet(&local("192.168.1.9", 24)));
+        assert!(!a.same_subnet(&local("192.168.2.9", 24)));
+        assert!(a.same_subnet(&local("192.168.2.9", 16)));
+        // 一方未知时取另一方的前缀长度，都未知时不判断
+        assert!(a.same_subnet(&local("192.168.1.9", 0)));
+        assert!(!local("192.168.1.5", 0).same_subnet(&local("192.168.1.5", 0)));
+        assert!(local("10.0.0.1", 32).same_subnet(&local("10.0.0.1", 32)));
+    }
+
+    #[test]
+    fn parse_skips_unusable_addresses() {
+        let entries = [
+            entry("192.168.1.5", 24),
+            entry("0.0.0.0", 0),
+            entry("127.0.0.1", 8),
+            entry("224.0.0.1", 4),
+            entry("255.255.255.255", 32),
+            entry("192.168.1.5", 16),
+            entry("10.0.0.2", 0),
+        ];
+        assert_eq!(
+            parse_local_addrs(&entries).unwrap(),
+            [local("192.168.1.5", 24), local("10.0.0.2", 0)]
+        );
+        assert!(parse_local_addrs(&[entry("10.0.0.2", 33)]).is_err());
+        let many = vec![entry("10.0.0.2", 24); MAX_LOCAL_ADDRS + 1];
+        assert!(parse_local_addrs(&many).is_err());
+    }
+
+    #[test]
+    fn local_path_by_public_ip_and_subnet() {
+        let a = client(2, "203.0.113.5:4000", &[local("192.168.1.5", 24)]);
+        let lan = client(3, "203.0.113.5:4001", &[local("192.168.1.9", 24)]);
+        let nat = client(4, "203.0.113.5:4002", &[local("192.168.2.9", 24)]);
+        let remote = client(5, "198.51.100.9:4000", &[local("192.168.1.9", 24)]);
+        assert_eq!(local_path(&a, &lan), Some(LocalPathReason::SameLan));
+        assert_eq!(local_path(&a, &nat), Some(LocalPathReason::SameNat));
+        assert_eq!(local_path(&a, &remote), None);
+        let v6 = client(6, "[2001:db8::1]:4000", &[]);
+        assert_eq!(local_path(&v6, &v6), None);
+    }
+
+    #[test]
+    fn infer_hairpin_from_same_nat_peers() {
+        let a = client(2, "203.0.113.5:4000", &[local("192.168.1.5", 24)]);
+        let lan = client(3, "203.0.113.5:4001", &[local("192.168.1.9", 24)]);
+        let nat = client(4, "203.0.113.5:4002", &[local("192.168.2.9", 24)]);
+        let nat2 = client(5, "203.0.113.5:4003", &[]);
+        let remote = client(6, "198.51.100.9:4000", &[]);
+        let peers = || [&a, &lan, &nat, &nat2, &remote].into_iter();
+        // 同一局域网和不同公网ip的设备不能用来判断
+        assert_eq!(infer_hairpin(&a, peers(), &[], &[3, 6]), None);
+        assert_eq!(infer_hairpin(&a, peers(), &[Ipv4Addr::from(3)], &[]), None);
+        assert_eq!(infer_hairpin(&a, peers(), &[], &[4]), Some(false));
+        // 和任意一台同一nat后的设备建立了p2p即视为支持
+        assert_eq!(
+            infer_hairpin(&a, peers(), &[Ipv4Addr::from(5)], &[4]),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn no_hairpin_when_either_side_known() {
+        let a = client(2, "203.0.113.5:4000", &[]);
+        let mut b = client(3, "203.0.113.5:4001", &[]);
+        assert!(!no_hairpin(&a, &b));
+        b.meta.client_status = Some(ClientStatusInfo {
+            hairpin: Some(true),
+            ..Default::default()
+        });
+        assert!(!no_hairpin(&a, &b));
+        b.meta.client_status.as_mut().unwrap().hairpin = Some(false);
+        assert!(no_hairpin(&a, &b));
+        assert!(no_hairpin(&b, &a));
+    }
+
+    #[cfg(feature = "web")]
+    #[test]
+    fn local_scope_addresses() {
+        for ip in [
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.0.1",
+            "100.64.0.1",
+            "100.127.255.255",
+        ] {
+            assert!(is_local_scope(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["100.63.255.255", "100.128.0.1", "8.8.8.8", "203.0.113.5"] {
+            assert!(!is_local_scope(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::core::task;
+
+/// 每个来源每个周期内最多输出的日志条数
+const BURST: u32 = 5;
+/// 汇总周期
+const PERIOD: Duration = Duration::from_secs(60);
+/// 最多跟踪的来源数量，超出后新来源的日志只计数
+const MAX_SOURCES: usize = 4096;
+/// 加密握手失败，告警按这个类型的总数计算
+pub const HANDSHAKE_FAILURES: &str = "handshake failures";
+/// 注册时token错误，告警按这个类型的总数计算
+pub const TOKEN_ERRORS: &str = "token errors";
+/// 设备时钟和服务端时钟的偏差超过允许值
+pub const CLOCK_SKEW: &str = "clock skew";
+
+/// 异常流量的日志限流，每个来源每个周期只输出前几条，其余的计数后在周期结束时输出汇总
+#[derive(Clone)]
+pub struct LogLimiter {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    // (来源，类型) -> 本周期计数
+    sources: HashMap<(IpAddr, &'static str), Counter>,
+    // 来源太多时没有跟踪的计数
+    overflow: HashMap<&'static str, u64>,
+    // 启动以来各类型的总数
+    totals: HashMap<&'static str, u64>,
+}
+
+#[derive(Default)]
+struct Counter {
+    logged: u32,
+    suppressed: u64,
+}
+
+impl LogLimiter {
+    pub fn new() -> Self {
+        let inner = Arc::new(Mutex::new(Inner::default()));
+        task::spawn("log summary", summary_task(inner.clone()));
+        Self { inner }
+    }
+    /// 记录一次异常，返回是否需要输出日志
+    pub fn check(&self, ip: IpAddr, kind: &'static str) -> bool {
+        let mut guard = self.inner.lock();
+        let guard = &mut *guard;
+        *guard.totals.entry(kind).or_default() += 1;
+        let len = guard.sources.len();
+        match guard.sources.get_mut(&(ip, kind)) {
+            Some(counter) => {
+                if counter.logged < BURST {
+                    counter.logged += 1;
+                    true
+                } else {
+                    counter.suppressed += 1;
+                    false
+                }
+            }
+            None if len < MAX_SOURCES => {
+                guard.sources.insert(
+                    (ip, kind),
+                    Counter {
+                        logged: 1,
+                        suppressed: 0,
+                    },
+                );
+                true
+            }
+            None => {
+                *guard.overflow.entry(kind).or_default() += 1;
+                false
+            }
+        }
+    }
+    /// 启动以来该类型异常的总数
+    pub fn total(&self, kind: &'static str) -> u64 {
+        self.inner.lock().totals.get(kind).copied().unwrap_or(0)
+    }
+    /// 启动以来各类型异常的总数
+    #[cfg(feature = "web")]
+    pub fn totals(&self) -> Vec<(&'static str, u64)> {
+        let mut totals: Vec<_> = self
+            .inner
+            .lock()
+            .totals
+            .iter()
+            .map(|(kind, count)| (*kind, *count))
+            .collect();
+        totals.sort();
+        totals
+    }
+}
+
+async fn summary_task(inner: Arc<Mutex<Inner>>) {
+    loop {
+        tokio::time::sleep(PERIOD).await;
+        let (sources, overflow) = {
+            let mut guard = inner.lock();
+            (
+                std::mem::take(&mut guard.sources),
+                std::mem::take(&mut guard.overflow),
+            )
+        };
+        for ((ip, kind), counter) in sources {
+            if counter.suppressed > 0 {
+                log::warn!(
+                    "{} {} from {} in last {}s",
+                    counter.suppressed,
+                    kind,
+                    ip,
+                    PERIOD.as_secs()
+                );
+            }
+        }
+        for (kind, count) in overflow {
+            log::warn!(
+                "{} {} from untracked sources in last {}s",
+                count,
+                kind,
+                PERIOD.as_secs()
+            );
+        }
+    }
+}
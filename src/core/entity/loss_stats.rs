@@ -0,0 +1,200 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// 丢包率的统计周期
+const LOSS_WINDOW: Duration = Duration::from_secs(60);
+/// 序号跳变超过该值时认为客户端重新开始计数，不计为丢包
+const MAX_SEQUENCE_JUMP: i64 = 1 << 16;
+
+/// 按中继数据附带的序号统计的客户端和服务端之间的丢包，注册时协商了序号的设备才有
+#[derive(Default)]
+pub struct LossStats {
+    // 服务端中继给该设备的数据的下一个序号
+    next_sequence: AtomicU32,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    // 收到的最大序号，扩展为64位处理回绕
+    highest: Option<i64>,
+    // 当前统计周期的开始时间、应收和实收数量
+    window_start: Option<Instant>,
+    expected: u64,
+    received: u64,
+    // 上一个完整统计周期的上行丢包率
+    last_up: Option<f64>,
+    // 客户端上一次上报的累计接收和丢失数量
+    last_report: Option<(u64, u64)>,
+    // 两次上报之间的下行丢包率
+    down: Option<f64>,
+}
+
+impl LossStats {
+    /// 服务端中继给该设备的数据附带的序号
+    pub fn next_sequence(&self) -> u32 {
+        self.next_sequence.fetch_add(1, Ordering::Relaxed)
+    }
+    /// 记录客户端发来的中继数据的序号
+    pub fn record(&self, sequence: u32) {
+        self.record_at(sequence, Instant::now())
+    }
+    fn record_at(&self, sequence: u32, now: Instant) {
+        let mut inner = self.inner.lock();
+        let window_start = *inner.window_start.get_or_insert(now);
+        if now.duration_since(window_start) >= LOSS_WINDOW {
+            inner.last_up = loss_rate(inner.expected, inner.received);
+            inner.window_start = Some(now);
+            inner.expected = 0;
+            inner.received = 0;
+        }
+        let Some(highest) = inner.highest else {
+            inner.highest = Some(sequence as i64);
+            inner.expected += 1;
+            inner.received += 1;
+            return;
+        };
+        let diff = sequence.wrapping_sub(highest as u32) as i32 as i64;
+        if !(-MAX_SEQUENCE_JUMP..=MAX_SEQUENCE_JUMP).contains(&diff) {
+            // 客户端重启了计数
+            inner.highest = Some(sequence as i64);
+            inner.expected += 1;
+        } else if diff > 0 {
+            inner.highest = Some(highest + diff);
+            inner.expected += diff as u64;
+        }
+        // 乱序或重复到达的数据只计入实收
+        inner.received += 1;
+    }
+    /// 客户端上报的按服务端序号统计的累计接收和丢失数量
+    pub fn report(&self, received: u64, lost: u64) {
+        let mut inner = self.inner.lock();
+        if let Some((last_received, last_lost)) = inner.last_report {
+            if received >= last_received && lost >= last_lost {
+                let lost = lost - last_lost;
+                inner.down = loss_rate(received - last_received + lost, received - last_received);
+            }
+        }
+        inner.last_report = Some((received, lost));
+    }
+    /// 上行丢包率(客户端到服务端)，百分比，统计周期内没有数据时为None
+    #[cfg(feature = "web")]
+    pub fn up_loss(&self) -> Option<f64> {
+        self.up_loss_at(Instant::now())
+    }
+    #[cfg(feature = "web")]
+    fn up_loss_at(&self, now: Instant) -> Option<f64> {
+        let inner = self.inner.lock();
+        let expired = inner
+            .window_start
+            .is_some_and(|start| now.duration_since(start) >= LOSS_WINDOW * 2);
+        if expired {
+            return None;
+        }
+        inner
+            .last_up
+            .or_else(|| loss_rate(inner.expected, inner.received))
+    }
+    /// 下行丢包率(服务端到客户端)，百分比，客户端还没有上报时为None
+    #[cfg(feature = "web")]
+    pub fn down_loss(&self) -> Option<f64> {
+        self.inner.lock().down
+    }
+}
+
+fn loss_rate(expected: u64, received: u64) -> Option<f64> {
+    if expected == 0 {
+        return None;
+    }
+    let lost = expected.saturating_sub(received);
+    Some(lost as f64 * 100.0 / expected as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 当前统计周期的(应收,实收)
+    fn counts(stats: &LossStats) -> (u64, u64) {
+        let inner = stats.inner.lock();
+        (inner.expected, inner.received)
+    }
+
+    fn record_all(stats: &LossStats, sequences: &[u32], now: Instant) {
+        for &sequence in sequences {
+            stats.record_at(sequence, now);
+        }
+    }
+
+    #[test]
+    fn next_sequence_increments() {
+        let stats = LossStats::default();
+        assert_eq!(stats.next_sequence(), 0);
+        assert_eq!(stats.next_sequence(), 1);
+    }
+
+    #[test]
+    fn gaps_count_as_lost() {
+        let stats = LossStats::default();
+        record_all(&stats, &[10, 11, 14, 15], Instant::now());
+        assert_eq!(counts(&stats), (6, 4));
+        assert_eq!(loss_rate(6, 4).map(|v| v.round()), Some(33.0));
+    }
+
+    #[test]
+    fn reordered_and_duplicate_packets() {
+        let stats = LossStats::default();
+        // 乱序到达的数据补上之前的空缺
+        record_all(&stats, &[1, 3, 2, 4], Instant::now());
+        assert_eq!(counts(&stats), (4, 4));
+        // 重复的数据只计入实收，丢包率不会小于0
+        stats.record_at(4, Instant::now());
+        assert_eq!(counts(&stats), (4, 5));
+        assert_eq!(loss_rate(4, 5), Some(0.0));
+    }
+
+    #[test]
+    fn sequence_wraps_around() {
+        let stats = LossStats::default();
+        record_all(&stats, &[u32::MAX - 1, u32::MAX, 1], Instant::now());
+        assert_eq!(counts(&stats), (4, 3));
+    }
+
+    #[test]
+    fn large_jump_restarts_counting() {
+        let stats = LossStats::default();
+        record_all(&stats, &[1_000_000, 0, 1], Instant::now());
+        assert_eq!(counts(&stats), (3, 3));
+    }
+
+    #[test]
+    fn report_uses_difference_between_reports() {
+        let stats = LossStats::default();
+        stats.report(100, 10);
+        assert_eq!(stats.inner.lock().down, None);
+        stats.report(190, 20);
+        assert_eq!(stats.inner.lock().down, Some(10.0));
+        // 客户端重启后累计数量变小，不计算丢包率
+        stats.report(5, 0);
+        assert_eq!(stats.inner.lock().down, Some(10.0));
+        stats.report(15, 0);
+        assert_eq!(stats.inner.lock().down, Some(0.0));
+    }
+
+    #[cfg(feature = "web")]
+    #[test]
+    fn up_loss_by_window() {
+        let start = Instant::now();
+        let stats = LossStats::default();
+        assert_eq!(stats.up_loss_at(start), None);
+        record_all(&stats, &[0, 1, 3], start);
+        assert_eq!(stats.up_loss_at(start), Some(25.0));
+        // 进入下一个统计周期后显示上一个完整周期的丢包率
+        record_all(&stats, &[4, 5], start + LOSS_WINDOW);
+        assert_eq!(stats.up_loss_at(start + LOSS_WINDOW), Some(25.0));
+        // 长时间没有数据
+        assert_eq!(stats.up_loss_at(start + LOSS_WINDOW * 3), None);
+    }
+}
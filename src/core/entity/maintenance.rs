@@ -0,0 +1,30 @@
+use std::time::Instant;
+
+use chrono::{DateTime, Local};
+
+/// 维护模式下建议客户端重试注册的默认间隔，秒
+pub const DEFAULT_RETRY_AFTER: u32 = 60;
+
+/// 维护模式，拒绝新的注册并告知客户端稍后重试，已在线的设备继续转发
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "web"), allow(dead_code))]
+pub struct Maintenance {
+    // 建议客户端重试注册的间隔，秒
+    pub retry_after: u32,
+    // 展示给客户端的说明，为空时使用默认的错误信息
+    pub message: Option<String>,
+    // 开启时间
+    pub since: DateTime<Local>,
+}
+
+/// 组迁移到其他服务器，组内设备收到重定向后连接新的服务器
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "web"), allow(dead_code))]
+pub struct Migration {
+    // 新服务器的地址，host:port
+    pub address: String,
+    // 开始时间
+    pub since: DateTime<Local>,
+    // 超过这个时间仍未迁移的设备不再等待，直接释放组
+    pub deadline: Instant,
+}
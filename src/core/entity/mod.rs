@@ -1,8 +1,54 @@
 use chrono::{DateTime, Local};
+use rand::Rng;
+use sha2::Digest;
 use std::collections::HashMap;
-use std::net::{Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::Sender;
 
+use crate::protocol::body::AES_GCM_ENCRYPTION_RESERVED;
+use crate::protocol::{HEAD_LEN, SEQUENCE_LEN};
+
+mod address_usage;
+mod app_service;
+mod client_config;
+mod device_list;
+mod local_addr;
+mod log_limiter;
+mod loss_stats;
+mod maintenance;
+mod path_mtu;
+mod peer_stats;
+mod reach_probe;
+mod relay_queue;
+mod suspicious;
+mod tag_rule;
+mod token_bucket;
+
+pub use address_usage::AddressChurn;
+pub use app_service::{parse_services, AppService};
+pub use client_config::{default_config, effective_config, recommend_mtu, ClientConfig};
+pub use device_list::{device_info_of, DeviceListCache};
+pub use local_addr::{infer_hairpin, local_path, no_hairpin, parse_local_addrs, LocalAddr};
+pub use log_limiter::{LogLimiter, CLOCK_SKEW, HANDSHAKE_FAILURES, TOKEN_ERRORS};
+pub use loss_stats::LossStats;
+pub use maintenance::{Maintenance, Migration, DEFAULT_RETRY_AFTER};
+pub use path_mtu::PathMtu;
+pub use peer_stats::PeerStats;
+pub use reach_probe::ReachProbes;
+#[cfg(feature = "web")]
+pub use reach_probe::ReachResult;
+pub use relay_queue::RelayQueue;
+pub use suspicious::{BlockPolicy, SuspiciousSources, MALFORMED_PACKETS, UNKNOWN_PACKETS};
+pub use tag_rule::{check_tags, relay_allowed, send_allowed, Flow, SendRule, TagRule};
+pub use token_bucket::TokenBucket;
+
+/// 同一设备重复注册时沿用上一次分配结果的时长
+const REGISTRATION_GUARD: Duration = Duration::from_secs(3);
+
 /// 网段信息
 #[derive(Default)]
 pub struct NetworkInfo {
@@ -14,30 +60,634 @@ pub struct NetworkInfo {
     pub mask_ip: u32,
     // 网关
     pub gateway_ip: u32,
-    // 纪元号
-    pub epoch: u64,
+    // 纪元号，组内设备变化时加1，通过bump_epoch修改
+    epoch: u32,
+    // 设备状态的纪元号，NAT类型或p2p连接变化时加1，通过bump_status_epoch修改
+    status_epoch: u32,
     // 网段下的客户端列表 ip->ClientInfo
     pub clients: HashMap<u32, ClientInfo>,
+    // 组策略
+    pub policy: GroupPolicy,
+    // 上一次分配的ip
+    pub last_allocated: u32,
+    // 发生冲突的ip -> 保留该ip的设备id
+    pub ip_conflicts: HashMap<u32, String>,
+    // 组内中继流量共享的令牌桶
+    pub relay_limiter: Option<TokenBucket>,
+    // 组内广播投递次数的令牌桶
+    pub broadcast_limiter: Option<TokenBucket>,
+    // 反射mDNS/SSDP发现数据包的令牌桶，为None时不反射
+    pub discovery_limiter: Option<TokenBucket>,
+    // 等待中继发送的数据
+    pub relay_queue: RelayQueue,
+    // 设备之间的中继和p2p统计
+    pub peer_stats: PeerStats,
+    // 等待对方响应的tcp打洞请求 (发起方,目标) -> (端口信息,请求时间)
+    pub tcp_punch: HashMap<(u32, u32), (TcpPunchInfo, Instant)>,
+    // 按纪元号缓存的设备列表编码
+    pub device_list: DeviceListCache,
+    // 启动以来组内中继的字节数，包括广播
+    pub relay_bytes: AtomicU64,
+    // 发给网关的广播被丢弃的数量
+    pub raw_broadcast_dropped: AtomicU64,
+    // 超出接收设备数量上限或每秒投递次数上限被丢弃的广播数量
+    pub broadcast_dropped: AtomicU64,
+    // 反射给组内其他设备的mDNS/SSDP数据包数量
+    pub discovery_reflected: AtomicU64,
+    // 超出每秒反射数量上限被丢弃的mDNS/SSDP数据包数量
+    pub discovery_dropped: AtomicU64,
+    // 不符合发送规则被丢弃的数量
+    pub send_rule_dropped: AtomicU64,
+    // ip的分配和回收次数
+    pub address_churn: AddressChurn,
+    // 管理员下发给组内所有设备的配置
+    pub client_config: Option<ClientConfig>,
+    // 迁移到其他服务器，迁移期间的注册请求回应重定向
+    pub migration: Option<Migration>,
+    // 设备id -> (刚分配的ip，注册时间)，短时间内重复注册时沿用
+    pub recent_registrations: HashMap<String, (u32, Instant)>,
 }
 
 impl NetworkInfo {
-    pub fn new(network_ip: u32, mask_ip: u32, gateway_ip: u32) -> Self {
+    pub fn new(network_ip: u32, mask_ip: u32, gateway_ip: u32, policy: GroupPolicy) -> Self {
         Self {
             network_ip,
             mask_ip,
             gateway_ip,
-            epoch: 0,
+            epoch: initial_epoch(),
+            status_epoch: 0,
             clients: Default::default(),
+            last_allocated: 0,
+            ip_conflicts: Default::default(),
+            relay_limiter: policy.relay_bandwidth.map(|v| TokenBucket::new(v.0)),
+            broadcast_limiter: policy.broadcast_rate.map(TokenBucket::new),
+            discovery_limiter: policy.discovery_reflect.map(TokenBucket::new),
+            relay_queue: Default::default(),
+            peer_stats: Default::default(),
+            tcp_punch: Default::default(),
+            device_list: Default::default(),
+            relay_bytes: AtomicU64::new(0),
+            raw_broadcast_dropped: AtomicU64::new(0),
+            broadcast_dropped: AtomicU64::new(0),
+            discovery_reflected: AtomicU64::new(0),
+            discovery_dropped: AtomicU64::new(0),
+            send_rule_dropped: AtomicU64::new(0),
+            address_churn: Default::default(),
+            client_config: None,
+            migration: None,
+            recent_registrations: Default::default(),
+            policy,
+        }
+    }
+    /// 按组的发送规则检查设备发出的ipv4数据，不允许时计数
+    ///
+    /// ipv4为None时表示数据无法解析(例如客户端间加密)
+    pub fn send_allowed(&self, source: u32, destination: Ipv4Addr, ipv4: Option<&[u8]>) -> bool {
+        let rules = &self.policy.send_rules[..];
+        if rules.is_empty() {
+            return true;
+        }
+        let from = self
+            .clients
+            .get(&source)
+            .map(|v| &v.tags[..])
+            .unwrap_or_default();
+        let flow = ipv4.and_then(Flow::parse);
+        if send_allowed(rules, from, destination, flow) {
+            return true;
+        }
+        self.send_rule_dropped.fetch_add(1, Ordering::Relaxed);
+        log::debug!(
+            "不符合发送规则 source={},destination={},{:?}",
+            Ipv4Addr::from(source),
+            destination,
+            flow
+        );
+        false
+    }
+    /// 纪元号，用于客户端判断设备列表是否变化
+    ///
+    /// 纪元号按u32回绕递增，只表示是否变化，客户端只应该比较是否相等，不能比较大小
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+    /// pong中的纪元号只有16位，取纪元号的低16位，同样只比较是否相等
+    pub fn pong_epoch(&self) -> u16 {
+        self.epoch as u16
+    }
+    /// 设备在短时间内重复注册时返回刚分配的ip，请求了其他ip时按新的注册处理
+    pub fn recent_registration(&mut self, device_id: &str, request_ip: u32) -> Option<u32> {
+        self.recent_registrations
+            .retain(|_, (_, time)| time.elapsed() < REGISTRATION_GUARD);
+        let (ip, _) = *self.recent_registrations.get(device_id)?;
+        if request_ip != 0 && request_ip != ip {
+            return None;
+        }
+        self.clients
+            .get(&ip)
+            .is_some_and(|x| x.device_id == device_id)
+            .then_some(ip)
+    }
+    /// 组内设备有变化
+    pub fn bump_epoch(&mut self) {
+        self.epoch = self.epoch.wrapping_add(1);
+    }
+    /// 设备状态的纪元号，设备列表中的NAT类型变化时改变，只比较是否相等
+    pub fn status_epoch(&self) -> u32 {
+        self.status_epoch
+    }
+    /// 设备的NAT类型或p2p连接有变化
+    pub fn bump_status_epoch(&mut self) {
+        self.status_epoch = self.status_epoch.wrapping_add(1);
+    }
+    /// 恢复保存的纪元号
+    ///
+    /// 保存之后到停止之前可能还有变化，恢复时向后跳过一段，保证和客户端缓存的值不同，包括pong中的低16位
+    pub fn restore_epoch(&mut self, epoch: u32) {
+        self.epoch = epoch.wrapping_add(EPOCH_RESTORE_GAP);
+    }
+    /// 主地址池
+    pub fn primary_pool(&self) -> AddressPool {
+        AddressPool {
+            gateway: self.gateway_ip,
+            netmask: self.mask_ip,
+        }
+    }
+    /// 所有地址池，按分配顺序排列
+    pub fn pools(&self) -> impl Iterator<Item = AddressPool> + '_ {
+        std::iter::once(self.primary_pool()).chain(self.policy.extra_pools.iter().copied())
+    }
+    /// ip可分配时所在的地址池
+    pub fn pool_of(&self, ip: u32) -> Option<AddressPool> {
+        self.pools().find(|pool| pool.is_assignable(ip))
+    }
+    /// 是否是某个地址池的广播地址
+    pub fn is_broadcast(&self, ip: u32) -> bool {
+        self.pools().any(|pool| pool.broadcast() == ip)
+    }
+    /// 是否是某个地址池的网关
+    pub fn is_gateway(&self, ip: u32) -> bool {
+        self.pools().any(|pool| pool.gateway == ip)
+    }
+    /// 按组的分配策略挑选一个未使用的ip，地址池按顺序使用
+    pub fn allocate_ip(&mut self, device_id: &str) -> Option<u32> {
+        let pools: Vec<AddressPool> = self.pools().collect();
+        pools
+            .into_iter()
+            .find_map(|pool| self.allocate_pool_ip(pool, device_id))
+    }
+    fn allocate_pool_ip(&mut self, pool: AddressPool, device_id: &str) -> Option<u32> {
+        let ip_range = pool.ip_range();
+        let len = ip_range.end.checked_sub(ip_range.start)?;
+        if len == 0 {
+            return None;
+        }
+        let offset = match self.policy.ip_allocation {
+            IpAllocation::FirstFree => 0,
+            IpAllocation::Random => rand::thread_rng().gen_range(0..len),
+            IpAllocation::Hash => {
+                let hash: [u8; 32] = sha2::Sha256::digest(device_id.as_bytes()).into();
+                u32::from_be_bytes(hash[..4].try_into().unwrap()) % len
+            }
+            IpAllocation::Sequential => {
+                if ip_range.contains(&self.last_allocated) {
+                    (self.last_allocated - ip_range.start + 1) % len
+                } else {
+                    0
+                }
+            }
+        };
+        for i in 0..len {
+            let ip = ip_range.start + (offset + i) % len;
+            if !self.is_free(pool, ip) {
+                continue;
+            }
+            self.last_allocated = ip;
+            return Some(ip);
+        }
+        None
+    }
+    /// ip是否可以分配，不能是网关、网络地址和广播地址，并且没有被占用、冲突或保留
+    pub fn is_free(&self, pool: AddressPool, ip: u32) -> bool {
+        pool.is_assignable(ip)
+            && !self.clients.contains_key(&ip)
+            && !self.ip_conflicts.contains_key(&ip)
+            && !self.policy.is_reserved(ip)
+    }
+    /// 可分配的ip数量，不包括保留的ip
+    pub fn capacity(&self) -> u64 {
+        let mut capacity = 0;
+        for pool in self.pools() {
+            let range = pool.ip_range();
+            // 保留范围可能重叠，裁剪到地址池内后合并，避免重复扣除
+            let mut reserved: Vec<(u32, u32)> = self
+                .policy
+                .reserved_ips
+                .iter()
+                .map(|reserved| {
+                    (
+                        u32::from(reserved.start).max(range.start),
+                        u32::from(reserved.end).min(range.end - 1),
+                    )
+                })
+                .filter(|(start, end)| start <= end)
+                .collect();
+            reserved.sort_unstable();
+            let mut merged: Vec<(u32, u32)> = Vec::with_capacity(reserved.len());
+            for (start, end) in reserved {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+                    _ => merged.push((start, end)),
+                }
+            }
+            let reserved_count: u64 = merged
+                .iter()
+                .map(|(start, end)| (end - start) as u64 + 1)
+                .sum();
+            let gateway_reserved = merged
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&pool.gateway));
+            // 去掉网关，网关在保留范围内时已经扣除过
+            let excluded = reserved_count + u64::from(!gateway_reserved);
+            capacity += (range.len() as u64).saturating_sub(excluded);
+        }
+        capacity
+    }
+    /// ip使用率是否达到告警阈值，返回(已分配，可分配)
+    pub fn address_warning(&self) -> Option<(u64, u64)> {
+        let percent = self.policy.address_warning?;
+        let capacity = self.capacity();
+        // 离线但未过期的设备也占用ip
+        let used = self.clients.len() as u64;
+        (capacity > 0 && used * 100 >= capacity * percent as u64).then_some((used, capacity))
+    }
+    /// 组内设备增加或减少后检查ip使用率，达到告警阈值时输出一次日志，降到阈值以下后重新计算
+    pub fn check_address_usage(&mut self, group: &str) {
+        let warning = self.address_warning();
+        if let Some((used, capacity)) = warning {
+            if !self.address_churn.warned {
+                log::warn!(
+                    "组内ip使用率达到{}%,即将无法分配ip group={:?},used={},capacity={}",
+                    used * 100 / capacity,
+                    group,
+                    used,
+                    capacity
+                );
+            }
+        }
+        self.address_churn.warned = warning.is_some();
+    }
+    /// 组开启了mDNS/SSDP反射并且没有超出每秒的反射数量上限，同时计数
+    pub fn discovery_allowed(&self) -> bool {
+        let Some(limiter) = &self.discovery_limiter else {
+            return false;
+        };
+        let allowed = limiter.try_acquire(1);
+        let counter = if allowed {
+            &self.discovery_reflected
+        } else {
+            &self.discovery_dropped
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        allowed
+    }
+    /// 在线并且估计过时钟偏差的设备中，偏差超过max的数量和估计过的总数，没有超过的为None
+    #[cfg(feature = "web")]
+    pub fn clock_skew_warning(&self, max: Duration) -> Option<(usize, usize)> {
+        let max = max.as_millis() as i64;
+        let mut skewed = 0;
+        let mut measured = 0;
+        for skew in self
+            .clients
+            .values()
+            .filter(|v| v.online)
+            .filter_map(|v| v.meta.clock_skew)
+        {
+            measured += 1;
+            if skew.abs() > max {
+                skewed += 1;
+            }
+        }
+        (skewed > 0).then_some((skewed, measured))
+    }
+    /// 在线设备中客户端加密和未加密的数量，两者都不为0时组网被分割成互不可见的两部分
+    pub fn secret_partition(&self) -> Option<(usize, usize)> {
+        let mut secret = 0;
+        let mut plaintext = 0;
+        for client in self.clients.values().filter(|v| v.online) {
+            if client.client_secret {
+                secret += 1;
+            } else {
+                plaintext += 1;
+            }
+        }
+        if secret > 0 && plaintext > 0 {
+            Some((secret, plaintext))
+        } else {
+            None
+        }
+    }
+}
+
+/// 恢复纪元号时跳过的变化数，小于2^16并且不是2^16的因数，保证低16位也不同
+const EPOCH_RESTORE_GAP: u32 = 0x8001;
+
+/// 新建组的纪元号从当前时间(秒)开始，服务重启后没有保存纪元号时，也基本不会和客户端缓存的值相同
+fn initial_epoch() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|time| time.as_secs() as u32)
+        .unwrap_or(0)
+}
+
+/// 组策略
+#[derive(Clone, Debug, Default)]
+pub struct GroupPolicy {
+    // 要求客户端间加密
+    pub require_client_encryption: bool,
+    // 要求和服务端加密
+    pub require_server_encryption: bool,
+    // 网关响应ping的方式
+    pub gateway_icmp: GatewayIcmp,
+    // 发给网关的广播的处理方式
+    pub raw_broadcast: RawBroadcast,
+    // ip分配策略
+    pub ip_allocation: IpAllocation,
+    // 保留的ip段，不参与自动分配
+    pub reserved_ips: Vec<IpRange>,
+    // 主网段之外的地址池，主网段的地址用完后按顺序使用
+    pub extra_pools: Vec<AddressPool>,
+    // 设备数量上限
+    pub max_clients: Option<usize>,
+    // ip使用率达到该百分比时告警
+    pub address_warning: Option<u8>,
+    // 离线超过该时长的设备回收ip，不再受一天未使用回收的限制
+    pub reclaim_offline: Option<Duration>,
+    // 同一公网ip的设备数量上限
+    pub max_clients_per_ip: Option<usize>,
+    // 组内中继的总带宽上限
+    pub relay_bandwidth: Option<Bandwidth>,
+    // 单个广播包的接收设备数量上限
+    pub broadcast_fanout: Option<usize>,
+    // 组内每秒广播的投递次数上限
+    pub broadcast_rate: Option<u64>,
+    // 每秒最多反射的mDNS/SSDP数据包数量，为None时不反射
+    pub discovery_reflect: Option<u64>,
+    // 中继的ipv4数据包大小上限，超出时分片或者回应icmp需要分片
+    pub mtu: Option<Mtu>,
+    // 返回给客户端的错误信息的语言
+    pub lang: Lang,
+    // 按标签放行中继流量的规则
+    pub tag_rules: Vec<TagRule>,
+    // 限制设备发出的中继流量的规则
+    pub send_rules: Vec<SendRule>,
+    // 由服务端对中继数据解密后按接收方的会话密钥重新加密
+    pub relay_encryption: bool,
+    // 设备之间互相不可见，不中继设备之间的数据
+    pub client_isolation: bool,
+    // 下发给组内设备的默认配置，优先级低于管理员设置的配置
+    pub client_defaults: Option<ClientConfig>,
+}
+
+impl GroupPolicy {
+    pub fn is_reserved(&self, ip: u32) -> bool {
+        self.reserved_ips.iter().any(|range| range.contains(ip))
+    }
+}
+
+/// 地址池
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AddressPool {
+    // 网关
+    pub gateway: u32,
+    // 掩码
+    pub netmask: u32,
+}
+
+impl AddressPool {
+    pub fn network(&self) -> u32 {
+        self.gateway & self.netmask
+    }
+    pub fn broadcast(&self) -> u32 {
+        self.gateway | !self.netmask
+    }
+    /// 可分配的ip段
+    pub fn ip_range(&self) -> Range<u32> {
+        self.network() + 1..self.broadcast()
+    }
+    /// 是否可以分配给设备
+    pub fn is_assignable(&self, ip: u32) -> bool {
+        ip != self.gateway && self.ip_range().contains(&ip)
+    }
+}
+
+impl FromStr for AddressPool {
+    type Err = String;
+
+    /// 格式为 网关/掩码位数，例如 10.26.1.1/24
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (gateway, prefix) = s
+            .split_once('/')
+            .ok_or_else(|| format!("'{}' format: gateway/prefix", s))?;
+        let gateway = gateway
+            .trim()
+            .parse::<Ipv4Addr>()
+            .map_err(|e| format!("'{}' {}", s, e))?;
+        let prefix = prefix
+            .trim()
+            .parse::<u8>()
+            .map_err(|e| format!("'{}' {}", s, e))?;
+        if !(1..=30).contains(&prefix) {
+            return Err(format!("'{}' prefix must be 1-30", s));
+        }
+        let pool = AddressPool {
+            gateway: gateway.into(),
+            netmask: u32::MAX << (32 - prefix),
+        };
+        if !pool.ip_range().contains(&pool.gateway) {
+            return Err(format!("'{}' invalid gateway", s));
+        }
+        Ok(pool)
+    }
+}
+
+/// ip段，包含首尾
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IpRange {
+    pub start: Ipv4Addr,
+    pub end: Ipv4Addr,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: u32) -> bool {
+        u32::from(self.start) <= ip && ip <= u32::from(self.end)
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('-').unwrap_or((s, s));
+        let start = start
+            .trim()
+            .parse::<Ipv4Addr>()
+            .map_err(|e| format!("'{}' {}", s, e))?;
+        let end = end
+            .trim()
+            .parse::<Ipv4Addr>()
+            .map_err(|e| format!("'{}' {}", s, e))?;
+        if start > end {
+            return Err(format!("'{}' start > end", s));
+        }
+        Ok(IpRange { start, end })
+    }
+}
+
+/// 来源地址段，格式为 ip[/掩码位数]，支持ipv4和ipv6
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SourceNet {
+    pub ip: IpAddr,
+    pub prefix: u8,
+}
+
+impl SourceNet {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.ip, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for SourceNet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (ip, prefix) = s.split_once('/').unwrap_or((s, ""));
+        let ip = ip
+            .trim()
+            .parse::<IpAddr>()
+            .map_err(|e| format!("'{}' {}", s, e))?
+            .to_canonical();
+        let max = if ip.is_ipv4() { 32 } else { 128 };
+        let prefix = if prefix.is_empty() {
+            max
+        } else {
+            match prefix.trim().parse::<u8>() {
+                Ok(prefix) if prefix <= max => prefix,
+                _ => return Err(format!("'{}' prefix must be 0-{}", s, max)),
+            }
+        };
+        Ok(SourceNet { ip, prefix })
+    }
+}
+
+/// 网关响应ping的方式
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum GatewayIcmp {
+    /// 全部响应
+    #[default]
+    All,
+    /// 只响应组内设备
+    Members,
+    /// 不响应
+    Off,
+}
+
+impl FromStr for GatewayIcmp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "all" => Ok(GatewayIcmp::All),
+            "members" => Ok(GatewayIcmp::Members),
+            "off" => Ok(GatewayIcmp::Off),
+            _ => Err(format!("not match '{}', enum: all/members/off", s)),
+        }
+    }
+}
+
+/// 客户端没有使用广播封装，直接把目标为广播地址的数据包发给网关时的处理方式
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RawBroadcast {
+    /// 由服务端转为广播发给组内其他设备
+    #[default]
+    Relay,
+    /// 丢弃并计数
+    Drop,
+}
+
+impl FromStr for RawBroadcast {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "relay" => Ok(RawBroadcast::Relay),
+            "drop" => Ok(RawBroadcast::Drop),
+            _ => Err(format!("not match '{}', enum: relay/drop", s)),
+        }
+    }
+}
+
+/// 设备的来源地址变化时按会话id转移会话的方式
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AddrRebind {
+    /// 来源ip和端口任意变化都转移，适用于移动网络切换
+    Any,
+    /// 只在公网ip不变、端口变化时转移，适用于按连接改写来源端口的运营商级nat
+    #[default]
+    Port,
+    /// 不转移，来源地址变化后设备需要重新注册
+    Off,
+}
+
+impl FromStr for AddrRebind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "any" => Ok(AddrRebind::Any),
+            "port" => Ok(AddrRebind::Port),
+            "off" => Ok(AddrRebind::Off),
+            _ => Err(format!("not match '{}', enum: any/port/off", s)),
+        }
+    }
+}
+
+/// 返回给客户端的错误信息的语言
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Lang {
+    /// 英文
+    #[default]
+    En,
+    /// 中文
+    Zh,
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "en" => Ok(Lang::En),
+            "zh" => Ok(Lang::Zh),
+            _ => Err(format!("not match '{}', enum: en/zh", s)),
         }
     }
 }
 
-/// 客户端信息
+/// 客户端信息，广播和中继遍历时用到的字段直接存放，其余不常用的信息放在meta中
 pub struct ClientInfo {
     // 设备ID
     pub device_id: String,
-    // 版本
-    pub version: String,
     // 名称
     pub name: String,
     // 客户端间是否加密
@@ -52,16 +702,110 @@ pub struct ClientInfo {
     pub virtual_ip: u32,
     // 建立的tcp连接发送端
     pub tcp_sender: Option<Sender<Vec<u8>>>,
+    pub timestamp: i64,
+    // 设备标签
+    pub tags: Vec<String>,
+    // 中继数据附带序号时的丢包统计，没有协商序号时为None
+    pub loss: Option<Box<LossStats>>,
+    // 探测到的服务端到设备的路径mtu(udp数据大小)，没有探测结果时为None
+    pub path_mtu: Option<u16>,
+    pub meta: Box<ClientMeta>,
+}
+
+impl ClientInfo {
+    /// 中继给该设备的ipv4数据包大小上限，路径mtu减去协议头部、序号和服务端加密的开销
+    pub fn relay_mtu(&self) -> Option<u16> {
+        let mut overhead = HEAD_LEN;
+        if self.loss.is_some() {
+            overhead += SEQUENCE_LEN;
+        }
+        if self.server_secret {
+            overhead += AES_GCM_ENCRYPTION_RESERVED;
+        }
+        self.path_mtu.map(|mtu| mtu - overhead as u16)
+    }
+    /// 设备的候选地址和来源：注册的地址(public)、nat类型探测看到的端口(port_sample)、预测的下一个端口(predicted)
+    /// 和上报的本地地址(local)
+    ///
+    /// 设备经公网连接服务端时不包括私有网段等本地范围的地址，这些地址在服务端所在的网络中指向的是无关的主机
+    #[cfg(feature = "web")]
+    pub fn candidates(&self) -> Vec<(SocketAddr, &'static str)> {
+        let mut candidates = vec![(self.address, "public")];
+        let ip = self.address.ip();
+        for port in &self.meta.port_samples {
+            let addr = SocketAddr::new(ip, *port);
+            if !candidates.iter().any(|(v, _)| *v == addr) {
+                candidates.push((addr, "port_sample"));
+            }
+        }
+        if let Some(prediction) = self.meta.port_prediction().filter(|v| v.stride != 0) {
+            let addr = SocketAddr::new(
+                ip,
+                prediction.last_port.wrapping_add_signed(prediction.stride),
+            );
+            if !candidates.iter().any(|(v, _)| *v == addr) {
+                candidates.push((addr, "predicted"));
+            }
+        }
+        if self.meta.local_port != 0 {
+            let private_path =
+                matches!(ip.to_canonical(), IpAddr::V4(ip) if local_addr::is_local_scope(ip));
+            for local in &self.meta.local_addrs {
+                if !private_path && local_addr::is_local_scope(local.ip) {
+                    continue;
+                }
+                let addr = SocketAddr::new(local.ip.into(), self.meta.local_port);
+                if !candidates.iter().any(|(v, _)| *v == addr) {
+                    candidates.push((addr, "local"));
+                }
+            }
+        }
+        candidates
+    }
+}
+
+/// 客户端的不常用信息，只在注册、管理接口和定时任务中访问
+pub struct ClientMeta {
+    // 版本
+    pub version: String,
     pub client_status: Option<ClientStatusInfo>,
     pub last_join_time: DateTime<Local>,
-    pub timestamp: i64,
+    // 管理员重新分配了ip，重新注册时使用该ip
+    pub reassigned: bool,
+    // 服务器测得的延迟，毫秒
+    pub rtt: Option<u32>,
+    // 管理员设置了标签，注册时不使用客户端上报的标签
+    pub tags_assigned: bool,
+    // nat类型探测时服务器依次看到的来源端口，用于对称nat的端口预测
+    pub port_samples: Vec<u16>,
+    // 离线的时间，在线时为None
+    pub offline_since: Option<Instant>,
+    // 管理员设置了保护，离线多久都不回收ip
+    pub protected: bool,
+    // 管理员设置了名称，注册时不使用客户端上报的名称
+    pub name_assigned: bool,
+    // 管理员填写的备注
+    pub notes: String,
+    // 管理员下发给该设备的配置，覆盖组的配置
+    pub client_config: Option<ClientConfig>,
+    // 路径mtu探测的状态
+    pub mtu_probe: PathMtu,
+    // 上一次按会话id转移来源地址的时间
+    pub last_rebind: Option<Instant>,
+    // 按时间同步请求估计的设备时钟减服务端时钟，毫秒
+    pub clock_skew: Option<i64>,
+    // 设备登记的应用服务
+    pub services: Vec<AppService>,
+    // 注册时上报的本地网卡地址
+    pub local_addrs: Vec<LocalAddr>,
+    // 注册时上报的udp监听端口，0表示未知
+    pub local_port: u16,
 }
 
 impl Default for ClientInfo {
     fn default() -> Self {
         Self {
             device_id: "".to_string(),
-            version: "".to_string(),
             name: "".to_string(),
             client_secret: false,
             server_secret: false,
@@ -69,11 +813,87 @@ impl Default for ClientInfo {
             online: false,
             virtual_ip: 0,
             tcp_sender: None,
+            timestamp: 0,
+            tags: Vec::new(),
+            loss: None,
+            path_mtu: None,
+            meta: Box::default(),
+        }
+    }
+}
+
+impl Default for ClientMeta {
+    fn default() -> Self {
+        Self {
+            version: "".to_string(),
             client_status: None,
             last_join_time: Local::now(),
-            timestamp: 0,
+            reassigned: false,
+            rtt: None,
+            tags_assigned: false,
+            port_samples: Vec::new(),
+            offline_since: None,
+            protected: false,
+            name_assigned: false,
+            notes: String::new(),
+            client_config: None,
+            mtu_probe: PathMtu::default(),
+            last_rebind: None,
+            clock_skew: None,
+            services: Vec::new(),
+            local_addrs: Vec::new(),
+            local_port: 0,
+        }
+    }
+}
+
+/// 最多保留的端口样本数
+const MAX_PORT_SAMPLES: usize = 8;
+
+impl ClientMeta {
+    /// 记录nat类型探测看到的来源端口，重复的端口只记录一次
+    pub fn record_port(&mut self, port: u16) {
+        if self.port_samples.contains(&port) {
+            return;
+        }
+        if self.port_samples.len() >= MAX_PORT_SAMPLES {
+            self.port_samples.remove(0);
         }
+        self.port_samples.push(port);
     }
+    /// 按端口样本预测对称nat下一次分配的端口，至少需要两个样本
+    pub fn port_prediction(&self) -> Option<PortPrediction> {
+        let last_port = *self.port_samples.last()?;
+        let deltas: Vec<i16> = self
+            .port_samples
+            .windows(2)
+            .map(|w| w[1].wrapping_sub(w[0]) as i16)
+            .collect();
+        // 出现次数最多的差值作为步长，次数相同时取最近的
+        let (stride, matched) = deltas
+            .iter()
+            .map(|d| (*d, deltas.iter().filter(|v| *v == d).count()))
+            .max_by_key(|(_, count)| *count)?;
+        Some(PortPrediction {
+            last_port,
+            stride,
+            matched: matched as u8,
+            samples: self.port_samples.len() as u8,
+        })
+    }
+}
+
+/// 对称nat的端口预测，下一个端口约为 last_port + stride
+#[derive(Clone, Copy, Debug)]
+pub struct PortPrediction {
+    // 最后看到的端口
+    pub last_port: u16,
+    // 相邻端口的差值
+    pub stride: i16,
+    // 符合该步长的差值数量
+    pub matched: u8,
+    // 样本数量
+    pub samples: u8,
 }
 
 pub struct ClientStatusInfo {
@@ -82,6 +902,8 @@ pub struct ClientStatusInfo {
     pub down_stream: u64,
     pub is_cone: bool,
     pub update_time: DateTime<Local>,
+    // nat是否支持回环(hairpin)，由打洞结果推断，未知时为None
+    pub hairpin: Option<bool>,
 }
 
 impl Default for ClientStatusInfo {
@@ -92,6 +914,369 @@ impl Default for ClientStatusInfo {
             down_stream: 0,
             is_cone: false,
             update_time: Local::now(),
+            hairpin: None,
+        }
+    }
+}
+
+/// tcp打洞的端口信息
+#[derive(Clone, Debug, Default)]
+pub struct TcpPunchInfo {
+    pub public_ip_list: Vec<u32>,
+    pub tcp_port: u32,
+    pub public_ports: Vec<u32>,
+}
+
+/// ip分配策略
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum IpAllocation {
+    /// 从小到大找第一个未使用的ip
+    #[default]
+    FirstFree,
+    /// 随机挑选，更难猜测相邻设备
+    Random,
+    /// 按设备id哈希挑选，不依赖持久化也能尽量分配到相同的ip
+    Hash,
+    /// 从上一次分配的ip之后顺序挑选
+    Sequential,
+}
+
+impl FromStr for IpAllocation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "first-free" => Ok(IpAllocation::FirstFree),
+            "random" => Ok(IpAllocation::Random),
+            "hash" => Ok(IpAllocation::Hash),
+            "sequential" => Ok(IpAllocation::Sequential),
+            _ => Err(format!(
+                "not match '{}', enum: first-free/random/hash/sequential",
+                s
+            )),
+        }
+    }
+}
+
+/// 带宽，字节/秒
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Bandwidth(pub u64);
+
+impl FromStr for Bandwidth {
+    type Err = String;
+
+    /// 单位为字节/秒，支持K、M、G后缀，例如 512K、10M
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_bytes(s).map(Bandwidth)
+    }
+}
+
+/// ipv4数据包大小上限
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Mtu(pub u16);
+
+impl FromStr for Mtu {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mtu = s
+            .trim()
+            .parse::<u16>()
+            .map_err(|e| format!("'{}' {}", s, e))?;
+        // ipv4要求所有主机都能接收576字节的数据包
+        if mtu < 576 {
+            return Err(format!("'{}' must be at least 576", s));
+        }
+        Ok(Mtu(mtu))
+    }
+}
+
+/// 解析字节数，支持K、M、G后缀，例如 512K、10M
+pub fn parse_bytes(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (num, unit) = match s.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_uppercase()),
+        _ => (s, 'B'),
+    };
+    let unit: u64 = match unit {
+        'B' => 1,
+        'K' => 1024,
+        'M' => 1024 * 1024,
+        'G' => 1024 * 1024 * 1024,
+        _ => return Err(format!("'{}' unit must be K/M/G", s)),
+    };
+    let num = num
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("'{}' {}", s, e))?;
+    if num == 0 {
+        return Err(format!("'{}' must be greater than 0", s));
+    }
+    num.checked_mul(unit)
+        .ok_or_else(|| format!("'{}' too large", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 10.26.0.0/29，网关10.26.0.1，可分配10.26.0.2-10.26.0.6
+    const GATEWAY: u32 = 0x0A1A_0001;
+
+    fn ip(last: u8) -> u32 {
+        u32::from(Ipv4Addr::new(10, 26, 0, last))
+    }
+
+    fn network_info(policy: GroupPolicy) -> NetworkInfo {
+        NetworkInfo::new(GATEWAY & 0xFFFF_FFF8, 0xFFFF_FFF8, GATEWAY, policy)
+    }
+
+    fn reserved(ranges: &[&str]) -> GroupPolicy {
+        GroupPolicy {
+            reserved_ips: ranges.iter().map(|s| s.parse().unwrap()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// 分配ip直到用完
+    fn allocate_all(network_info: &mut NetworkInfo) -> Vec<u32> {
+        let mut allocated = Vec::new();
+        while let Some(virtual_ip) = network_info.allocate_ip("device") {
+            network_info.clients.insert(
+                virtual_ip,
+                ClientInfo {
+                    virtual_ip,
+                    ..Default::default()
+                },
+            );
+            allocated.push(virtual_ip);
+        }
+        allocated
+    }
+
+    #[test]
+    fn first_free_skips_used_addresses() {
+        let mut network_info = network_info(GroupPolicy::default());
+        assert_eq!(network_info.capacity(), 5);
+        network_info.clients.insert(ip(2), ClientInfo::default());
+        network_info.ip_conflicts.insert(ip(3), "other".into());
+        assert_eq!(allocate_all(&mut network_info), [ip(4), ip(5), ip(6)]);
+    }
+
+    #[test]
+    fn reserved_addresses_at_edges() {
+        // 保留范围覆盖整个地址池
+        let mut full = network_info(reserved(&["10.0.0.0-10.255.255.255"]));
+        assert_eq!(full.capacity(), 0);
+        assert_eq!(full.allocate_ip("device"), None);
+        // 保留范围包含网关、网络地址和最后一个可分配地址
+        let mut network_info = network_info(reserved(&["10.26.0.0-10.26.0.2", "10.26.0.6"]));
+        assert_eq!(network_info.capacity(), 3);
+        assert_eq!(allocate_all(&mut network_info), [ip(3), ip(4), ip(5)]);
+    }
+
+    #[test]
+    fn overlapping_reserved_ranges_are_counted_once() {
+        let policy = reserved(&[
+            "10.26.0.2-10.26.0.4",
+            "10.26.0.3-10.26.0.5",
+            "10.26.0.4",
+            // 地址池之外的范围不影响容量
+            "10.26.1.0-10.26.1.255",
+        ]);
+        let mut network_info = network_info(policy);
+        assert_eq!(network_info.capacity(), 1);
+        assert_eq!(allocate_all(&mut network_info), [ip(6)]);
+    }
+
+    #[test]
+    fn pools_are_used_in_order_until_exhausted() {
+        let policy = GroupPolicy {
+            extra_pools: vec!["10.26.1.1/29".parse().unwrap()],
+            ..reserved(&["10.26.1.2-10.26.1.5"])
+        };
+        let mut network_info = network_info(policy);
+        // 主地址池5个，额外地址池只剩10.26.1.6可分配
+        assert_eq!(network_info.capacity(), 6);
+        let allocated = allocate_all(&mut network_info);
+        assert_eq!(allocated.len(), 6);
+        assert_eq!(allocated[5], u32::from(Ipv4Addr::new(10, 26, 1, 6)));
+        assert_eq!(network_info.allocate_ip("device"), None);
+    }
+
+    #[test]
+    fn allocation_strategies_stay_in_range() {
+        for ip_allocation in [
+            IpAllocation::FirstFree,
+            IpAllocation::Random,
+            IpAllocation::Hash,
+            IpAllocation::Sequential,
+        ] {
+            let policy = GroupPolicy {
+                ip_allocation,
+                ..reserved(&["10.26.0.4"])
+            };
+            let mut allocated = allocate_all(&mut network_info(policy));
+            allocated.sort_unstable();
+            assert_eq!(
+                allocated,
+                [ip(2), ip(3), ip(5), ip(6)],
+                "{:?}",
+                ip_allocation
+            );
         }
     }
+
+    #[test]
+    fn sequential_and_hash_allocation() {
+        let mut network_info = network_info(GroupPolicy {
+            ip_allocation: IpAllocation::Sequential,
+            ..Default::default()
+        });
+        network_info.last_allocated = ip(5);
+        assert_eq!(network_info.allocate_ip("a"), Some(ip(6)));
+        // 到末尾后从头开始，跳过网关
+        assert_eq!(network_info.allocate_ip("a"), Some(ip(2)));
+
+        let mut network_info = network_info_with(IpAllocation::Hash);
+        let first = network_info.allocate_ip("device-a");
+        assert_eq!(
+            network_info_with(IpAllocation::Hash).allocate_ip("device-a"),
+            first
+        );
+        network_info
+            .clients
+            .insert(first.unwrap(), ClientInfo::default());
+        // 哈希到的ip被占用时顺延
+        assert_ne!(network_info.allocate_ip("device-a"), first);
+    }
+
+    fn network_info_with(ip_allocation: IpAllocation) -> NetworkInfo {
+        network_info(GroupPolicy {
+            ip_allocation,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn is_free_matches_allocation_checks() {
+        let mut network_info = network_info(reserved(&["10.26.0.5"]));
+        network_info.clients.insert(ip(2), ClientInfo::default());
+        network_info.ip_conflicts.insert(ip(3), "other".into());
+        let pool = network_info.primary_pool();
+        let free: Vec<u32> = (0..=7)
+            .map(ip)
+            .filter(|v| network_info.is_free(pool, *v))
+            .collect();
+        assert_eq!(free, [ip(4), ip(6)]);
+    }
+
+    #[cfg(feature = "web")]
+    #[test]
+    fn candidates_skip_local_scope_over_public_path() {
+        let mut client_info = ClientInfo {
+            address: "203.0.113.5:4000".parse().unwrap(),
+            ..Default::default()
+        };
+        client_info.meta.local_port = 5000;
+        client_info.meta.local_addrs = ["192.168.1.5", "100.64.0.7", "198.51.100.9"]
+            .iter()
+            .map(|ip| LocalAddr {
+                ip: ip.parse().unwrap(),
+                prefix_len: 24,
+            })
+            .collect();
+        let local = |client_info: &ClientInfo| -> Vec<SocketAddr> {
+            client_info
+                .candidates()
+                .into_iter()
+                .filter(|(_, source)| *source == "local")
+                .map(|(addr, _)| addr)
+                .collect()
+        };
+        assert_eq!(local(&client_info), ["198.51.100.9:5000".parse().unwrap()]);
+        // 设备经私有网络连接服务端时探测所有本地地址
+        client_info.address = "192.168.1.5:4000".parse().unwrap();
+        assert_eq!(local(&client_info).len(), 3);
+    }
+
+    #[test]
+    fn parse_ranges_and_pools() {
+        let range: IpRange = "10.26.0.5".parse().unwrap();
+        assert!(range.contains(ip(5)) && !range.contains(ip(6)));
+        let range: IpRange = " 10.26.0.2 - 10.26.0.4 ".parse().unwrap();
+        assert!(range.contains(ip(2)) && range.contains(ip(4)) && !range.contains(ip(1)));
+        assert!("10.26.0.4-10.26.0.2".parse::<IpRange>().is_err());
+
+        let pool: AddressPool = "10.26.1.1/24".parse().unwrap();
+        assert_eq!(pool.network(), u32::from(Ipv4Addr::new(10, 26, 1, 0)));
+        assert_eq!(pool.broadcast(), u32::from(Ipv4Addr::new(10, 26, 1, 255)));
+        assert!(!pool.is_assignable(pool.gateway));
+        assert!(!pool.is_assignable(pool.network()));
+        assert!(!pool.is_assignable(pool.broadcast()));
+        // 网关不能是网络地址或广播地址，掩码最长30位
+        for s in [
+            "10.26.1.0/24",
+            "10.26.1.255/24",
+            "10.26.1.1/31",
+            "10.26.1.1/0",
+        ] {
+            assert!(s.parse::<AddressPool>().is_err(), "{}", s);
+        }
+    }
+
+    fn samples(ports: &[u16]) -> ClientMeta {
+        let mut meta = ClientMeta::default();
+        for port in ports {
+            meta.record_port(*port);
+        }
+        meta
+    }
+
+    #[test]
+    fn port_prediction_needs_two_samples() {
+        assert!(samples(&[]).port_prediction().is_none());
+        assert!(samples(&[4000]).port_prediction().is_none());
+        // 重复的端口只记录一次
+        assert!(samples(&[4000, 4000]).port_prediction().is_none());
+    }
+
+    #[test]
+    fn port_prediction_uses_most_common_stride() {
+        let prediction = samples(&[4000, 4002, 4004, 4010, 4012])
+            .port_prediction()
+            .unwrap();
+        assert_eq!(prediction.last_port, 4012);
+        assert_eq!(prediction.stride, 2);
+        assert_eq!((prediction.matched, prediction.samples), (3, 5));
+        // 次数相同时取最近的步长
+        let prediction = samples(&[4000, 4001, 4003]).port_prediction().unwrap();
+        assert_eq!((prediction.stride, prediction.matched), (2, 1));
+        // 递减的端口
+        let prediction = samples(&[5000, 4990, 4980]).port_prediction().unwrap();
+        assert_eq!(prediction.stride, -10);
+    }
+
+    #[test]
+    fn port_prediction_wraps_around() {
+        let prediction = samples(&[65534, 0, 2]).port_prediction().unwrap();
+        assert_eq!(prediction.stride, 2);
+        assert_eq!(
+            prediction.last_port.wrapping_add_signed(prediction.stride),
+            4
+        );
+    }
+
+    #[test]
+    fn port_samples_are_bounded() {
+        let ports: Vec<u16> = (0..MAX_PORT_SAMPLES as u16 + 3).map(|i| 4000 + i).collect();
+        let meta = samples(&ports);
+        assert_eq!(meta.port_samples.len(), MAX_PORT_SAMPLES);
+        assert_eq!(meta.port_samples[0], 4003);
+        let prediction = meta.port_prediction().unwrap();
+        assert_eq!(
+            (prediction.stride, prediction.samples),
+            (1, MAX_PORT_SAMPLES as u8)
+        );
+    }
 }
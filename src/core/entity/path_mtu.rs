@@ -0,0 +1,166 @@
+use std::time::{Duration, Instant};
+
+/// 探测的udp数据大小下限，ipv4的最小mtu 576减去ip和udp头部
+pub const MIN_PROBE_SIZE: u16 = 548;
+/// 探测的udp数据大小上限，以太网mtu 1500减去ip和udp头部
+pub const MAX_PROBE_SIZE: u16 = 1472;
+/// 查找范围小于该值时结束查找
+const PRECISION: u16 = 8;
+/// 查找结束后重新探测的间隔，路径可能变化
+const REPROBE_INTERVAL: Duration = Duration::from_secs(600);
+
+/// 服务端到设备的路径mtu探测，二分查找能到达设备的最大udp数据大小
+#[derive(Default)]
+pub struct PathMtu {
+    // 查找范围，low可以到达，high之上不能到达，不在查找时都为0
+    low: u16,
+    high: u16,
+    // 本次查找是否收到过回应，从未收到时说明客户端不支持探测
+    acked: bool,
+    // 等待回应的探测 (id,大小)
+    pending: Option<(u32, u16)>,
+    // 上一次查找结束的时间
+    settled: Option<Instant>,
+    // 查找结果，确认可以到达的最大udp数据大小
+    confirmed: Option<u16>,
+    // 查找结果变化后还没有被取走
+    changed: bool,
+    // 查找结果变化的时间，unix时间戳毫秒，作为推荐配置的版本
+    version: i64,
+}
+
+impl PathMtu {
+    /// 返回下一个要探测的大小，不需要探测时返回None
+    ///
+    /// 定时调用，上一个探测到这时还没有回应视为超出路径mtu
+    pub fn next_probe(&mut self, id: u32) -> Option<u16> {
+        if let Some((_, size)) = self.pending.take() {
+            self.high = size - 1;
+        }
+        if self.high == 0 {
+            if self
+                .settled
+                .is_some_and(|settled| settled.elapsed() < REPROBE_INTERVAL)
+            {
+                return None;
+            }
+            self.low = MIN_PROBE_SIZE;
+            self.high = MAX_PROBE_SIZE;
+            self.acked = false;
+        }
+        if self.high - self.low < PRECISION {
+            self.settle();
+            return None;
+        }
+        let size = self.low + (self.high - self.low).div_ceil(2);
+        self.pending = Some((id, size));
+        Some(size)
+    }
+    /// 收到探测回应，返回是否需要继续探测
+    pub fn reply(&mut self, id: u32) -> bool {
+        match self.pending {
+            Some((pending_id, size)) if pending_id == id => {
+                self.pending = None;
+                self.low = size;
+                self.acked = true;
+                true
+            }
+            _ => false,
+        }
+    }
+    /// 确认可以到达的最大udp数据大小
+    pub fn confirmed(&self) -> Option<u16> {
+        self.confirmed
+    }
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+    /// 取出查找结果的变化
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+    fn settle(&mut self) {
+        // 一直没有回应的客户端不支持探测，不记录结果
+        let confirmed = self.acked.then_some(self.low);
+        self.changed = confirmed != self.confirmed;
+        if self.changed {
+            self.version = chrono::Local::now().timestamp_millis();
+        }
+        self.confirmed = confirmed;
+        self.settled = Some(Instant::now());
+        self.low = 0;
+        self.high = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 模拟到路径mtu为path的设备的一次查找，返回探测次数
+    fn search(probe: &mut PathMtu, path: Option<u16>) -> u32 {
+        let mut id = 0;
+        while let Some(size) = probe.next_probe(id) {
+            if path.is_some_and(|path| size <= path) {
+                assert!(probe.reply(id));
+            }
+            id += 1;
+        }
+        id
+    }
+
+    #[test]
+    fn converges_below_path_mtu() {
+        for path in [MIN_PROBE_SIZE + PRECISION, 1200, 1400, 1452, MAX_PROBE_SIZE] {
+            let mut probe = PathMtu::default();
+            let probes = search(&mut probe, Some(path));
+            assert!(probes <= 8, "{} probes", probes);
+            let confirmed = probe.confirmed().unwrap();
+            assert!(
+                confirmed <= path && path - confirmed < PRECISION,
+                "{}",
+                path
+            );
+            assert!(probe.take_changed());
+            assert!(!probe.take_changed());
+        }
+    }
+
+    #[test]
+    fn no_reply_records_nothing() {
+        let mut probe = PathMtu::default();
+        assert!(search(&mut probe, None) > 0);
+        assert_eq!(probe.confirmed(), None);
+        assert!(!probe.take_changed());
+    }
+
+    #[test]
+    fn stale_or_unknown_reply_is_ignored() {
+        let mut probe = PathMtu::default();
+        let size = probe.next_probe(1).unwrap();
+        assert!(!probe.reply(2));
+        // 超时后视为超出路径mtu，迟到的回应被忽略
+        let next = probe.next_probe(3).unwrap();
+        assert!(next < size);
+        assert!(!probe.reply(1));
+        assert!(probe.reply(3));
+        assert!(!probe.reply(3));
+    }
+
+    #[test]
+    fn reprobes_after_interval() {
+        let mut probe = PathMtu::default();
+        search(&mut probe, Some(1400));
+        assert_eq!(probe.next_probe(100), None);
+        let Some(settled) = Instant::now().checked_sub(REPROBE_INTERVAL) else {
+            return;
+        };
+        probe.settled = Some(settled);
+        assert!(probe.next_probe(100).is_some());
+        // 路径变化后结果随之更新
+        search(&mut probe, Some(1000));
+        let confirmed = probe.confirmed().unwrap();
+        assert!(confirmed <= 1000 && 1000 - confirmed < PRECISION);
+        assert!(probe.take_changed());
+    }
+}
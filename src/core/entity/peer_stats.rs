@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// 设备两两之间的连接统计，用于诊断打洞总是失败的网络环境
+#[derive(Default)]
+pub struct PeerStats {
+    // (较小的ip,较大的ip) -> 连接统计
+    links: Mutex<HashMap<(u32, u32), PeerLink>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PeerLink {
+    // 经服务器中继的数据包数
+    pub relay_packets: u64,
+    // 经服务器中继的字节数
+    pub relay_bytes: u64,
+    // 最后一次中继的时间
+    pub last_relay: Option<Instant>,
+    // 状态上报中是p2p的次数
+    pub p2p_reports: u64,
+    // 状态上报中不是p2p，并且有中继流量的次数
+    pub relay_reports: u64,
+}
+
+impl PeerLink {
+    /// p2p成功率，没有上报时返回None
+    #[cfg(feature = "web")]
+    pub fn p2p_rate(&self) -> Option<f64> {
+        let total = self.p2p_reports + self.relay_reports;
+        if total == 0 {
+            None
+        } else {
+            Some(self.p2p_reports as f64 / total as f64)
+        }
+    }
+}
+
+fn key(a: u32, b: u32) -> (u32, u32) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+impl PeerStats {
+    /// 记录一次中继
+    pub fn relay(&self, source: u32, destination: u32, len: usize) {
+        let mut links = self.links.lock();
+        let link = links.entry(key(source, destination)).or_default();
+        link.relay_packets += 1;
+        link.relay_bytes += len as u64;
+        link.last_relay = Some(Instant::now());
+    }
+    /// 客户端上报状态时记录和其他设备之间是否是p2p
+    ///
+    /// 不在p2p列表中的设备，只有最近有中继流量才计为中继
+    pub fn report(&self, source: u32, p2p_list: &[Ipv4Addr]) {
+        let now = Instant::now();
+        let mut links = self.links.lock();
+        for ip in p2p_list {
+            let ip: u32 = (*ip).into();
+            if ip != source {
+                links.entry(key(source, ip)).or_default().p2p_reports += 1;
+            }
+        }
+        for ((a, b), link) in links.iter_mut() {
+            if *a != source && *b != source {
+                continue;
+            }
+            let peer = if *a == source { *b } else { *a };
+            if p2p_list.contains(&peer.into()) {
+                continue;
+            }
+            if link
+                .last_relay
+                .is_some_and(|time| now.duration_since(time) < Duration::from_secs(60))
+            {
+                link.relay_reports += 1;
+            }
+        }
+    }
+    /// 删除和该ip相关的统计
+    pub fn remove(&self, ip: u32) {
+        self.links.lock().retain(|(a, b), _| *a != ip && *b != ip);
+    }
+    #[cfg(feature = "web")]
+    pub fn links(&self) -> Vec<((u32, u32), PeerLink)> {
+        self.links
+            .lock()
+            .iter()
+            .map(|(k, v)| (*k, v.clone()))
+            .collect()
+    }
+}
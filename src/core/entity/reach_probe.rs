@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+#[cfg(feature = "web")]
+use std::net::SocketAddr;
+use std::sync::Arc;
+#[cfg(feature = "web")]
+use std::time::Duration;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+
+/// 候选地址的探测结果(地址，来源，延迟)，没有回应时延迟为None
+#[cfg(feature = "web")]
+pub type ReachResult = (SocketAddr, &'static str, Option<Duration>);
+
+// id -> (发送时间，收到回应的时间)
+type Pending = HashMap<u32, (Instant, Option<Instant>)>;
+
+/// 管理接口发起的可达性探测，探测复用路径mtu探测的报文，按id区分
+#[derive(Clone, Default)]
+pub struct ReachProbes {
+    inner: Arc<Mutex<Pending>>,
+}
+
+impl ReachProbes {
+    /// 登记一个已发送的探测
+    #[cfg(feature = "web")]
+    pub fn sent(&self, id: u32) {
+        self.inner.lock().insert(id, (Instant::now(), None));
+    }
+    /// 收到探测回应，返回是否是可达性探测的回应
+    pub fn reply(&self, id: u32) -> bool {
+        let mut inner = self.inner.lock();
+        if inner.is_empty() {
+            return false;
+        }
+        match inner.get_mut(&id) {
+            Some((_, replied)) => {
+                replied.get_or_insert_with(Instant::now);
+                true
+            }
+            None => false,
+        }
+    }
+    /// 结束探测，返回探测的延迟，没有回应时返回None
+    #[cfg(feature = "web")]
+    pub fn finish(&self, id: u32) -> Option<Duration> {
+        let (sent, replied) = self.inner.lock().remove(&id)?;
+        replied.map(|replied| replied.duration_since(sent))
+    }
+}
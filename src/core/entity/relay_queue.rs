@@ -0,0 +1,84 @@
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+
+use parking_lot::Mutex;
+
+/// 组内等待中继发送的数据，udp发送缓冲区满时使用
+#[derive(Default)]
+pub struct RelayQueue {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    packets: VecDeque<(Vec<u8>, SocketAddr)>,
+    // 队列占用的字节数
+    bytes: usize,
+    // 队列满丢弃的数据包数
+    dropped: u64,
+}
+
+impl RelayQueue {
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().packets.is_empty()
+    }
+    /// 加入队列，超出上限时丢弃
+    ///
+    /// 返回加入前队列是否为空，丢弃时返回None
+    pub fn push(&self, buf: Vec<u8>, addr: SocketAddr, limit: usize) -> Option<bool> {
+        let mut inner = self.inner.lock();
+        if inner.bytes + buf.len() > limit {
+            inner.dropped += 1;
+            return None;
+        }
+        let was_empty = inner.packets.is_empty();
+        inner.bytes += buf.len();
+        inner.packets.push_back((buf, addr));
+        Some(was_empty)
+    }
+    /// 取出一个数据包，同时返回队列中是否还有数据
+    pub fn pop(&self) -> Option<(Vec<u8>, SocketAddr, bool)> {
+        let mut inner = self.inner.lock();
+        let (buf, addr) = inner.packets.pop_front()?;
+        inner.bytes -= buf.len();
+        Some((buf, addr, !inner.packets.is_empty()))
+    }
+    /// 取出队首发往同一地址、长度相同的连续数据包，合并后用于分段发送，最后一个数据包可以更短
+    ///
+    /// 返回合并的数据、分段大小、目标地址，以及队列中是否还有数据
+    pub fn pop_segments(
+        &self,
+        max_segments: usize,
+        max_bytes: usize,
+    ) -> Option<(Vec<u8>, usize, SocketAddr, bool)> {
+        let mut inner = self.inner.lock();
+        let (mut buf, addr) = inner.packets.pop_front()?;
+        let segment_size = buf.len();
+        inner.bytes -= segment_size;
+        let mut count = 1;
+        while count < max_segments {
+            match inner.packets.front() {
+                Some((next, next_addr))
+                    if *next_addr == addr
+                        && !next.is_empty()
+                        && next.len() <= segment_size
+                        && buf.len() + next.len() <= max_bytes => {}
+                _ => break,
+            }
+            let (next, _) = inner.packets.pop_front().unwrap();
+            inner.bytes -= next.len();
+            buf.extend_from_slice(&next);
+            count += 1;
+            if next.len() < segment_size {
+                break;
+            }
+        }
+        Some((buf, segment_size, addr, !inner.packets.is_empty()))
+    }
+    /// 队列占用的字节数和丢弃的数据包数
+    #[cfg(feature = "web")]
+    pub fn stats(&self) -> (usize, u64) {
+        let inner = self.inner.lock();
+        (inner.bytes, inner.dropped)
+    }
+}
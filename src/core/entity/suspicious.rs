@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use parking_lot::Mutex;
+
+use crate::core::task;
+
+/// 统计封禁阈值的周期
+const PERIOD: Duration = Duration::from_secs(60);
+/// 来源超过这个时长没有异常且未被封禁时不再跟踪
+const RETAIN: Duration = Duration::from_secs(3600);
+/// 最多跟踪的来源数量，超出后新来源只由日志限流计数
+const MAX_SOURCES: usize = 4096;
+/// 畸形数据包
+pub const MALFORMED_PACKETS: &str = "malformed packets";
+/// 未知协议的数据包
+pub const UNKNOWN_PACKETS: &str = "unknown packets";
+
+/// 自动封禁的策略
+#[derive(Clone, Copy, Debug)]
+pub struct BlockPolicy {
+    // 每个周期内的异常次数达到该值时封禁，0表示只记录不封禁
+    pub threshold: u64,
+    // 封禁时长
+    pub duration: Duration,
+}
+
+impl Default for BlockPolicy {
+    fn default() -> Self {
+        Self {
+            threshold: 0,
+            duration: Duration::from_secs(600),
+        }
+    }
+}
+
+/// 可疑来源，持续发送畸形数据包或未知协议数据包的地址，超过阈值后临时封禁
+#[derive(Clone)]
+pub struct SuspiciousSources {
+    inner: Arc<Mutex<Inner>>,
+    // 当前封禁的来源数量，为0时收包路径不需要加锁检查
+    blocked: Arc<AtomicUsize>,
+}
+
+#[derive(Default)]
+struct Inner {
+    policy: BlockPolicy,
+    sources: HashMap<IpAddr, Source>,
+}
+
+#[cfg_attr(not(feature = "web"), allow(dead_code))]
+struct Source {
+    // 类型 -> 开始跟踪以来的次数
+    counts: HashMap<&'static str, u64>,
+    // 本周期的异常次数
+    window: u64,
+    window_start: Instant,
+    first_seen: DateTime<Local>,
+    last_seen: DateTime<Local>,
+    last_instant: Instant,
+    // 封禁到期时间
+    blocked_until: Option<Instant>,
+    // 被封禁的次数
+    blocks: u32,
+}
+
+/// 可疑来源的快照
+#[cfg(feature = "web")]
+pub struct SuspiciousSource {
+    pub ip: IpAddr,
+    pub counts: Vec<(&'static str, u64)>,
+    pub first_seen: DateTime<Local>,
+    pub last_seen: DateTime<Local>,
+    // 剩余封禁时长，未封禁时为None
+    pub blocked: Option<Duration>,
+    pub blocks: u32,
+}
+
+impl SuspiciousSources {
+    pub fn new() -> Self {
+        let inner = Arc::new(Mutex::new(Inner::default()));
+        let blocked = Arc::new(AtomicUsize::new(0));
+        task::spawn(
+            "suspicious sources",
+            cleanup_task(inner.clone(), blocked.clone()),
+        );
+        Self { inner, blocked }
+    }
+    pub fn set_policy(&self, policy: BlockPolicy) {
+        self.inner.lock().policy = policy;
+    }
+    /// 记录一次异常，本周期的次数达到阈值时封禁该来源
+    pub fn record(&self, ip: IpAddr, kind: &'static str) {
+        self.record_at(ip, kind, Instant::now())
+    }
+    fn record_at(&self, ip: IpAddr, kind: &'static str, now: Instant) {
+        let mut guard = self.inner.lock();
+        let policy = guard.policy;
+        if !guard.sources.contains_key(&ip) && guard.sources.len() >= MAX_SOURCES {
+            return;
+        }
+        let source = guard.sources.entry(ip).or_insert_with(|| Source {
+            counts: HashMap::new(),
+            window: 0,
+            window_start: now,
+            first_seen: Local::now(),
+            last_seen: Local::now(),
+            last_instant: now,
+            blocked_until: None,
+            blocks: 0,
+        });
+        *source.counts.entry(kind).or_default() += 1;
+        source.last_seen = Local::now();
+        source.last_instant = now;
+        if now - source.window_start >= PERIOD {
+            source.window = 0;
+            source.window_start = now;
+        }
+        source.window += 1;
+        if policy.threshold == 0 || source.window < policy.threshold {
+            return;
+        }
+        if source.blocked_until.is_none() {
+            self.blocked.fetch_add(1, Ordering::Relaxed);
+        }
+        source.blocked_until = Some(now + policy.duration);
+        source.blocks += 1;
+        source.window = 0;
+        log::warn!(
+            "来源{}在{}s内发送{}次异常数据，封禁{}s",
+            ip,
+            PERIOD.as_secs(),
+            policy.threshold,
+            policy.duration.as_secs()
+        );
+    }
+    /// 来源是否在封禁中
+    pub fn is_blocked(&self, ip: IpAddr) -> bool {
+        self.is_blocked_at(ip, Instant::now())
+    }
+    fn is_blocked_at(&self, ip: IpAddr, now: Instant) -> bool {
+        if self.blocked.load(Ordering::Relaxed) == 0 {
+            return false;
+        }
+        self.inner
+            .lock()
+            .sources
+            .get(&ip)
+            .and_then(|source| source.blocked_until)
+            .is_some_and(|until| until > now)
+    }
+    /// 解除封禁，返回该来源是否在封禁中
+    #[cfg(feature = "web")]
+    pub fn unblock(&self, ip: IpAddr) -> bool {
+        let mut guard = self.inner.lock();
+        let Some(source) = guard.sources.get_mut(&ip) else {
+            return false;
+        };
+        source.window = 0;
+        if source.blocked_until.take().is_some() {
+            self.blocked.fetch_sub(1, Ordering::Relaxed);
+            log::info!("手动解除封禁 {}", ip);
+            true
+        } else {
+            false
+        }
+    }
+    /// 所有可疑来源，按最后一次异常的时间倒序
+    #[cfg(feature = "web")]
+    pub fn list(&self) -> Vec<SuspiciousSource> {
+        let now = Instant::now();
+        let mut list: Vec<SuspiciousSource> = self
+            .inner
+            .lock()
+            .sources
+            .iter()
+            .map(|(ip, source)| {
+                let mut counts: Vec<_> = source
+                    .counts
+                    .iter()
+                    .map(|(kind, count)| (*kind, *count))
+                    .collect();
+                counts.sort();
+                SuspiciousSource {
+                    ip: *ip,
+                    counts,
+                    first_seen: source.first_seen,
+                    last_seen: source.last_seen,
+                    blocked: source
+                        .blocked_until
+                        .filter(|until| *until > now)
+                        .map(|until| until - now),
+                    blocks: source.blocks,
+                }
+            })
+            .collect();
+        list.sort_by_key(|source| std::cmp::Reverse(source.last_seen));
+        list
+    }
+}
+
+/// 定时解除到期的封禁，清理长时间没有异常的来源
+async fn cleanup_task(inner: Arc<Mutex<Inner>>, blocked: Arc<AtomicUsize>) {
+    loop {
+        tokio::time::sleep(PERIOD).await;
+        inner.lock().cleanup(&blocked, Instant::now());
+    }
+}
+
+impl Inner {
+    fn cleanup(&mut self, blocked: &AtomicUsize, now: Instant) {
+        for (ip, source) in self.sources.iter_mut() {
+            if source.blocked_until.is_some_and(|until| until <= now) {
+                source.blocked_until = None;
+                blocked.fetch_sub(1, Ordering::Relaxed);
+                log::info!("封禁到期 {}", ip);
+            }
+        }
+        self.sources.retain(|_, source| {
+            source.blocked_until.is_some() || now - source.last_instant < RETAIN
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IP: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(203, 0, 113, 5));
+    const OTHER: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(203, 0, 113, 6));
+
+    /// 不启动清理任务，由测试调用cleanup
+    fn sources(threshold: u64) -> SuspiciousSources {
+        let sources = SuspiciousSources {
+            inner: Arc::default(),
+            blocked: Arc::default(),
+        };
+        sources.set_policy(BlockPolicy {
+            threshold,
+            duration: Duration::from_secs(600),
+        });
+        sources
+    }
+
+    fn cleanup(sources: &SuspiciousSources, now: Instant) {
+        sources.inner.lock().cleanup(&sources.blocked, now);
+    }
+
+    #[test]
+    fn blocks_at_threshold() {
+        let now = Instant::now();
+        let sources = sources(3);
+        sources.record_at(IP, MALFORMED_PACKETS, now);
+        sources.record_at(IP, UNKNOWN_PACKETS, now);
+        assert!(!sources.is_blocked_at(IP, now));
+        sources.record_at(IP, MALFORMED_PACKETS, now);
+        assert!(sources.is_blocked_at(IP, now));
+        assert!(!sources.is_blocked_at(OTHER, now));
+        assert_eq!(sources.blocked.load(Ordering::Relaxed), 1);
+        let source = &sources.inner.lock().sources[&IP];
+        assert_eq!(source.counts[MALFORMED_PACKETS], 2);
+        assert_eq!(source.blocks, 1);
+    }
+
+    #[test]
+    fn zero_threshold_only_records() {
+        let now = Instant::now();
+        let sources = sources(0);
+        for _ in 0..100 {
+            sources.record_at(IP, MALFORMED_PACKETS, now);
+        }
+        assert!(!sources.is_blocked_at(IP, now));
+        assert_eq!(
+            sources.inner.lock().sources[&IP].counts[MALFORMED_PACKETS],
+            100
+        );
+    }
+
+    #[test]
+    fn window_resets_after_period() {
+        let now = Instant::now();
+        let sources = sources(3);
+        sources.record_at(IP, MALFORMED_PACKETS, now);
+        sources.record_at(IP, MALFORMED_PACKETS, now + Duration::from_secs(30));
+        // 新的周期重新计数
+        sources.record_at(IP, MALFORMED_PACKETS, now + PERIOD);
+        assert!(!sources.is_blocked_at(IP, now + PERIOD));
+    }
+
+    #[test]
+    fn block_expires_and_cleanup_releases_count() {
+        let now = Instant::now();
+        let sources = sources(1);
+        sources.record_at(IP, MALFORMED_PACKETS, now);
+        // 封禁期间继续发送异常数据会延长封禁，但只计数一次
+        sources.record_at(IP, MALFORMED_PACKETS, now + Duration::from_secs(300));
+        assert_eq!(sources.blocked.load(Ordering::Relaxed), 1);
+        let expiry = now + Duration::from_secs(900);
+        assert!(sources.is_blocked_at(IP, expiry - Duration::from_secs(1)));
+        assert!(!sources.is_blocked_at(IP, expiry));
+        cleanup(&sources, expiry);
+        assert_eq!(sources.blocked.load(Ordering::Relaxed), 0);
+        assert_eq!(sources.inner.lock().sources[&IP].blocks, 2);
+        // 长时间没有异常的来源不再跟踪
+        cleanup(&sources, now + Duration::from_secs(300) + RETAIN);
+        assert!(sources.inner.lock().sources.is_empty());
+    }
+
+    #[test]
+    fn tracked_sources_are_bounded() {
+        let now = Instant::now();
+        let sources = sources(0);
+        for i in 0..MAX_SOURCES as u32 + 10 {
+            sources.record_at(IpAddr::V4(i.into()), MALFORMED_PACKETS, now);
+        }
+        assert_eq!(sources.inner.lock().sources.len(), MAX_SOURCES);
+    }
+
+    #[cfg(feature = "web")]
+    #[test]
+    fn unblock_resets_window() {
+        let now = Instant::now();
+        let sources = sources(2);
+        sources.record_at(IP, MALFORMED_PACKETS, now);
+        sources.record_at(IP, MALFORMED_PACKETS, now);
+        assert!(sources.unblock(IP));
+        assert!(!sources.unblock(IP));
+        assert!(!sources.unblock(OTHER));
+        assert_eq!(sources.blocked.load(Ordering::Relaxed), 0);
+        sources.record_at(IP, MALFORMED_PACKETS, now);
+        assert!(!sources.is_blocked(IP));
+    }
+}
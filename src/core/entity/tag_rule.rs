@@ -0,0 +1,416 @@
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+
+use packet::ip::ipv4::packet::IpV4Packet;
+use packet::ip::ipv4::protocol::Protocol;
+
+/// 每个设备最多的标签数量
+const MAX_TAGS: usize = 16;
+/// 标签的最大长度
+const MAX_TAG_LEN: usize = 32;
+
+/// 检查设备标签，标签只能包含字母、数字、'-'和'_'
+pub fn check_tags(tags: &[String]) -> Result<(), String> {
+    if tags.len() > MAX_TAGS {
+        return Err(format!("at most {} tags", MAX_TAGS));
+    }
+    for tag in tags {
+        check_tag(tag)?;
+    }
+    Ok(())
+}
+
+fn check_tag(tag: &str) -> Result<(), String> {
+    if tag.is_empty()
+        || tag.len() > MAX_TAG_LEN
+        || !tag
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    {
+        return Err(format!(
+            "invalid tag {:?}, 1-{} letters, digits, '-' or '_'",
+            tag, MAX_TAG_LEN
+        ));
+    }
+    Ok(())
+}
+
+/// 按标签放行中继流量的规则，格式为 来源标签>目标标签[/协议[/端口[-端口]]]，来源标签为*时匹配所有设备
+///
+/// 例如 laptops>servers/tcp/22 表示带laptops标签的设备可以访问带servers标签的设备的tcp 22端口。
+/// 带有规则中目标标签的设备只接收规则放行的中继数据，其他设备不受影响
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TagRule {
+    // 来源标签，None匹配所有设备
+    from: Option<String>,
+    // 目标标签
+    to: String,
+    // ip协议号，None匹配所有协议
+    protocol: Option<u8>,
+    // 目标端口范围，只对tcp和udp有效
+    ports: Option<(u16, u16)>,
+}
+
+impl FromStr for TagRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tags, filter) = s.split_once('/').unwrap_or((s, ""));
+        let (from, to) = tags
+            .split_once('>')
+            .ok_or_else(|| format!("'{}' format: from>to[/protocol[/port[-port]]]", s))?;
+        let from = match from.trim() {
+            "*" => None,
+            from => {
+                check_tag(from)?;
+                Some(from.to_string())
+            }
+        };
+        let to = to.trim();
+        check_tag(to)?;
+        let (protocol, ports) = parse_filter(s, filter)?;
+        Ok(TagRule {
+            from,
+            to: to.to_string(),
+            protocol,
+            ports,
+        })
+    }
+}
+
+/// (ip协议号,目标端口范围)
+type Filter = (Option<u8>, Option<(u16, u16)>);
+
+/// 解析 协议[/端口[-端口]]，s为整条规则，用于错误信息
+fn parse_filter(s: &str, filter: &str) -> Result<Filter, String> {
+    let (protocol, ports) = match filter.split_once('/') {
+        Some((protocol, ports)) => (protocol, Some(ports)),
+        None => (filter, None),
+    };
+    let protocol = match protocol.trim().to_ascii_lowercase().as_str() {
+        "" | "any" => None,
+        "icmp" => Some(Protocol::Icmp.into()),
+        "tcp" => Some(Protocol::Tcp.into()),
+        "udp" => Some(Protocol::Udp.into()),
+        protocol => return Err(format!("'{}' protocol must be tcp/udp/icmp/any", protocol)),
+    };
+    let ports = match ports {
+        None => None,
+        Some(_)
+            if protocol != Some(Protocol::Tcp.into()) && protocol != Some(Protocol::Udp.into()) =>
+        {
+            return Err(format!("'{}' ports require tcp or udp", s));
+        }
+        Some(ports) => {
+            let (start, end) = ports.split_once('-').unwrap_or((ports, ports));
+            let start = start
+                .trim()
+                .parse::<u16>()
+                .map_err(|e| format!("'{}' port {}", s, e))?;
+            let end = end
+                .trim()
+                .parse::<u16>()
+                .map_err(|e| format!("'{}' port {}", s, e))?;
+            if start > end {
+                return Err(format!("'{}' port range start > end", s));
+            }
+            Some((start, end))
+        }
+    };
+    Ok((protocol, ports))
+}
+
+/// 限制设备发出的中继流量的规则，格式为 标签[/协议[/端口[-端口]]][@目标ip]
+///
+/// 例如 iot/tcp/1883@10.26.0.2 表示带iot标签的设备只能访问10.26.0.2的tcp 1883端口。
+/// 带有规则中标签的设备只能发出规则放行的中继数据，其他设备不受影响
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SendRule {
+    // 发送方的标签
+    tag: String,
+    // ip协议号，None匹配所有协议
+    protocol: Option<u8>,
+    // 目标端口范围，只对tcp和udp有效
+    ports: Option<(u16, u16)>,
+    // 目标虚拟ip，None匹配所有设备
+    destination: Option<Ipv4Addr>,
+}
+
+impl FromStr for SendRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rule, destination) = match s.split_once('@') {
+            Some((rule, destination)) => {
+                let destination = destination
+                    .trim()
+                    .parse::<Ipv4Addr>()
+                    .map_err(|e| format!("'{}' {}", s, e))?;
+                (rule, Some(destination))
+            }
+            None => (s, None),
+        };
+        let (tag, filter) = rule.split_once('/').unwrap_or((rule, ""));
+        let tag = tag.trim();
+        check_tag(tag)?;
+        let (protocol, ports) = parse_filter(s, filter)?;
+        Ok(SendRule {
+            tag: tag.to_string(),
+            protocol,
+            ports,
+            destination,
+        })
+    }
+}
+
+impl SendRule {
+    fn matches(&self, destination: Ipv4Addr, flow: Option<Flow>) -> bool {
+        if self.destination.is_some_and(|ip| ip != destination) {
+            return false;
+        }
+        flow_matches(self.protocol, self.ports, flow)
+    }
+}
+
+/// 是否允许设备发出该数据，设备没有被任何规则限制时允许
+///
+/// flow为None时表示数据无法解析(例如客户端间加密)，只匹配不限协议的规则
+pub fn send_allowed(
+    rules: &[SendRule],
+    from: &[String],
+    destination: Ipv4Addr,
+    flow: Option<Flow>,
+) -> bool {
+    let mut restricted = false;
+    for rule in rules.iter().filter(|rule| from.contains(&rule.tag)) {
+        if rule.matches(destination, flow) {
+            return true;
+        }
+        restricted = true;
+    }
+    !restricted
+}
+
+/// 中继的ipv4数据包的协议和目标端口，客户端间加密的数据无法解析
+#[derive(Clone, Copy, Debug)]
+pub struct Flow {
+    protocol: u8,
+    // 分片的后续部分没有端口，只匹配不限端口的规则
+    port: Option<u16>,
+}
+
+impl Flow {
+    pub fn parse(ipv4: &[u8]) -> Option<Flow> {
+        let packet = IpV4Packet::new(ipv4).ok()?;
+        let protocol = packet.protocol();
+        let port = match protocol {
+            Protocol::Tcp | Protocol::Udp if packet.offset() == 0 => {
+                let payload = packet.payload();
+                (payload.len() >= 4).then(|| u16::from_be_bytes([payload[2], payload[3]]))
+            }
+            _ => None,
+        };
+        Some(Flow {
+            protocol: protocol.into(),
+            port,
+        })
+    }
+}
+
+impl TagRule {
+    fn matches(&self, from: &[String], flow: Option<Flow>) -> bool {
+        if let Some(tag) = &self.from {
+            if !from.contains(tag) {
+                return false;
+            }
+        }
+        flow_matches(self.protocol, self.ports, flow)
+    }
+}
+
+fn flow_matches(protocol: Option<u8>, ports: Option<(u16, u16)>, flow: Option<Flow>) -> bool {
+    if protocol.is_none() {
+        return true;
+    }
+    let Some(flow) = flow else {
+        return false;
+    };
+    if protocol != Some(flow.protocol) {
+        return false;
+    }
+    match (ports, flow.port) {
+        (None, _) => true,
+        (Some((start, end)), Some(port)) => start <= port && port <= end,
+        (Some(_), None) => false,
+    }
+}
+
+/// 是否允许中继到目标设备，目标设备没有被任何规则限制时允许
+///
+/// flow为None时表示数据无法解析(例如客户端间加密)，只匹配不限协议的规则
+pub fn relay_allowed(
+    rules: &[TagRule],
+    from: &[String],
+    to: &[String],
+    flow: Option<Flow>,
+) -> bool {
+    let mut restricted = false;
+    for rule in rules.iter().filter(|rule| to.contains(&rule.to)) {
+        if rule.matches(from, flow) {
+            return true;
+        }
+        restricted = true;
+    }
+    !restricted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP: u8 = 6;
+    const UDP: u8 = 17;
+    const ICMP: u8 = 1;
+
+    /// 目标端口为port的ipv4数据包，offset为分片偏移
+    fn ipv4(protocol: u8, port: u16, offset: u16) -> Vec<u8> {
+        let mut buf = vec![0u8; 28];
+        buf[0] = 0x45;
+        buf[6..8].copy_from_slice(&offset.to_be_bytes());
+        buf[9] = protocol;
+        buf[20..22].copy_from_slice(&40000u16.to_be_bytes());
+        buf[22..24].copy_from_slice(&port.to_be_bytes());
+        buf
+    }
+
+    fn flow(protocol: u8, port: u16) -> Option<Flow> {
+        Flow::parse(&ipv4(protocol, port, 0))
+    }
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn check_tag_limits() {
+        assert!(check_tags(&tags(&["iot", "a-b_c", "X9"])).is_ok());
+        for tag in ["", "a b", "a.b", "中文", &"x".repeat(MAX_TAG_LEN + 1)] {
+            assert!(check_tags(&tags(&[tag])).is_err(), "{:?}", tag);
+        }
+        let many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("t{}", i)).collect();
+        assert!(check_tags(&many).is_err());
+    }
+
+    #[test]
+    fn parse_tag_rule() {
+        let rule: TagRule = " laptops > servers /TCP/ 22-23".parse().unwrap();
+        assert_eq!(
+            rule,
+            TagRule {
+                from: Some("laptops".into()),
+                to: "servers".into(),
+                protocol: Some(TCP),
+                ports: Some((22, 23)),
+            }
+        );
+        let rule: TagRule = "*>servers".parse().unwrap();
+        assert_eq!((rule.from, rule.protocol, rule.ports), (None, None, None));
+        let rule: TagRule = "a>b/udp/53".parse().unwrap();
+        assert_eq!(rule.ports, Some((53, 53)));
+        for s in [
+            "servers",
+            "a>",
+            ">b",
+            "a b>c",
+            "a>b/sctp",
+            "a>b/icmp/1",
+            "a>b/any/80",
+            "a>b/tcp/80-22",
+            "a>b/tcp/70000",
+            "a>b/tcp/x",
+        ] {
+            assert!(s.parse::<TagRule>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn parse_send_rule() {
+        let rule: SendRule = "iot/tcp/1883@10.26.0.2".parse().unwrap();
+        assert_eq!(
+            rule,
+            SendRule {
+                tag: "iot".into(),
+                protocol: Some(TCP),
+                ports: Some((1883, 1883)),
+                destination: Some(Ipv4Addr::new(10, 26, 0, 2)),
+            }
+        );
+        let rule: SendRule = "iot@10.26.0.2".parse().unwrap();
+        assert_eq!((rule.protocol, rule.ports), (None, None));
+        for s in ["iot@10.26.0", "*", "iot/udp/1-0", "iot/icmp/7"] {
+            assert!(s.parse::<SendRule>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn parse_flow() {
+        let flow = flow(TCP, 22).unwrap();
+        assert_eq!((flow.protocol, flow.port), (TCP, Some(22)));
+        let flow = Flow::parse(&ipv4(ICMP, 22, 0)).unwrap();
+        assert_eq!(flow.port, None);
+        // 分片的后续部分没有端口
+        let flow = Flow::parse(&ipv4(UDP, 53, 100)).unwrap();
+        assert_eq!(flow.port, None);
+        assert!(Flow::parse(&[0x45; 10]).is_none());
+    }
+
+    #[test]
+    fn relay_rules() {
+        let rules: Vec<TagRule> = ["laptops>servers/tcp/22", "*>servers/icmp"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let servers = tags(&["servers"]);
+        let laptops = tags(&["laptops"]);
+        assert!(relay_allowed(&rules, &laptops, &servers, flow(TCP, 22)));
+        assert!(!relay_allowed(&rules, &laptops, &servers, flow(TCP, 80)));
+        assert!(!relay_allowed(&rules, &laptops, &servers, flow(UDP, 22)));
+        assert!(!relay_allowed(
+            &rules,
+            &tags(&["guest"]),
+            &servers,
+            flow(TCP, 22)
+        ));
+        assert!(relay_allowed(
+            &rules,
+            &tags(&["guest"]),
+            &servers,
+            flow(ICMP, 0)
+        ));
+        // 没有被规则限制的设备不受影响
+        assert!(relay_allowed(&rules, &[], &laptops, flow(UDP, 53)));
+        // 无法解析的数据只匹配不限协议的规则
+        assert!(!relay_allowed(&rules, &laptops, &servers, None));
+        let any: Vec<TagRule> = vec!["laptops>servers".parse().unwrap()];
+        assert!(relay_allowed(&any, &laptops, &servers, None));
+        // 分片的后续部分只匹配不限端口的规则
+        let fragment = Flow::parse(&ipv4(TCP, 22, 100));
+        assert!(!relay_allowed(&rules, &laptops, &servers, fragment));
+    }
+
+    #[test]
+    fn send_rules() {
+        let rules: Vec<SendRule> = ["iot/tcp/1883@10.26.0.2", "iot/udp/53"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let broker = Ipv4Addr::new(10, 26, 0, 2);
+        let other = Ipv4Addr::new(10, 26, 0, 3);
+        let iot = tags(&["iot"]);
+        assert!(send_allowed(&rules, &iot, broker, flow(TCP, 1883)));
+        assert!(!send_allowed(&rules, &iot, other, flow(TCP, 1883)));
+        assert!(send_allowed(&rules, &iot, other, flow(UDP, 53)));
+        assert!(!send_allowed(&rules, &iot, broker, None));
+        assert!(send_allowed(&rules, &tags(&["laptops"]), other, None));
+    }
+}
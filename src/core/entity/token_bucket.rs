@@ -0,0 +1,154 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// 令牌桶，单位为字节(中继带宽)或次数(广播投递)，桶容量为一秒的量
+pub struct TokenBucket {
+    // 每秒生成的令牌数
+    rate: u64,
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    tokens: u64,
+    last_refill: Instant,
+    // 统计周期内通过的字节数
+    window_start: Instant,
+    window_bytes: u64,
+    // 上一个统计周期的速率
+    last_rate: u64,
+}
+
+impl TokenBucket {
+    pub fn new(rate: u64) -> Self {
+        Self::starting_at(rate, Instant::now())
+    }
+    fn starting_at(rate: u64, now: Instant) -> Self {
+        Self {
+            rate,
+            inner: Mutex::new(Inner {
+                tokens: rate,
+                last_refill: now,
+                window_start: now,
+                window_bytes: 0,
+                last_rate: 0,
+            }),
+        }
+    }
+    #[cfg(feature = "web")]
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+    /// 取出令牌，令牌不足时返回false
+    pub fn try_acquire(&self, len: u64) -> bool {
+        self.acquire_at(len, Instant::now())
+    }
+    fn acquire_at(&self, len: u64, now: Instant) -> bool {
+        let mut inner = self.inner.lock();
+        let elapsed = now.duration_since(inner.last_refill);
+        let refill = (elapsed.as_micros() as u64).saturating_mul(self.rate) / 1_000_000;
+        if refill > 0 {
+            inner.tokens = inner.tokens.saturating_add(refill).min(self.rate);
+            inner.last_refill = now;
+        }
+        inner.roll_window(now);
+        if inner.tokens < len {
+            return false;
+        }
+        inner.tokens -= len;
+        inner.window_bytes += len;
+        true
+    }
+    /// 最近一秒的速率，字节/秒
+    #[cfg(feature = "web")]
+    pub fn current_rate(&self) -> u64 {
+        self.rate_at(Instant::now())
+    }
+    #[cfg(feature = "web")]
+    fn rate_at(&self, now: Instant) -> u64 {
+        let mut inner = self.inner.lock();
+        inner.roll_window(now);
+        inner.last_rate
+    }
+}
+
+impl Inner {
+    fn roll_window(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.window_start);
+        if elapsed >= Duration::from_secs(1) {
+            self.last_rate = if elapsed >= Duration::from_secs(2) {
+                // 上一个统计周期之后没有流量
+                0
+            } else {
+                self.window_bytes
+            };
+            self.window_start = now;
+            self.window_bytes = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn starts_full_and_drains() {
+        let start = Instant::now();
+        let bucket = TokenBucket::starting_at(1000, start);
+        assert!(bucket.acquire_at(600, start));
+        assert!(bucket.acquire_at(400, start));
+        assert!(!bucket.acquire_at(1, start));
+    }
+
+    #[test]
+    fn refills_at_rate_up_to_capacity() {
+        let start = Instant::now();
+        let bucket = TokenBucket::starting_at(1000, start);
+        assert!(bucket.acquire_at(1000, start));
+        // 100ms生成100个令牌
+        assert!(!bucket.acquire_at(101, start + ms(100)));
+        assert!(bucket.acquire_at(100, start + ms(100)));
+        assert!(!bucket.acquire_at(1, start + ms(100)));
+        // 空闲很久后最多只有一秒的令牌
+        assert!(!bucket.acquire_at(1001, start + ms(10_000)));
+        assert!(bucket.acquire_at(1000, start + ms(10_000)));
+    }
+
+    #[test]
+    fn rejected_acquire_keeps_tokens() {
+        let start = Instant::now();
+        let bucket = TokenBucket::starting_at(1000, start);
+        assert!(!bucket.acquire_at(1500, start));
+        assert!(bucket.acquire_at(1000, start));
+    }
+
+    #[test]
+    fn small_intervals_are_not_lost() {
+        let start = Instant::now();
+        let bucket = TokenBucket::starting_at(10, start);
+        assert!(bucket.acquire_at(10, start));
+        // 每次间隔不足一个令牌时不更新时间，令牌会累积
+        for i in 1..10 {
+            assert!(!bucket.acquire_at(1, start + ms(i * 10)));
+        }
+        assert!(bucket.acquire_at(1, start + ms(100)));
+    }
+
+    #[cfg(feature = "web")]
+    #[test]
+    fn current_rate_uses_last_window() {
+        let start = Instant::now();
+        let bucket = TokenBucket::starting_at(1000, start);
+        assert!(bucket.acquire_at(300, start));
+        assert!(bucket.acquire_at(200, start + ms(500)));
+        assert_eq!(bucket.rate_at(start + ms(900)), 0);
+        assert_eq!(bucket.rate_at(start + ms(1000)), 500);
+        // 之后没有流量
+        assert_eq!(bucket.rate_at(start + ms(3500)), 0);
+    }
+}
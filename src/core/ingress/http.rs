@@ -0,0 +1,286 @@
+use std::io;
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::core::ingress::Target;
+
+/// 读取http请求头或tls握手的数据上限
+const MAX_HEAD: usize = 16 * 1024;
+/// tls握手记录的类型
+const TLS_HANDSHAKE: u8 = 0x16;
+
+/// http(s)入口的路由，主机名映射到组内设备的端口，主机名以'*.'开头时匹配所有子域名
+#[derive(Clone, Debug)]
+pub struct HttpRoute {
+    pub host: String,
+    pub group: String,
+    pub target: Target,
+    pub port: u16,
+}
+
+impl FromStr for HttpRoute {
+    type Err = String;
+
+    /// 格式为 主机名=组:设备:端口
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("'{}' must be host=group:device:port", s);
+        let (host, rest) = s.split_once('=').ok_or_else(err)?;
+        let (rest, port) = rest.rsplit_once(':').ok_or_else(err)?;
+        let (group, target) = rest.rsplit_once(':').ok_or_else(err)?;
+        let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+        if host.is_empty() || group.trim().is_empty() {
+            return Err(err());
+        }
+        let port = match port.trim().parse::<u16>() {
+            Ok(port @ 1..) => port,
+            _ => return Err(format!("'{}' invalid port", s)),
+        };
+        Ok(HttpRoute {
+            host,
+            group: group.trim().to_string(),
+            target: Target::from_str(target).map_err(|e| format!("'{}' {}", s, e))?,
+            port,
+        })
+    }
+}
+
+/// http(s)入口配置
+#[derive(Clone, Debug)]
+pub struct HttpIngressConfig {
+    pub addrs: Vec<SocketAddr>,
+    pub routes: Vec<HttpRoute>,
+}
+
+/// 按主机名查找路由，精确匹配优先，其次是最长的通配后缀
+pub fn find_route<'a>(routes: &'a [HttpRoute], host: &str) -> Option<&'a HttpRoute> {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if let Some(route) = routes.iter().find(|route| route.host == host) {
+        return Some(route);
+    }
+    routes
+        .iter()
+        .filter(|route| {
+            route
+                .host
+                .strip_prefix('*')
+                .is_some_and(|suffix| host.ends_with(suffix))
+        })
+        .max_by_key(|route| route.host.len())
+}
+
+/// 读取连接开头的http请求头或tls的ClientHello，返回主机名、是否是tls以及已读取的数据
+pub async fn sniff_host(stream: &mut TcpStream) -> io::Result<(Option<String>, bool, Vec<u8>)> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 2048];
+    loop {
+        let len = stream.read(&mut chunk).await?;
+        if len == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+        buf.extend_from_slice(&chunk[..len]);
+        if buf[0] == TLS_HANDSHAKE {
+            if buf.len() >= 5 {
+                let record_len = u16::from_be_bytes([buf[3], buf[4]]) as usize;
+                if buf.len() >= 5 + record_len {
+                    return Ok((tls_server_name(&buf[5..5 + record_len]), true, buf));
+                }
+            }
+        } else if let Some(end) = buf.windows(4).position(|v| v == b"\r\n\r\n") {
+            return Ok((http_host(&buf[..end]), false, buf));
+        }
+        if buf.len() >= MAX_HEAD {
+            return Ok((None, buf[0] == TLS_HANDSHAKE, buf));
+        }
+    }
+}
+
+/// 请求头中的Host，去掉端口
+fn http_host(head: &[u8]) -> Option<String> {
+    let head = std::str::from_utf8(head).ok()?;
+    let value = head.split("\r\n").skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("host")
+            .then_some(value.trim())
+    })?;
+    let host = match value.rsplit_once(':') {
+        Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => host,
+        _ => value,
+    };
+    Some(host.to_string())
+}
+
+/// ClientHello中的服务器名称(SNI)
+fn tls_server_name(handshake: &[u8]) -> Option<String> {
+    // 握手类型1为ClientHello，之后是3字节长度、2字节版本和32字节随机数
+    if *handshake.first()? != 1 {
+        return None;
+    }
+    let mut i = 4 + 2 + 32;
+    // 会话id
+    i += 1 + *handshake.get(i)? as usize;
+    // 加密套件
+    i += 2 + u16::from_be_bytes([*handshake.get(i)?, *handshake.get(i + 1)?]) as usize;
+    // 压缩方式
+    i += 1 + *handshake.get(i)? as usize;
+    let extensions_len = u16::from_be_bytes([*handshake.get(i)?, *handshake.get(i + 1)?]) as usize;
+    i += 2;
+    let end = (i + extensions_len).min(handshake.len());
+    while i + 4 <= end {
+        let kind = u16::from_be_bytes([handshake[i], handshake[i + 1]]);
+        let len = u16::from_be_bytes([handshake[i + 2], handshake[i + 3]]) as usize;
+        i += 4;
+        let data = handshake.get(i..i + len)?;
+        if kind == 0 {
+            // 名称列表长度(2)、名称类型(1)、名称长度(2)
+            if data.len() < 5 || data[2] != 0 {
+                return None;
+            }
+            let name_len = u16::from_be_bytes([data[3], data[4]]) as usize;
+            let name = data.get(5..5 + name_len)?;
+            return String::from_utf8(name.to_vec()).ok();
+        }
+        i += len;
+    }
+    None
+}
+
+/// 回应http错误并关闭连接，https连接直接关闭
+pub async fn reject(stream: &mut TcpStream, tls: bool, status: &str) -> io::Result<()> {
+    if !tls {
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            status
+        );
+        stream.write_all(response.as_bytes()).await?;
+    }
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes(hosts: &[&str]) -> Vec<HttpRoute> {
+        hosts
+            .iter()
+            .enumerate()
+            .map(|(i, host)| {
+                format!("{}=office:10.26.0.{}:80", host, i + 2)
+                    .parse()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// 只带指定扩展的ClientHello握手消息
+    fn client_hello(extensions: &[u8], extensions_len: u16) -> Vec<u8> {
+        let mut hello = vec![1, 0, 0, 0, 3, 3];
+        hello.extend_from_slice(&[0; 32]);
+        // 会话id、加密套件、压缩方式
+        hello.extend_from_slice(&[0, 0, 2, 0x13, 0x01, 1, 0]);
+        hello.extend_from_slice(&extensions_len.to_be_bytes());
+        hello.extend_from_slice(extensions);
+        hello
+    }
+
+    fn sni_extension(name: &[u8]) -> Vec<u8> {
+        let mut ext = vec![0, 0];
+        ext.extend_from_slice(&(name.len() as u16 + 5).to_be_bytes());
+        ext.extend_from_slice(&(name.len() as u16 + 3).to_be_bytes());
+        ext.push(0);
+        ext.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        ext.extend_from_slice(name);
+        ext
+    }
+
+    #[test]
+    fn parse_route() {
+        let route: HttpRoute = " Web.Example.com. =office:nas:8080".parse().unwrap();
+        assert_eq!(route.host, "web.example.com");
+        assert_eq!(route.group, "office");
+        assert!(matches!(route.target, Target::Name(ref name) if name == "nas"));
+        assert_eq!(route.port, 8080);
+        for s in [
+            "web=office:nas:0",
+            "web=office:nas",
+            "=office:nas:80",
+            "web=:nas:80",
+        ] {
+            assert!(s.parse::<HttpRoute>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn exact_match_wins_over_wildcard() {
+        let routes = routes(&["*.example.com", "*.web.example.com", "api.web.example.com"]);
+        let target = |host| find_route(&routes, host).map(|route| route.target.clone());
+        assert!(
+            matches!(target("api.web.example.com"), Some(Target::Ip(ip)) if ip.octets()[3] == 4)
+        );
+        // 最长的通配后缀优先
+        assert!(
+            matches!(target("www.web.example.com"), Some(Target::Ip(ip)) if ip.octets()[3] == 3)
+        );
+        assert!(matches!(target("www.example.com"), Some(Target::Ip(ip)) if ip.octets()[3] == 2));
+        // 通配符不匹配上级域名本身
+        assert!(target("example.com").is_none());
+        assert!(target("badexample.com").is_none());
+    }
+
+    #[test]
+    fn trailing_dot_and_case_are_ignored() {
+        let routes = routes(&["web.example.com", "*.example.org"]);
+        assert!(find_route(&routes, "WEB.example.com.").is_some());
+        assert!(find_route(&routes, "www.example.org.").is_some());
+        assert!(find_route(&routes, "web.example.com..x").is_none());
+    }
+
+    #[test]
+    fn http_host_strips_port() {
+        let head = |host: &str| format!("GET / HTTP/1.1\r\nHOST: {}\r\nAccept: */*", host);
+        assert_eq!(
+            http_host(head("web.example.com").as_bytes()).unwrap(),
+            "web.example.com"
+        );
+        assert_eq!(
+            http_host(head("web.example.com:8080").as_bytes()).unwrap(),
+            "web.example.com"
+        );
+        assert_eq!(http_host(head("[::1]:8080").as_bytes()).unwrap(), "[::1]");
+        assert_eq!(http_host(head("[::1]").as_bytes()).unwrap(), "[::1]");
+        // 请求行中的冒号不会被当作Host
+        assert!(http_host(b"GET http://a:80/ HTTP/1.1\r\nAccept: */*").is_none());
+    }
+
+    #[test]
+    fn sni_from_client_hello() {
+        let ext = sni_extension(b"web.example.com");
+        let mut extensions = vec![0, 0x2b, 0, 3, 2, 3, 4];
+        extensions.extend_from_slice(&ext);
+        let hello = client_hello(&extensions, extensions.len() as u16);
+        assert_eq!(tls_server_name(&hello).unwrap(), "web.example.com");
+    }
+
+    #[test]
+    fn sni_with_truncated_extensions() {
+        let ext = sni_extension(b"web.example.com");
+        // 扩展数据被截断
+        let hello = client_hello(&ext[..ext.len() - 3], ext.len() as u16);
+        assert!(tls_server_name(&hello).is_none());
+        // 扩展总长度比实际数据短，名称超出声明的范围
+        let hello = client_hello(&ext, 3);
+        assert!(tls_server_name(&hello).is_none());
+        // 名称长度超出扩展数据
+        let mut bad = ext.clone();
+        bad[8] = 0xff;
+        let hello = client_hello(&bad, bad.len() as u16);
+        assert!(tls_server_name(&hello).is_none());
+        // 不是ClientHello或者在固定字段内截断
+        assert!(tls_server_name(&[2]).is_none());
+        assert!(tls_server_name(&client_hello(&[], 0)[..40]).is_none());
+    }
+}
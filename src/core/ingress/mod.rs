@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use packet::ip::ipv4;
+use packet::ip::ipv4::packet::IpV4Packet;
+use parking_lot::Mutex;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::core::service::PacketHandler;
+use crate::core::store::cache::AppCache;
+use crate::core::task;
+
+mod http;
+mod socks5;
+mod tcp;
+
+pub use http::{HttpIngressConfig, HttpRoute};
+
+/// 同时存在的代理连接数上限
+const MAX_CONNECTIONS: usize = 1024;
+/// 每个连接缓存的待处理数据包数量，处理不过来时丢弃，由设备重传
+const INBOUND_QUEUE: usize = 256;
+/// 网关一侧使用的端口范围
+const LOCAL_PORTS: std::ops::RangeInclusive<u16> = 40000..=65000;
+/// socks5握手的超时时间
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// socks5认证失败的日志类型
+const SOCKS5_AUTH_FAILURES: &str = "socks5 auth failures";
+
+/// socks5代理的用户，用户只能访问对应组内的设备
+#[derive(Clone)]
+pub struct Socks5User {
+    pub username: String,
+    pub password: String,
+    pub group: String,
+}
+
+impl fmt::Debug for Socks5User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.username, self.group)
+    }
+}
+
+impl FromStr for Socks5User {
+    type Err = String;
+
+    /// 格式为 用户名:密码:组，密码可以包含':'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("'{}' must be username:password:group", s);
+        let (username, rest) = s.split_once(':').ok_or_else(err)?;
+        let (password, group) = rest.rsplit_once(':').ok_or_else(err)?;
+        // socks5的用户名和密码最长255字节
+        if username.is_empty() || username.len() > 255 || password.len() > 255 {
+            return Err(format!("'{}' username and password must be 1-255 bytes", s));
+        }
+        if group.trim().is_empty() {
+            return Err(err());
+        }
+        Ok(Socks5User {
+            username: username.to_string(),
+            password: password.to_string(),
+            group: group.trim().to_string(),
+        })
+    }
+}
+
+/// 代理连接的目标，设备的虚拟ip或名称
+#[derive(Clone, Debug)]
+pub enum Target {
+    Ip(Ipv4Addr),
+    Name(String),
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("empty target".into());
+        }
+        Ok(match s.parse::<Ipv4Addr>() {
+            Ok(ip) => Target::Ip(ip),
+            Err(_) => Target::Name(s.to_string()),
+        })
+    }
+}
+
+/// socks5代理入口配置
+#[derive(Clone, Debug)]
+pub struct Socks5Config {
+    pub addr: SocketAddr,
+    pub users: Vec<Socks5User>,
+}
+
+// (组，设备ip，设备端口，网关端口)
+type ConnKey = (String, u32, u16, u16);
+
+/// 代理连接表，设备发给网关的tcp数据按连接分发
+#[derive(Clone, Default)]
+pub struct IngressTable {
+    inner: Arc<Mutex<HashMap<ConnKey, Sender<Vec<u8>>>>>,
+}
+
+impl IngressTable {
+    /// 设备发给网关的tcp数据属于代理连接时交给该连接处理，返回是否已处理
+    pub fn deliver(&self, group: &str, virtual_ip: u32, ipv4: &[u8]) -> bool {
+        let Some((device_port, local_port)) = tcp_ports(ipv4) else {
+            return false;
+        };
+        let inner = self.inner.lock();
+        if inner.is_empty() {
+            return false;
+        }
+        let key = (group.to_string(), virtual_ip, device_port, local_port);
+        match inner.get(&key) {
+            Some(sender) => {
+                let _ = sender.try_send(ipv4.to_vec());
+                true
+            }
+            None => false,
+        }
+    }
+    /// 分配网关一侧的端口，连接数达到上限时返回None
+    fn open(
+        &self,
+        group: &str,
+        virtual_ip: u32,
+        device_port: u16,
+    ) -> Option<(ConnKey, Receiver<Vec<u8>>)> {
+        let mut inner = self.inner.lock();
+        if inner.len() >= MAX_CONNECTIONS {
+            return None;
+        }
+        let span = LOCAL_PORTS.end() - LOCAL_PORTS.start() + 1;
+        let start = rand::random::<u16>() % span;
+        for offset in 0..span {
+            let local_port = LOCAL_PORTS.start() + (start + offset) % span;
+            let key = (group.to_string(), virtual_ip, device_port, local_port);
+            if inner.contains_key(&key) {
+                continue;
+            }
+            let (sender, receiver) = channel(INBOUND_QUEUE);
+            inner.insert(key.clone(), sender);
+            return Some((key, receiver));
+        }
+        None
+    }
+    fn close(&self, key: &ConnKey) {
+        self.inner.lock().remove(key);
+    }
+}
+
+/// 发往网关的tcp数据的(来源端口，目标端口)
+fn tcp_ports(ipv4: &[u8]) -> Option<(u16, u16)> {
+    let packet = IpV4Packet::new(ipv4).ok()?;
+    if packet.protocol() != ipv4::protocol::Protocol::Tcp || packet.offset() != 0 {
+        return None;
+    }
+    let tcp = packet.payload();
+    if tcp.len() < 20 {
+        return None;
+    }
+    Some((
+        u16::from_be_bytes([tcp[0], tcp[1]]),
+        u16::from_be_bytes([tcp[2], tcp[3]]),
+    ))
+}
+
+/// 接受socks5连接，认证后以组的网关ip连接组内设备
+pub async fn start_socks5(
+    listener: TcpListener,
+    config: Socks5Config,
+    cache: AppCache,
+    handler: PacketHandler,
+) {
+    let users = Arc::new(config.users);
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(rs) => rs,
+            Err(e) => {
+                log::error!("socks5 accept {:?}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let users = users.clone();
+        let cache = cache.clone();
+        let handler = handler.clone();
+        task::spawn("socks5 conn", async move {
+            if let Err(e) = proxy(stream, addr, &users, &cache, handler).await {
+                log::info!("socks5连接结束 addr={},{:?}", addr, e);
+            }
+        });
+    }
+}
+
+async fn proxy(
+    mut stream: TcpStream,
+    addr: SocketAddr,
+    users: &[Socks5User],
+    cache: &AppCache,
+    handler: PacketHandler,
+) -> io::Result<()> {
+    let request =
+        match tokio::time::timeout(HANDSHAKE_TIMEOUT, socks5::accept(&mut stream, users)).await {
+            Ok(Ok(request)) => request,
+            Ok(Err(e)) => {
+                if e.kind() == io::ErrorKind::PermissionDenied
+                    && cache.log_limiter.check(addr.ip(), SOCKS5_AUTH_FAILURES)
+                {
+                    log::warn!("socks5认证失败 addr={}", addr);
+                }
+                return Err(e);
+            }
+            Err(_) => return Err(io::Error::from(io::ErrorKind::TimedOut)),
+        };
+    let user = &users[request.user];
+    log::info!(
+        "socks5连接 addr={},user={:?},target={:?}:{}",
+        addr,
+        user,
+        request.target,
+        request.port
+    );
+    let tunnel = match open_tunnel(cache, handler, &user.group, &request.target, request.port).await
+    {
+        Ok(tunnel) => tunnel,
+        Err(e) => {
+            let code = match e.kind() {
+                io::ErrorKind::ConnectionRefused => socks5::CONNECTION_REFUSED,
+                io::ErrorKind::NotFound | io::ErrorKind::TimedOut => socks5::HOST_UNREACHABLE,
+                _ => socks5::GENERAL_FAILURE,
+            };
+            socks5::reply(&mut stream, code, None).await?;
+            return Err(e);
+        }
+    };
+    socks5::reply(&mut stream, socks5::SUCCEEDED, Some(tunnel.local)).await?;
+    tunnel.connection.relay(stream, Vec::new()).await
+}
+
+/// 接受http(s)连接，按主机名转发到路由配置的设备端口
+pub async fn start_http(
+    listener: TcpListener,
+    routes: Arc<Vec<HttpRoute>>,
+    cache: AppCache,
+    handler: PacketHandler,
+) {
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(rs) => rs,
+            Err(e) => {
+                log::error!("http ingress accept {:?}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let routes = routes.clone();
+        let cache = cache.clone();
+        let handler = handler.clone();
+        task::spawn("http ingress conn", async move {
+            if let Err(e) = http_proxy(stream, addr, &routes, &cache, handler).await {
+                log::info!("http入口连接结束 addr={},{:?}", addr, e);
+            }
+        });
+    }
+}
+
+/// 同一连接只按第一个请求的主机名路由
+async fn http_proxy(
+    mut stream: TcpStream,
+    addr: SocketAddr,
+    routes: &[HttpRoute],
+    cache: &AppCache,
+    handler: PacketHandler,
+) -> io::Result<()> {
+    let (host, tls, head) =
+        match tokio::time::timeout(HANDSHAKE_TIMEOUT, http::sniff_host(&mut stream)).await {
+            Ok(rs) => rs?,
+            Err(_) => return Err(io::Error::from(io::ErrorKind::TimedOut)),
+        };
+    let Some(route) = host
+        .as_deref()
+        .and_then(|host| http::find_route(routes, host))
+    else {
+        http::reject(&mut stream, tls, "404 Not Found").await?;
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no route for host {:?}", host),
+        ));
+    };
+    log::info!(
+        "http入口连接 addr={},host={:?},group={},target={:?}:{}",
+        addr,
+        host,
+        route.group,
+        route.target,
+        route.port
+    );
+    let tunnel = match open_tunnel(cache, handler, &route.group, &route.target, route.port).await {
+        Ok(tunnel) => tunnel,
+        Err(e) => {
+            let status = match e.kind() {
+                io::ErrorKind::TimedOut => "504 Gateway Timeout",
+                _ => "502 Bad Gateway",
+            };
+            http::reject(&mut stream, tls, status).await?;
+            return Err(e);
+        }
+    };
+    tunnel.connection.relay(stream, head).await
+}
+
+/// 代理连接在连接表中的登记，释放时移除
+struct ConnGuard {
+    table: IngressTable,
+    key: ConnKey,
+}
+
+impl Drop for ConnGuard {
+    fn drop(&mut self) {
+        self.table.close(&self.key);
+    }
+}
+
+/// 以组的网关ip建立的到组内设备的tcp连接
+struct Tunnel {
+    connection: tcp::VirtualTcp,
+    // 网关一侧的地址
+    local: (Ipv4Addr, u16),
+    _guard: ConnGuard,
+}
+
+/// 连接组内在线的设备，设备不在线时返回NotFound，设备拒绝连接时返回ConnectionRefused，没有回应时返回TimedOut
+async fn open_tunnel(
+    cache: &AppCache,
+    handler: PacketHandler,
+    group: &str,
+    target: &Target,
+    port: u16,
+) -> io::Result<Tunnel> {
+    let Some((device_ip, gateway_ip)) = resolve(cache, group, target) else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{:?} not online", target),
+        ));
+    };
+    let Some((key, inbound)) = cache.ingress.open(group, device_ip, port) else {
+        return Err(io::Error::other("too many connections"));
+    };
+    let local = (gateway_ip, key.3);
+    let guard = ConnGuard {
+        table: cache.ingress.clone(),
+        key,
+    };
+    let endpoint = tcp::Endpoint {
+        group: group.to_string(),
+        local,
+        remote: (Ipv4Addr::from(device_ip), port),
+    };
+    let connection = tcp::VirtualTcp::connect(endpoint, handler, inbound).await?;
+    Ok(Tunnel {
+        connection,
+        local,
+        _guard: guard,
+    })
+}
+
+/// 按虚拟ip或名称找到组内在线的设备，返回(设备ip，组的网关ip)
+fn resolve(cache: &AppCache, group: &str, target: &Target) -> Option<(u32, Ipv4Addr)> {
+    let network_info = cache.virtual_network.get(&group.to_string())?;
+    let guard = network_info.read();
+    let client = match target {
+        Target::Ip(ip) => guard.clients.get(&u32::from(*ip)),
+        Target::Name(name) => guard
+            .clients
+            .values()
+            .find(|client| client.online && client.name.eq_ignore_ascii_case(name)),
+    }
+    .filter(|client| client.online)?;
+    Some((client.virtual_ip, guard.gateway_ip.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_socks5_user() {
+        let user: Socks5User = "alice:pa:ss:word:office".parse().unwrap();
+        assert_eq!(user.username, "alice");
+        assert_eq!(user.password, "pa:ss:word");
+        assert_eq!(user.group, "office");
+        let user: Socks5User = "alice::office".parse().unwrap();
+        assert_eq!(user.password, "");
+        for s in [
+            "alice:secret:",
+            "alice:secret: ",
+            "alice:secret",
+            ":secret:office",
+        ] {
+            assert!(s.parse::<Socks5User>().is_err(), "{}", s);
+        }
+        let long = format!("alice:{}:office", "x".repeat(256));
+        assert!(long.parse::<Socks5User>().is_err());
+    }
+
+    #[test]
+    fn open_limits_connections() {
+        let table = IngressTable::default();
+        let mut ports = std::collections::HashSet::new();
+        let mut keys = Vec::new();
+        for _ in 0..MAX_CONNECTIONS {
+            let (key, _receiver) = table.open("office", 1, 80).unwrap();
+            assert!(LOCAL_PORTS.contains(&key.3));
+            // 同一设备端口的连接分配到不同的网关端口
+            assert!(ports.insert(key.3));
+            keys.push(key);
+        }
+        assert!(table.open("office", 1, 80).is_none());
+        assert!(table.open("home", 2, 22).is_none());
+        table.close(&keys[0]);
+        assert!(table.open("office", 1, 80).is_some());
+    }
+}
@@ -0,0 +1,224 @@
+use std::io;
+use std::net::Ipv4Addr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::core::ingress::{Socks5User, Target};
+
+const VERSION: u8 = 5;
+/// 用户名密码认证(RFC 1929)
+const METHOD_PASSWORD: u8 = 2;
+const NO_ACCEPTABLE_METHODS: u8 = 0xff;
+const CMD_CONNECT: u8 = 1;
+const ATYP_IPV4: u8 = 1;
+const ATYP_DOMAIN: u8 = 3;
+
+pub const SUCCEEDED: u8 = 0;
+pub const GENERAL_FAILURE: u8 = 1;
+pub const HOST_UNREACHABLE: u8 = 4;
+pub const CONNECTION_REFUSED: u8 = 5;
+const COMMAND_NOT_SUPPORTED: u8 = 7;
+const ADDRESS_NOT_SUPPORTED: u8 = 8;
+
+/// CONNECT请求，域名按组内设备的名称解析
+pub struct Request {
+    // 认证通过的用户在列表中的位置
+    pub user: usize,
+    pub target: Target,
+    pub port: u16,
+}
+
+/// 完成协商、认证并读取CONNECT请求，只支持用户名密码认证，认证失败返回PermissionDenied
+pub async fn accept(stream: &mut TcpStream, users: &[Socks5User]) -> io::Result<Request> {
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).await?;
+    if head[0] != VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not socks5"));
+    }
+    let mut methods = vec![0u8; head[1] as usize];
+    stream.read_exact(&mut methods).await?;
+    if !methods.contains(&METHOD_PASSWORD) {
+        stream.write_all(&[VERSION, NO_ACCEPTABLE_METHODS]).await?;
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "password auth required",
+        ));
+    }
+    stream.write_all(&[VERSION, METHOD_PASSWORD]).await?;
+    let user = authenticate(stream, users).await?;
+    let mut head = [0u8; 4];
+    stream.read_exact(&mut head).await?;
+    if head[0] != VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not socks5"));
+    }
+    let target = match head[3] {
+        ATYP_IPV4 => {
+            let mut ip = [0u8; 4];
+            stream.read_exact(&mut ip).await?;
+            Target::Ip(Ipv4Addr::from(ip))
+        }
+        ATYP_DOMAIN => {
+            let len = stream.read_u8().await? as usize;
+            let mut name = vec![0u8; len];
+            stream.read_exact(&mut name).await?;
+            let name = String::from_utf8(name)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid name"))?;
+            Target::Name(name)
+        }
+        _ => {
+            reply(stream, ADDRESS_NOT_SUPPORTED, None).await?;
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "address type not supported",
+            ));
+        }
+    };
+    let port = stream.read_u16().await?;
+    if head[1] != CMD_CONNECT {
+        reply(stream, COMMAND_NOT_SUPPORTED, None).await?;
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "command not supported",
+        ));
+    }
+    Ok(Request { user, target, port })
+}
+
+/// 用户名密码认证，返回用户在列表中的位置
+async fn authenticate(stream: &mut TcpStream, users: &[Socks5User]) -> io::Result<usize> {
+    // 子协商的版本号为1
+    if stream.read_u8().await? != 1 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "auth version"));
+    }
+    let len = stream.read_u8().await? as usize;
+    let mut username = vec![0u8; len];
+    stream.read_exact(&mut username).await?;
+    let len = stream.read_u8().await? as usize;
+    let mut password = vec![0u8; len];
+    stream.read_exact(&mut password).await?;
+    let user = users.iter().position(|user| {
+        user.username.as_bytes() == username && user.password.as_bytes() == password
+    });
+    match user {
+        Some(user) => {
+            stream.write_all(&[1, 0]).await?;
+            Ok(user)
+        }
+        None => {
+            stream.write_all(&[1, 1]).await?;
+            Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "invalid username or password",
+            ))
+        }
+    }
+}
+
+/// 回应CONNECT请求，bind为网关一侧的地址
+pub async fn reply(
+    stream: &mut TcpStream,
+    code: u8,
+    bind: Option<(Ipv4Addr, u16)>,
+) -> io::Result<()> {
+    let (ip, port) = bind.unwrap_or((Ipv4Addr::UNSPECIFIED, 0));
+    let mut buf = [0u8; 10];
+    buf[0] = VERSION;
+    buf[1] = code;
+    buf[3] = ATYP_IPV4;
+    buf[4..8].copy_from_slice(&ip.octets());
+    buf[8..10].copy_from_slice(&port.to_be_bytes());
+    stream.write_all(&buf).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn users() -> Vec<Socks5User> {
+        vec!["alice:secret:office".parse().unwrap()]
+    }
+
+    /// 客户端一次发完数据，返回服务端的处理结果和客户端收到的回应
+    async fn run(client_bytes: &[u8]) -> (io::Result<Request>, Vec<u8>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+        client.write_all(client_bytes).await.unwrap();
+        client.shutdown().await.unwrap();
+        let rs = accept(&mut server, &users()).await;
+        // 读完未处理的数据再关闭，否则关闭时会重置连接
+        server.read_to_end(&mut Vec::new()).await.unwrap();
+        drop(server);
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).await.unwrap();
+        (rs, received)
+    }
+
+    fn auth(username: &[u8], password: &[u8]) -> Vec<u8> {
+        let mut buf = vec![VERSION, 1, METHOD_PASSWORD, 1, username.len() as u8];
+        buf.extend_from_slice(username);
+        buf.push(password.len() as u8);
+        buf.extend_from_slice(password);
+        buf
+    }
+
+    #[tokio::test]
+    async fn connect_by_ip_and_name() {
+        let mut bytes = auth(b"alice", b"secret");
+        bytes.extend_from_slice(&[VERSION, CMD_CONNECT, 0, ATYP_IPV4, 10, 26, 0, 2, 0, 80]);
+        let (rs, received) = run(&bytes).await;
+        let request = rs.unwrap();
+        assert_eq!(request.user, 0);
+        assert!(matches!(request.target, Target::Ip(ip) if ip == Ipv4Addr::new(10, 26, 0, 2)));
+        assert_eq!(request.port, 80);
+        assert_eq!(received, [VERSION, METHOD_PASSWORD, 1, 0]);
+
+        let mut bytes = auth(b"alice", b"secret");
+        bytes.extend_from_slice(&[VERSION, CMD_CONNECT, 0, ATYP_DOMAIN, 3]);
+        bytes.extend_from_slice(b"nas");
+        bytes.extend_from_slice(&22u16.to_be_bytes());
+        let request = run(&bytes).await.0.unwrap();
+        assert!(matches!(request.target, Target::Name(name) if name == "nas"));
+        assert_eq!(request.port, 22);
+    }
+
+    #[tokio::test]
+    async fn requires_password_method() {
+        // 只提供无认证方式
+        let (rs, received) = run(&[VERSION, 1, 0]).await;
+        assert_eq!(rs.err().unwrap().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(received, [VERSION, NO_ACCEPTABLE_METHODS]);
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_password() {
+        let (rs, received) = run(&auth(b"alice", b"wrong")).await;
+        assert_eq!(rs.err().unwrap().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(received, [VERSION, METHOD_PASSWORD, 1, 1]);
+        let (rs, _) = run(&auth(b"bob", b"secret")).await;
+        assert_eq!(rs.err().unwrap().kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn rejects_unsupported_requests() {
+        // BIND命令
+        let mut bytes = auth(b"alice", b"secret");
+        bytes.extend_from_slice(&[VERSION, 2, 0, ATYP_IPV4, 10, 26, 0, 2, 0, 80]);
+        let (rs, received) = run(&bytes).await;
+        assert_eq!(rs.err().unwrap().kind(), io::ErrorKind::Unsupported);
+        assert_eq!(received[4..6], [VERSION, COMMAND_NOT_SUPPORTED]);
+
+        // ipv6地址
+        let mut bytes = auth(b"alice", b"secret");
+        bytes.extend_from_slice(&[VERSION, CMD_CONNECT, 0, 4]);
+        bytes.extend_from_slice(&[0; 16]);
+        bytes.extend_from_slice(&80u16.to_be_bytes());
+        let (rs, received) = run(&bytes).await;
+        assert_eq!(rs.err().unwrap().kind(), io::ErrorKind::Unsupported);
+        assert_eq!(received[4..6], [VERSION, ADDRESS_NOT_SUPPORTED]);
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::VecDeque;
+use std::io;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use packet::ip::ipv4;
+use packet::ip::ipv4::packet::IpV4Packet;
+use packet::tcp;
+use packet::tcp::tcp::TcpPacket;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::Receiver;
+use tokio::time::{sleep_until, timeout_at, Instant};
+
+use crate::core::service::PacketHandler;
+
+/// 发送的数据段大小上限，留出中继协议头部和加密的开销，避免数据包需要分片
+const MSS: usize = 1200;
+/// 已发送未确认的数据上限
+const MAX_IN_FLIGHT: usize = 64 * 1024;
+/// 通告给设备的接收窗口，收到的数据直接写入代理连接，不在服务端缓存
+const WINDOW: u16 = u16::MAX;
+const INITIAL_RTO: Duration = Duration::from_secs(1);
+const MAX_RTO: Duration = Duration::from_secs(16);
+/// SYN的重传次数
+const SYN_RETRIES: u32 = 4;
+/// 数据连续重传的次数上限，超过后认为设备已不可达
+const MAX_RETRIES: u32 = 8;
+/// 双方都没有数据超过该时长后关闭连接
+const IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// 连接两端的地址，local为组的网关ip
+pub struct Endpoint {
+    pub group: String,
+    pub local: (Ipv4Addr, u16),
+    pub remote: (Ipv4Addr, u16),
+}
+
+/// 设备发给网关的tcp数据段
+struct Segment {
+    sequence: u32,
+    acknowledgment: u32,
+    flags: u8,
+    window: u16,
+    data: Vec<u8>,
+    mss: Option<u16>,
+}
+
+impl Segment {
+    fn parse(ipv4: &[u8]) -> Option<Segment> {
+        let packet = IpV4Packet::new(ipv4).ok()?;
+        if packet.protocol() != ipv4::protocol::Protocol::Tcp {
+            return None;
+        }
+        let end = (packet.length() as usize).min(ipv4.len());
+        let start = packet.header().len();
+        if end < start {
+            return None;
+        }
+        let tcp_packet = TcpPacket::new(
+            packet.source_ip(),
+            packet.destination_ip(),
+            &ipv4[start..end],
+        )
+        .ok()?;
+        if !tcp_packet.is_valid() {
+            return None;
+        }
+        Some(Segment {
+            sequence: tcp_packet.sequence(),
+            acknowledgment: tcp_packet.acknowledgment(),
+            flags: tcp_packet.flags().bits(),
+            window: tcp_packet.window(),
+            data: tcp_packet.payload().to_vec(),
+            mss: mss_option(tcp_packet.options()),
+        })
+    }
+}
+
+/// 从tcp选项中取出mss
+fn mss_option(options: &[u8]) -> Option<u16> {
+    let mut i = 0;
+    while i < options.len() {
+        match options[i] {
+            0 => return None,
+            1 => i += 1,
+            kind => {
+                let len = *options.get(i + 1)? as usize;
+                if len < 2 || i + len > options.len() {
+                    return None;
+                }
+                if kind == 2 && len == 4 {
+                    return Some(u16::from_be_bytes([options[i + 2], options[i + 3]]));
+                }
+                i += len;
+            }
+        }
+    }
+    None
+}
+
+/// 服务端以组的网关ip作为tcp客户端连接组内设备，数据包经推送给设备的路径发送，设备使用自己的协议栈应答
+///
+/// 简化实现：不做拥塞控制，在途数据有固定上限，超时后从未确认处整体重传，乱序到达的数据丢弃等待设备重传
+pub struct VirtualTcp {
+    endpoint: Endpoint,
+    handler: PacketHandler,
+    inbound: Receiver<Vec<u8>>,
+    // 第一个未确认字节的序号
+    snd_una: u32,
+    // 已发送未确认的数据，从snd_una开始
+    unacked: VecDeque<u8>,
+    // 设备通告的接收窗口
+    peer_window: usize,
+    mss: usize,
+    // 期望收到的下一个序号
+    rcv_nxt: u32,
+    fin_sent: bool,
+    fin_acked: bool,
+    fin_received: bool,
+}
+
+impl VirtualTcp {
+    /// 三次握手，设备回应RST时返回ConnectionRefused，多次重传没有回应时返回TimedOut
+    pub async fn connect(
+        endpoint: Endpoint,
+        handler: PacketHandler,
+        inbound: Receiver<Vec<u8>>,
+    ) -> io::Result<VirtualTcp> {
+        let iss = rand::random::<u32>();
+        let mut connection = VirtualTcp {
+            endpoint,
+            handler,
+            inbound,
+            snd_una: iss.wrapping_add(1),
+            unacked: VecDeque::new(),
+            peer_window: 0,
+            mss: MSS,
+            rcv_nxt: 0,
+            fin_sent: false,
+            fin_acked: false,
+            fin_received: false,
+        };
+        let mut rto = INITIAL_RTO;
+        for _ in 0..=SYN_RETRIES {
+            connection.send(iss, tcp::SYN, &[]);
+            let deadline = Instant::now() + rto;
+            loop {
+                let packet = match timeout_at(deadline, connection.inbound.recv()).await {
+                    Ok(Some(packet)) => packet,
+                    Ok(None) => return Err(io::Error::other("connection closed")),
+                    Err(_) => break,
+                };
+                let Some(segment) = Segment::parse(&packet) else {
+                    continue;
+                };
+                if segment.acknowledgment != iss.wrapping_add(1) {
+                    continue;
+                }
+                if segment.flags & tcp::RST != 0 {
+                    return Err(io::Error::from(io::ErrorKind::ConnectionRefused));
+                }
+                if segment.flags & (tcp::SYN | tcp::ACK) == tcp::SYN | tcp::ACK {
+                    connection.rcv_nxt = segment.sequence.wrapping_add(1);
+                    connection.peer_window = segment.window as usize;
+                    if let Some(mss) = segment.mss {
+                        connection.mss = MSS.min(mss.max(64) as usize);
+                    }
+                    connection.send_ack();
+                    return Ok(connection);
+                }
+            }
+            rto = (rto * 2).min(MAX_RTO);
+        }
+        Err(io::Error::from(io::ErrorKind::TimedOut))
+    }
+    /// 在代理连接和设备之间转发数据，双方都关闭后返回
+    ///
+    /// initial为已经从代理连接读取的数据，先发给设备
+    pub async fn relay(mut self, stream: TcpStream, initial: Vec<u8>) -> io::Result<()> {
+        let (mut reader, mut writer) = stream.into_split();
+        let mut buf = vec![0u8; MAX_IN_FLIGHT];
+        let mut local_eof = false;
+        let mut rto = INITIAL_RTO;
+        let mut retries = 0;
+        let mut retransmit_at: Option<Instant> = None;
+        if !initial.is_empty() {
+            self.send_data(&initial);
+            retransmit_at = Some(Instant::now() + rto);
+        }
+        let mut idle_at = Instant::now() + IDLE_TIMEOUT;
+        while !(self.fin_received && self.fin_acked) {
+            let space = MAX_IN_FLIGHT
+                .min(self.peer_window)
+                .saturating_sub(self.unacked.len());
+            let readable = !local_eof && space > 0;
+            tokio::select! {
+                packet = self.inbound.recv() => {
+                    let Some(packet) = packet else {
+                        return Err(io::Error::other("connection closed"));
+                    };
+                    let Some(segment) = Segment::parse(&packet) else {
+                        continue;
+                    };
+                    idle_at = Instant::now() + IDLE_TIMEOUT;
+                    if self.on_segment(segment, &mut writer).await? {
+                        rto = INITIAL_RTO;
+                        retries = 0;
+                        retransmit_at = self.outstanding().then(|| Instant::now() + rto);
+                    }
+                }
+                rs = reader.read(&mut buf[..space.min(MAX_IN_FLIGHT)]), if readable => {
+                    let len = rs?;
+                    idle_at = Instant::now() + IDLE_TIMEOUT;
+                    if len == 0 {
+                        local_eof = true;
+                        self.send_fin();
+                    } else {
+                        self.send_data(&buf[..len]);
+                    }
+                    if retransmit_at.is_none() {
+                        retransmit_at = Some(Instant::now() + rto);
+                    }
+                }
+                _ = sleep_until(retransmit_at.unwrap_or(idle_at)), if retransmit_at.is_some() => {
+                    retries += 1;
+                    if retries > MAX_RETRIES {
+                        self.send_rst();
+                        return Err(io::Error::from(io::ErrorKind::TimedOut));
+                    }
+                    rto = (rto * 2).min(MAX_RTO);
+                    self.retransmit();
+                    retransmit_at = Some(Instant::now() + rto);
+                }
+                _ = sleep_until(idle_at) => {
+                    self.send_rst();
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "idle"));
+                }
+            }
+        }
+        Ok(())
+    }
+    /// 处理设备发来的数据段，返回是否确认了新的数据
+    async fn on_segment(
+        &mut self,
+        segment: Segment,
+        writer: &mut OwnedWriteHalf,
+    ) -> io::Result<bool> {
+        if segment.flags & tcp::RST != 0 {
+            return Err(io::Error::from(io::ErrorKind::ConnectionReset));
+        }
+        let mut progress = false;
+        if segment.flags & tcp::ACK != 0 {
+            let acked = segment.acknowledgment.wrapping_sub(self.snd_una) as usize;
+            let outstanding = self.unacked.len() + (self.fin_sent && !self.fin_acked) as usize;
+            if acked > 0 && acked <= outstanding {
+                let data = acked.min(self.unacked.len());
+                self.unacked.drain(..data);
+                if acked > data {
+                    self.fin_acked = true;
+                }
+                self.snd_una = segment.acknowledgment;
+                progress = true;
+            }
+            self.peer_window = segment.window as usize;
+        }
+        let mut need_ack = false;
+        if segment.flags & tcp::SYN != 0 {
+            // 握手的ACK丢失，设备重传了SYN|ACK
+            need_ack = true;
+        } else if !segment.data.is_empty() {
+            if segment.sequence == self.rcv_nxt && !self.fin_received {
+                writer.write_all(&segment.data).await?;
+                self.rcv_nxt = self.rcv_nxt.wrapping_add(segment.data.len() as u32);
+            }
+            need_ack = true;
+        }
+        if segment.flags & tcp::FIN != 0 {
+            let fin_sequence = segment.sequence.wrapping_add(segment.data.len() as u32);
+            if !self.fin_received && fin_sequence == self.rcv_nxt {
+                self.fin_received = true;
+                self.rcv_nxt = self.rcv_nxt.wrapping_add(1);
+                let _ = writer.shutdown().await;
+            }
+            need_ack = true;
+        }
+        if need_ack {
+            self.send_ack();
+        }
+        Ok(progress)
+    }
+    fn outstanding(&self) -> bool {
+        !self.unacked.is_empty() || (self.fin_sent && !self.fin_acked)
+    }
+    fn send_data(&mut self, data: &[u8]) {
+        let mut sequence = self.snd_una.wrapping_add(self.unacked.len() as u32);
+        for chunk in data.chunks(self.mss) {
+            self.send(sequence, tcp::ACK | tcp::PSH, chunk);
+            sequence = sequence.wrapping_add(chunk.len() as u32);
+        }
+        self.unacked.extend(data);
+    }
+    fn send_fin(&mut self) {
+        let sequence = self.snd_una.wrapping_add(self.unacked.len() as u32);
+        self.send(sequence, tcp::FIN | tcp::ACK, &[]);
+        self.fin_sent = true;
+    }
+    fn send_ack(&self) {
+        let sequence = self.snd_una.wrapping_add(self.unacked.len() as u32);
+        let sequence = sequence.wrapping_add(self.fin_sent as u32);
+        self.send(sequence, tcp::ACK, &[]);
+    }
+    fn send_rst(&self) {
+        let sequence = self.snd_una.wrapping_add(self.unacked.len() as u32);
+        self.send(sequence, tcp::RST | tcp::ACK, &[]);
+    }
+    /// 从第一个未确认的字节开始重传，设备的接收窗口为0时只发送一个数据段作为探测
+    fn retransmit(&self) {
+        let limit = self.peer_window.max(self.mss).min(self.unacked.len());
+        let data: Vec<u8> = self.unacked.iter().take(limit).copied().collect();
+        let mut sequence = self.snd_una;
+        for chunk in data.chunks(self.mss) {
+            self.send(sequence, tcp::ACK | tcp::PSH, chunk);
+            sequence = sequence.wrapping_add(chunk.len() as u32);
+        }
+        if self.fin_sent && !self.fin_acked && limit == self.unacked.len() {
+            self.send(sequence, tcp::FIN | tcp::ACK, &[]);
+        }
+    }
+    /// 构造ipv4数据包推送给设备，SYN带上mss选项
+    fn send(&self, sequence: u32, flags: u8, data: &[u8]) {
+        let (local_ip, local_port) = self.endpoint.local;
+        let (remote_ip, remote_port) = self.endpoint.remote;
+        let syn = flags & tcp::SYN != 0;
+        let tcp_len = if syn { 24 } else { 20 };
+        let len = 20 + tcp_len + data.len();
+        let mut buf = vec![0u8; len];
+        let mut packet = IpV4Packet::unchecked(&mut buf);
+        packet.set_version_and_header_len(5);
+        packet.set_length(len as u16);
+        packet.set_id(rand::random());
+        // 不分片
+        packet.set_flags(0b010);
+        packet.set_ttl(64);
+        packet.set_protocol(ipv4::protocol::Protocol::Tcp);
+        packet.set_source_ip(local_ip);
+        packet.set_destination_ip(remote_ip);
+        packet.update_checksum();
+        if syn {
+            buf[40..44].copy_from_slice(&[2, 4, (MSS >> 8) as u8, MSS as u8]);
+        }
+        let mut segment = TcpPacket::unchecked(local_ip, remote_ip, &mut buf[20..]);
+        segment.set_source_port(local_port);
+        segment.set_destination_port(remote_port);
+        segment.set_sequence(sequence);
+        if flags & tcp::ACK != 0 {
+            segment.set_acknowledgment(self.rcv_nxt);
+        }
+        segment.set_data_offset(tcp_len as u8 / 4);
+        segment.set_flags(flags);
+        segment.set_window(WINDOW);
+        segment.payload_mut().copy_from_slice(data);
+        segment.update_checksum();
+        self.handler
+            .push_ipv4(&self.endpoint.group, remote_ip.into(), &buf);
+    }
+}
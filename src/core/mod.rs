@@ -1,5 +1,26 @@
+mod alert;
+mod ddns;
 mod entity;
+mod ingress;
+mod offload;
+mod port_mapping;
+mod public_addr;
+mod runtime;
 mod server;
 mod service;
 mod store;
+pub mod task;
+pub use alert::{AlertConfig, AlertRule, EmailConfig, EmailTemplate, SmtpServer, WebhookUrl};
+pub use ddns::{DdnsConfig, DdnsProvider, DEFAULT_IP_URL};
+pub use entity::{
+    default_config, parse_bytes, AddrRebind, AddressPool, Bandwidth, BlockPolicy, GatewayIcmp,
+    GroupPolicy, IpAllocation, IpRange, Lang, Mtu, RawBroadcast, SendRule, SourceNet, TagRule,
+};
+pub use ingress::{HttpIngressConfig, HttpRoute, Socks5Config, Socks5User};
+pub use port_mapping::{MappingMode, PortMappingConfig};
+pub use public_addr::AddrSource;
+pub use runtime::{parse_cpu_list, RuntimeConfig};
 pub use server::start;
+pub use service::messages::Messages;
+pub use store::snapshot::{S3Location, SnapshotConfig};
+pub use store::state::StateDump;
@@ -0,0 +1,189 @@
+use std::io;
+use std::net::SocketAddr;
+
+use tokio::net::UdpSocket;
+
+/// 一次分段发送的最大段数，内核限制为64
+pub const MAX_SEGMENTS: usize = 64;
+/// 一次分段发送的最大字节数，不能超过udp数据报的上限
+pub const MAX_SEGMENT_BYTES: usize = 65000;
+
+/// udp分段发送(GSO)和接收合并(GRO)，只在linux上可用
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UdpOffload {
+    // 发往同一地址的多个数据包一次发送，由内核或网卡分段
+    pub gso: bool,
+    // 同一来源的多个数据包合并后一次接收
+    pub gro: bool,
+}
+
+/// 检测内核是否支持，并在socket上开启接收合并
+pub fn enable(udp: &UdpSocket) -> UdpOffload {
+    #[cfg(target_os = "linux")]
+    {
+        linux::enable(udp)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = udp;
+        UdpOffload::default()
+    }
+}
+
+/// 开启接收合并后接收数据，同时返回分段大小，没有合并时分段大小为None
+pub async fn recv_from(
+    udp: &UdpSocket,
+    buf: &mut [u8],
+) -> io::Result<(usize, SocketAddr, Option<usize>)> {
+    #[cfg(target_os = "linux")]
+    {
+        udp.async_io(tokio::io::Interest::READABLE, || linux::recv_gro(udp, buf))
+            .await
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let (len, addr) = udp.recv_from(buf).await?;
+        Ok((len, addr, None))
+    }
+}
+
+/// 把buf按segment_size分段发送到同一地址，最后一段可以更短
+pub async fn send_segments(
+    udp: &UdpSocket,
+    buf: &[u8],
+    segment_size: usize,
+    addr: SocketAddr,
+) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        udp.async_io(tokio::io::Interest::WRITABLE, || {
+            linux::send_gso(udp, buf, segment_size, addr)
+        })
+        .await
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        for segment in buf.chunks(segment_size) {
+            udp.send_to(segment, addr).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::io;
+    use std::mem::{size_of, size_of_val, zeroed};
+    use std::net::SocketAddr;
+    use std::os::fd::AsRawFd;
+    use std::ptr;
+
+    use libc::{c_int, c_void, socklen_t};
+    use socket2::SockAddr;
+    use tokio::net::UdpSocket;
+
+    use super::UdpOffload;
+
+    pub fn enable(udp: &UdpSocket) -> UdpOffload {
+        let fd = udp.as_raw_fd();
+        let mut value: c_int = 0;
+        let mut len = size_of::<c_int>() as socklen_t;
+        // 能读取UDP_SEGMENT选项说明内核支持分段发送
+        let gso = unsafe {
+            libc::getsockopt(
+                fd,
+                libc::SOL_UDP,
+                libc::UDP_SEGMENT,
+                &mut value as *mut c_int as *mut c_void,
+                &mut len,
+            )
+        } == 0;
+        let enable: c_int = 1;
+        let gro = unsafe {
+            libc::setsockopt(
+                fd,
+                libc::SOL_UDP,
+                libc::UDP_GRO,
+                &enable as *const c_int as *const c_void,
+                size_of::<c_int>() as socklen_t,
+            )
+        } == 0;
+        UdpOffload { gso, gro }
+    }
+
+    pub fn recv_gro(
+        udp: &UdpSocket,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr, Option<usize>)> {
+        let fd = udp.as_raw_fd();
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut c_void,
+            iov_len: buf.len(),
+        };
+        // u64保证cmsghdr的对齐
+        let mut control = [0u64; 8];
+        let mut segment_size = None;
+        let (len, addr) = unsafe {
+            SockAddr::try_init(|storage, storage_len| {
+                let mut msg: libc::msghdr = zeroed();
+                msg.msg_name = storage as *mut c_void;
+                msg.msg_namelen = *storage_len;
+                msg.msg_iov = &mut iov;
+                msg.msg_iovlen = 1;
+                msg.msg_control = control.as_mut_ptr() as *mut c_void;
+                msg.msg_controllen = size_of_val(&control) as _;
+                let len = libc::recvmsg(fd, &mut msg, 0);
+                if len < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                *storage_len = msg.msg_namelen;
+                let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+                while !cmsg.is_null() {
+                    if (*cmsg).cmsg_level == libc::SOL_UDP && (*cmsg).cmsg_type == libc::UDP_GRO {
+                        let size = ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const c_int);
+                        segment_size = Some(size as usize);
+                    }
+                    cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+                }
+                Ok(len as usize)
+            })?
+        };
+        let addr = addr
+            .as_socket()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid address"))?;
+        Ok((len, addr, segment_size))
+    }
+
+    pub fn send_gso(
+        udp: &UdpSocket,
+        buf: &[u8],
+        segment_size: usize,
+        addr: SocketAddr,
+    ) -> io::Result<()> {
+        let fd = udp.as_raw_fd();
+        let addr = SockAddr::from(addr);
+        let mut iov = libc::iovec {
+            iov_base: buf.as_ptr() as *mut c_void,
+            iov_len: buf.len(),
+        };
+        let mut control = [0u64; 4];
+        unsafe {
+            let mut msg: libc::msghdr = zeroed();
+            msg.msg_name = addr.as_ptr() as *mut c_void;
+            msg.msg_namelen = addr.len();
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr() as *mut c_void;
+            msg.msg_controllen = libc::CMSG_SPACE(size_of::<u16>() as u32) as _;
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_UDP;
+            (*cmsg).cmsg_type = libc::UDP_SEGMENT;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<u16>() as u32) as _;
+            ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut u16, segment_size as u16);
+            if libc::sendmsg(fd, &msg, 0) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use std::io;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::str::FromStr;
+use std::time::Duration;
+
+mod natpmp;
+mod upnp;
+
+/// 映射的租期，到期前续租
+const LEASE: Duration = Duration::from_secs(3600);
+/// 映射失败后重试的间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 端口映射协议
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MappingProtocol {
+    NatPmp,
+    Upnp,
+}
+
+/// 端口映射方式，auto表示先尝试nat-pmp，失败后使用upnp
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MappingMode(pub Vec<MappingProtocol>);
+
+impl FromStr for MappingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "natpmp" | "nat-pmp" => Ok(MappingMode(vec![MappingProtocol::NatPmp])),
+            "upnp" => Ok(MappingMode(vec![MappingProtocol::Upnp])),
+            "auto" => Ok(MappingMode(vec![
+                MappingProtocol::NatPmp,
+                MappingProtocol::Upnp,
+            ])),
+            _ => Err(format!("'{}' must be natpmp/upnp/auto", s)),
+        }
+    }
+}
+
+/// 端口映射配置
+#[derive(Clone, Debug)]
+pub struct PortMappingConfig {
+    pub mode: MappingMode,
+    // 路由器地址，nat-pmp使用，不指定时使用默认网关
+    pub gateway: Option<Ipv4Addr>,
+    // 需要映射的端口，true表示tcp
+    pub ports: Vec<(u16, bool)>,
+}
+
+/// 定时申请和续租端口映射，外部地址变化时输出日志
+pub async fn port_mapping_task(config: PortMappingConfig) {
+    let mut mapped: Vec<Option<SocketAddrV4>> = vec![None; config.ports.len()];
+    loop {
+        let mut success = true;
+        for (index, (port, tcp)) in config.ports.iter().copied().enumerate() {
+            let protocol = if tcp { "tcp" } else { "udp" };
+            match map_port(&config, port, tcp).await {
+                Ok((mapping_protocol, external)) => {
+                    if mapped[index] != Some(external) {
+                        log::info!(
+                            "端口映射成功 {:?},{} {} -> {}",
+                            mapping_protocol,
+                            protocol,
+                            port,
+                            external
+                        );
+                        println!("端口映射: {} {} -> {}", protocol, port, external);
+                        mapped[index] = Some(external);
+                    }
+                }
+                Err(e) => {
+                    success = false;
+                    log::warn!("端口映射失败 {} {},{:?}", protocol, port, e);
+                }
+            }
+        }
+        let interval = if success { LEASE / 2 } else { RETRY_INTERVAL };
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn map_port(
+    config: &PortMappingConfig,
+    port: u16,
+    tcp: bool,
+) -> io::Result<(MappingProtocol, SocketAddrV4)> {
+    let mut last_err = io::Error::other("no mapping protocol");
+    for protocol in &config.mode.0 {
+        let rs = match protocol {
+            MappingProtocol::NatPmp => {
+                let gateway = match config.gateway.or_else(default_gateway) {
+                    Some(gateway) => gateway,
+                    None => {
+                        last_err = io::Error::other("default gateway not found");
+                        continue;
+                    }
+                };
+                natpmp::map(gateway, port, tcp, LEASE).await
+            }
+            MappingProtocol::Upnp => upnp::map(port, tcp, LEASE).await,
+        };
+        match rs {
+            Ok(external) => return Ok((*protocol, external)),
+            Err(e) => {
+                log::debug!(
+                    "{:?}端口映射失败 port={},tcp={},{:?}",
+                    protocol,
+                    port,
+                    tcp,
+                    e
+                );
+                last_err = e;
+            }
+        }
+    }
+    Err(last_err)
+}
+
+/// 从路由表中读取ipv4默认网关
+#[cfg(target_os = "linux")]
+fn default_gateway() -> Option<Ipv4Addr> {
+    let route = std::fs::read_to_string("/proc/net/route").ok()?;
+    route.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.get(1) != Some(&"00000000") {
+            return None;
+        }
+        // 小端序的十六进制
+        let gateway = u32::from_str_radix(fields.get(2)?, 16).ok()?;
+        Some(Ipv4Addr::from(gateway.to_le_bytes()))
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_gateway() -> Option<Ipv4Addr> {
+    None
+}
@@ -0,0 +1,56 @@
+use std::io;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+
+const NAT_PMP_PORT: u16 = 5351;
+
+/// 通过nat-pmp申请映射，外部端口优先使用相同的端口
+pub async fn map(
+    gateway: Ipv4Addr,
+    port: u16,
+    tcp: bool,
+    lease: Duration,
+) -> io::Result<SocketAddrV4> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.connect((gateway, NAT_PMP_PORT)).await?;
+    let response = request(&socket, &[0, 0]).await?;
+    let external_ip = Ipv4Addr::new(response[8], response[9], response[10], response[11]);
+    let op = if tcp { 2 } else { 1 };
+    let mut map_request = vec![0, op, 0, 0];
+    map_request.extend_from_slice(&port.to_be_bytes());
+    map_request.extend_from_slice(&port.to_be_bytes());
+    map_request.extend_from_slice(&(lease.as_secs() as u32).to_be_bytes());
+    let response = request(&socket, &map_request).await?;
+    if response.len() < 16 || response[1] != 128 + op {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid nat-pmp response",
+        ));
+    }
+    let external_port = u16::from_be_bytes([response[10], response[11]]);
+    Ok(SocketAddrV4::new(external_ip, external_port))
+}
+
+/// 发送请求，按照协议从250毫秒开始每次加倍等待时间重试
+async fn request(socket: &UdpSocket, request: &[u8]) -> io::Result<Vec<u8>> {
+    let mut buf = [0u8; 64];
+    let mut timeout = Duration::from_millis(250);
+    for _ in 0..4 {
+        socket.send(request).await?;
+        if let Ok(len) = tokio::time::timeout(timeout, socket.recv(&mut buf)).await {
+            let len = len?;
+            if len < 12 || buf[0] != 0 || buf[1] != 128 + request[1] {
+                continue;
+            }
+            let result = u16::from_be_bytes([buf[2], buf[3]]);
+            if result != 0 {
+                return Err(io::Error::other(format!("nat-pmp result code {}", result)));
+            }
+            return Ok(buf[..len].to_vec());
+        }
+        timeout *= 2;
+    }
+    Err(io::Error::new(io::ErrorKind::TimedOut, "nat-pmp timeout"))
+}
@@ -0,0 +1,170 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddrV4};
+use std::str::FromStr;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+
+use crate::core::alert::WebhookUrl;
+use crate::core::store::snapshot::parse_response;
+
+const SSDP_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::new(239, 255, 255, 250), 1900);
+const SERVICE_TYPES: [&str; 2] = [
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+
+/// 网关设备的控制地址
+struct Gateway {
+    control: WebhookUrl,
+    service_type: &'static str,
+    // 本机访问网关使用的地址
+    local_ip: Ipv4Addr,
+}
+
+/// 通过upnp-igd申请映射，外部端口使用相同的端口
+pub async fn map(port: u16, tcp: bool, lease: Duration) -> io::Result<SocketAddrV4> {
+    let gateway = discover().await?;
+    let protocol = if tcp { "TCP" } else { "UDP" };
+    let args = format!(
+        "<NewRemoteHost></NewRemoteHost><NewExternalPort>{}</NewExternalPort>\
+         <NewProtocol>{}</NewProtocol><NewInternalPort>{}</NewInternalPort>\
+         <NewInternalClient>{}</NewInternalClient><NewEnabled>1</NewEnabled>\
+         <NewPortMappingDescription>vnts</NewPortMappingDescription>\
+         <NewLeaseDuration>{}</NewLeaseDuration>",
+        port,
+        protocol,
+        port,
+        gateway.local_ip,
+        lease.as_secs()
+    );
+    soap(&gateway, "AddPortMapping", &args).await?;
+    let response = soap(&gateway, "GetExternalIPAddress", "").await?;
+    let external_ip = tag_value(&response, "NewExternalIPAddress")
+        .and_then(|ip| Ipv4Addr::from_str(ip.trim()).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no external ip"))?;
+    Ok(SocketAddrV4::new(external_ip, port))
+}
+
+/// 用ssdp搜索网关设备，再从设备描述中找到wan连接服务的控制地址
+async fn discover() -> io::Result<Gateway> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    let request = "M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\n\
+                   MAN: \"ssdp:discover\"\r\nMX: 2\r\n\
+                   ST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\r\n";
+    socket.send_to(request.as_bytes(), SSDP_ADDR).await?;
+    let mut buf = [0u8; 2048];
+    let location = loop {
+        let (len, _) = tokio::time::timeout(Duration::from_secs(3), socket.recv_from(&mut buf))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "no upnp gateway found"))??;
+        let response = String::from_utf8_lossy(&buf[..len]);
+        let location = response.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.trim()
+                .eq_ignore_ascii_case("location")
+                .then(|| value.trim().to_string())
+        });
+        if let Some(location) = location {
+            break location;
+        }
+    };
+    let location = WebhookUrl::from_str(&location).map_err(io::Error::other)?;
+    let description = http(&location, "GET", &[], "").await?;
+    for service_type in SERVICE_TYPES {
+        let Some(index) = description.find(service_type) else {
+            continue;
+        };
+        let Some(control_url) = tag_value(&description[index..], "controlURL") else {
+            continue;
+        };
+        let control = if control_url.starts_with("http://") {
+            WebhookUrl::from_str(control_url).map_err(io::Error::other)?
+        } else {
+            WebhookUrl {
+                host: location.host.clone(),
+                port: location.port,
+                path: format!("/{}", control_url.trim_start_matches('/')),
+            }
+        };
+        let local_ip = local_ip(&control).await?;
+        return Ok(Gateway {
+            control,
+            service_type,
+            local_ip,
+        });
+    }
+    Err(io::Error::other("no wan connection service"))
+}
+
+async fn local_ip(control: &WebhookUrl) -> io::Result<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket
+        .connect((control.host.as_str(), control.port))
+        .await?;
+    match socket.local_addr()?.ip() {
+        IpAddr::V4(ip) => Ok(ip),
+        IpAddr::V6(_) => Err(io::Error::other("ipv6 gateway not supported")),
+    }
+}
+
+async fn soap(gateway: &Gateway, action: &str, args: &str) -> io::Result<String> {
+    let body = format!(
+        "<?xml version=\"1.0\"?>\r\n<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+         s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\"><s:Body>\
+         <u:{action} xmlns:u=\"{service}\">{args}</u:{action}></s:Body></s:Envelope>",
+        action = action,
+        service = gateway.service_type,
+        args = args
+    );
+    let soap_action = format!("\"{}#{}\"", gateway.service_type, action);
+    http(
+        &gateway.control,
+        "POST",
+        &[
+            ("Content-Type", "text/xml; charset=\"utf-8\""),
+            ("SOAPAction", &soap_action),
+        ],
+        &body,
+    )
+    .await
+}
+
+async fn http(
+    url: &WebhookUrl,
+    method: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> io::Result<String> {
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        method,
+        url.path,
+        url.host,
+        url.port,
+        body.len()
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    let mut stream = tokio::time::timeout(
+        Duration::from_secs(5),
+        TcpStream::connect((url.host.as_str(), url.port)),
+    )
+    .await??;
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    let mut response = Vec::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut response)).await??;
+    let body = parse_response(&response)?;
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// 取第一个名为name的标签的内容，忽略命名空间前缀
+fn tag_value<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("{}>", name))? + name.len() + 1;
+    let end = xml[start..].find("</")? + start;
+    Some(&xml[start..end])
+}
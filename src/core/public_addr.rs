@@ -0,0 +1,232 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use tokio::net::UdpSocket;
+
+use crate::protocol::control_packet::{self, AddrPacket};
+use crate::protocol::{NetPacket, Protocol, MAX_TTL};
+
+/// 重新探测公网地址的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(300);
+/// stun协议的magic cookie
+const MAGIC_COOKIE: u32 = 0x2112A442;
+
+/// 服务端自己的公网地址，探测到之后在握手和注册响应中返回给客户端
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PublicAddr {
+    pub ipv4: Option<Ipv4Addr>,
+    pub ipv6: Option<Ipv6Addr>,
+}
+
+/// 公网地址的探测来源，格式为 stun://host[:port] 或者 vnts://host[:port]
+#[derive(Clone, Debug)]
+pub enum AddrSource {
+    // stun服务器，默认端口3478，可以同时探测ipv4和ipv6
+    Stun(String),
+    // 上游vnts，默认端口29872，只能探测ipv4
+    Vnts(String),
+}
+
+impl FromStr for AddrSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (authority, default_port, stun) = if let Some(rest) = s.strip_prefix("stun://") {
+            (rest, 3478, true)
+        } else if let Some(rest) = s.strip_prefix("vnts://") {
+            (rest, 29872, false)
+        } else {
+            return Err(format!("'{}' must start with stun:// or vnts://", s));
+        };
+        let authority = authority.trim_end_matches('/');
+        // 没有端口时补上默认端口，ipv6地址需要用[]括起来
+        let has_port = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                port.parse::<u16>()
+                    .map_err(|e| format!("'{}' port {}", s, e))?;
+                !host.is_empty() && (!host.contains(':') || host.ends_with(']'))
+            }
+            None => false,
+        };
+        if authority.is_empty() || authority.starts_with(':') {
+            return Err(format!("'{}' missing host", s));
+        }
+        let authority = if has_port {
+            authority.to_string()
+        } else {
+            format!("{}:{}", authority, default_port)
+        };
+        Ok(if stun {
+            AddrSource::Stun(authority)
+        } else {
+            AddrSource::Vnts(authority)
+        })
+    }
+}
+
+impl AddrSource {
+    fn authority(&self) -> &str {
+        match self {
+            AddrSource::Stun(authority) | AddrSource::Vnts(authority) => authority,
+        }
+    }
+    async fn query(&self, server: SocketAddr) -> io::Result<IpAddr> {
+        let bind: SocketAddr = if server.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(server).await?;
+        let (request, id) = match self {
+            AddrSource::Stun(_) => {
+                let id: [u8; 12] = rand::random();
+                (stun_request(&id), Some(id))
+            }
+            AddrSource::Vnts(_) => (vnts_request()?, None),
+        };
+        let mut buf = [0u8; 1024];
+        for _ in 0..3 {
+            socket.send(&request).await?;
+            let len =
+                match tokio::time::timeout(Duration::from_secs(1), socket.recv(&mut buf)).await {
+                    Ok(rs) => rs?,
+                    Err(_) => continue,
+                };
+            let ip = match &id {
+                Some(id) => parse_stun_response(&buf[..len], id),
+                None => parse_vnts_response(&buf[..len]).map(IpAddr::V4),
+            };
+            if let Some(ip) = ip {
+                return Ok(ip);
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::TimedOut, "no response"))
+    }
+}
+
+fn stun_request(id: &[u8; 12]) -> Vec<u8> {
+    let mut request = Vec::with_capacity(20);
+    // binding request，没有属性
+    request.extend_from_slice(&0x0001u16.to_be_bytes());
+    request.extend_from_slice(&0u16.to_be_bytes());
+    request.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    request.extend_from_slice(id);
+    request
+}
+
+/// 解析binding响应中的XOR-MAPPED-ADDRESS，没有时使用MAPPED-ADDRESS
+fn parse_stun_response(buf: &[u8], id: &[u8; 12]) -> Option<IpAddr> {
+    if buf.len() < 20
+        || buf[0..2] != 0x0101u16.to_be_bytes()
+        || buf[4..8] != MAGIC_COOKIE.to_be_bytes()
+        || &buf[8..20] != id
+    {
+        return None;
+    }
+    let len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+    let mut attrs = buf.get(20..20 + len)?;
+    let mut mapped = None;
+    while attrs.len() >= 4 {
+        let kind = u16::from_be_bytes([attrs[0], attrs[1]]);
+        let len = u16::from_be_bytes([attrs[2], attrs[3]]) as usize;
+        let value = attrs.get(4..4 + len)?;
+        match kind {
+            0x0020 => return parse_stun_address(value, Some(id)),
+            0x0001 => mapped = parse_stun_address(value, None),
+            _ => {}
+        }
+        // 属性按4字节对齐
+        attrs = attrs.get((4 + len + 3) & !3..).unwrap_or_default();
+    }
+    mapped
+}
+
+fn parse_stun_address(value: &[u8], xor_id: Option<&[u8; 12]>) -> Option<IpAddr> {
+    let mut mask = [0u8; 16];
+    if let Some(id) = xor_id {
+        mask[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        mask[4..].copy_from_slice(id);
+    }
+    let len = match value.get(1)? {
+        1 => 4,
+        2 => 16,
+        _ => return None,
+    };
+    let mut octets = [0u8; 16];
+    for (i, v) in value.get(4..4 + len)?.iter().enumerate() {
+        octets[i] = v ^ mask[i];
+    }
+    Some(if len == 4 {
+        IpAddr::V4(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(octets))
+    })
+}
+
+/// 和客户端一样向上游vnts发送地址请求
+fn vnts_request() -> io::Result<Vec<u8>> {
+    let mut packet = NetPacket::new(vec![0u8; 12])?;
+    packet.set_default_version();
+    packet.set_gateway_flag(true);
+    packet.set_protocol(Protocol::Control);
+    packet.set_transport_protocol(control_packet::Protocol::AddrRequest.into());
+    packet.first_set_ttl(MAX_TTL);
+    Ok(packet.into_buffer())
+}
+
+fn parse_vnts_response(buf: &[u8]) -> Option<Ipv4Addr> {
+    let packet = NetPacket::new(buf).ok()?;
+    if packet.is_encrypt()
+        || packet.protocol() != Protocol::Control
+        || control_packet::Protocol::from(packet.transport_protocol())
+            != control_packet::Protocol::AddrResponse
+    {
+        return None;
+    }
+    let ipv4 = AddrPacket::new(packet.payload()).ok()?.ipv4();
+    (!ipv4.is_unspecified()).then_some(ipv4)
+}
+
+/// 定时探测公网地址，所有来源都失败时保留上一次的结果
+pub async fn detect_task(sources: Vec<AddrSource>, public_addr: Arc<RwLock<PublicAddr>>) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let mut detected = PublicAddr::default();
+        for source in &sources {
+            let servers = match tokio::net::lookup_host(source.authority()).await {
+                Ok(servers) => servers,
+                Err(e) => {
+                    log::warn!("解析公网地址探测来源失败 {:?},{:?}", source, e);
+                    continue;
+                }
+            };
+            for server in servers {
+                if server.is_ipv4() && detected.ipv4.is_some()
+                    || server.is_ipv6() && detected.ipv6.is_some()
+                {
+                    continue;
+                }
+                match source.query(server).await {
+                    Ok(IpAddr::V4(ip)) => detected.ipv4 = detected.ipv4.or(Some(ip)),
+                    Ok(IpAddr::V6(ip)) => detected.ipv6 = detected.ipv6.or(Some(ip)),
+                    Err(e) => log::warn!("探测公网地址失败 {:?},{},{:?}", source, server, e),
+                }
+            }
+        }
+        let mut guard = public_addr.write();
+        let new = PublicAddr {
+            ipv4: detected.ipv4.or(guard.ipv4),
+            ipv6: detected.ipv6.or(guard.ipv6),
+        };
+        if *guard != new {
+            log::info!("服务端公网地址变化 {:?} -> {:?}", *guard, new);
+            *guard = new;
+        }
+    }
+}
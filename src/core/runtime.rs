@@ -0,0 +1,84 @@
+use std::io;
+
+use tokio::runtime::{Builder, Runtime};
+
+/// tokio运行时的线程配置
+#[derive(Clone, Debug, Default)]
+pub struct RuntimeConfig {
+    // 工作线程数，None时等于cpu核数
+    pub worker_threads: Option<usize>,
+    // 阻塞任务线程数的上限，None时使用tokio的默认值
+    pub max_blocking_threads: Option<usize>,
+    // 限制进程只在这些cpu上运行，为空时不限制
+    pub cpu_affinity: Vec<usize>,
+}
+
+impl RuntimeConfig {
+    /// 设置cpu亲和性后创建运行时，之后创建的线程(包括web后台的线程)继承主线程的亲和性
+    pub fn build(&self) -> io::Result<Runtime> {
+        if !self.cpu_affinity.is_empty() {
+            set_affinity(&self.cpu_affinity)?;
+        }
+        let mut builder = Builder::new_multi_thread();
+        builder.enable_all();
+        if let Some(worker_threads) = self.worker_threads {
+            builder.worker_threads(worker_threads);
+        }
+        if let Some(max_blocking_threads) = self.max_blocking_threads {
+            builder.max_blocking_threads(max_blocking_threads);
+        }
+        builder.build()
+    }
+}
+
+/// 解析cpu列表，格式为逗号分隔的编号或范围，例如 0-3,6
+pub fn parse_cpu_list(s: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = Vec::new();
+    for item in s.split(',').map(str::trim) {
+        let (start, end) = match item.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => (item, item),
+        };
+        let start: usize = start
+            .parse()
+            .map_err(|_| format!("invalid cpu '{}'", item))?;
+        let end: usize = end.parse().map_err(|_| format!("invalid cpu '{}'", item))?;
+        if start > end || end >= MAX_CPUS {
+            return Err(format!(
+                "invalid cpu range '{}', max {}",
+                item,
+                MAX_CPUS - 1
+            ));
+        }
+        cpus.extend(start..=end);
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(cpus)
+}
+
+/// cpu编号的上限，和linux的CPU_SETSIZE相同
+const MAX_CPUS: usize = 1024;
+
+#[cfg(target_os = "linux")]
+fn set_affinity(cpus: &[usize]) -> io::Result<()> {
+    use std::mem::{size_of, zeroed};
+    unsafe {
+        let mut set: libc::cpu_set_t = zeroed();
+        for cpu in cpus {
+            libc::CPU_SET(*cpu, &mut set);
+        }
+        if libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_affinity(_cpus: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "cpu affinity is only supported on linux",
+    ))
+}
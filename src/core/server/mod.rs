@@ -4,8 +4,16 @@ use std::sync::Arc;
 use tokio::net::{TcpListener, UdpSocket};
 
 use crate::cipher::RsaCipher;
+use crate::core::alert;
+use crate::core::ddns;
+use crate::core::ingress;
+use crate::core::offload::{self, UdpOffload};
+use crate::core::port_mapping;
+use crate::core::public_addr;
 use crate::core::service::PacketHandler;
 use crate::core::store::cache::AppCache;
+use crate::core::store::{audit, reclaim, snapshot, state};
+use crate::core::task;
 use crate::ConfigInfo;
 
 mod tcp;
@@ -15,6 +23,7 @@ mod web;
 
 pub async fn start(
     udp: std::net::UdpSocket,
+    nat_probe_udp: Option<std::net::UdpSocket>,
     tcp: std::net::TcpListener,
     #[cfg(feature = "web")] http: Option<std::net::TcpListener>,
     config: ConfigInfo,
@@ -22,23 +31,171 @@ pub async fn start(
 ) -> io::Result<()> {
     let udp = Arc::new(UdpSocket::from_std(udp)?);
     let cache = AppCache::new();
+    cache.suspicious.set_policy(config.block_policy);
+    cache
+        .white_tokens
+        .write()
+        .configure(config.white_token.as_ref());
+    if let Some(state_file) = &config.state_file {
+        if state_file.exists() {
+            let dump = state::StateDump::load(state_file)?;
+            let count = state::restore(&cache, &config, dump).await;
+            log::info!("恢复设备注册信息 path={:?},count={}", state_file, count);
+            println!("恢复设备注册信息: {}台设备", count);
+        }
+        task::spawn(
+            "state save",
+            state::save_task(cache.clone(), state_file.clone()),
+        );
+    }
+    #[cfg(feature = "sqlite")]
+    if let Some(state_db) = &config.state_db {
+        let store = crate::core::store::sqlite::SqliteStore::open(state_db)?;
+        if let Some(dump) = store.load()? {
+            let count = state::restore(&cache, &config, dump).await;
+            log::info!("恢复设备注册信息 path={:?},count={}", state_db, count);
+            println!("恢复设备注册信息: {}台设备", count);
+        }
+        task::spawn(
+            "state db save",
+            crate::core::store::sqlite::save_task(cache.clone(), store, state_db.clone()),
+        );
+    }
+    task::spawn("session audit", audit::audit_task(cache.clone()));
+    if config.default_policy.reclaim_offline.is_some()
+        || config
+            .group_policy
+            .values()
+            .any(|policy| policy.reclaim_offline.is_some())
+    {
+        task::spawn("offline reclaim", reclaim::reclaim_task(cache.clone()));
+    }
+    if let Some(snapshot) = &config.snapshot {
+        task::spawn(
+            "s3 snapshot",
+            snapshot::snapshot_task(cache.clone(), snapshot.clone()),
+        );
+    }
+    if let Some(alert) = &config.alert {
+        task::spawn("alert", alert::alert_task(cache.clone(), alert.clone()));
+    }
+    if let Some(ddns) = &config.ddns {
+        task::spawn("ddns", ddns::ddns_task(ddns.clone()));
+    }
+    if let Some(port_mapping) = &config.port_mapping {
+        task::spawn(
+            "port mapping",
+            port_mapping::port_mapping_task(port_mapping.clone()),
+        );
+    }
+    if !config.public_addr_sources.is_empty() {
+        task::spawn(
+            "public addr detect",
+            public_addr::detect_task(
+                config.public_addr_sources.clone(),
+                cache.public_addr.clone(),
+            ),
+        );
+    }
+    let offload = if config.udp_offload {
+        let offload = offload::enable(&udp);
+        log::info!("udp卸载 {:?}", offload);
+        println!("udp分段发送: {}, udp接收合并: {}", offload.gso, offload.gro);
+        offload
+    } else {
+        UdpOffload::default()
+    };
     let handler = PacketHandler::new(
         cache.clone(),
         config.clone(),
         rsa_cipher.clone(),
         udp.clone(),
+        offload,
+    );
+    let tcp_handle = task::spawn(
+        "tcp accept",
+        tcp::start(
+            TcpListener::from_std(tcp)?,
+            handler.clone(),
+            tcp::TcpOptions {
+                max_packet_size: config.max_packet_size,
+                keepalive: config.tcp_keepalive,
+                idle_timeout: config.tcp_idle_timeout,
+            },
+        ),
     );
-    let tcp_handle = tokio::spawn(tcp::start(TcpListener::from_std(tcp)?, handler.clone()));
-    let udp_handle = tokio::spawn(udp::start(udp, handler.clone()));
-    #[cfg(not(feature = "web"))]
-    let _ = tokio::try_join!(tcp_handle, udp_handle);
-    #[cfg(feature = "web")]
-    if let Some(http) = http {
-        if let Err(e) = web::start(http, cache, config).await {
-            log::error!("{:?}", e);
+    let udp_handle = task::spawn(
+        "udp recv",
+        udp::start(udp, handler.clone(), config.max_packet_size, offload),
+    );
+    if let Some(socks5) = &config.socks5 {
+        let listener = TcpListener::bind(socks5.addr).await?;
+        log::info!("socks5代理监听: {}", socks5.addr);
+        println!("socks5代理监听: {}", socks5.addr);
+        task::spawn(
+            "socks5 accept",
+            ingress::start_socks5(listener, socks5.clone(), cache.clone(), handler.clone()),
+        );
+    }
+    if let Some(http_ingress) = &config.http_ingress {
+        let routes = Arc::new(http_ingress.routes.clone());
+        for addr in &http_ingress.addrs {
+            let listener = TcpListener::bind(addr).await?;
+            log::info!("http入口监听: {}", addr);
+            println!("http入口监听: {}", addr);
+            task::spawn(
+                "http ingress accept",
+                ingress::start_http(listener, routes.clone(), cache.clone(), handler.clone()),
+            );
         }
-    } else {
+    }
+    if let Some(nat_probe_udp) = nat_probe_udp {
+        task::spawn(
+            "nat probe recv",
+            udp::start_nat_probe(UdpSocket::from_std(nat_probe_udp)?, handler.clone()),
+        );
+    }
+    let alert = config.alert.clone();
+    let serve = async move {
+        #[cfg(not(feature = "web"))]
         let _ = tokio::try_join!(tcp_handle, udp_handle);
+        #[cfg(feature = "web")]
+        if let Some(http) = http {
+            if let Err(e) = web::start(http, cache, config, handler).await {
+                log::error!("{:?}", e);
+            }
+        } else {
+            let _ = tokio::try_join!(tcp_handle, udp_handle);
+        }
+    };
+    tokio::select! {
+        _ = serve => {}
+        _ = shutdown_signal() => log::info!("收到退出信号"),
+    }
+    if let Some(alert) = &alert {
+        alert::notify(alert, &alert::Alert::server("stop")).await;
     }
     Ok(())
 }
+
+/// 等待ctrl+c或者SIGTERM
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(e) => {
+                log::warn!("监听SIGTERM失败 {:?}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
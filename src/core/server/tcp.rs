@@ -1,92 +1,139 @@
 use crate::core::service::PacketHandler;
+use crate::core::task;
 use crate::protocol::NetPacket;
+use socket2::{SockRef, TcpKeepalive};
 use std::io;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::tcp::OwnedReadHalf;
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::mpsc::{channel, Sender};
+use tokio::sync::mpsc::channel;
+use tokio::time::Instant;
 
-pub async fn start(tcp: TcpListener, handler: PacketHandler) {
-    if let Err(e) = accept(tcp, handler).await {
+/// tcp连接的参数
+#[derive(Clone, Copy, Debug)]
+pub struct TcpOptions {
+    // 数据包大小上限
+    pub max_packet_size: usize,
+    // 连接空闲多久后开始发送tcp keepalive探测，None表示不开启
+    pub keepalive: Option<Duration>,
+    // 多久没有收到数据时关闭连接，None表示不关闭
+    pub idle_timeout: Option<Duration>,
+}
+
+pub async fn start(tcp: TcpListener, handler: PacketHandler, options: TcpOptions) {
+    if let Err(e) = accept(tcp, handler, options).await {
         log::error!("accept {:?}", e);
     }
 }
 
-async fn accept(tcp: TcpListener, handler: PacketHandler) -> io::Result<()> {
+async fn accept(tcp: TcpListener, handler: PacketHandler, options: TcpOptions) -> io::Result<()> {
     loop {
         let (stream, addr) = tcp.accept().await?;
+        if handler.is_blocked(addr) {
+            continue;
+        }
         let _ = stream.set_nodelay(true);
-        stream_handle(stream, addr, handler.clone()).await;
+        if let Some(time) = options.keepalive {
+            let keepalive = TcpKeepalive::new().with_time(time);
+            #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+            let keepalive = keepalive.with_interval(time);
+            if let Err(e) = SockRef::from(&stream).set_tcp_keepalive(&keepalive) {
+                log::warn!("设置tcp keepalive失败 {},{:?}", addr, e);
+            }
+        }
+        stream_handle(stream, addr, handler.clone(), options).await;
     }
 }
 
-async fn stream_handle(stream: TcpStream, addr: SocketAddr, handler: PacketHandler) {
-    let (r, mut w) = stream.into_split();
-
-    let (sender, mut receiver) = channel::<Vec<u8>>(100);
-    tokio::spawn(async move {
-        while let Some(data) = receiver.recv().await {
-            let len = data.len();
-            if let Err(e) = w
-                .write_all(&[
-                    (len >> 24) as u8,
-                    (len >> 16) as u8,
-                    (len >> 8) as u8,
-                    len as u8,
-                ])
-                .await
-            {
-                log::info!("发送失败,链接终止:{:?},{:?}", addr, e);
-                break;
-            }
-            if let Err(e) = w.write_all(&data).await {
-                log::info!("发送失败,链接终止:{:?},{:?}", addr, e);
-                break;
-            }
-        }
-        let _ = w.shutdown().await;
-    });
-    tokio::spawn(async move {
-        if let Err(e) = tcp_read(r, addr, sender, handler).await {
-            log::warn!("tcp_read {:?}", e)
+async fn stream_handle(
+    stream: TcpStream,
+    addr: SocketAddr,
+    handler: PacketHandler,
+    options: TcpOptions,
+) {
+    task::spawn(&format!("tcp connection {}", addr), async move {
+        if let Err(e) = connection(stream, addr, &handler, options).await {
+            log::info!("链接终止:{:?},{:?}", addr, e);
         }
+        // 连接断开后设备离线，释放发送队列
+        handler.tcp_closed(addr);
     });
 }
 
-async fn tcp_read(
-    mut read: OwnedReadHalf,
+/// 每个连接只使用一个任务，同时处理接收的数据和发送队列
+async fn connection(
+    mut stream: TcpStream,
     addr: SocketAddr,
-    sender: Sender<Vec<u8>>,
-    handler: PacketHandler,
+    handler: &PacketHandler,
+    options: TcpOptions,
 ) -> io::Result<()> {
-    let mut head = [0; 4];
-    let mut buf = [0; 65536];
+    let max_packet_size = options.max_packet_size;
+    let (mut r, mut w) = stream.split();
+    // 其他客户端中继过来的数据通过队列发送
+    let (sender, mut receiver) = channel::<Vec<u8>>(100);
     let sender = Some(sender);
+    // 缓冲区能放下一个完整的数据帧
+    let mut buf = vec![0; 4 + max_packet_size];
+    let mut len = 0;
+    // 对端消失且没有发送FIN时连接不会断开，客户端会定时发送心跳，长时间没有数据则关闭连接
+    let idle_timeout = options.idle_timeout.unwrap_or(Duration::MAX);
+    let mut deadline = Instant::now().checked_add(idle_timeout);
     loop {
-        read.read_exact(&mut head).await?;
-        let len = ((head[0] as usize) << 24)
-            | ((head[1] as usize) << 16)
-            | ((head[2] as usize) << 8)
-            | head[3] as usize;
-        if len < 12 || len > buf.len() {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "length overflow",
-            ));
-        }
-        read.read_exact(&mut buf[..len]).await?;
-        let packet = NetPacket::new0(len, &mut buf)?;
-        if let Some(rs) = handler.handle(packet, addr, &sender).await {
-            if sender
-                .as_ref()
-                .unwrap()
-                .send(rs.buffer().to_vec())
-                .await
-                .is_err()
-            {
-                return Err(io::Error::new(io::ErrorKind::WriteZero, "send error"));
+        tokio::select! {
+            rs = r.read(&mut buf[len..]) => {
+                let n = rs?;
+                if n == 0 {
+                    return Ok(());
+                }
+                deadline = Instant::now().checked_add(idle_timeout);
+                len += n;
+                let mut start = 0;
+                // 处理缓冲区中所有完整的数据帧
+                while len - start >= 4 {
+                    let frame_len =
+                        u32::from_be_bytes(buf[start..start + 4].try_into().unwrap()) as usize;
+                    if !(12..=max_packet_size).contains(&frame_len) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "length overflow",
+                        ));
+                    }
+                    if len - start < 4 + frame_len {
+                        break;
+                    }
+                    let frame = &mut buf[start + 4..start + 4 + frame_len];
+                    let packet = NetPacket::new(frame)?;
+                    if let Some(rs) = handler.handle(packet, addr, &sender).await {
+                        write_frame(&mut w, rs.buffer()).await?;
+                    }
+                    start += 4 + frame_len;
+                }
+                buf.copy_within(start..len, 0);
+                len -= start;
+            }
+            data = receiver.recv() => {
+                // 发送端由当前任务持有，队列不会关闭
+                if let Some(data) = data {
+                    write_frame(&mut w, &data).await?;
+                }
+            }
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "idle timeout"));
             }
         }
     }
 }
+
+/// 发送一个数据帧，4字节长度加数据
+async fn write_frame<W: AsyncWriteExt + Unpin>(w: &mut W, data: &[u8]) -> io::Result<()> {
+    let len = data.len();
+    w.write_all(&[
+        (len >> 24) as u8,
+        (len >> 16) as u8,
+        (len >> 8) as u8,
+        len as u8,
+    ])
+    .await?;
+    w.write_all(data).await
+}
@@ -1,32 +1,100 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use tokio::net::UdpSocket;
 
+use crate::core::entity::MALFORMED_PACKETS;
+use crate::core::offload::{self, UdpOffload};
 use crate::core::service::PacketHandler;
+use crate::core::task;
 use crate::protocol::NetPacket;
 
-pub async fn start(main_udp: Arc<UdpSocket>, handler: PacketHandler) {
+/// udp数据报的上限
+const MAX_DATAGRAM_SIZE: usize = 65536;
+
+pub async fn start(
+    main_udp: Arc<UdpSocket>,
+    handler: PacketHandler,
+    max_packet_size: usize,
+    offload: UdpOffload,
+) {
+    // 超出数据包上限的数据报会被截断，解析失败后丢弃，开启接收合并时一次可能收到多个数据包
+    let buf_size = if offload.gro {
+        MAX_DATAGRAM_SIZE
+    } else {
+        max_packet_size.min(MAX_DATAGRAM_SIZE)
+    };
     loop {
-        let mut buf = vec![0u8; 65536];
-        match main_udp.recv_from(&mut buf).await {
-            Ok((len, addr)) => {
-                let handler = handler.clone();
-                let udp = main_udp.clone();
-                tokio::spawn(async move {
-                    match NetPacket::new(&mut buf[..len]) {
-                        Ok(net_packet) => {
-                            if let Some(rs) = handler.handle(net_packet, addr, &None).await {
-                                if let Err(e) = udp.send_to(rs.buffer(), addr).await {
-                                    log::error!("{:?} {}", e, addr)
-                                }
-                            }
-                        }
-                        Err(e) => {
+        let mut buf = vec![0u8; buf_size];
+        let rs = if offload.gro {
+            offload::recv_from(&main_udp, &mut buf).await
+        } else {
+            main_udp
+                .recv_from(&mut buf)
+                .await
+                .map(|(len, addr)| (len, addr, None))
+        };
+        match rs {
+            Ok((len, addr, Some(segment_size))) if segment_size > 0 && len > segment_size => {
+                for segment in buf[..len].chunks(segment_size) {
+                    spawn_handle(&main_udp, &handler, segment.to_vec(), addr);
+                }
+            }
+            Ok((len, addr, _)) => {
+                buf.truncate(len);
+                spawn_handle(&main_udp, &handler, buf, addr);
+            }
+            Err(e) => {
+                log::error!("{:?}", e)
+            }
+        }
+    }
+}
+
+fn spawn_handle(udp: &Arc<UdpSocket>, handler: &PacketHandler, mut buf: Vec<u8>, addr: SocketAddr) {
+    if handler.is_blocked(addr) {
+        return;
+    }
+    let handler = handler.clone();
+    let udp = udp.clone();
+    task::spawn("udp packet", async move {
+        match NetPacket::new(&mut buf[..]) {
+            Ok(net_packet) => {
+                if let Some(rs) = handler.handle(net_packet, addr, &None).await {
+                    if let Err(e) = udp.send_to(rs.buffer(), addr).await {
+                        log::error!("{:?} {}", e, addr)
+                    }
+                }
+            }
+            Err(e) => {
+                if handler.suspicious(addr, MALFORMED_PACKETS) {
+                    log::error!("{:?} {}", e, addr)
+                }
+            }
+        }
+    });
+}
+
+/// nat类型探测端口
+pub async fn start_nat_probe(probe_udp: UdpSocket, handler: PacketHandler) {
+    let mut buf = vec![0u8; 65536];
+    loop {
+        match probe_udp.recv_from(&mut buf).await {
+            Ok((_, addr)) if handler.is_blocked(addr) => {}
+            Ok((len, addr)) => match NetPacket::new(&mut buf[..len]) {
+                Ok(net_packet) => {
+                    if let Some(rs) = handler.handle_nat_probe(net_packet, addr).await {
+                        if let Err(e) = probe_udp.send_to(rs.buffer(), addr).await {
                             log::error!("{:?} {}", e, addr)
                         }
                     }
-                });
-            }
+                }
+                Err(e) => {
+                    if handler.suspicious(addr, MALFORMED_PACKETS) {
+                        log::error!("{:?} {}", e, addr)
+                    }
+                }
+            },
             Err(e) => {
                 log::error!("{:?}", e)
             }
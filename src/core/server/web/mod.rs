@@ -1,15 +1,23 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::net;
 use std::sync::Arc;
 
 use actix_web::dev::Service;
+use actix_web::http::header::HeaderMap;
 use actix_web::web::Data;
 use actix_web::{middleware, post, web, App, HttpRequest, HttpResponse, HttpServer};
 
 use actix_web_static_files::ResourceFiles;
 
 use crate::core::server::web::service::VntsWebService;
-use crate::core::server::web::vo::{LoginData, ResponseMessage};
+use crate::core::server::web::vo::{
+    ClientConfigQuery, CreateApiToken, CreateGroup, LogLevel, LoginData, MigrateGroup,
+    NetworkMapQuery, ProbeReachability, ReassignIp, ReleaseIp, ResponseMessage, SaveUser,
+    SendNotice, SetClientConfig, SetDeviceInfo, SetMaintenance, SetProtected, SetTags,
+    UnblockSource,
+};
+use crate::core::service::PacketHandler;
+use crate::core::store::admin::Role;
 use crate::core::store::cache::AppCache;
 use crate::ConfigInfo;
 
@@ -26,6 +34,135 @@ async fn login(service: Data<VntsWebService>, data: web::Json<LoginData>) -> Htt
     }
 }
 
+#[post("/logout")]
+async fn logout(req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
+    if let Some(auth) = bearer(req.headers()) {
+        service.logout(auth);
+    }
+    HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None))
+}
+
+#[post("/users")]
+async fn users(_req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
+    let info = service.users();
+    HttpResponse::Ok().json(ResponseMessage::success(info))
+}
+
+#[post("/save_user")]
+async fn save_user(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<SaveUser>,
+) -> HttpResponse {
+    match service.save_user(data.0) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/delete_user")]
+async fn delete_user(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<HashMap<String, String>>,
+) -> HttpResponse {
+    let Some(username) = data.get("username") else {
+        return HttpResponse::Ok().json(ResponseMessage::fail("no username found".into()));
+    };
+    match service.delete_user(username) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/api_tokens")]
+async fn api_tokens(_req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
+    let info = service.api_tokens();
+    HttpResponse::Ok().json(ResponseMessage::success(info))
+}
+
+#[post("/create_api_token")]
+async fn create_api_token(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<CreateApiToken>,
+) -> HttpResponse {
+    let info = service.create_api_token(data.0);
+    HttpResponse::Ok().json(ResponseMessage::success(info))
+}
+
+#[post("/delete_api_token")]
+async fn delete_api_token(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<HashMap<String, String>>,
+) -> HttpResponse {
+    let Some(id) = data.get("id") else {
+        return HttpResponse::Ok().json(ResponseMessage::fail("no id found".into()));
+    };
+    match service.delete_api_token(id) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/white_tokens")]
+async fn white_tokens(_req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
+    let info = service.white_tokens();
+    HttpResponse::Ok().json(ResponseMessage::success(info))
+}
+
+#[post("/add_white_token")]
+async fn add_white_token(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<HashMap<String, String>>,
+) -> HttpResponse {
+    let Some(token) = data.get("token") else {
+        return HttpResponse::Ok().json(ResponseMessage::fail("no token found".into()));
+    };
+    match service.add_white_token(token.clone()) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/remove_white_token")]
+async fn remove_white_token(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<HashMap<String, String>>,
+) -> HttpResponse {
+    let Some(token) = data.get("token") else {
+        return HttpResponse::Ok().json(ResponseMessage::fail("no token found".into()));
+    };
+    match service.remove_white_token(token) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/sessions")]
+async fn sessions(_req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
+    let info = service.sessions();
+    HttpResponse::Ok().json(ResponseMessage::success(info))
+}
+
+#[post("/revoke_session")]
+async fn revoke_session(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<HashMap<String, String>>,
+) -> HttpResponse {
+    let Some(id) = data.get("id") else {
+        return HttpResponse::Ok().json(ResponseMessage::fail("no id found".into()));
+    };
+    match service.revoke_session(id) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
 #[post("/group_list")]
 async fn group_list(_req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
     let info = service.group_list();
@@ -46,28 +183,328 @@ async fn group_info(
     }
 }
 
+#[post("/groups")]
+async fn groups(_req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
+    let info = service.groups();
+    HttpResponse::Ok().json(ResponseMessage::success(info))
+}
+
+#[post("/create_group")]
+async fn create_group(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<CreateGroup>,
+) -> HttpResponse {
+    match service.create_group(data.0).await {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/delete_group")]
+async fn delete_group(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    group: web::Json<HashMap<String, String>>,
+) -> HttpResponse {
+    if let Some(group) = group.get("group") {
+        match service.delete_group(group) {
+            Ok(count) => HttpResponse::Ok().json(ResponseMessage::success(count)),
+            Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+        }
+    } else {
+        HttpResponse::Ok().json(ResponseMessage::fail("no group found".into()))
+    }
+}
+
+#[post("/peer_stats")]
+async fn peer_stats(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    group: web::Json<HashMap<String, String>>,
+) -> HttpResponse {
+    if let Some(group) = group.get("group") {
+        let info = service.peer_stats(group);
+        HttpResponse::Ok().json(ResponseMessage::success(info))
+    } else {
+        HttpResponse::Ok().json(ResponseMessage::fail("no group found".into()))
+    }
+}
+
+#[post("/network_map")]
+async fn network_map(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<NetworkMapQuery>,
+) -> HttpResponse {
+    let Some(map) = service.network_map(&data.group) else {
+        return HttpResponse::Ok().json(ResponseMessage::fail("no group found".into()));
+    };
+    match data.format.as_deref() {
+        None | Some("json") => HttpResponse::Ok().json(ResponseMessage::success(map)),
+        Some("dot") => HttpResponse::Ok()
+            .content_type("text/vnd.graphviz; charset=utf-8")
+            .body(map.to_dot()),
+        Some(format) => HttpResponse::Ok().json(ResponseMessage::fail(format!(
+            "unknown format {:?}, json/dot",
+            format
+        ))),
+    }
+}
+
+#[post("/hostile_traffic")]
+async fn hostile_traffic(_req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
+    let info = service.hostile_traffic();
+    HttpResponse::Ok().json(ResponseMessage::success(info))
+}
+
+#[post("/session_audit")]
+async fn session_audit(_req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
+    let info = service.session_audit();
+    HttpResponse::Ok().json(ResponseMessage::success(info))
+}
+
+#[post("/suspicious_sources")]
+async fn suspicious_sources(_req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
+    let info = service.suspicious_sources();
+    HttpResponse::Ok().json(ResponseMessage::success(info))
+}
+
+#[post("/unblock_source")]
+async fn unblock_source(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<UnblockSource>,
+) -> HttpResponse {
+    match service.unblock_source(data.0) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/log_level")]
+async fn log_level(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<LogLevel>,
+) -> HttpResponse {
+    match service.log_level(data.0) {
+        Ok(levels) => HttpResponse::Ok().json(ResponseMessage::success(levels)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/migrate_group")]
+async fn migrate_group(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<MigrateGroup>,
+) -> HttpResponse {
+    match service.migrate_group(data.0) {
+        Ok(count) => HttpResponse::Ok().json(ResponseMessage::success(count)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/maintenance")]
+async fn maintenance(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<SetMaintenance>,
+) -> HttpResponse {
+    let status = service.maintenance(data.0);
+    HttpResponse::Ok().json(ResponseMessage::success(status))
+}
+
+#[cfg(feature = "profiling")]
+#[post("/debug/profile")]
+async fn debug_profile(_req: HttpRequest, service: Data<VntsWebService>) -> HttpResponse {
+    let info = service.debug_profile();
+    HttpResponse::Ok().json(ResponseMessage::success(info))
+}
+
+/// 调试接口，只在profiling特性下注册
+fn debug_routes(cfg: &mut web::ServiceConfig) {
+    #[cfg(feature = "profiling")]
+    cfg.service(debug_profile);
+    #[cfg(not(feature = "profiling"))]
+    let _ = cfg;
+}
+
+#[post("/reassign_ip")]
+async fn reassign_ip(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<ReassignIp>,
+) -> HttpResponse {
+    match service.reassign_ip(data.0).await {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/set_device_info")]
+async fn set_device_info(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<SetDeviceInfo>,
+) -> HttpResponse {
+    match service.set_device_info(data.0) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/client_config")]
+async fn client_config(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<ClientConfigQuery>,
+) -> HttpResponse {
+    match service.client_config(data.0) {
+        Ok(config) => HttpResponse::Ok().json(ResponseMessage::success(config)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/set_client_config")]
+async fn set_client_config(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<SetClientConfig>,
+) -> HttpResponse {
+    match service.set_client_config(data.0) {
+        Ok(count) => HttpResponse::Ok().json(ResponseMessage::success(count)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/send_notice")]
+async fn send_notice(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<SendNotice>,
+) -> HttpResponse {
+    match service.send_notice(data.0) {
+        Ok(count) => HttpResponse::Ok().json(ResponseMessage::success(count)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/probe_reachability")]
+async fn probe_reachability(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<ProbeReachability>,
+) -> HttpResponse {
+    match service.probe_reachability(data.0).await {
+        Ok(results) => HttpResponse::Ok().json(ResponseMessage::success(results)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/release_ip")]
+async fn release_ip(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<ReleaseIp>,
+) -> HttpResponse {
+    match service.release_ip(data.0) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/set_protected")]
+async fn set_protected(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<SetProtected>,
+) -> HttpResponse {
+    match service.set_protected(data.0) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
+#[post("/set_tags")]
+async fn set_tags(
+    _req: HttpRequest,
+    service: Data<VntsWebService>,
+    data: web::Json<SetTags>,
+) -> HttpResponse {
+    match service.set_tags(data.0) {
+        Ok(_) => HttpResponse::Ok().json(ResponseMessage::success(Option::<()>::None)),
+        Err(e) => HttpResponse::Ok().json(ResponseMessage::fail(e)),
+    }
+}
+
 #[derive(Clone)]
 struct AuthApi {
-    api_set: Arc<HashSet<String>>,
+    // 接口 -> 需要的最低角色
+    api_set: Arc<HashMap<String, Role>>,
 }
 
 fn auth_api_set() -> AuthApi {
-    let mut api_set = HashSet::new();
-    api_set.insert("/group_info".to_string());
-    api_set.insert("/group_list".to_string());
+    let mut api_set = HashMap::new();
+    api_set.insert("/logout".to_string(), Role::ReadOnly);
+    api_set.insert("/group_info".to_string(), Role::ReadOnly);
+    api_set.insert("/group_list".to_string(), Role::ReadOnly);
+    api_set.insert("/reassign_ip".to_string(), Role::Operator);
+    api_set.insert("/set_tags".to_string(), Role::Operator);
+    api_set.insert("/set_device_info".to_string(), Role::Operator);
+    api_set.insert("/release_ip".to_string(), Role::Operator);
+    api_set.insert("/send_notice".to_string(), Role::Operator);
+    api_set.insert("/client_config".to_string(), Role::ReadOnly);
+    api_set.insert("/set_client_config".to_string(), Role::Operator);
+    api_set.insert("/set_protected".to_string(), Role::Operator);
+    api_set.insert("/peer_stats".to_string(), Role::ReadOnly);
+    api_set.insert("/network_map".to_string(), Role::ReadOnly);
+    api_set.insert("/groups".to_string(), Role::ReadOnly);
+    api_set.insert("/create_group".to_string(), Role::Operator);
+    api_set.insert("/delete_group".to_string(), Role::Admin);
+    api_set.insert("/hostile_traffic".to_string(), Role::ReadOnly);
+    api_set.insert("/suspicious_sources".to_string(), Role::ReadOnly);
+    api_set.insert("/session_audit".to_string(), Role::ReadOnly);
+    api_set.insert("/unblock_source".to_string(), Role::Admin);
+    api_set.insert("/log_level".to_string(), Role::Admin);
+    api_set.insert("/maintenance".to_string(), Role::Admin);
+    api_set.insert("/migrate_group".to_string(), Role::Admin);
+    api_set.insert("/debug/profile".to_string(), Role::Admin);
+    api_set.insert("/users".to_string(), Role::Admin);
+    api_set.insert("/save_user".to_string(), Role::Admin);
+    api_set.insert("/delete_user".to_string(), Role::Admin);
+    api_set.insert("/api_tokens".to_string(), Role::Admin);
+    api_set.insert("/create_api_token".to_string(), Role::Admin);
+    api_set.insert("/delete_api_token".to_string(), Role::Admin);
+    api_set.insert("/white_tokens".to_string(), Role::Admin);
+    api_set.insert("/add_white_token".to_string(), Role::Admin);
+    api_set.insert("/remove_white_token".to_string(), Role::Admin);
+    api_set.insert("/sessions".to_string(), Role::Admin);
+    api_set.insert("/revoke_session".to_string(), Role::Admin);
     AuthApi {
         api_set: Arc::new(api_set),
     }
 }
 
+fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("Authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
 pub async fn start(
     lst: net::TcpListener,
     cache: AppCache,
     config: ConfigInfo,
+    handler: PacketHandler,
 ) -> std::io::Result<()> {
-    let web_service = VntsWebService::new(cache, config);
+    let workers = config.web_workers;
+    let web_service = VntsWebService::new(cache, config, handler);
     let auth_api = auth_api_set();
-    HttpServer::new(move || {
+    let mut server = HttpServer::new(move || {
         let generated = generate();
         App::new()
             .app_data(Data::new(web_service.clone()))
@@ -75,32 +512,63 @@ pub async fn start(
             .wrap_fn(|request, srv| {
                 let auth_api: &Data<AuthApi> = request.app_data().unwrap();
                 let path = request.path();
-                if path == "/login" || !auth_api.api_set.contains(path) {
+                let Some(&required) = auth_api.api_set.get(path) else {
                     return srv.call(request);
-                }
+                };
                 let service: &Data<VntsWebService> = request.app_data().unwrap();
-                if let Some(authorization) = request.headers().get("Authorization") {
-                    if let Ok(auth) = authorization.to_str() {
-                        if auth.starts_with("Bearer ") {
-                            let auth = &auth["Bearer ".len()..];
-                            if service.check_auth(&auth.to_string()) {
-                                return srv.call(request);
-                            }
-                        }
-                    }
-                }
-                Box::pin(async move {
-                    Ok(request
-                        .into_response(HttpResponse::Ok().json(ResponseMessage::unauthorized())))
-                })
+                let role = bearer(request.headers())
+                    .and_then(|auth| service.check_auth(&auth.to_string()));
+                let response = match role {
+                    Some(role) if role >= required => return srv.call(request),
+                    Some(_) => ResponseMessage::forbidden(),
+                    None => ResponseMessage::unauthorized(),
+                };
+                Box::pin(
+                    async move { Ok(request.into_response(HttpResponse::Ok().json(response))) },
+                )
             })
             .wrap(middleware::Compress::default())
             .service(login)
+            .service(logout)
+            .service(users)
+            .service(save_user)
+            .service(delete_user)
+            .service(api_tokens)
+            .service(create_api_token)
+            .service(delete_api_token)
+            .service(white_tokens)
+            .service(add_white_token)
+            .service(remove_white_token)
+            .service(sessions)
+            .service(revoke_session)
             .service(group_list)
             .service(group_info)
+            .service(reassign_ip)
+            .service(set_tags)
+            .service(set_device_info)
+            .service(release_ip)
+            .service(send_notice)
+            .service(probe_reachability)
+            .service(client_config)
+            .service(set_client_config)
+            .service(set_protected)
+            .service(peer_stats)
+            .service(network_map)
+            .service(groups)
+            .service(create_group)
+            .service(delete_group)
+            .service(hostile_traffic)
+            .service(suspicious_sources)
+            .service(session_audit)
+            .service(unblock_source)
+            .service(log_level)
+            .service(maintenance)
+            .service(migrate_group)
+            .configure(debug_routes)
             .service(ResourceFiles::new("/", generated))
-    })
-    .listen(lst)?
-    .run()
-    .await
+    });
+    if let Some(workers) = workers {
+        server = server.workers(workers);
+    }
+    server.listen(lst)?.run().await
 }
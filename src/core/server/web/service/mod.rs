@@ -1,26 +1,51 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Local;
 use crossbeam_utils::atomic::AtomicCell;
-use std::net::{SocketAddr, SocketAddrV4};
+use std::collections::BTreeSet;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use crate::core::entity::{ClientConfig, Maintenance, DEFAULT_RETRY_AFTER};
 use crate::core::server::web::vo::{
-    ClientInfo, ClientStatusInfo, GroupList, LoginData, NetworkInfo,
+    AddressUsage, ApiTokenInfo, ClientConfigData, ClientConfigQuery, ClientInfo, ClientStatusInfo,
+    CreateApiToken, CreateGroup, GroupList, GroupSummary, HostileTraffic, LogLevel, LogLevels,
+    LoginData, MaintenanceStatus, MapLink, MapNode, MigrateGroup, NetworkInfo, NetworkMap,
+    PeerLinkInfo, ProbeReachability, Reachability, ReassignIp, RelayBandwidth, ReleaseIp, SaveUser,
+    SecretPartition, SendNotice, ServiceInfo, SessionAudit, SessionInfo, SetClientConfig,
+    SetDeviceInfo, SetMaintenance, SetProtected, SetTags, SuspiciousSourceInfo, UnblockSource,
+    UserInfo, WhiteTokenInfo, WhiteTokenList,
 };
+use crate::core::service::PacketHandler;
+use crate::core::store::admin::{Role, Session};
 use crate::core::store::cache::AppCache;
+use crate::core::store::state;
+use crate::core::{AddressPool, Bandwidth};
+use crate::error::Error;
+use crate::logger::parse_level;
+use crate::proto::message::NoticeLevel;
 use crate::ConfigInfo;
 
+/// 等待组内设备迁移的默认时长
+const DEFAULT_MIGRATE_WAIT: Duration = Duration::from_secs(600);
+
 #[derive(Clone)]
 pub struct VntsWebService {
     cache: AppCache,
     config: ConfigInfo,
+    handler: PacketHandler,
     login_time: Arc<AtomicCell<(Instant, usize)>>,
 }
 
 impl VntsWebService {
-    pub fn new(cache: AppCache, config: ConfigInfo) -> Self {
+    pub fn new(cache: AppCache, config: ConfigInfo, handler: PacketHandler) -> Self {
         Self {
             cache,
             config,
+            handler,
             login_time: Arc::new(AtomicCell::new((Instant::now(), 0))),
         }
     }
@@ -32,14 +57,27 @@ impl VntsWebService {
         if count >= 3 && time.elapsed() < Duration::from_secs(60) {
             return Err("一分钟后再试".into());
         }
-        if login_data.username == self.config.username
+        // 启动参数指定的账号是内置的管理员
+        let role = if login_data.username == self.config.username
             && login_data.password == self.config.password
         {
+            Some(Role::Admin)
+        } else {
+            self.cache
+                .admin_store
+                .read()
+                .verify(&login_data.username, &login_data.password)
+        };
+        if let Some(role) = role {
             self.login_time.store((time, 0));
             let auth = uuid::Uuid::new_v4().to_string().replace("-", "");
             self.cache
                 .auth_map
-                .insert(auth.clone(), (), Duration::from_secs(3600 * 24))
+                .insert(
+                    auth.clone(),
+                    Session::new(login_data.username, role),
+                    Duration::from_secs(3600 * 24),
+                )
                 .await;
             Ok(auth)
         } else {
@@ -47,8 +85,302 @@ impl VntsWebService {
             Err("账号或密码错误".into())
         }
     }
-    pub fn check_auth(&self, auth: &String) -> bool {
-        self.cache.auth_map.get(auth).is_some()
+    pub fn logout(&self, auth: &str) {
+        self.cache.auth_map.remove(&auth.to_string());
+    }
+    pub async fn reassign_ip(&self, data: ReassignIp) -> Result<(), String> {
+        self.cache
+            .reassign_ip(&data.group, data.virtual_ip.into(), data.new_ip.into())
+            .await
+            .map_err(err_message)
+    }
+    pub fn set_device_info(&self, data: SetDeviceInfo) -> Result<(), String> {
+        self.cache
+            .set_device_info(&data.group, data.virtual_ip.into(), data.name, data.notes)
+            .map_err(err_message)
+    }
+    pub fn client_config(
+        &self,
+        data: ClientConfigQuery,
+    ) -> Result<Option<ClientConfigData>, String> {
+        let network_info = self
+            .cache
+            .virtual_network
+            .get_val(&data.group)
+            .ok_or_else(|| "group not found".to_string())?;
+        let guard = network_info.read();
+        let config = match data.virtual_ip {
+            None => guard.client_config.as_ref(),
+            Some(virtual_ip) => guard
+                .clients
+                .get(&virtual_ip.into())
+                .ok_or_else(|| "device not found".to_string())?
+                .meta
+                .client_config
+                .as_ref(),
+        };
+        Ok(config.map(|config| ClientConfigData {
+            version: config.version,
+            values: config.values.clone(),
+            data: STANDARD.encode(&config.data),
+        }))
+    }
+    /// 设置组或设备的配置，并推送给受影响的在线设备，返回推送的设备数
+    pub fn set_client_config(&self, data: SetClientConfig) -> Result<usize, String> {
+        let config = match data.config {
+            Some(config) => Some(ClientConfig {
+                version: Local::now().timestamp_millis(),
+                values: config.values,
+                data: STANDARD
+                    .decode(&config.data)
+                    .map_err(|e| format!("data must be base64, {}", e))?,
+            }),
+            None => None,
+        };
+        let virtual_ip = data.virtual_ip.map(u32::from);
+        self.cache
+            .set_client_config(&data.group, virtual_ip, config)
+            .map_err(err_message)?;
+        self.handler
+            .push_client_config(&data.group, virtual_ip)
+            .map_err(err_message)
+    }
+    /// 向组内设备推送通知，返回推送的设备数
+    pub fn send_notice(&self, data: SendNotice) -> Result<usize, String> {
+        if data.text.is_empty() || data.text.len() > 1024 {
+            return Err("text length must be 1-1024".into());
+        }
+        let level = match data.level.as_deref() {
+            None | Some("info") => NoticeLevel::Info,
+            Some("warning") => NoticeLevel::Warning,
+            Some(level) => return Err(format!("unknown level {:?}, info/warning", level)),
+        };
+        self.handler
+            .push_notice(
+                &data.group,
+                data.virtual_ip.map(u32::from),
+                level,
+                &data.text,
+            )
+            .map_err(err_message)
+    }
+    /// 从服务端探测设备的候选地址，用于排查设备之间无法直连的原因
+    pub async fn probe_reachability(
+        &self,
+        data: ProbeReachability,
+    ) -> Result<Vec<Reachability>, String> {
+        let results = self
+            .handler
+            .probe_reachability(&data.group, data.virtual_ip.into())
+            .await
+            .map_err(err_message)?;
+        Ok(results
+            .into_iter()
+            .map(|(addr, source, rtt)| Reachability {
+                address: addr.to_string(),
+                source: source.to_string(),
+                reachable: rtt.is_some(),
+                rtt: rtt.map(|rtt| rtt.as_millis() as u64),
+            })
+            .collect())
+    }
+    pub fn release_ip(&self, data: ReleaseIp) -> Result<(), String> {
+        self.cache
+            .release_ip(&data.group, data.virtual_ip.into())
+            .map_err(err_message)
+    }
+    pub fn set_protected(&self, data: SetProtected) -> Result<(), String> {
+        self.cache
+            .set_protected(&data.group, data.virtual_ip.into(), data.protected)
+            .map_err(err_message)
+    }
+    pub fn set_tags(&self, data: SetTags) -> Result<(), String> {
+        self.cache
+            .set_tags(&data.group, data.virtual_ip.into(), data.tags)
+            .map_err(err_message)
+    }
+    /// 校验登录凭证或api token，返回角色
+    pub fn check_auth(&self, auth: &String) -> Option<Role> {
+        if let Some(session) = self.cache.auth_map.get(auth) {
+            return Some(session.role);
+        }
+        self.cache.admin_store.read().token_role(auth)
+    }
+    pub fn users(&self) -> Vec<UserInfo> {
+        self.cache
+            .admin_store
+            .read()
+            .users()
+            .into_iter()
+            .map(|user| UserInfo {
+                username: user.username,
+                role: user.role,
+            })
+            .collect()
+    }
+    pub fn save_user(&self, data: SaveUser) -> Result<(), String> {
+        if data.username == self.config.username {
+            return Err("cannot modify the built-in user".into());
+        }
+        self.cache.admin_store.write().save_user(
+            data.username.clone(),
+            data.password,
+            data.role,
+        )?;
+        log::info!(
+            "保存web后台账号 username={},role={:?}",
+            data.username,
+            data.role
+        );
+        // 角色或密码变化后需要重新登录
+        self.remove_sessions(|session| session.username == data.username);
+        self.persist();
+        Ok(())
+    }
+    pub fn delete_user(&self, username: &str) -> Result<(), String> {
+        if !self.cache.admin_store.write().delete_user(username) {
+            return Err("user not found".into());
+        }
+        log::info!("删除web后台账号 username={}", username);
+        self.remove_sessions(|session| session.username == username);
+        self.persist();
+        Ok(())
+    }
+    pub fn api_tokens(&self) -> Vec<ApiTokenInfo> {
+        self.cache
+            .admin_store
+            .read()
+            .tokens()
+            .into_iter()
+            .map(|token| ApiTokenInfo {
+                id: token.id,
+                name: token.name,
+                role: token.role,
+                create_time: token.create_time,
+                token: None,
+            })
+            .collect()
+    }
+    pub fn create_api_token(&self, data: CreateApiToken) -> ApiTokenInfo {
+        let (api_token, token) = self
+            .cache
+            .admin_store
+            .write()
+            .create_token(data.name, data.role);
+        log::info!(
+            "创建api token id={},name={},role={:?}",
+            api_token.id,
+            api_token.name,
+            api_token.role
+        );
+        self.persist();
+        ApiTokenInfo {
+            id: api_token.id,
+            name: api_token.name,
+            role: api_token.role,
+            create_time: api_token.create_time,
+            token: Some(token),
+        }
+    }
+    pub fn delete_api_token(&self, id: &str) -> Result<(), String> {
+        if !self.cache.admin_store.write().delete_token(id) {
+            return Err("api token not found".into());
+        }
+        log::info!("删除api token id={}", id);
+        self.persist();
+        Ok(())
+    }
+    pub fn white_tokens(&self) -> WhiteTokenList {
+        let white_tokens = self.cache.white_tokens.read();
+        let tokens = white_tokens
+            .list()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|token| {
+                let (clients, online) = self
+                    .cache
+                    .virtual_network
+                    .get_val(&token.token)
+                    .map(|info| {
+                        let guard = info.read();
+                        let online = guard.clients.values().filter(|v| v.online).count();
+                        (guard.clients.len(), online)
+                    })
+                    .unwrap_or_default();
+                WhiteTokenInfo {
+                    token: token.token,
+                    configured: token.configured,
+                    create_time: token.create_time,
+                    first_seen: token.first_seen,
+                    last_used: token.last_used,
+                    registrations: token.registrations,
+                    clients,
+                    online,
+                }
+            })
+            .collect();
+        WhiteTokenList {
+            enabled: white_tokens.is_enabled(),
+            tokens,
+        }
+    }
+    pub fn add_white_token(&self, token: String) -> Result<(), String> {
+        let enabled = self.cache.white_tokens.read().is_enabled();
+        if self.cache.white_tokens.write().add(token.clone())? {
+            log::info!("添加白名单token token={:?}", token);
+            if !enabled {
+                log::warn!("开启token白名单,不在白名单中的token不能再注册");
+            }
+            self.persist();
+        }
+        Ok(())
+    }
+    pub fn remove_white_token(&self, token: &str) -> Result<(), String> {
+        self.cache.white_tokens.write().remove(token)?;
+        // 已注册的设备不受影响，重新注册时才会被拒绝
+        log::info!("删除白名单token token={:?}", token);
+        self.persist();
+        Ok(())
+    }
+    pub fn sessions(&self) -> Vec<SessionInfo> {
+        let mut sessions: Vec<SessionInfo> = self
+            .cache
+            .auth_map
+            .key_values()
+            .into_iter()
+            .map(|(_, session)| SessionInfo {
+                id: session.id,
+                username: session.username,
+                role: session.role,
+                login_time: session.login_time,
+            })
+            .collect();
+        sessions.sort_by(|v1, v2| v1.login_time.cmp(&v2.login_time));
+        sessions
+    }
+    pub fn revoke_session(&self, id: &str) -> Result<(), String> {
+        if self.remove_sessions(|session| session.id == id) == 0 {
+            return Err("session not found".into());
+        }
+        Ok(())
+    }
+    fn remove_sessions(&self, f: impl Fn(&Session) -> bool) -> usize {
+        let mut count = 0;
+        for (auth, session) in self.cache.auth_map.key_values() {
+            if f(&session) {
+                self.cache.auth_map.remove(&auth);
+                count += 1;
+            }
+        }
+        count
+    }
+    /// 账号和api token修改后立即保存，未开启--state-file时只保存在内存中
+    fn persist(&self) {
+        if let Some(path) = &self.config.state_file {
+            if let Err(e) = state::export(&self.cache).save(path) {
+                log::error!("保存web后台账号失败 path={:?},{:?}", path, e);
+            }
+        }
     }
     pub fn group_list(&self) -> GroupList {
         let group_list: Vec<String> = self
@@ -60,6 +392,313 @@ impl VntsWebService {
             .collect();
         GroupList { group_list }
     }
+    pub fn groups(&self) -> Vec<GroupSummary> {
+        let mut groups: Vec<GroupSummary> = self
+            .cache
+            .virtual_network
+            .key_values()
+            .into_iter()
+            .map(|(group, info)| {
+                let last_activity = self
+                    .cache
+                    .virtual_network
+                    .last_access(&group)
+                    .and_then(|time| chrono::Duration::from_std(time.elapsed()).ok())
+                    .map(|elapsed| Local::now() - elapsed)
+                    .unwrap_or_else(Local::now);
+                let guard = info.read();
+                GroupSummary {
+                    network_ip: guard.network_ip.into(),
+                    mask_ip: guard.mask_ip.into(),
+                    gateway_ip: guard.gateway_ip.into(),
+                    client_count: guard.clients.len(),
+                    online_count: guard.clients.values().filter(|x| x.online).count(),
+                    address_capacity: guard.capacity(),
+                    last_activity: last_activity.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    group,
+                }
+            })
+            .collect();
+        groups.sort_by(|v1, v2| v1.group.cmp(&v2.group));
+        groups
+    }
+    pub async fn create_group(&self, data: CreateGroup) -> Result<(), String> {
+        if data.group.is_empty() || data.group.len() > 128 {
+            return Err("group length must be 1-128".into());
+        }
+        let pool = match &data.subnet {
+            Some(subnet) => AddressPool::from_str(subnet)?,
+            None => AddressPool {
+                gateway: self.config.gateway.into(),
+                netmask: self.config.netmask.into(),
+            },
+        };
+        let mut policy = self.config.group_policy(&data.group);
+        if data.max_clients.is_some() {
+            policy.max_clients = data.max_clients;
+        }
+        if data.max_clients_per_ip.is_some() {
+            policy.max_clients_per_ip = data.max_clients_per_ip;
+        }
+        if let Some(relay_bandwidth) = &data.relay_bandwidth {
+            policy.relay_bandwidth = Some(Bandwidth::from_str(relay_bandwidth)?);
+        }
+        let network_info = crate::core::entity::NetworkInfo::new(
+            pool.network(),
+            pool.netmask,
+            pool.gateway,
+            policy,
+        );
+        self.cache
+            .create_group(&data.group, network_info)
+            .await
+            .map_err(err_message)
+    }
+    pub fn delete_group(&self, group: &str) -> Result<usize, String> {
+        self.cache.delete_group(group).map_err(err_message)
+    }
+    pub fn peer_stats(&self, group: &str) -> Option<Vec<PeerLinkInfo>> {
+        let info = self.cache.virtual_network.get_val(&group.to_string())?;
+        let links = info.read().peer_stats.links();
+        let mut list: Vec<PeerLinkInfo> = links
+            .into_iter()
+            .map(|((a, b), link)| PeerLinkInfo {
+                ip_a: a.into(),
+                ip_b: b.into(),
+                relay_packets: link.relay_packets,
+                relay_bytes: link.relay_bytes,
+                last_relay_secs: link.last_relay.map(|time| time.elapsed().as_secs()),
+                p2p_reports: link.p2p_reports,
+                relay_reports: link.relay_reports,
+                p2p_rate: link.p2p_rate(),
+            })
+            .collect();
+        list.sort_by_key(|v| (v.ip_a, v.ip_b));
+        Some(list)
+    }
+    /// 组的拓扑，p2p来自设备上报的p2p列表，中继来自最近一分钟的中继统计
+    pub fn network_map(&self, group: &str) -> Option<NetworkMap> {
+        let info = self.cache.virtual_network.get_val(&group.to_string())?;
+        let guard = info.read();
+        let mut nodes: Vec<MapNode> = guard
+            .clients
+            .values()
+            .map(|client| MapNode {
+                virtual_ip: client.virtual_ip.into(),
+                name: client.name.clone(),
+                online: client.online,
+                is_cone: client
+                    .meta
+                    .client_status
+                    .as_ref()
+                    .map(|status| status.is_cone),
+                tags: client.tags.clone(),
+            })
+            .collect();
+        nodes.sort_by_key(|node| node.virtual_ip);
+        let mut p2p = BTreeSet::new();
+        for client in guard.clients.values().filter(|client| client.online) {
+            for peer in client.meta.client_status.iter().flat_map(|v| &v.p2p_list) {
+                let peer: u32 = (*peer).into();
+                if guard.clients.get(&peer).is_some_and(|v| v.online) {
+                    p2p.insert((client.virtual_ip.min(peer), client.virtual_ip.max(peer)));
+                }
+            }
+        }
+        let mut relay = BTreeSet::new();
+        for ((a, b), link) in guard.peer_stats.links() {
+            let recent = link
+                .last_relay
+                .is_some_and(|time| time.elapsed() < Duration::from_secs(60));
+            if recent && !p2p.contains(&(a, b)) {
+                relay.insert((a, b));
+            }
+        }
+        let link = |(a, b): (u32, u32), kind: &str| MapLink {
+            ip_a: a.into(),
+            ip_b: b.into(),
+            kind: kind.to_string(),
+        };
+        let mut links: Vec<MapLink> = guard
+            .clients
+            .values()
+            .filter(|client| client.online)
+            .map(|client| link((guard.gateway_ip, client.virtual_ip), "server"))
+            .collect();
+        links.sort_by_key(|link| link.ip_b);
+        links.extend(p2p.into_iter().map(|v| link(v, "p2p")));
+        links.extend(relay.into_iter().map(|v| link(v, "relay")));
+        Some(NetworkMap {
+            group: group.to_string(),
+            gateway_ip: guard.gateway_ip.into(),
+            subnets: guard
+                .pools()
+                .map(|pool| {
+                    format!(
+                        "{}/{}",
+                        Ipv4Addr::from(pool.network()),
+                        pool.netmask.count_ones()
+                    )
+                })
+                .collect(),
+            nodes,
+            links,
+        })
+    }
+    pub fn hostile_traffic(&self) -> Vec<HostileTraffic> {
+        self.cache
+            .log_limiter
+            .totals()
+            .into_iter()
+            .map(|(kind, count)| HostileTraffic {
+                kind: kind.to_string(),
+                count,
+            })
+            .collect()
+    }
+    pub fn session_audit(&self) -> SessionAudit {
+        let totals = self.cache.audit.totals();
+        SessionAudit {
+            runs: totals.runs,
+            last_run: totals
+                .last_run
+                .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string()),
+            addr_sessions: totals.addr_sessions,
+            ip_sessions: totals.ip_sessions,
+            missing_ip_sessions: totals.missing_ip_sessions,
+            cipher_sessions: totals.cipher_sessions,
+            online_flags: totals.online_flags,
+        }
+    }
+    pub fn suspicious_sources(&self) -> Vec<SuspiciousSourceInfo> {
+        self.cache
+            .suspicious
+            .list()
+            .into_iter()
+            .map(|source| SuspiciousSourceInfo {
+                ip: source.ip,
+                counts: source
+                    .counts
+                    .into_iter()
+                    .map(|(kind, count)| HostileTraffic {
+                        kind: kind.to_string(),
+                        count,
+                    })
+                    .collect(),
+                first_seen: source.first_seen.format("%Y-%m-%d %H:%M:%S").to_string(),
+                last_seen: source.last_seen.format("%Y-%m-%d %H:%M:%S").to_string(),
+                blocked_seconds: source.blocked.map_or(0, |time| time.as_secs().max(1)),
+                blocks: source.blocks,
+            })
+            .collect()
+    }
+    pub fn unblock_source(&self, data: UnblockSource) -> Result<(), String> {
+        if self.cache.suspicious.unblock(data.ip) {
+            Ok(())
+        } else {
+            Err(format!("{} is not blocked", data.ip))
+        }
+    }
+    /// 调整日志级别，module为空时调整全局级别，level为空字符串时恢复配置文件中的级别，都不传时只查询
+    pub fn log_level(&self, data: LogLevel) -> Result<LogLevels, String> {
+        let log_control = self
+            .config
+            .log_control
+            .as_ref()
+            .ok_or_else(|| "logging is disabled".to_string())?;
+        let module = data.module.as_deref().filter(|module| !module.is_empty());
+        let level = match data.level.as_deref().filter(|level| !level.is_empty()) {
+            Some(level) => Some(parse_level(level)?),
+            None => None,
+        };
+        if module.is_some() || data.level.is_some() {
+            log_control.set_level(module, level)?;
+        }
+        let overrides = log_control.overrides();
+        Ok(LogLevels {
+            root: overrides.root.map(|level| level.to_string()),
+            modules: overrides
+                .modules
+                .into_iter()
+                .map(|(module, level)| (module, level.to_string()))
+                .collect(),
+        })
+    }
+    /// 把组迁移到其他服务器，返回通知的设备数
+    pub fn migrate_group(&self, data: MigrateGroup) -> Result<usize, String> {
+        let address = match data.address.filter(|address| !address.is_empty()) {
+            Some(address) => {
+                let port = address
+                    .rsplit_once(':')
+                    .map(|(host, port)| (host, port.parse::<u16>()));
+                if !matches!(port, Some((host, Ok(port))) if !host.is_empty() && port != 0) {
+                    return Err(format!("invalid address {:?}, expected host:port", address));
+                }
+                Some(address)
+            }
+            None => None,
+        };
+        let wait = data.wait.map_or(DEFAULT_MIGRATE_WAIT, Duration::from_secs);
+        self.handler
+            .migrate_group(&data.group, address, wait)
+            .map_err(err_message)
+    }
+    /// 开启或关闭维护模式，返回当前状态
+    pub fn maintenance(&self, data: SetMaintenance) -> MaintenanceStatus {
+        if let Some(enabled) = data.enabled {
+            let mut guard = self.cache.maintenance.write();
+            if enabled {
+                let maintenance = Maintenance {
+                    retry_after: data.retry_after.unwrap_or(DEFAULT_RETRY_AFTER),
+                    message: data.message.filter(|message| !message.is_empty()),
+                    since: guard
+                        .as_ref()
+                        .map_or_else(Local::now, |maintenance| maintenance.since),
+                };
+                log::info!(
+                    "开启维护模式 retry_after={},message={:?}",
+                    maintenance.retry_after,
+                    maintenance.message
+                );
+                *guard = Some(maintenance);
+            } else if guard.take().is_some() {
+                log::info!("关闭维护模式");
+            }
+        }
+        let online = self
+            .cache
+            .virtual_network
+            .key_values()
+            .into_iter()
+            .map(|(_, info)| info.read().clients.values().filter(|x| x.online).count())
+            .sum();
+        let guard = self.cache.maintenance.read();
+        MaintenanceStatus {
+            enabled: guard.is_some(),
+            retry_after: guard
+                .as_ref()
+                .map_or(DEFAULT_RETRY_AFTER, |maintenance| maintenance.retry_after),
+            message: guard
+                .as_ref()
+                .and_then(|maintenance| maintenance.message.clone()),
+            since: guard
+                .as_ref()
+                .map(|maintenance| maintenance.since.format("%Y-%m-%d %H:%M:%S").to_string()),
+            online,
+        }
+    }
+    #[cfg(feature = "profiling")]
+    pub fn debug_profile(&self) -> crate::core::server::web::vo::DebugProfile {
+        let heap = crate::profiling::heap_stats();
+        crate::core::server::web::vo::DebugProfile {
+            heap_allocated: heap.allocated,
+            heap_peak: heap.peak,
+            allocations: heap.allocations,
+            deallocations: heap.deallocations,
+            cpu_time_ms: crate::profiling::cpu_time_ms(),
+            threads: crate::profiling::thread_count(),
+        }
+    }
     pub fn group_info(&self, group: String) -> Option<NetworkInfo> {
         if let Some(info) = self.cache.virtual_network.get(&group) {
             let guard = info.read();
@@ -79,7 +718,7 @@ impl VntsWebService {
                         }
                     }
                 };
-                let status_info = if let Some(client_status) = &into.client_status {
+                let status_info = if let Some(client_status) = &into.meta.client_status {
                     Some(ClientStatusInfo {
                         p2p_list: client_status.p2p_list.clone(),
                         up_stream: client_status.up_stream,
@@ -89,6 +728,7 @@ impl VntsWebService {
                             "{}",
                             client_status.update_time.format("%Y-%m-%d %H:%M:%S")
                         ),
+                        hairpin: client_status.hairpin,
                     })
                 } else {
                     None
@@ -96,7 +736,7 @@ impl VntsWebService {
 
                 let client_info = ClientInfo {
                     device_id: into.device_id.clone(),
-                    version: into.version.clone(),
+                    version: into.meta.version.clone(),
                     name: into.name.clone(),
                     client_secret: into.client_secret,
                     server_secret: into.server_secret,
@@ -104,13 +744,109 @@ impl VntsWebService {
                     online: into.online,
                     virtual_ip: into.virtual_ip.into(),
                     status_info,
-                    last_join_time: into.last_join_time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    last_join_time: into
+                        .meta
+                        .last_join_time
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string(),
+                    rtt: into.meta.rtt,
+                    up_loss: into.loss.as_ref().and_then(|loss| loss.up_loss()),
+                    down_loss: into.loss.as_ref().and_then(|loss| loss.down_loss()),
+                    path_mtu: into.path_mtu,
+                    clock_skew: into.meta.clock_skew,
+                    local_addresses: into
+                        .meta
+                        .local_addrs
+                        .iter()
+                        .map(|v| format!("{}/{}:{}", v.ip, v.prefix_len, into.meta.local_port))
+                        .collect(),
+                    tags: into.tags.clone(),
+                    tags_assigned: into.meta.tags_assigned,
+                    protected: into.meta.protected,
+                    name_assigned: into.meta.name_assigned,
+                    notes: into.meta.notes.clone(),
                 };
+                for service in &into.meta.services {
+                    network.services.push(ServiceInfo {
+                        name: service.name.clone(),
+                        port: service.port,
+                        protocol: service.protocol().to_string(),
+                        virtual_ip: into.virtual_ip.into(),
+                        device_name: into.name.clone(),
+                        online: into.online,
+                    });
+                }
                 network.clients.push(client_info);
             }
+            if let Some((secret, plaintext)) = guard.secret_partition() {
+                network.warnings.push(format!(
+                    "{}台设备开启了客户端加密,{}台未开启,两部分设备之间互相不可见",
+                    secret, plaintext
+                ));
+                network.secret_partition = Some(SecretPartition { secret, plaintext });
+            }
+            if let Some(migration) = &guard.migration {
+                network.warnings.push(format!(
+                    "{}开始迁移到{},{}台设备未迁移",
+                    migration.since.format("%Y-%m-%d %H:%M:%S"),
+                    migration.address,
+                    guard.clients.values().filter(|x| x.online).count()
+                ));
+            }
+            if let Some(max) = self.config.max_clock_skew {
+                if let Some((skewed, measured)) = guard.clock_skew_warning(max) {
+                    // 几台设备都和服务端相差很大时更可能是服务端的时间不准
+                    if skewed == measured && measured >= 3 {
+                        network.warnings.push(format!(
+                            "{}台设备的时钟都和服务端相差超过{}秒,服务端的系统时间可能不准,请开启ntp同步",
+                            skewed,
+                            max.as_secs()
+                        ));
+                    } else {
+                        network.warnings.push(format!(
+                            "{}台设备的时钟和服务端相差超过{}秒,可能影响打洞时机,请在设备上开启ntp同步",
+                            skewed,
+                            max.as_secs()
+                        ));
+                    }
+                }
+            }
+            if let Some((used, capacity)) = guard.address_warning() {
+                network.warnings.push(format!(
+                    "ip使用率达到{}%,{}/{},即将无法分配ip",
+                    used * 100 / capacity,
+                    used,
+                    capacity
+                ));
+            }
+            let capacity = guard.capacity();
+            let used = guard.clients.len() as u64;
+            let (allocations_last_hour, releases_last_hour) = guard.address_churn.last_hour();
+            network.address_usage = AddressUsage {
+                used,
+                free: capacity.saturating_sub(used),
+                capacity,
+                allocations_last_hour,
+                releases_last_hour,
+            };
+            (network.relay_queue_bytes, network.relay_queue_dropped) = guard.relay_queue.stats();
+            network.raw_broadcast_dropped = guard.raw_broadcast_dropped.load(Ordering::Relaxed);
+            network.broadcast_dropped = guard.broadcast_dropped.load(Ordering::Relaxed);
+            network.discovery_reflected = guard.discovery_reflected.load(Ordering::Relaxed);
+            network.discovery_dropped = guard.discovery_dropped.load(Ordering::Relaxed);
+            network.send_rule_dropped = guard.send_rule_dropped.load(Ordering::Relaxed);
+            if let Some(limiter) = &guard.relay_limiter {
+                network.relay_bandwidth = Some(RelayBandwidth {
+                    limit: limiter.rate(),
+                    current: limiter.current_rate(),
+                });
+            }
             network
                 .clients
                 .sort_by(|v1, v2| v1.virtual_ip.cmp(&v2.virtual_ip));
+            network
+                .services
+                .sort_by(|v1, v2| (&v1.name, v1.virtual_ip).cmp(&(&v2.name, v2.virtual_ip)));
             Some(network)
         } else {
             None
@@ -145,3 +881,10 @@ impl VntsWebService {
     //     data
     // }
 }
+
+fn err_message(e: Error) -> String {
+    match e {
+        Error::Other(msg) => msg,
+        e => e.to_string(),
+    }
+}
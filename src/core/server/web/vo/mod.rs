@@ -1,8 +1,10 @@
-use std::collections::HashMap;
-use std::net::{Ipv4Addr, SocketAddr};
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
 use serde::{Deserialize, Serialize};
 
+use crate::core::store::admin::Role;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ResponseMessage<V> {
     data: V,
@@ -37,6 +39,13 @@ impl ResponseMessage<Option<()>> {
             code: 401,
         }
     }
+    pub fn forbidden() -> ResponseMessage<Option<()>> {
+        Self {
+            data: Option::<()>::None,
+            message: Some("forbidden".into()),
+            code: 403,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -59,6 +68,27 @@ pub struct ClientInfo {
     pub virtual_ip: Ipv4Addr,
     pub status_info: Option<ClientStatusInfo>,
     pub last_join_time: String,
+    // 服务器测得的延迟，毫秒
+    pub rtt: Option<u32>,
+    // 按中继数据的序号统计的上行(设备到服务器)和下行(服务器到设备)丢包率，百分比，设备不支持序号时为None
+    pub up_loss: Option<f64>,
+    pub down_loss: Option<f64>,
+    // 探测到的服务器到设备的路径mtu(udp数据大小)，没有开启探测或还没有结果时为None
+    pub path_mtu: Option<u16>,
+    // 估计的设备时钟减服务端时钟，毫秒，设备没有发送时间同步请求时为None
+    pub clock_skew: Option<i64>,
+    // 注册时上报的本地地址，ip/前缀长度:端口
+    pub local_addresses: Vec<String>,
+    // 设备标签
+    pub tags: Vec<String>,
+    // 标签是否由管理员设置
+    pub tags_assigned: bool,
+    // 受保护的设备离线多久都不回收ip
+    pub protected: bool,
+    // 名称是管理员设置的
+    pub name_assigned: bool,
+    // 管理员填写的备注
+    pub notes: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -68,6 +98,8 @@ pub struct ClientStatusInfo {
     pub down_stream: u64,
     pub is_cone: bool,
     pub update_time: String,
+    // nat是否支持回环(hairpin)，由打洞结果推断，未知时为null
+    pub hairpin: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -80,6 +112,30 @@ pub struct NetworkInfo {
     pub gateway_ip: Ipv4Addr,
     // 网段下的客户端列表
     pub clients: Vec<ClientInfo>,
+    // 设备登记的应用服务，按名称排序
+    pub services: Vec<ServiceInfo>,
+    // 客户端加密设置不一致导致的分区
+    pub secret_partition: Option<SecretPartition>,
+    // 告警信息
+    pub warnings: Vec<String>,
+    // 组内中继带宽
+    pub relay_bandwidth: Option<RelayBandwidth>,
+    // 中继发送队列占用的字节数
+    pub relay_queue_bytes: usize,
+    // 中继发送队列满丢弃的数据包数
+    pub relay_queue_dropped: u64,
+    // 直接发给网关被丢弃的广播包数
+    pub raw_broadcast_dropped: u64,
+    // 超出接收设备数量上限或每秒投递次数上限被丢弃的广播包数
+    pub broadcast_dropped: u64,
+    // 反射给组内其他设备的mDNS/SSDP数据包数
+    pub discovery_reflected: u64,
+    // 超出每秒反射数量上限被丢弃的mDNS/SSDP数据包数
+    pub discovery_dropped: u64,
+    // 不符合发送规则被丢弃的数据包数
+    pub send_rule_dropped: u64,
+    // ip使用情况
+    pub address_usage: AddressUsage,
 }
 
 impl NetworkInfo {
@@ -89,10 +145,64 @@ impl NetworkInfo {
             mask_ip,
             gateway_ip,
             clients: Default::default(),
+            services: Default::default(),
+            secret_partition: None,
+            warnings: Default::default(),
+            relay_bandwidth: None,
+            relay_queue_bytes: 0,
+            relay_queue_dropped: 0,
+            raw_broadcast_dropped: 0,
+            broadcast_dropped: 0,
+            discovery_reflected: 0,
+            discovery_dropped: 0,
+            send_rule_dropped: 0,
+            address_usage: Default::default(),
         }
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceInfo {
+    pub name: String,
+    pub port: u16,
+    // tcp或udp
+    pub protocol: String,
+    // 提供服务的设备
+    pub virtual_ip: Ipv4Addr,
+    pub device_name: String,
+    pub online: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AddressUsage {
+    // 已分配的ip数，包括离线但未过期的设备
+    pub used: u64,
+    // 未分配的ip数
+    pub free: u64,
+    // 可分配的ip数，不包括保留的ip
+    pub capacity: u64,
+    // 最近一小时分配的ip数
+    pub allocations_last_hour: u64,
+    // 最近一小时回收的ip数
+    pub releases_last_hour: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SecretPartition {
+    // 开启客户端加密的在线设备数
+    pub secret: usize,
+    // 未开启客户端加密的在线设备数
+    pub plaintext: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelayBandwidth {
+    // 上限，字节/秒
+    pub limit: u64,
+    // 当前速率，字节/秒
+    pub current: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GroupList {
     pub group_list: Vec<String>,
@@ -108,3 +218,403 @@ pub struct LoginData {
     pub username: String,
     pub password: String,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveUser {
+    pub username: String,
+    // 修改已有账号时为null则保留原密码
+    pub password: Option<String>,
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserInfo {
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateApiToken {
+    pub name: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub role: Role,
+    pub create_time: String,
+    // 只在创建时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// token白名单，未开启时不限制注册的token
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WhiteTokenList {
+    pub enabled: bool,
+    pub tokens: Vec<WhiteTokenInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WhiteTokenInfo {
+    pub token: String,
+    // 启动参数配置的token，不能删除
+    pub configured: bool,
+    pub create_time: String,
+    pub first_seen: Option<String>,
+    pub last_used: Option<String>,
+    // 注册成功的次数
+    pub registrations: u64,
+    // 组内的设备数和在线设备数
+    pub clients: usize,
+    pub online: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub username: String,
+    pub role: Role,
+    pub login_time: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReassignIp {
+    pub group: String,
+    pub virtual_ip: Ipv4Addr,
+    pub new_ip: Ipv4Addr,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetDeviceInfo {
+    pub group: String,
+    pub virtual_ip: Ipv4Addr,
+    // 为null时在设备下次注册时恢复使用客户端上报的名称
+    pub name: Option<String>,
+    // 为null时不修改，空字符串清除备注
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SendNotice {
+    pub group: String,
+    // 为null时推送给组内所有在线设备
+    pub virtual_ip: Option<Ipv4Addr>,
+    // info(默认)或warning
+    pub level: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClientConfigQuery {
+    pub group: String,
+    // 为null时查询组的配置
+    pub virtual_ip: Option<Ipv4Addr>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetClientConfig {
+    pub group: String,
+    // 为null时设置组的配置
+    pub virtual_ip: Option<Ipv4Addr>,
+    // 为null时清除配置
+    pub config: Option<ClientConfigData>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClientConfigData {
+    // 修改时间，unix时间戳毫秒，设置时忽略
+    #[serde(default)]
+    pub version: i64,
+    #[serde(default)]
+    pub values: BTreeMap<String, String>,
+    // 原始数据，base64编码
+    #[serde(default)]
+    pub data: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProbeReachability {
+    pub group: String,
+    pub virtual_ip: Ipv4Addr,
+}
+
+/// 设备候选地址的探测结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Reachability {
+    pub address: String,
+    // public：注册的地址，port_sample：nat类型探测看到的端口，predicted：预测的端口，local：上报的本地地址
+    pub source: String,
+    pub reachable: bool,
+    // 延迟，毫秒
+    pub rtt: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReleaseIp {
+    pub group: String,
+    pub virtual_ip: Ipv4Addr,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetProtected {
+    pub group: String,
+    pub virtual_ip: Ipv4Addr,
+    // 受保护的设备离线多久都不回收ip
+    pub protected: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetTags {
+    pub group: String,
+    pub virtual_ip: Ipv4Addr,
+    // 为null时恢复使用客户端上报的标签
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateGroup {
+    pub group: String,
+    // 网段，格式为 网关/掩码位数，默认使用启动参数的网段
+    pub subnet: Option<String>,
+    // 设备数量上限
+    pub max_clients: Option<usize>,
+    // 同一公网ip的设备数量上限
+    pub max_clients_per_ip: Option<usize>,
+    // 组内中继的总带宽上限，例如 10M
+    pub relay_bandwidth: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupSummary {
+    pub group: String,
+    // 网段
+    pub network_ip: Ipv4Addr,
+    // 掩码
+    pub mask_ip: Ipv4Addr,
+    // 网关
+    pub gateway_ip: Ipv4Addr,
+    // 设备数
+    pub client_count: usize,
+    // 在线设备数
+    pub online_count: usize,
+    // 可分配的ip数，不包括保留的ip
+    pub address_capacity: u64,
+    // 最后活动时间
+    pub last_activity: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PeerLinkInfo {
+    pub ip_a: Ipv4Addr,
+    pub ip_b: Ipv4Addr,
+    // 经服务器中继的数据包数
+    pub relay_packets: u64,
+    // 经服务器中继的字节数
+    pub relay_bytes: u64,
+    // 距离最后一次中继的秒数
+    pub last_relay_secs: Option<u64>,
+    // 状态上报中是p2p的次数
+    pub p2p_reports: u64,
+    // 状态上报中不是p2p，并且有中继流量的次数
+    pub relay_reports: u64,
+    // p2p成功率
+    pub p2p_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkMapQuery {
+    pub group: String,
+    // json(默认)或者dot
+    pub format: Option<String>,
+}
+
+/// 组的拓扑
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkMap {
+    pub group: String,
+    // 网关，所有设备都通过服务器连接到网关
+    pub gateway_ip: Ipv4Addr,
+    // 组内的网段，格式为 网段/掩码位数
+    pub subnets: Vec<String>,
+    pub nodes: Vec<MapNode>,
+    pub links: Vec<MapLink>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MapNode {
+    pub virtual_ip: Ipv4Addr,
+    pub name: String,
+    pub online: bool,
+    // 上报的nat类型是否为锥形，未上报时为null
+    pub is_cone: Option<bool>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MapLink {
+    pub ip_a: Ipv4Addr,
+    pub ip_b: Ipv4Addr,
+    // p2p：设备之间直连，relay：经服务器中继，server：设备和网关之间
+    pub kind: String,
+}
+
+impl NetworkMap {
+    /// 转换为Graphviz DOT格式，p2p为实线，中继为虚线，设备和网关之间为点线
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("graph {} {{\n", dot_quote(&self.group));
+        dot.push_str(&format!(
+            "  {} [label={}, shape=box];\n",
+            dot_quote(&self.gateway_ip.to_string()),
+            dot_quote(&format!(
+                "gateway\n{}\n{}",
+                self.gateway_ip,
+                self.subnets.join("\n")
+            ))
+        ));
+        for node in &self.nodes {
+            let mut label = format!("{}\n{}", node.name, node.virtual_ip);
+            if !node.tags.is_empty() {
+                label.push_str(&format!("\n[{}]", node.tags.join(",")));
+            }
+            dot.push_str(&format!(
+                "  {} [label={}{}];\n",
+                dot_quote(&node.virtual_ip.to_string()),
+                dot_quote(&label),
+                if node.online { "" } else { ", style=dashed" }
+            ));
+        }
+        for link in &self.links {
+            let style = match link.kind.as_str() {
+                "p2p" => "solid",
+                "relay" => "dashed",
+                _ => "dotted",
+            };
+            dot.push_str(&format!(
+                "  {} -- {} [style={}];\n",
+                dot_quote(&link.ip_a.to_string()),
+                dot_quote(&link.ip_b.to_string()),
+                style
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn dot_quote(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionAudit {
+    // 检查次数
+    pub runs: u64,
+    pub last_run: Option<String>,
+    // 删除的指向不存在设备的addr_session
+    pub addr_sessions: u64,
+    // 删除的指向不存在设备的ip_session
+    pub ip_sessions: u64,
+    // 补上的缺失的ip_session
+    pub missing_ip_sessions: u64,
+    // 删除的没有绑定设备的加密会话
+    pub cipher_sessions: u64,
+    // 没有连接上下文但仍标记为在线的设备
+    pub online_flags: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostileTraffic {
+    // 异常类型
+    pub kind: String,
+    // 启动以来的次数
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuspiciousSourceInfo {
+    pub ip: IpAddr,
+    // 各类型异常的次数
+    pub counts: Vec<HostileTraffic>,
+    pub first_seen: String,
+    pub last_seen: String,
+    // 剩余封禁时长，单位为秒，0表示未封禁
+    pub blocked_seconds: u64,
+    // 被封禁的次数
+    pub blocks: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnblockSource {
+    pub ip: IpAddr,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogLevel {
+    // 模块，例如 vnts::core::service::client，为空时调整全局级别
+    pub module: Option<String>,
+    // error、warn、info、debug、trace、off，为空时恢复配置文件中的级别
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MigrateGroup {
+    pub group: String,
+    // 新服务器的地址，host:port，为null时取消迁移
+    pub address: Option<String>,
+    // 等待设备迁移的最长时间，秒，为null时使用默认值
+    pub wait: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetMaintenance {
+    // 为null时只查询当前状态
+    pub enabled: Option<bool>,
+    // 建议客户端重试注册的间隔，秒，为null时使用默认值
+    pub retry_after: Option<u32>,
+    // 展示给客户端的说明，为null时使用默认的错误信息
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    pub retry_after: u32,
+    pub message: Option<String>,
+    // 开启时间
+    pub since: Option<String>,
+    // 仍然在线的设备数，降到0后可以安全升级
+    pub online: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogLevels {
+    // 运行中调整的全局级别
+    pub root: Option<String>,
+    // 运行中调整的模块级别
+    pub modules: BTreeMap<String, String>,
+}
+
+#[cfg(feature = "profiling")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DebugProfile {
+    // 当前占用的堆内存，字节
+    pub heap_allocated: usize,
+    // 启动以来占用的最大堆内存，字节
+    pub heap_peak: usize,
+    // 启动以来的分配次数
+    pub allocations: u64,
+    // 启动以来的释放次数
+    pub deallocations: u64,
+    // 进程占用的cpu时间，毫秒
+    pub cpu_time_ms: Option<u64>,
+    // 进程的线程数
+    pub threads: Option<u64>,
+}
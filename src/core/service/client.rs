@@ -1,15 +1,20 @@
 #![allow(dead_code)]
 
-use std::net::SocketAddr;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use tokio::net::UdpSocket;
+use packet::ip::ipv4::packet::IpV4Packet;
+use parking_lot::RwLock;
 
 use crate::cipher::RsaCipher;
-use crate::core::entity::ClientInfo;
+use crate::core::entity::{relay_allowed, ClientInfo, Flow, NetworkInfo, TagRule};
+use crate::core::service::gateway;
+use crate::core::service::scheduler::RelayScheduler;
 use crate::core::store::cache::{AppCache, Context};
 use crate::error::*;
-use crate::protocol::NetPacket;
+use crate::protocol::body::ENCRYPTION_RESERVED;
+use crate::protocol::{ip_turn_packet, NetPacket, Protocol, HEAD_LEN, SEQUENCE_LEN};
 use crate::ConfigInfo;
 
 #[derive(Clone)]
@@ -17,7 +22,7 @@ pub struct ClientPacketHandler {
     cache: AppCache,
     config: ConfigInfo,
     rsa_cipher: Option<RsaCipher>,
-    udp: Arc<UdpSocket>,
+    scheduler: RelayScheduler,
 }
 
 impl ClientPacketHandler {
@@ -25,25 +30,26 @@ impl ClientPacketHandler {
         cache: AppCache,
         config: ConfigInfo,
         rsa_cipher: Option<RsaCipher>,
-        udp: Arc<UdpSocket>,
+        scheduler: RelayScheduler,
     ) -> Self {
         Self {
             cache,
             config,
             rsa_cipher,
-            udp,
+            scheduler,
         }
     }
 }
 
 impl ClientPacketHandler {
+    /// 返回需要回应给发送方的ipv4数据包
     pub fn handle<B: AsRef<[u8]> + AsMut<[u8]>>(
         &self,
         net_packet: NetPacket<B>,
         addr: SocketAddr,
-    ) -> Result<()> {
+    ) -> Result<Option<Vec<u8>>> {
         if let Some(context) = self.cache.get_context(&addr) {
-            self.handle0(net_packet, context)
+            self.handle0(net_packet, addr, context)
         } else {
             Err(Error::Disconnect)
         }
@@ -55,43 +61,439 @@ impl ClientPacketHandler {
     fn handle0<B: AsRef<[u8]> + AsMut<[u8]>>(
         &self,
         mut net_packet: NetPacket<B>,
-        context: Context,
-    ) -> Result<()> {
+        addr: SocketAddr,
+        context: Arc<Context>,
+    ) -> Result<Option<Vec<u8>>> {
+        let network_info = context.network_info.read();
+        // 序号只用于统计丢包，转发前去掉
+        if let Some(client_info) = network_info.clients.get(&context.virtual_ip) {
+            if let Some(loss) = client_info
+                .loss
+                .as_ref()
+                .filter(|_| client_info.address == addr)
+            {
+                if let Some(sequence) = net_packet.take_sequence() {
+                    loss.record(sequence);
+                }
+            }
+        }
         if net_packet.incr_ttl() > 1 {
             if self.config.check_finger {
-                let finger = crate::cipher::Finger::new(&context.group);
-                finger.check_finger(&net_packet)?;
+                context.finger.check_finger(&net_packet)?;
             }
             let destination = net_packet.destination();
-            if destination.is_broadcast() || self.config.broadcast == destination {
+            if network_info.policy.client_isolation {
+                return Ok(None);
+            }
+            let reencrypt = if network_info.policy.relay_encryption {
+                self.decrypt_relay(&network_info, context.virtual_ip, addr, &mut net_packet)?;
+                Some(&self.cache)
+            } else {
+                None
+            };
+            if !send_allowed(&network_info, context.virtual_ip, destination, &net_packet) {
+                return Ok(None);
+            }
+            if destination.is_broadcast() || network_info.is_broadcast(destination.into()) {
                 //处理广播
-                broadcast(&self.udp, context, net_packet);
-            } else if let Some(client_info) =
-                context.network_info.read().clients.get(&destination.into())
-            {
-                send_one(&self.udp, client_info, &net_packet);
+                let recipients =
+                    broadcast_recipients(&network_info, context.virtual_ip, &net_packet, &[]);
+                if !broadcast_allowed(&network_info, recipients.len()) {
+                    return Ok(None);
+                }
+                // 按实际接收的设备数计入中继带宽
+                let len = net_packet.buffer().len() * recipients.len();
+                if !relay_acquire(&network_info, len) {
+                    return Ok(None);
+                }
+                match check_mtu(&network_info, None, &net_packet)? {
+                    Some(Oversized::Fragments(fragments)) => {
+                        for fragment in fragments {
+                            broadcast(
+                                &self.scheduler,
+                                &context.network_info,
+                                &network_info,
+                                &recipients,
+                                fragment,
+                                reencrypt,
+                            );
+                        }
+                    }
+                    Some(Oversized::Unreachable(ipv4)) => return Ok(Some(ipv4)),
+                    None => broadcast(
+                        &self.scheduler,
+                        &context.network_info,
+                        &network_info,
+                        &recipients,
+                        net_packet,
+                        reencrypt,
+                    ),
+                }
+            } else if destination.is_multicast() {
+                // mDNS/SSDP反射给组内其他设备，其他组播没有接收方
+                if !is_plain_ipv4(&net_packet)
+                    || !gateway::is_discovery(net_packet.payload())
+                    || !network_info.discovery_allowed()
+                {
+                    return Ok(None);
+                }
+                let recipients = broadcast_recipients(
+                    &network_info,
+                    context.virtual_ip,
+                    &net_packet,
+                    &[context.virtual_ip.into()],
+                );
+                if recipients.is_empty() || !broadcast_allowed(&network_info, recipients.len()) {
+                    return Ok(None);
+                }
+                // 按实际接收的设备数计入中继带宽
+                let len = net_packet.buffer().len() * recipients.len();
+                if !relay_acquire(&network_info, len) {
+                    return Ok(None);
+                }
+                broadcast(
+                    &self.scheduler,
+                    &context.network_info,
+                    &network_info,
+                    &recipients,
+                    net_packet,
+                    reencrypt,
+                );
+            } else if let Some(client_info) = network_info.clients.get(&destination.into()) {
+                if !TagFilter::new(&network_info, context.virtual_ip, &net_packet)
+                    .allowed(client_info)
+                {
+                    return Ok(None);
+                }
+                if !relay_acquire(&network_info, net_packet.buffer().len()) {
+                    return Ok(None);
+                }
+                network_info.peer_stats.relay(
+                    context.virtual_ip,
+                    destination.into(),
+                    net_packet.buffer().len(),
+                );
+                clamp_mss(
+                    &network_info,
+                    network_info.clients.get(&context.virtual_ip),
+                    client_info,
+                    &mut net_packet,
+                );
+                match check_mtu(&network_info, Some(client_info), &net_packet)? {
+                    Some(Oversized::Fragments(fragments)) => {
+                        for fragment in fragments {
+                            send_one(
+                                &self.scheduler,
+                                &context.network_info,
+                                &network_info,
+                                client_info,
+                                &fragment,
+                                reencrypt,
+                            );
+                        }
+                    }
+                    Some(Oversized::Unreachable(ipv4)) => return Ok(Some(ipv4)),
+                    None => send_one(
+                        &self.scheduler,
+                        &context.network_info,
+                        &network_info,
+                        client_info,
+                        &net_packet,
+                        reencrypt,
+                    ),
+                }
             }
         }
+        Ok(None)
+    }
+    /// 服务端中继加密：没有开启客户端加密的设备发来的加密数据使用的是和服务端的会话密钥，先解密再按接收方重新加密
+    fn decrypt_relay<B: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        network_info: &NetworkInfo,
+        source: u32,
+        addr: SocketAddr,
+        net_packet: &mut NetPacket<B>,
+    ) -> Result<()> {
+        if !net_packet.is_encrypt() {
+            return Ok(());
+        }
+        let Some(sender) = network_info.clients.get(&source) else {
+            return Ok(());
+        };
+        if sender.client_secret || !sender.server_secret {
+            return Ok(());
+        }
+        let Some(aes) = self.cache.get_cipher(&addr) else {
+            return Err(Error::NoKey);
+        };
+        aes.decrypt_ipv4(net_packet)?;
         Ok(())
     }
 }
 
-fn broadcast<B: AsRef<[u8]>>(udp_socket: &UdpSocket, context: Context, net_packet: NetPacket<B>) {
-    for client_info in context.network_info.read().clients.values() {
-        send_one(udp_socket, client_info, &net_packet);
+/// 超出mtu的ipv4数据包
+enum Oversized {
+    // 由网关分片后的数据包
+    Fragments(Vec<NetPacket<Vec<u8>>>),
+    // 设置了不分片，回应给发送方的icmp需要分片
+    Unreachable(Vec<u8>),
+}
+
+/// 中继的数据是否是服务端可以解析的ipv4数据包，客户端间加密的数据无法解析
+fn is_plain_ipv4<B: AsRef<[u8]>>(net_packet: &NetPacket<B>) -> bool {
+    !net_packet.is_encrypt()
+        && net_packet.protocol() == Protocol::IpTurn
+        && ip_turn_packet::Protocol::from(net_packet.transport_protocol())
+            == ip_turn_packet::Protocol::Ipv4
+}
+
+/// 中继的ipv4数据包大小上限，取组的mtu和目标设备的路径mtu中较小的
+fn relay_mtu(network_info: &NetworkInfo, destination: Option<&ClientInfo>) -> Option<u16> {
+    let group = network_info.policy.mtu.map(|mtu| mtu.0);
+    match (group, destination.and_then(ClientInfo::relay_mtu)) {
+        (Some(group), Some(path)) => Some(group.min(path)),
+        (group, path) => group.or(path),
+    }
+}
+
+/// 探测到两端设备的路径mtu后，按较小的mtu调小中继的tcp SYN中的mss，避免之后的数据需要分片
+fn clamp_mss<B: AsRef<[u8]> + AsMut<[u8]>>(
+    network_info: &NetworkInfo,
+    source: Option<&ClientInfo>,
+    destination: &ClientInfo,
+    net_packet: &mut NetPacket<B>,
+) {
+    let Some(path) = [source, Some(destination)]
+        .into_iter()
+        .flatten()
+        .filter_map(ClientInfo::relay_mtu)
+        .min()
+    else {
+        return;
+    };
+    if !is_plain_ipv4(net_packet) {
+        return;
+    }
+    let mtu = network_info.policy.mtu.map_or(path, |mtu| mtu.0.min(path));
+    // ip和tcp头部各20字节，无法解析的数据原样转发
+    let _ = gateway::clamp_mss(net_packet.payload_mut(), mtu.saturating_sub(40));
+}
+
+/// 检查中继的ipv4数据包是否超出组的mtu或目标设备的路径mtu，客户端间加密的数据无法分片，原样转发
+fn check_mtu<B: AsRef<[u8]>>(
+    network_info: &NetworkInfo,
+    destination: Option<&ClientInfo>,
+    net_packet: &NetPacket<B>,
+) -> Result<Option<Oversized>> {
+    let Some(mtu) = relay_mtu(network_info, destination) else {
+        return Ok(None);
+    };
+    if !is_plain_ipv4(net_packet) {
+        return Ok(None);
+    }
+    let ipv4 = net_packet.payload();
+    if ipv4.len() <= mtu as usize {
+        return Ok(None);
+    }
+    let packet = IpV4Packet::new(ipv4)?;
+    if packet.flags() & 0b010 != 0 {
+        let icmp = gateway::icmp_fragmentation_needed(ipv4, mtu, network_info.gateway_ip.into())?;
+        return Ok(Some(Oversized::Unreachable(icmp)));
+    }
+    let head = &net_packet.buffer()[..HEAD_LEN];
+    let mut fragments = Vec::new();
+    for fragment in gateway::fragment_ipv4(ipv4, mtu as usize)? {
+        let mut buf = Vec::with_capacity(HEAD_LEN + fragment.len());
+        buf.extend_from_slice(head);
+        buf.extend_from_slice(&fragment);
+        fragments.push(NetPacket::new(buf)?);
+    }
+    Ok(Some(Oversized::Fragments(fragments)))
+}
+
+/// 组内中继带宽超出上限时丢弃数据
+pub(super) fn relay_acquire(network_info: &NetworkInfo, len: usize) -> bool {
+    let acquired = match &network_info.relay_limiter {
+        Some(limiter) => limiter.try_acquire(len as u64),
+        None => true,
+    };
+    if acquired {
+        network_info
+            .relay_bytes
+            .fetch_add(len as u64, Ordering::Relaxed);
+    }
+    acquired
+}
+
+/// 检查广播的接收设备数量和组内每秒的广播投递次数，超出上限时丢弃并计数
+pub(super) fn broadcast_allowed(network_info: &NetworkInfo, recipients: usize) -> bool {
+    let allowed = network_info
+        .policy
+        .broadcast_fanout
+        .is_none_or(|max| recipients <= max)
+        && match &network_info.broadcast_limiter {
+            Some(limiter) => limiter.try_acquire(recipients as u64),
+            None => true,
+        };
+    if !allowed {
+        network_info
+            .broadcast_dropped
+            .fetch_add(1, Ordering::Relaxed);
+    }
+    allowed
+}
+
+/// 按组的发送规则过滤设备发出的ip数据，打洞等客户端之间的控制消息不受限制
+fn send_allowed<B: AsRef<[u8]>>(
+    network_info: &NetworkInfo,
+    source: u32,
+    destination: Ipv4Addr,
+    net_packet: &NetPacket<B>,
+) -> bool {
+    if network_info.policy.send_rules.is_empty()
+        || net_packet.protocol() != Protocol::IpTurn
+        || ip_turn_packet::Protocol::from(net_packet.transport_protocol())
+            != ip_turn_packet::Protocol::Ipv4
+    {
+        return true;
     }
+    let ipv4 = (!net_packet.is_encrypt()).then(|| net_packet.payload());
+    network_info.send_allowed(source, destination, ipv4)
+}
+
+/// 按组的标签规则过滤中继数据
+struct TagFilter<'a> {
+    rules: &'a [TagRule],
+    // 发送方的标签
+    from: &'a [String],
+    flow: Option<Flow>,
 }
 
+impl<'a> TagFilter<'a> {
+    fn new<B: AsRef<[u8]>>(
+        network_info: &'a NetworkInfo,
+        source: u32,
+        net_packet: &NetPacket<B>,
+    ) -> Self {
+        let rules = &network_info.policy.tag_rules[..];
+        let from = network_info
+            .clients
+            .get(&source)
+            .map(|v| &v.tags[..])
+            .unwrap_or_default();
+        // 只过滤ip数据，打洞等客户端之间的控制消息不受限制
+        let is_ipv4 = net_packet.protocol() == Protocol::IpTurn
+            && ip_turn_packet::Protocol::from(net_packet.transport_protocol())
+                == ip_turn_packet::Protocol::Ipv4;
+        let flow = if rules.is_empty() || !is_ipv4 || net_packet.is_encrypt() {
+            None
+        } else {
+            Flow::parse(net_packet.payload())
+        };
+        let rules = if is_ipv4 { rules } else { &[] };
+        Self { rules, from, flow }
+    }
+    fn allowed(&self, client_info: &ClientInfo) -> bool {
+        self.rules.is_empty() || relay_allowed(self.rules, self.from, &client_info.tags, self.flow)
+    }
+}
+
+/// 广播的接收设备：在线、加密设置和数据一致、不在exclude中并且标签规则允许，组开启了设备隔离时没有接收设备
+pub(super) fn broadcast_recipients<'a, B: AsRef<[u8]>>(
+    network_info: &'a NetworkInfo,
+    source: u32,
+    net_packet: &NetPacket<B>,
+    exclude: &[Ipv4Addr],
+) -> Vec<&'a ClientInfo> {
+    if network_info.policy.client_isolation {
+        return Vec::new();
+    }
+    let filter = TagFilter::new(network_info, source, net_packet);
+    network_info
+        .clients
+        .iter()
+        .filter(|(ip, client_info)| {
+            client_info.online
+                && client_info.client_secret == net_packet.is_encrypt()
+                && !exclude.contains(&(**ip).into())
+                && filter.allowed(client_info)
+        })
+        .map(|(_, client_info)| client_info)
+        .collect()
+}
+
+/// 逐个发给接收设备，经过send_one附加丢包统计的序号和按接收方重新加密
+pub(super) fn broadcast<B: AsRef<[u8]>>(
+    scheduler: &RelayScheduler,
+    network: &Arc<RwLock<NetworkInfo>>,
+    network_info: &NetworkInfo,
+    recipients: &[&ClientInfo],
+    net_packet: NetPacket<B>,
+    reencrypt: Option<&AppCache>,
+) {
+    for client_info in recipients {
+        send_one(
+            scheduler,
+            network,
+            network_info,
+            client_info,
+            &net_packet,
+            reencrypt,
+        );
+    }
+}
+
+/// reencrypt不为None时，明文数据发给和服务端建立了加密会话的设备前使用该设备的会话密钥加密
 fn send_one<B: AsRef<[u8]>>(
-    udp_socket: &UdpSocket,
+    scheduler: &RelayScheduler,
+    network: &Arc<RwLock<NetworkInfo>>,
+    network_info: &NetworkInfo,
     client_info: &ClientInfo,
     net_packet: &NetPacket<B>,
+    reencrypt: Option<&AppCache>,
 ) {
-    if client_info.online && client_info.client_secret == net_packet.is_encrypt() {
-        if let Some(sender) = &client_info.tcp_sender {
-            let _ = sender.try_send(net_packet.buffer().to_vec());
-        } else {
-            let _ = udp_socket.try_send_to(net_packet.buffer(), client_info.address);
+    if !client_info.online || client_info.client_secret != net_packet.is_encrypt() {
+        return;
+    }
+    let encrypted;
+    let buf = match reencrypt {
+        Some(cache) if client_info.server_secret && !net_packet.is_encrypt() => {
+            match server_encrypt(cache, client_info.address, net_packet) {
+                Ok(packet) => {
+                    encrypted = packet;
+                    encrypted.buffer()
+                }
+                Err(e) => {
+                    log::warn!("中继加密失败 {},{:?}", client_info.address, e);
+                    return;
+                }
+            }
         }
+        _ => net_packet.buffer(),
+    };
+    if let Some(sender) = &client_info.tcp_sender {
+        let _ = sender.try_send(buf.to_vec());
+    } else if let Some(loss) = &client_info.loss {
+        let mut sequenced = Vec::with_capacity(buf.len() + SEQUENCE_LEN);
+        sequenced.extend_from_slice(buf);
+        sequenced.extend_from_slice(&loss.next_sequence().to_be_bytes());
+        scheduler.send(network, network_info, &sequenced, client_info.address);
+    } else {
+        scheduler.send(network, network_info, buf, client_info.address);
     }
 }
+
+/// 使用接收方和服务端的会话密钥加密
+fn server_encrypt<B: AsRef<[u8]>>(
+    cache: &AppCache,
+    addr: SocketAddr,
+    net_packet: &NetPacket<B>,
+) -> Result<NetPacket<Vec<u8>>> {
+    let aes = cache.get_cipher(&addr).ok_or(Error::NoKey)?;
+    let mut buf = Vec::with_capacity(net_packet.buffer().len() + ENCRYPTION_RESERVED);
+    buf.extend_from_slice(net_packet.buffer());
+    buf.resize(buf.len() + ENCRYPTION_RESERVED, 0);
+    let mut packet = NetPacket::new_encrypt(buf)?;
+    aes.encrypt_ipv4(&mut packet)?;
+    Ok(packet)
+}
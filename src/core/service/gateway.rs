@@ -0,0 +1,250 @@
+use std::io;
+use std::net::Ipv4Addr;
+
+use packet::icmp;
+use packet::ip::ipv4;
+use packet::ip::ipv4::packet::IpV4Packet;
+use packet::tcp;
+use packet::tcp::tcp::TcpPacket;
+use packet::udp::udp::UdpPacket;
+
+/// 网关上的tcp回显服务，用于验证tcp数据的转发
+///
+/// 无状态实现：序列号使用对端的确认号，不保存连接信息
+/// SYN -> SYN|ACK，数据 -> 回显数据，FIN -> FIN|ACK
+/// 返回回应的ipv4数据包，不是发往回显端口的数据直接丢弃
+pub fn tcp_echo<B: AsRef<[u8]>>(ipv4: &IpV4Packet<B>, port: u16) -> io::Result<Option<Vec<u8>>> {
+    let source = ipv4.source_ip();
+    let destination = ipv4.destination_ip();
+    let tcp_packet = TcpPacket::new(source, destination, ipv4.payload())?;
+    if tcp_packet.destination_port() != port {
+        return Ok(None);
+    }
+    if !tcp_packet.is_valid() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "tcp checksum err",
+        ));
+    }
+    let flags = tcp_packet.flags();
+    if flags.contains(tcp::RST) {
+        return Ok(None);
+    }
+    let (sequence, acknowledgment, reply_flags, data) = if flags.contains(tcp::SYN) {
+        if flags.contains(tcp::ACK) {
+            return Ok(None);
+        }
+        (
+            rand::random::<u32>(),
+            tcp_packet.sequence().wrapping_add(1),
+            tcp::SYN | tcp::ACK,
+            &[][..],
+        )
+    } else if flags.contains(tcp::ACK) {
+        let data = tcp_packet.payload();
+        let fin = flags.contains(tcp::FIN);
+        if data.is_empty() && !fin {
+            // 纯确认包不需要回应
+            return Ok(None);
+        }
+        let mut reply_flags = tcp::ACK;
+        if !data.is_empty() {
+            reply_flags |= tcp::PSH;
+        }
+        if fin {
+            reply_flags |= tcp::FIN;
+        }
+        (
+            tcp_packet.acknowledgment(),
+            tcp_packet
+                .sequence()
+                .wrapping_add(data.len() as u32)
+                .wrapping_add(fin as u32),
+            reply_flags,
+            data,
+        )
+    } else {
+        return Ok(None);
+    };
+    let len = 20 + 20 + data.len();
+    let mut buf = vec![0u8; len];
+    let mut reply = IpV4Packet::unchecked(&mut buf);
+    reply.set_version_and_header_len(5);
+    reply.set_length(len as u16);
+    reply.set_id(rand::random());
+    // 不分片
+    reply.set_flags(0b010);
+    reply.set_ttl(64);
+    reply.set_protocol(ipv4::protocol::Protocol::Tcp);
+    reply.set_source_ip(destination);
+    reply.set_destination_ip(source);
+    reply.update_checksum();
+    let mut reply_tcp = TcpPacket::unchecked(destination, source, reply.payload_mut());
+    reply_tcp.set_source_port(tcp_packet.destination_port());
+    reply_tcp.set_destination_port(tcp_packet.source_port());
+    reply_tcp.set_sequence(sequence);
+    reply_tcp.set_acknowledgment(acknowledgment);
+    reply_tcp.set_data_offset(5);
+    reply_tcp.set_flags(reply_flags);
+    reply_tcp.set_window(u16::MAX);
+    reply_tcp.payload_mut().copy_from_slice(data);
+    reply_tcp.update_checksum();
+    Ok(Some(buf))
+}
+
+/// 构造网关发出的udp数据包，source和destination为(ip，端口)
+pub fn udp_ipv4(source: (Ipv4Addr, u16), destination: (Ipv4Addr, u16), data: &[u8]) -> Vec<u8> {
+    let len = 20 + 8 + data.len();
+    let mut buf = vec![0u8; len];
+    let mut packet = IpV4Packet::unchecked(&mut buf);
+    packet.set_version_and_header_len(5);
+    packet.set_length(len as u16);
+    packet.set_id(rand::random());
+    packet.set_ttl(64);
+    packet.set_protocol(ipv4::protocol::Protocol::Udp);
+    packet.set_source_ip(source.0);
+    packet.set_destination_ip(destination.0);
+    packet.update_checksum();
+    let udp = packet.payload_mut();
+    udp[4..6].copy_from_slice(&((8 + data.len()) as u16).to_be_bytes());
+    udp[8..].copy_from_slice(data);
+    let mut udp = UdpPacket::unchecked(source.0, destination.0, udp);
+    udp.set_source_port(source.1);
+    udp.set_destination_port(destination.1);
+    udp.update_checksum();
+    buf
+}
+
+/// ipv4分片，调用方需要先判断是否允许分片(DF)
+///
+/// 每个分片复制原始头部，已经是分片的数据包会继续按原偏移分片
+pub fn fragment_ipv4(ipv4: &[u8], mtu: usize) -> io::Result<Vec<Vec<u8>>> {
+    let packet = IpV4Packet::new(ipv4)?;
+    let header = packet.header();
+    let total_len = (packet.length() as usize).min(ipv4.len());
+    if total_len < header.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "length err"));
+    }
+    let payload = &ipv4[header.len()..total_len];
+    // 除最后一片外，分片数据长度必须是8的倍数
+    let max_data = mtu.saturating_sub(header.len()) & !7;
+    if max_data == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "mtu too small"));
+    }
+    let base_offset = packet.offset() as usize * 8;
+    let more_fragments = packet.flags() & 0b001;
+    let count = payload.len().div_ceil(max_data);
+    let mut fragments = Vec::with_capacity(count);
+    for (i, chunk) in payload.chunks(max_data).enumerate() {
+        let mut buf = Vec::with_capacity(header.len() + chunk.len());
+        buf.extend_from_slice(header);
+        buf.extend_from_slice(chunk);
+        let mut fragment = IpV4Packet::unchecked(&mut buf);
+        fragment.set_length((header.len() + chunk.len()) as u16);
+        fragment.set_flags(if i + 1 < count { 0b001 } else { more_fragments });
+        fragment.set_offset(((base_offset + i * max_data) / 8) as u16);
+        fragment.update_checksum();
+        fragments.push(buf);
+    }
+    Ok(fragments)
+}
+
+/// icmp目的不可达(需要分片)，由网关回应给发送方
+pub fn icmp_fragmentation_needed(ipv4: &[u8], mtu: u16, gateway: Ipv4Addr) -> io::Result<Vec<u8>> {
+    let packet = IpV4Packet::new(ipv4)?;
+    // 原始头部和8字节数据
+    let quote_len = (packet.header().len() + 8).min(ipv4.len());
+    let len = 20 + 8 + quote_len;
+    let mut buf = vec![0u8; len];
+    let mut reply = IpV4Packet::unchecked(&mut buf);
+    reply.set_version_and_header_len(5);
+    reply.set_length(len as u16);
+    reply.set_id(rand::random());
+    reply.set_ttl(64);
+    reply.set_protocol(ipv4::protocol::Protocol::Icmp);
+    reply.set_source_ip(gateway);
+    reply.set_destination_ip(packet.source_ip());
+    reply.update_checksum();
+    let icmp = reply.payload_mut();
+    icmp[0] = icmp::Kind::DestinationUnreachable.into();
+    // code 4: 需要分片但设置了DF
+    icmp[1] = 4;
+    icmp[6..8].copy_from_slice(&mtu.to_be_bytes());
+    icmp[8..].copy_from_slice(&ipv4[..quote_len]);
+    icmp::icmp::IcmpPacket::unchecked(icmp).update_checksum();
+    Ok(buf)
+}
+
+/// mDNS的组播地址和端口
+const MDNS: (Ipv4Addr, u16) = (Ipv4Addr::new(224, 0, 0, 251), 5353);
+/// SSDP的组播地址和端口
+const SSDP: (Ipv4Addr, u16) = (Ipv4Addr::new(239, 255, 255, 250), 1900);
+
+/// 是否是发往mDNS或SSDP组播地址的udp数据包，设备发现(打印机、投屏等)依赖这两种组播
+pub fn is_discovery(ipv4: &[u8]) -> bool {
+    let Ok(packet) = IpV4Packet::new(ipv4) else {
+        return false;
+    };
+    if packet.protocol() != ipv4::protocol::Protocol::Udp || packet.offset() != 0 {
+        return false;
+    }
+    let udp = packet.payload();
+    if udp.len() < 8 {
+        return false;
+    }
+    let port = u16::from_be_bytes([udp[2], udp[3]]);
+    let destination = (packet.destination_ip(), port);
+    destination == MDNS || destination == SSDP
+}
+
+/// 把tcp SYN中的mss选项调小到不超过mss，返回是否修改
+///
+/// 经服务端中继的tcp连接按调小后的mss分段，分段后的数据包不超过路径mtu
+pub fn clamp_mss(ipv4: &mut [u8], mss: u16) -> io::Result<bool> {
+    let packet = IpV4Packet::new(&*ipv4)?;
+    if packet.protocol() != ipv4::protocol::Protocol::Tcp || packet.offset() != 0 {
+        return Ok(false);
+    }
+    let source = packet.source_ip();
+    let destination = packet.destination_ip();
+    let header_len = packet.header().len();
+    let total_len = (packet.length() as usize).min(ipv4.len());
+    if total_len < header_len {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "length err"));
+    }
+    let segment = &mut ipv4[header_len..total_len];
+    let tcp_packet = TcpPacket::new(source, destination, &*segment)?;
+    if !tcp_packet.flags().contains(tcp::SYN) {
+        return Ok(false);
+    }
+    let options_end = tcp_packet.data_offset() as usize * 4;
+    let mut i = 20;
+    while i < options_end {
+        match segment[i] {
+            // 选项结束
+            0 => break,
+            // 填充
+            1 => i += 1,
+            kind => {
+                let Some(&len) = segment.get(i + 1) else {
+                    break;
+                };
+                let len = len as usize;
+                if len < 2 || i + len > options_end {
+                    break;
+                }
+                if kind == 2 && len == 4 {
+                    let value = u16::from_be_bytes([segment[i + 2], segment[i + 3]]);
+                    if value <= mss {
+                        return Ok(false);
+                    }
+                    segment[i + 2..i + 4].copy_from_slice(&mss.to_be_bytes());
+                    TcpPacket::unchecked(source, destination, segment).update_checksum();
+                    return Ok(true);
+                }
+                i += len;
+            }
+        }
+    }
+    Ok(false)
+}
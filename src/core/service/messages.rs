@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::core::entity::Lang;
+use crate::error::Error;
+
+/// 返回给客户端的错误信息
+///
+/// 内置英文和中文，可以通过文件覆盖，文件格式为 {"zh": {"group_full": "..."}}
+#[derive(Debug, Default)]
+pub struct Messages {
+    // 语言 -> (key -> 文本)
+    custom: HashMap<Lang, HashMap<String, String>>,
+}
+
+impl Messages {
+    pub fn load(path: &Path) -> io::Result<Messages> {
+        let data = std::fs::read(path)?;
+        let custom: HashMap<String, HashMap<String, String>> = serde_json::from_slice(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut messages = Messages::default();
+        for (lang, texts) in custom {
+            let lang =
+                Lang::from_str(&lang).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if let Some(key) = texts.keys().find(|key| builtin(Lang::En, key).is_none()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown key {:?}, keys: {}", key, KEYS.join("/")),
+                ));
+            }
+            messages.custom.insert(lang, texts);
+        }
+        Ok(messages)
+    }
+    /// 错误信息，没有对应文本的错误使用错误本身的描述
+    pub fn get(&self, lang: Lang, e: &Error) -> String {
+        let key = match key(e) {
+            Some(key) => key,
+            None => {
+                return match e {
+                    Error::Other(msg) => msg.clone(),
+                    e => e.to_string(),
+                }
+            }
+        };
+        if let Some(text) = self.custom.get(&lang).and_then(|texts| texts.get(key)) {
+            return text.clone();
+        }
+        builtin(lang, key).unwrap_or(key).to_string()
+    }
+}
+
+const KEYS: [&str; 16] = [
+    "token_error",
+    "disconnect",
+    "address_exhausted",
+    "ip_already_exists",
+    "invalid_ip",
+    "no_key",
+    "client_encryption_required",
+    "server_encryption_required",
+    "group_full",
+    "source_limit",
+    "peer_offline",
+    "maintenance",
+    "token_length",
+    "device_id_length",
+    "name_length",
+    "invalid_tags",
+];
+
+fn key(e: &Error) -> Option<&'static str> {
+    match e {
+        Error::TokenError => Some("token_error"),
+        Error::Disconnect => Some("disconnect"),
+        Error::AddressExhausted => Some("address_exhausted"),
+        Error::IpAlreadyExists => Some("ip_already_exists"),
+        Error::InvalidIp => Some("invalid_ip"),
+        Error::NoKey => Some("no_key"),
+        Error::EncryptionRequired(key) | Error::InvalidRequest(key) => Some(key),
+        Error::GroupFull => Some("group_full"),
+        Error::SourceLimit => Some("source_limit"),
+        Error::PeerOffline => Some("peer_offline"),
+        Error::Maintenance => Some("maintenance"),
+        Error::Io(_) | Error::Channel(_) | Error::Protobuf(_) | Error::Other(_) => None,
+    }
+}
+
+fn builtin(lang: Lang, key: &str) -> Option<&'static str> {
+    let (en, zh) = match key {
+        "token_error" => ("invalid token", "token错误"),
+        "disconnect" => (
+            "session expired, please register again",
+            "连接已失效,请重新注册",
+        ),
+        "address_exhausted" => ("no free ip address in the group", "组内没有可分配的ip"),
+        "ip_already_exists" => ("ip address already in use", "ip已被其他设备占用"),
+        "invalid_ip" => (
+            "ip address is not in the group's subnet",
+            "ip不在组的网段内",
+        ),
+        "no_key" => (
+            "encryption session expired, please handshake again",
+            "加密会话已失效,请重新握手",
+        ),
+        "client_encryption_required" => {
+            ("group requires client encryption", "该组要求开启客户端加密")
+        }
+        "server_encryption_required" => {
+            ("group requires server encryption", "该组要求和服务端加密")
+        }
+        "group_full" => ("group client limit reached", "组内设备数量已达上限"),
+        "source_limit" => (
+            "too many devices from the same public ip",
+            "同一公网ip的设备数量已达上限",
+        ),
+        "peer_offline" => ("peer is offline", "对方不在线"),
+        "maintenance" => (
+            "server under maintenance, please retry later",
+            "服务器维护中,请稍后重试",
+        ),
+        "token_length" => ("group length error", "组名长度错误"),
+        "device_id_length" => ("device_id length error", "设备id长度错误"),
+        "name_length" => ("name length error", "设备名称长度错误"),
+        "invalid_tags" => (
+            "invalid tags, at most 16 tags of 1-32 letters, digits, '-' or '_'",
+            "标签错误,最多16个,每个1-32个字母、数字、'-'或'_'",
+        ),
+        _ => return None,
+    };
+    Some(match lang {
+        Lang::En => en,
+        Lang::Zh => zh,
+    })
+}
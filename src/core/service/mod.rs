@@ -5,20 +5,30 @@ use tokio::net::UdpSocket;
 use tokio::sync::mpsc::Sender;
 
 use crate::cipher::RsaCipher;
+use crate::core::entity::{LogLimiter, SuspiciousSources};
+use crate::core::offload::UdpOffload;
 use crate::core::service::client::ClientPacketHandler;
+use crate::core::service::scheduler::RelayScheduler;
 use crate::core::service::server::ServerPacketHandler;
 use crate::core::store::cache::AppCache;
+use crate::core::task;
 use crate::error::*;
 use crate::protocol::NetPacket;
 use crate::ConfigInfo;
 
 pub mod client;
+pub mod gateway;
+pub mod messages;
+pub mod scheduler;
 pub mod server;
+pub mod turn;
 
 #[derive(Clone)]
 pub struct PacketHandler {
     client: ClientPacketHandler,
     server: ServerPacketHandler,
+    log_limiter: LogLimiter,
+    suspicious: SuspiciousSources,
 }
 
 impl PacketHandler {
@@ -27,16 +37,36 @@ impl PacketHandler {
         config: ConfigInfo,
         rsa_cipher: Option<RsaCipher>,
         udp: Arc<UdpSocket>,
+        offload: UdpOffload,
     ) -> Self {
+        let scheduler = RelayScheduler::new(
+            udp,
+            config.relay_queue_size,
+            config.relay_batch_delay,
+            offload,
+        );
+        let log_limiter = cache.log_limiter.clone();
+        let suspicious = cache.suspicious.clone();
         let client = ClientPacketHandler::new(
             cache.clone(),
             config.clone(),
             rsa_cipher.clone(),
-            udp.clone(),
+            scheduler.clone(),
         );
+        let pmtu_probe = config.pmtu_probe;
         let server =
-            ServerPacketHandler::new(cache.clone(), config.clone(), rsa_cipher.clone(), udp);
-        Self { client, server }
+            ServerPacketHandler::new(cache.clone(), config.clone(), rsa_cipher.clone(), scheduler);
+        task::spawn("rtt probe", server.clone().probe_rtt_task());
+        task::spawn("local path hint", server.clone().local_path_hint_task());
+        if pmtu_probe {
+            task::spawn("pmtu probe", server.clone().probe_mtu_task());
+        }
+        Self {
+            client,
+            server,
+            log_limiter,
+            suspicious,
+        }
     }
 }
 
@@ -50,21 +80,117 @@ impl PacketHandler {
         self.handle0(net_packet, addr, tcp_sender)
             .await
             .unwrap_or_else(|e| {
-                log::error!("addr={},{:?}", addr, e);
+                if self.log_limiter.check(addr.ip(), "handle errors") {
+                    log::error!("addr={},{:?}", addr, e);
+                }
                 None
             })
     }
-    async fn handle0<B: AsRef<[u8]> + AsMut<[u8]>>(
+    /// 记录一次可疑数据，返回日志是否需要输出，同一来源的日志过多时只输出汇总
+    pub fn suspicious(&self, addr: SocketAddr, kind: &'static str) -> bool {
+        self.suspicious.record(addr.ip(), kind);
+        self.log_limiter.check(addr.ip(), kind)
+    }
+    /// 管理员向组内设备推送通知
+    #[cfg(feature = "web")]
+    pub fn push_notice(
+        &self,
+        group: &str,
+        virtual_ip: Option<u32>,
+        level: crate::proto::message::NoticeLevel,
+        text: &str,
+    ) -> Result<usize> {
+        self.server.push_notice(group, virtual_ip, level, text)
+    }
+    /// 管理员修改配置后推送给受影响的设备
+    #[cfg(feature = "web")]
+    pub fn push_client_config(&self, group: &str, virtual_ip: Option<u32>) -> Result<usize> {
+        self.server.push_client_config(group, virtual_ip)
+    }
+    /// 管理员把组迁移到其他服务器
+    #[cfg(feature = "web")]
+    pub fn migrate_group(
+        &self,
+        group: &str,
+        address: Option<String>,
+        wait: std::time::Duration,
+    ) -> Result<usize> {
+        self.server.migrate_group(group, address, wait)
+    }
+    /// 管理员探测设备的候选地址是否可达
+    #[cfg(feature = "web")]
+    pub async fn probe_reachability(
+        &self,
+        group: &str,
+        virtual_ip: u32,
+    ) -> Result<Vec<crate::core::entity::ReachResult>> {
+        self.server.probe_reachability(group, virtual_ip).await
+    }
+    /// 以网关的身份把ipv4数据包推送给组内在线的设备，返回设备是否在线
+    pub fn push_ipv4(&self, group: &str, virtual_ip: u32, ipv4: &[u8]) -> bool {
+        match self.server.push_ipv4(group, virtual_ip, ipv4) {
+            Ok(online) => online,
+            Err(e) => {
+                log::warn!("推送数据失败 group={},{:?}", group, e);
+                false
+            }
+        }
+    }
+    /// tcp连接断开
+    pub fn tcp_closed(&self, addr: SocketAddr) {
+        self.server.tcp_closed(addr)
+    }
+    /// 来源是否被临时封禁，封禁的来源收到的数据直接丢弃
+    pub fn is_blocked(&self, addr: SocketAddr) -> bool {
+        self.suspicious.is_blocked(addr.ip())
+    }
+    /// 处理探测端口收到的数据
+    pub async fn handle_nat_probe<B: AsRef<[u8]>>(
         &self,
         net_packet: NetPacket<B>,
         addr: SocketAddr,
+    ) -> Option<NetPacket<Vec<u8>>> {
+        self.server
+            .handle_nat_probe(net_packet, addr)
+            .await
+            .unwrap_or_else(|e| {
+                if self.log_limiter.check(addr.ip(), "nat probe errors") {
+                    log::error!("nat probe addr={},{:?}", addr, e);
+                }
+                None
+            })
+    }
+    async fn handle0<B: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        mut net_packet: NetPacket<B>,
+        addr: SocketAddr,
         tcp_sender: &Option<Sender<Vec<u8>>>,
     ) -> Result<Option<NetPacket<Vec<u8>>>> {
+        // 会话id只用于识别来源，转发前去掉
+        if let Some(session_id) = net_packet.take_session_id() {
+            if tcp_sender.is_none() {
+                self.server
+                    .rebind_session(session_id, addr, &net_packet)
+                    .await;
+            }
+        }
         if net_packet.is_gateway() {
             self.server.handle(net_packet, addr, tcp_sender).await
         } else {
-            self.client.handle(net_packet, addr)?;
-            Ok(None)
+            let source = net_packet.source();
+            match self.client.handle(net_packet, addr) {
+                Ok(Some(ipv4)) => self.server.gateway_reply(&ipv4, addr, source).map(Some),
+                Ok(None) => Ok(None),
+                Err(Error::Disconnect) => {
+                    if self.log_limiter.check(addr.ip(), "relay without session") {
+                        log::warn!("addr={},source={},{:?}", addr, source, Error::Disconnect);
+                    }
+                    self.server
+                        .relay_error_reply(addr, source, Error::Disconnect)
+                        .map(Some)
+                }
+                Err(e) => Err(e),
+            }
         }
     }
 }
@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::{Mutex, RwLock};
+use tokio::net::UdpSocket;
+use tokio::sync::Notify;
+
+use crate::core::entity::NetworkInfo;
+use crate::core::offload::{self, UdpOffload, MAX_SEGMENTS, MAX_SEGMENT_BYTES};
+use crate::core::task;
+
+/// 中继发送调度
+///
+/// udp发送缓冲区未满时直接发送，满了之后数据进入各组自己的队列，
+/// 由发送任务在有数据的组之间轮流发送，一个组的流量不会挤占其他组。
+/// 开启分段发送时，队列中发往同一地址的连续数据包合并为一次发送。
+/// 配置了合并延迟时数据总是先进入队列，发送任务空闲时被唤醒后等待该时长再发送，
+/// 期间到达的数据包一起发送
+#[derive(Clone)]
+pub struct RelayScheduler {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    udp: Arc<UdpSocket>,
+    // 每个组的队列上限，字节
+    queue_limit: usize,
+    // 合并发送的最大延迟
+    batch_delay: Option<Duration>,
+    // 分段发送，发送失败时关闭
+    gso: AtomicBool,
+    // 队列中有数据的组
+    ready: Mutex<VecDeque<Arc<RwLock<NetworkInfo>>>>,
+    notify: Notify,
+}
+
+impl RelayScheduler {
+    pub fn new(
+        udp: Arc<UdpSocket>,
+        queue_limit: usize,
+        batch_delay: Option<Duration>,
+        offload: UdpOffload,
+    ) -> Self {
+        let inner = Arc::new(Inner {
+            udp,
+            queue_limit,
+            batch_delay,
+            gso: AtomicBool::new(offload.gso),
+            ready: Mutex::new(VecDeque::new()),
+            notify: Notify::new(),
+        });
+        task::spawn("relay scheduler", send_task(inner.clone()));
+        Self { inner }
+    }
+    pub fn udp(&self) -> &UdpSocket {
+        &self.inner.udp
+    }
+    /// 发送中继数据，network_info是network的读锁
+    pub fn send(
+        &self,
+        network: &Arc<RwLock<NetworkInfo>>,
+        network_info: &NetworkInfo,
+        buf: &[u8],
+        addr: SocketAddr,
+    ) {
+        let queue = &network_info.relay_queue;
+        if self.inner.batch_delay.is_none() && queue.is_empty() {
+            match self.inner.udp.try_send_to(buf, addr) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                _ => return,
+            }
+        }
+        if let Some(true) = queue.push(buf.to_vec(), addr, self.inner.queue_limit) {
+            self.inner.ready.lock().push_back(network.clone());
+            self.inner.notify.notify_one();
+        }
+    }
+}
+
+async fn send_task(inner: Arc<Inner>) {
+    loop {
+        let network = inner.ready.lock().pop_front();
+        let network = match network {
+            Some(network) => network,
+            None => {
+                inner.notify.notified().await;
+                if let Some(delay) = inner.batch_delay {
+                    tokio::time::sleep(delay).await;
+                }
+                continue;
+            }
+        };
+        let packet = if inner.gso.load(Ordering::Relaxed) {
+            network
+                .read()
+                .relay_queue
+                .pop_segments(MAX_SEGMENTS, MAX_SEGMENT_BYTES)
+        } else {
+            network
+                .read()
+                .relay_queue
+                .pop()
+                .map(|(buf, addr, more)| (buf, 0, addr, more))
+        };
+        if let Some((buf, segment_size, addr, more)) = packet {
+            if segment_size > 0 && buf.len() > segment_size {
+                send_segments(&inner, &buf, segment_size, addr).await;
+            } else if let Err(e) = inner.udp.send_to(&buf, addr).await {
+                log::warn!("中继发送失败 addr={},{:?}", addr, e);
+            }
+            if more {
+                // 放到队尾，轮到其他组发送
+                inner.ready.lock().push_back(network);
+            }
+        }
+    }
+}
+
+async fn send_segments(inner: &Inner, buf: &[u8], segment_size: usize, addr: SocketAddr) {
+    match offload::send_segments(&inner.udp, buf, segment_size, addr).await {
+        Ok(()) => {}
+        // 网卡不支持校验和卸载时返回EIO，之后不再分段发送
+        Err(e) if e.raw_os_error() == Some(5) || e.kind() == io::ErrorKind::InvalidInput => {
+            inner.gso.store(false, Ordering::Relaxed);
+            log::warn!("udp分段发送失败,关闭分段发送 addr={},{:?}", addr, e);
+            for segment in buf.chunks(segment_size) {
+                if let Err(e) = inner.udp.send_to(segment, addr).await {
+                    log::warn!("中继发送失败 addr={},{:?}", addr, e);
+                }
+            }
+        }
+        Err(e) => log::warn!("中继发送失败 addr={},{:?}", addr, e),
+    }
+}
@@ -4,31 +4,62 @@ use packet::ip::ipv4;
 use packet::ip::ipv4::packet::IpV4Packet;
 use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{io, result};
 
 use protobuf::Message;
-use tokio::net::UdpSocket;
 use tokio::sync::mpsc::Sender;
 
-use crate::cipher::{Aes256GcmCipher, Finger, RsaCipher};
-use crate::core::entity::{ClientInfo, ClientStatusInfo, NetworkInfo};
+use crate::cipher::{handshake_proof, Aes256GcmCipher, Finger, RsaCipher};
+use crate::core::entity::{
+    check_tags, device_info_of, effective_config, infer_hairpin, local_path, no_hairpin,
+    parse_local_addrs, parse_services, recommend_mtu, AddrRebind, ClientInfo, ClientStatusInfo,
+    GatewayIcmp, Lang, NetworkInfo, PathMtu, RawBroadcast, TcpPunchInfo, CLOCK_SKEW,
+    DEFAULT_RETRY_AFTER, HANDSHAKE_FAILURES, TOKEN_ERRORS, UNKNOWN_PACKETS,
+};
+use crate::core::service::scheduler::RelayScheduler;
+use crate::core::service::{client, gateway};
 use crate::core::store::cache::{AppCache, Context};
+use crate::doctor::MIN_SANE_TIME;
 use crate::error::*;
 use crate::proto::message;
-use crate::proto::message::{DeviceList, RegistrationRequest, RegistrationResponse};
-use crate::protocol::body::ENCRYPTION_RESERVED;
+use crate::proto::message::{MaintenanceInfo, RegistrationRequest, RegistrationResponse};
+use crate::protocol::body::{AES_GCM_ENCRYPTION_RESERVED, ENCRYPTION_RESERVED};
 use crate::protocol::ip_turn_packet::BroadcastPacket;
-use crate::protocol::{control_packet, error_packet, service_packet, NetPacket, Protocol, MAX_TTL};
+use crate::protocol::{
+    control_packet, error_packet, service_packet, NetPacket, Protocol, HEAD_LEN, MAX_TTL,
+};
 use crate::{protocol, ConfigInfo};
 
+/// 密钥交换中公钥的最大长度
+const MAX_PUBLIC_KEY_LEN: usize = 1024;
+/// 密钥交换中算法名称的最大长度
+const MAX_ALGORITHM_LEN: usize = 32;
+/// 迁移期间检查设备是否已离开并重新通知的间隔
+#[cfg(feature = "web")]
+const MIGRATE_INTERVAL: Duration = Duration::from_secs(10);
+/// 路径mtu探测的间隔，也是等待探测回应的时长
+const MTU_PROBE_INTERVAL: Duration = Duration::from_secs(2);
+/// 注册后推送本地路径提示的延迟，保证设备先收到注册响应，也是批量推送的间隔
+const LOCAL_PATH_HINT_DELAY: Duration = Duration::from_secs(1);
+/// 可达性探测向每个候选地址发送的次数
+#[cfg(feature = "web")]
+const REACH_PROBE_ATTEMPTS: usize = 3;
+/// 可达性探测每次发送的间隔
+#[cfg(feature = "web")]
+const REACH_PROBE_INTERVAL: Duration = Duration::from_millis(300);
+/// 最后一次发送后等待回应的时长
+#[cfg(feature = "web")]
+const REACH_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Clone)]
 pub struct ServerPacketHandler {
     cache: AppCache,
     config: ConfigInfo,
     rsa_cipher: Option<RsaCipher>,
-    udp: Arc<UdpSocket>,
+    scheduler: RelayScheduler,
 }
 
 impl ServerPacketHandler {
@@ -36,13 +67,13 @@ impl ServerPacketHandler {
         cache: AppCache,
         config: ConfigInfo,
         rsa_cipher: Option<RsaCipher>,
-        udp: Arc<UdpSocket>,
+        scheduler: RelayScheduler,
     ) -> Self {
         Self {
             cache,
             config,
             rsa_cipher,
-            udp,
+            scheduler,
         }
     }
 }
@@ -66,15 +97,28 @@ impl ServerPacketHandler {
                 }
                 service_packet::Protocol::SecretHandshakeRequest => {
                     // 加密握手
-                    let rs = self.secret_handshake(net_packet, addr).await?;
-                    return Ok(Some(rs));
+                    return match self.secret_handshake(net_packet, addr).await {
+                        Ok(rs) => Ok(Some(rs)),
+                        Err(e) => {
+                            if self.cache.log_limiter.check(addr.ip(), HANDSHAKE_FAILURES) {
+                                log::warn!("加密握手失败 addr={},{:?}", addr, e);
+                            }
+                            Ok(None)
+                        }
+                    };
                 }
                 _ => {}
             }
         }
         // 解密
         let aes = if net_packet.is_encrypt() {
-            if let Some(aes) = self.cache.cipher_session.get(&addr) {
+            if let Some(aes) = self.cache.get_cipher(&addr) {
+                if aes.exhausted() {
+                    // 会话密钥的加密计数器用完，删除后客户端收到NoKey会重新握手
+                    log::info!("会话密钥需要更换:{}", addr);
+                    self.cache.remove_cipher(&addr);
+                    return Ok(Some(self.handle_err(addr, source, Error::NoKey)?));
+                }
                 aes.decrypt_ipv4(&mut net_packet)?;
                 Some(aes)
             } else {
@@ -85,7 +129,7 @@ impl ServerPacketHandler {
             None
         };
         let mut packet = match self
-            .handle0(net_packet, addr, tcp_sender, aes.is_some())
+            .handle0(net_packet, addr, tcp_sender, aes.as_deref())
             .await
         {
             Ok(rs) => {
@@ -121,56 +165,119 @@ impl ServerPacketHandler {
         source: Ipv4Addr,
         e: Error,
     ) -> Result<NetPacket<Vec<u8>>> {
-        log::warn!("addr={},source={},{:?}", addr, source, e);
-        let rs = vec![0u8; 12 + ENCRYPTION_RESERVED];
-        let mut packet = NetPacket::new_encrypt(rs)?;
-        match e {
-            Error::Io(_) => {}
-            Error::Channel(_) => {}
-            Error::Protobuf(_) => {}
-
-            Error::AddressExhausted => {
-                packet.set_transport_protocol(error_packet::Protocol::AddressExhausted.into());
-            }
-            Error::TokenError => {
-                packet.set_transport_protocol(error_packet::Protocol::TokenError.into());
-            }
-            Error::IpAlreadyExists => {
-                packet.set_transport_protocol(error_packet::Protocol::IpAlreadyExists.into());
-            }
-            Error::InvalidIp => {
-                packet.set_transport_protocol(error_packet::Protocol::InvalidIp.into());
-            }
-            Error::Other(msg) => {
-                //设置返回内容
-                let bytes = msg.as_bytes();
-                let rs = vec![0u8; 12 + bytes.len() + ENCRYPTION_RESERVED];
-                packet = NetPacket::new_encrypt(rs)?;
-                packet.set_payload(bytes)?;
-            }
-            Error::Disconnect => {
-                packet.set_transport_protocol(error_packet::Protocol::Disconnect.into());
-            }
-            Error::NoKey => {
-                packet.set_transport_protocol(error_packet::Protocol::NoKey.into());
-            }
+        let lang = self.lang_of(&addr);
+        self.handle_err_lang(addr, source, lang, e)
+    }
+    fn handle_err_lang(
+        &self,
+        addr: SocketAddr,
+        source: Ipv4Addr,
+        lang: Lang,
+        e: Error,
+    ) -> Result<NetPacket<Vec<u8>>> {
+        // token错误可能是在猜测token，同一来源只输出前几条
+        let log = match e {
+            Error::TokenError => self.cache.log_limiter.check(addr.ip(), TOKEN_ERRORS),
+            // 维护期间拒绝注册是预期的，注册时已输出日志
+            Error::Maintenance => false,
+            _ => true,
+        };
+        if log {
+            log::warn!("addr={},source={},error={:?}", addr, source, e);
         }
+        self.error_packet(lang, source, e)
+    }
+    /// 已注册的地址使用所在组的语言，否则使用默认语言
+    fn lang_of(&self, addr: &SocketAddr) -> Lang {
+        match self.cache.get_context(addr) {
+            Some(context) => context.network_info.read().policy.lang,
+            None => self.config.default_policy.lang,
+        }
+    }
+    /// 组的语言，组还不存在时使用配置的组策略
+    fn group_lang(&self, group: &str) -> Lang {
+        match self.cache.virtual_network.get_val(&group.to_string()) {
+            Some(info) => info.read().policy.lang,
+            None => self.config.group_policy(group).lang,
+        }
+    }
+    /// 错误回应，传输协议为错误码，内容为错误信息
+    ///
+    /// 旧版本客户端不认识的错误码会按Other处理，显示错误信息
+    fn error_packet(&self, lang: Lang, source: Ipv4Addr, e: Error) -> Result<NetPacket<Vec<u8>>> {
+        let message = self.config.messages.get(lang, &e);
+        if let Error::Maintenance = e {
+            let (retry_after, text) = match self.cache.maintenance.read().as_ref() {
+                Some(maintenance) => (maintenance.retry_after, maintenance.message.clone()),
+                None => (DEFAULT_RETRY_AFTER, None),
+            };
+            let mut info = MaintenanceInfo::new();
+            info.retry_after = retry_after;
+            info.message = text.unwrap_or(message);
+            // 旧版本客户端按Other处理，显示的内容中仍然能看到说明文字
+            return self.raw_error_packet(
+                source,
+                error_packet::Protocol::Maintenance,
+                &info.write_to_bytes()?,
+            );
+        }
+        let code = match e {
+            Error::Io(_) | Error::Channel(_) | Error::Protobuf(_) | Error::Other(_) => {
+                error_packet::Protocol::Other(0)
+            }
+            Error::AddressExhausted => error_packet::Protocol::AddressExhausted,
+            Error::TokenError => error_packet::Protocol::TokenError,
+            Error::IpAlreadyExists => error_packet::Protocol::IpAlreadyExists,
+            Error::InvalidIp => error_packet::Protocol::InvalidIp,
+            Error::Disconnect => error_packet::Protocol::Disconnect,
+            Error::NoKey => error_packet::Protocol::NoKey,
+            Error::EncryptionRequired(_) => error_packet::Protocol::EncryptionRequired,
+            // 旧版本客户端按组内设备已满处理
+            Error::GroupFull | Error::SourceLimit => error_packet::Protocol::GroupFull,
+            Error::InvalidRequest(_) => error_packet::Protocol::InvalidRequest,
+            Error::PeerOffline => error_packet::Protocol::PeerOffline,
+            Error::Maintenance => error_packet::Protocol::Maintenance,
+        };
+        self.raw_error_packet(source, code, message.as_bytes())
+    }
+    fn raw_error_packet(
+        &self,
+        source: Ipv4Addr,
+        code: error_packet::Protocol,
+        bytes: &[u8],
+    ) -> Result<NetPacket<Vec<u8>>> {
+        //设置返回内容
+        let rs = vec![0u8; 12 + bytes.len() + ENCRYPTION_RESERVED];
+        let mut packet = NetPacket::new_encrypt(rs)?;
+        packet.set_payload(bytes)?;
         packet.set_protocol(Protocol::Error);
+        packet.set_transport_protocol(code.into());
         self.common_param(&mut packet, source);
         Ok(packet)
     }
+    /// 中继数据处理失败时回应错误，例如连接上下文已失效时回应Disconnect，客户端据此重新注册
+    pub fn relay_error_reply(
+        &self,
+        addr: SocketAddr,
+        source: Ipv4Addr,
+        e: Error,
+    ) -> Result<NetPacket<Vec<u8>>> {
+        let lang = self.lang_of(&addr);
+        let mut packet = self.error_packet(lang, source, e)?;
+        if let Some(aes) = self.cache.get_cipher(&addr) {
+            aes.encrypt_ipv4(&mut packet)?;
+        }
+        Ok(packet)
+    }
     async fn handle0<B: AsRef<[u8]> + AsMut<[u8]>>(
         &self,
         net_packet: NetPacket<B>,
         addr: SocketAddr,
         tcp_sender: &Option<Sender<Vec<u8>>>,
-        server_secret: bool,
+        aes: Option<&Aes256GcmCipher>,
     ) -> Result<Option<NetPacket<Vec<u8>>>> {
         // 处理不需要连接上下文的请求
-        let mut net_packet = match self
-            .not_context(net_packet, addr, tcp_sender, server_secret)
-            .await
-        {
+        let mut net_packet = match self.not_context(net_packet, addr, tcp_sender, aes).await {
             Ok(rs) => {
                 return rs;
             }
@@ -182,6 +289,10 @@ impl ServerPacketHandler {
         } else {
             return Err(Error::Disconnect);
         };
+        if let Some(aes) = aes {
+            check_session_finger(aes, &context.finger)?;
+        }
+        self.check_ip_conflict(&context, addr)?;
 
         match net_packet.protocol() {
             Protocol::Service => {
@@ -190,6 +301,17 @@ impl ServerPacketHandler {
                         //拉取网段设备信息
                         return self.poll_device_list(net_packet, addr, &context);
                     }
+                    service_packet::Protocol::Unregister => {
+                        log::info!(
+                            "设备注销 group={},virtual_ip={},id={:?},addr={}",
+                            context.group,
+                            Ipv4Addr::from(context.virtual_ip),
+                            context.device_id,
+                            addr
+                        );
+                        self.offline(&context, addr);
+                        return Ok(None);
+                    }
                     service_packet::Protocol::ClientStatusInfo => {
                         //客户端上报信息
                         let client_status_info =
@@ -197,15 +319,61 @@ impl ServerPacketHandler {
                         self.up_client_status_info(client_status_info, &context);
                         return Ok(None);
                     }
+                    service_packet::Protocol::ResolveRequest => {
+                        //名称解析
+                        let request =
+                            message::ResolveRequest::parse_from_bytes(net_packet.payload())?;
+                        return self.resolve(request, &context);
+                    }
+                    service_packet::Protocol::ServiceRegister => {
+                        //登记应用服务
+                        let request =
+                            message::ServiceRegister::parse_from_bytes(net_packet.payload())?;
+                        self.service_register(request, &context)?;
+                        return Ok(None);
+                    }
+                    service_packet::Protocol::ServiceQueryRequest => {
+                        //查询应用服务
+                        let request =
+                            message::ServiceQueryRequest::parse_from_bytes(net_packet.payload())?;
+                        return self.service_query(request, &context);
+                    }
+                    service_packet::Protocol::KeyExchangeRequest
+                    | service_packet::Protocol::KeyExchangeResponse => {
+                        //转发端到端密钥交换
+                        let key_exchange =
+                            message::KeyExchange::parse_from_bytes(net_packet.payload())?;
+                        self.key_exchange(key_exchange, net_packet.transport_protocol(), &context)?;
+                        return Ok(None);
+                    }
+                    service_packet::Protocol::TcpPunchRequest => {
+                        //tcp打洞协调
+                        let request =
+                            message::TcpPunchRequest::parse_from_bytes(net_packet.payload())?;
+                        return self.tcp_punch_request(request, addr, &context);
+                    }
                     _ => {}
                 }
             }
             Protocol::Control => {
                 // 控制数据
-                if let control_packet::Protocol::Ping =
-                    protocol::control_packet::Protocol::from(net_packet.transport_protocol())
-                {
-                    return self.control_ping(net_packet, &context);
+                match protocol::control_packet::Protocol::from(net_packet.transport_protocol()) {
+                    control_packet::Protocol::Ping => {
+                        return self.control_ping(net_packet, &context);
+                    }
+                    control_packet::Protocol::Pong => {
+                        //服务器探测延迟的回应
+                        self.control_pong(net_packet, &context)?;
+                        return Ok(None);
+                    }
+                    control_packet::Protocol::PortPredictionRequest => {
+                        return self.port_prediction(net_packet, &context);
+                    }
+                    control_packet::Protocol::MtuProbeReply => {
+                        self.control_mtu_probe_reply(net_packet, &context)?;
+                        return Ok(None);
+                    }
+                    _ => {}
                 }
             }
             Protocol::IpTurn => {
@@ -213,29 +381,95 @@ impl ServerPacketHandler {
                     protocol::ip_turn_packet::Protocol::Ipv4Broadcast => {
                         //处理选择性广播,进过网关还原成原始广播
                         let broadcast_packet = BroadcastPacket::new(net_packet.payload())?;
-                        let exclude = broadcast_packet.addresses();
+                        let exclude = self.p2p_exclude(&context, broadcast_packet.addresses());
                         let broadcast_net_packet = NetPacket::new(broadcast_packet.data()?)?;
+                        let ipv4 = (!broadcast_net_packet.is_encrypt())
+                            .then(|| broadcast_net_packet.payload());
+                        if !context.network_info.read().send_allowed(
+                            context.virtual_ip,
+                            broadcast_net_packet.destination(),
+                            ipv4,
+                        ) {
+                            return Ok(None);
+                        }
                         self.broadcast(&context, broadcast_net_packet, &exclude)?;
                         return Ok(None);
                     }
                     protocol::ip_turn_packet::Protocol::Ipv4 => {
                         let destination = net_packet.destination();
                         let source = net_packet.source();
+                        let ip_destination =
+                            IpV4Packet::new(net_packet.payload())?.destination_ip();
+                        if ip_destination.is_multicast()
+                            && self.reflect_discovery(&context, &net_packet, ip_destination)?
+                        {
+                            return Ok(None);
+                        }
+                        if ip_destination.is_broadcast()
+                            || context
+                                .network_info
+                                .read()
+                                .is_broadcast(ip_destination.into())
+                        {
+                            self.raw_broadcast(&context, &net_packet, ip_destination)?;
+                            return Ok(None);
+                        }
+                        let to_gateway = context
+                            .network_info
+                            .read()
+                            .is_gateway(ip_destination.into());
+                        if to_gateway
+                            && self.cache.ingress.deliver(
+                                &context.group,
+                                context.virtual_ip,
+                                net_packet.payload(),
+                            )
+                        {
+                            // socks5代理连接的数据
+                            return Ok(None);
+                        }
+                        if let Some(port) = self.config.turn_port.filter(|_| to_gateway) {
+                            let packets = self.cache.turn.handle(
+                                &context.network_info.read(),
+                                &context.group,
+                                port,
+                                net_packet.payload(),
+                            );
+                            if let Some(packets) = packets {
+                                //网关turn服务
+                                for (virtual_ip, ipv4) in packets {
+                                    self.push_ipv4(&context.group, virtual_ip, &ipv4)?;
+                                }
+                                return Ok(None);
+                            }
+                        }
                         let mut ipv4 = IpV4Packet::new(net_packet.payload_mut())?;
-                        if let ipv4::protocol::Protocol::Icmp = ipv4.protocol() {
+                        if !to_gateway {
+                            // 只有发给网关的数据由服务端处理
+                        } else if let ipv4::protocol::Protocol::Icmp = ipv4.protocol() {
+                            let icmp_source = ipv4.source_ip();
                             let mut icmp_packet = icmp::IcmpPacket::new(ipv4.payload_mut())?;
                             if icmp_packet.kind() == Kind::EchoRequest {
+                                if !self.gateway_icmp_allowed(&context, icmp_source) {
+                                    return Ok(None);
+                                }
                                 //开启ping
-                                icmp_packet.set_kind(Kind::EchoReply);
-                                icmp_packet.update_checksum();
-                                ipv4.set_source_ip(destination);
-                                ipv4.set_destination_ip(source);
-                                ipv4.update_checksum();
+                                icmp_packet.replace_kind(Kind::EchoReply);
+                                ipv4.replace_source_ip(destination);
+                                ipv4.replace_destination_ip(source);
                                 return Ok(Some(NetPacket::new0(
                                     net_packet.data_len(),
                                     net_packet.raw_buffer().to_vec(),
                                 )?));
                             }
+                        } else if let ipv4::protocol::Protocol::Tcp = ipv4.protocol() {
+                            if let Some(port) = self.config.gateway_echo_port {
+                                //网关tcp回显服务
+                                return match gateway::tcp_echo(&ipv4, port)? {
+                                    Some(reply) => Ok(Some(ip_turn_packet(&reply)?)),
+                                    None => Ok(None),
+                                };
+                            }
                         }
                     }
                     _ => {}
@@ -243,40 +477,442 @@ impl ServerPacketHandler {
             }
             _ => {}
         }
-        log::error!(
-            "Unknown={:?},{:?},{:?},{:?}",
-            net_packet.destination(),
-            net_packet.source(),
-            net_packet.protocol(),
-            net_packet.transport_protocol()
-        );
+        self.cache.suspicious.record(addr.ip(), UNKNOWN_PACKETS);
+        if self.cache.log_limiter.check(addr.ip(), UNKNOWN_PACKETS) {
+            log::error!(
+                "Unknown={},{:?},{:?},{:?},{:?}",
+                addr,
+                net_packet.destination(),
+                net_packet.source(),
+                net_packet.protocol(),
+                net_packet.transport_protocol()
+            );
+        }
         // Err(Error::Other("Unknown".into()))
         Ok(None)
     }
 }
 
+impl ServerPacketHandler {
+    /// 客户端把目标为广播地址的数据包直接发给了网关，按组策略转为广播或者丢弃
+    fn raw_broadcast<B: AsRef<[u8]>>(
+        &self,
+        context: &Context,
+        net_packet: &NetPacket<B>,
+        destination: Ipv4Addr,
+    ) -> Result<()> {
+        let policy = context.network_info.read().policy.raw_broadcast;
+        match policy {
+            RawBroadcast::Relay => {
+                if !context.network_info.read().send_allowed(
+                    context.virtual_ip,
+                    destination,
+                    Some(net_packet.payload()),
+                ) {
+                    return Ok(());
+                }
+                let mut packet = NetPacket::new(net_packet.buffer().to_vec())?;
+                packet.set_gateway_flag(false);
+                packet.set_destination(destination);
+                self.broadcast(context, packet, &[context.virtual_ip.into()])?;
+            }
+            RawBroadcast::Drop => {
+                context
+                    .network_info
+                    .read()
+                    .raw_broadcast_dropped
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+    /// 客户端把mDNS/SSDP组播发给了网关，组开启了反射时转为广播发给组内其他设备，返回是否已处理
+    fn reflect_discovery<B: AsRef<[u8]>>(
+        &self,
+        context: &Context,
+        net_packet: &NetPacket<B>,
+        destination: Ipv4Addr,
+    ) -> Result<bool> {
+        if !gateway::is_discovery(net_packet.payload()) {
+            return Ok(false);
+        }
+        {
+            let guard = context.network_info.read();
+            if guard.discovery_limiter.is_none() {
+                return Ok(false);
+            }
+            if !guard.discovery_allowed()
+                || !guard.send_allowed(context.virtual_ip, destination, Some(net_packet.payload()))
+            {
+                return Ok(true);
+            }
+        }
+        let mut packet = NetPacket::new(net_packet.buffer().to_vec())?;
+        packet.set_gateway_flag(false);
+        packet.set_destination(destination);
+        self.broadcast(context, packet, &[context.virtual_ip.into()])?;
+        Ok(true)
+    }
+    /// 网关是否响应来自该地址的ping
+    fn gateway_icmp_allowed(&self, context: &Context, source: Ipv4Addr) -> bool {
+        let guard = context.network_info.read();
+        match guard.policy.gateway_icmp {
+            GatewayIcmp::All => true,
+            GatewayIcmp::Members => guard.clients.contains_key(&source.into()),
+            GatewayIcmp::Off => false,
+        }
+    }
+}
+
+impl ServerPacketHandler {
+    /// 把公钥转发给目标设备，target替换为发起方
+    fn key_exchange(
+        &self,
+        mut key_exchange: message::KeyExchange,
+        transport_protocol: u8,
+        context: &Context,
+    ) -> Result<()> {
+        if key_exchange.public_key.len() > MAX_PUBLIC_KEY_LEN
+            || key_exchange.algorithm.len() > MAX_ALGORITHM_LEN
+        {
+            return Err(Error::Other("key exchange too large".into()));
+        }
+        let source = context.virtual_ip;
+        let target = key_exchange.target;
+        let guard = context.network_info.read();
+        let peer = match guard.clients.get(&target) {
+            Some(peer) if peer.online && target != source => peer,
+            _ => return Err(Error::PeerOffline),
+        };
+        key_exchange.target = source;
+        self.push_to_client(
+            peer,
+            Protocol::Service,
+            transport_protocol,
+            &key_exchange.write_to_bytes()?,
+        )
+    }
+    /// tcp打洞协调
+    ///
+    /// 双方都发起请求后，向双方下发对方的端口信息和统一的开始时间，
+    /// 只有一方发起时将请求转发给对方，等待对方发起请求
+    fn tcp_punch_request(
+        &self,
+        request: message::TcpPunchRequest,
+        addr: SocketAddr,
+        context: &Context,
+    ) -> Result<Option<NetPacket<Vec<u8>>>> {
+        let source = context.virtual_ip;
+        let target = request.target;
+        let mut info = TcpPunchInfo {
+            public_ip_list: request.public_ip_list,
+            tcp_port: request.tcp_port,
+            public_ports: request.public_ports,
+        };
+        if info.public_ip_list.is_empty() {
+            if let Some(ip) = public_ipv4(addr) {
+                info.public_ip_list.push(ip.into());
+            }
+        }
+        let mut lock = context.network_info.write();
+        let guard = &mut *lock;
+        let peer = match guard.clients.get(&target) {
+            Some(peer) if peer.online && target != source => peer,
+            _ => return Err(Error::PeerOffline),
+        };
+        // 请求10秒内有效
+        guard
+            .tcp_punch
+            .retain(|_, (_, time)| time.elapsed() < Duration::from_secs(10));
+        let peer_info = match guard.tcp_punch.remove(&(target, source)) {
+            Some((peer_info, _)) => peer_info,
+            None => {
+                let mut forward = message::TcpPunchRequest::new();
+                forward.target = source;
+                forward.tcp_port = info.tcp_port;
+                forward.public_ports = info.public_ports.clone();
+                forward.public_ip_list = info.public_ip_list.clone();
+                self.push_to_client(
+                    peer,
+                    Protocol::Service,
+                    service_packet::Protocol::TcpPunchRequest.into(),
+                    &forward.write_to_bytes()?,
+                )?;
+                guard
+                    .tcp_punch
+                    .insert((source, target), (info, Instant::now()));
+                return Ok(None);
+            }
+        };
+        // 留出下发指令的时间
+        let start_time = Local::now().timestamp_millis() + 500;
+        let mut peer_start = message::TcpPunchStart::new();
+        peer_start.peer = source;
+        peer_start.peer_public_ip_list = info.public_ip_list;
+        peer_start.peer_tcp_port = info.tcp_port;
+        peer_start.peer_public_ports = info.public_ports;
+        peer_start.start_time = start_time;
+        self.push_to_client(
+            peer,
+            Protocol::Service,
+            service_packet::Protocol::TcpPunchStart.into(),
+            &peer_start.write_to_bytes()?,
+        )?;
+        drop(lock);
+        log::info!(
+            "tcp打洞 group={:?},{}<->{}",
+            context.group,
+            Ipv4Addr::from(source),
+            Ipv4Addr::from(target)
+        );
+        let mut start = message::TcpPunchStart::new();
+        start.peer = target;
+        start.peer_public_ip_list = peer_info.public_ip_list;
+        start.peer_tcp_port = peer_info.tcp_port;
+        start.peer_public_ports = peer_info.public_ports;
+        start.start_time = start_time;
+        let bytes = start.write_to_bytes()?;
+        let mut packet = NetPacket::new_encrypt(vec![0u8; 12 + bytes.len() + ENCRYPTION_RESERVED])?;
+        packet.set_protocol(Protocol::Service);
+        packet.set_transport_protocol(service_packet::Protocol::TcpPunchStart.into());
+        packet.set_payload(&bytes)?;
+        Ok(Some(packet))
+    }
+    /// 网关生成的ipv4数据包回应给发送方
+    pub fn gateway_reply(
+        &self,
+        ipv4: &[u8],
+        addr: SocketAddr,
+        source: Ipv4Addr,
+    ) -> Result<NetPacket<Vec<u8>>> {
+        let mut packet = ip_turn_packet(ipv4)?;
+        self.common_param(&mut packet, source);
+        if let Some(aes) = self.cache.get_cipher(&addr) {
+            aes.encrypt_ipv4(&mut packet)?;
+        }
+        Ok(packet)
+    }
+    /// 服务器主动向客户端发送数据
+    fn push_to_client(
+        &self,
+        client_info: &ClientInfo,
+        protocol: Protocol,
+        transport_protocol: u8,
+        payload: &[u8],
+    ) -> Result<()> {
+        let packet = self.client_packet(client_info, protocol, transport_protocol, payload)?;
+        if let Some(sender) = &client_info.tcp_sender {
+            let _ = sender.try_send(packet.buffer().to_vec());
+        } else {
+            let _ = self
+                .scheduler
+                .udp()
+                .try_send_to(packet.buffer(), client_info.address);
+        }
+        Ok(())
+    }
+}
+
+impl ServerPacketHandler {
+    /// 构造发给设备的数据包，和服务端加密时使用设备的会话密钥加密
+    fn client_packet(
+        &self,
+        client_info: &ClientInfo,
+        protocol: Protocol,
+        transport_protocol: u8,
+        payload: &[u8],
+    ) -> Result<NetPacket<Vec<u8>>> {
+        let mut packet =
+            NetPacket::new_encrypt(vec![0u8; 12 + payload.len() + ENCRYPTION_RESERVED])?;
+        packet.set_protocol(protocol);
+        packet.set_transport_protocol(transport_protocol);
+        packet.set_payload(payload)?;
+        self.common_param(&mut packet, client_info.virtual_ip.into());
+        if client_info.server_secret {
+            if let Some(aes) = self.cache.get_cipher(&client_info.address) {
+                aes.encrypt_ipv4(&mut packet)?;
+            }
+        }
+        Ok(packet)
+    }
+    /// 从服务端的udp端口向设备的每个候选地址发送探测，返回每个地址的探测结果
+    ///
+    /// 探测复用路径mtu探测的报文，每个地址发送REACH_PROBE_ATTEMPTS次，任意一次回应即视为可达
+    #[cfg(feature = "web")]
+    pub async fn probe_reachability(
+        &self,
+        group: &str,
+        virtual_ip: u32,
+    ) -> Result<Vec<crate::core::entity::ReachResult>> {
+        let network_info = self
+            .cache
+            .virtual_network
+            .get_val(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        // (地址，来源，每次发送的(id，数据包)）
+        let mut probes = Vec::new();
+        {
+            let guard = network_info.read();
+            let client_info = guard
+                .clients
+                .get(&virtual_ip)
+                .filter(|v| v.online)
+                .ok_or_else(|| Error::Other("device not online".into()))?;
+            for (addr, source) in client_info.candidates() {
+                let mut packets = Vec::with_capacity(REACH_PROBE_ATTEMPTS);
+                for _ in 0..REACH_PROBE_ATTEMPTS {
+                    let id = rand::random();
+                    let mut payload = [0u8; 4];
+                    control_packet::MtuProbePacket::new(&mut payload[..])?.set_id(id);
+                    let packet = self.client_packet(
+                        client_info,
+                        Protocol::Control,
+                        control_packet::Protocol::MtuProbe.into(),
+                        &payload,
+                    )?;
+                    packets.push((id, packet));
+                }
+                probes.push((addr, source, packets));
+            }
+        }
+        for attempt in 0..REACH_PROBE_ATTEMPTS {
+            if attempt > 0 {
+                tokio::time::sleep(REACH_PROBE_INTERVAL).await;
+            }
+            for (addr, _, packets) in &probes {
+                let (id, packet) = &packets[attempt];
+                self.cache.reach_probes.sent(*id);
+                let _ = self.scheduler.udp().try_send_to(packet.buffer(), *addr);
+            }
+        }
+        tokio::time::sleep(REACH_PROBE_TIMEOUT).await;
+        let results: Vec<_> = probes
+            .into_iter()
+            .map(|(addr, source, packets)| {
+                let rtt = packets
+                    .iter()
+                    .filter_map(|(id, _)| self.cache.reach_probes.finish(*id))
+                    .min();
+                (addr, source, rtt)
+            })
+            .collect();
+        log::info!(
+            "可达性探测 group={},virtual_ip={},results={:?}",
+            group,
+            Ipv4Addr::from(virtual_ip),
+            results
+        );
+        Ok(results)
+    }
+    /// 以网关的身份把ipv4数据包推送给组内在线的设备，返回设备是否在线
+    pub fn push_ipv4(&self, group: &str, virtual_ip: u32, ipv4: &[u8]) -> Result<bool> {
+        let Some(network_info) = self.cache.virtual_network.get(&group.to_string()) else {
+            return Ok(false);
+        };
+        let guard = network_info.read();
+        let Some(client_info) = guard.clients.get(&virtual_ip).filter(|v| v.online) else {
+            return Ok(false);
+        };
+        self.push_to_client(
+            client_info,
+            Protocol::IpTurn,
+            protocol::ip_turn_packet::Protocol::Ipv4.into(),
+            ipv4,
+        )?;
+        Ok(true)
+    }
+}
+
+impl ServerPacketHandler {
+    /// 同一个ip被两个设备持有时，由先注册的设备保留该ip，后注册的设备被强制重新注册
+    fn check_ip_conflict(&self, context: &Context, addr: SocketAddr) -> Result<()> {
+        let conflict = context
+            .network_info
+            .read()
+            .clients
+            .get(&context.virtual_ip)
+            .is_some_and(|owner| owner.device_id != context.device_id);
+        if !conflict {
+            return Ok(());
+        }
+        let mut guard = context.network_info.write();
+        let owner = match guard.clients.get(&context.virtual_ip) {
+            Some(owner) if owner.device_id != context.device_id => owner,
+            _ => return Ok(()),
+        };
+        let owner_addr = owner.address;
+        let owner_newer = owner.timestamp > context.timestamp;
+        log::warn!(
+            "audit ip冲突 group={:?},virtual_ip={},id={:?},addr={},timestamp={},owner_id={:?},owner_addr={},owner_timestamp={}",
+            context.group,
+            Ipv4Addr::from(context.virtual_ip),
+            context.device_id,
+            addr,
+            context.timestamp,
+            owner.device_id,
+            owner_addr,
+            owner.timestamp
+        );
+        if owner_newer {
+            // 记录的设备是后注册的，删除记录，双方都重新注册
+            let owner = guard.clients.remove(&context.virtual_ip).unwrap();
+            guard
+                .ip_conflicts
+                .insert(context.virtual_ip, context.device_id.clone());
+            guard.bump_epoch();
+            guard.address_churn.record_release();
+            guard.check_address_usage(&context.group);
+            drop(guard);
+            self.cache.remove_addr_session(&owner.address);
+        } else {
+            let owner_id = owner.device_id.clone();
+            guard.ip_conflicts.insert(context.virtual_ip, owner_id);
+            drop(guard);
+        }
+        self.cache.remove_addr_session(&addr);
+        Err(Error::Disconnect)
+    }
+}
+
 impl ServerPacketHandler {
     async fn not_context<B: AsRef<[u8]>>(
         &self,
         net_packet: NetPacket<B>,
         addr: SocketAddr,
         tcp_sender: &Option<Sender<Vec<u8>>>,
-        server_secret: bool,
+        aes: Option<&Aes256GcmCipher>,
     ) -> result::Result<Result<Option<NetPacket<Vec<u8>>>>, NetPacket<B>> {
         if net_packet.protocol() == Protocol::Service {
             if let service_packet::Protocol::RegistrationRequest =
                 protocol::service_packet::Protocol::from(net_packet.transport_protocol())
             {
                 //注册
-                return Ok(self
-                    .register(net_packet, addr, tcp_sender, server_secret)
-                    .await);
+                return Ok(
+                    match self.register(&net_packet, addr, tcp_sender, aes).await {
+                        Err(e) => {
+                            // 还没有连接上下文，按注册的组选择错误信息的语言
+                            let lang = RegistrationRequest::parse_from_bytes(net_packet.payload())
+                                .map(|request| self.group_lang(&request.token))
+                                .unwrap_or(self.config.default_policy.lang);
+                            self.handle_err_lang(addr, net_packet.source(), lang, e)
+                                .map(Some)
+                        }
+                        rs => rs,
+                    },
+                );
             }
         } else if net_packet.protocol() == Protocol::Control {
-            if let control_packet::Protocol::AddrRequest =
-                protocol::control_packet::Protocol::from(net_packet.transport_protocol())
-            {
-                return Ok(self.control_addr_request(addr));
+            match protocol::control_packet::Protocol::from(net_packet.transport_protocol()) {
+                control_packet::Protocol::AddrRequest => {
+                    return Ok(self.control_addr_request(addr));
+                }
+                control_packet::Protocol::NatProbeRequest => {
+                    return Ok(self.nat_probe(net_packet, addr, false).await.map(Some));
+                }
+                control_packet::Protocol::TimeRequest => {
+                    return Ok(self.control_time_request(net_packet, addr));
+                }
+                _ => {}
             }
         }
         Err(net_packet)
@@ -295,22 +931,280 @@ impl ServerPacketHandler {
         packet.set_transport_protocol(control_packet::Protocol::Pong.into());
         packet.set_payload(net_packet.payload())?;
         let mut pong_packet = control_packet::PongPacket::new(packet.payload_mut())?;
-        let epoch = context.network_info.read().epoch;
-        // 这里给客户端的是丢失精度的，可能导致客户端无法感知变更
-        pong_packet.set_epoch(epoch as u16);
+        let epoch = context.network_info.read().pong_epoch();
+        pong_packet.set_epoch(epoch);
+        Ok(Some(packet))
+    }
+    /// 探测端口只处理nat类型探测请求
+    pub async fn handle_nat_probe<B: AsRef<[u8]>>(
+        &self,
+        net_packet: NetPacket<B>,
+        addr: SocketAddr,
+    ) -> Result<Option<NetPacket<Vec<u8>>>> {
+        if net_packet.protocol() != Protocol::Control
+            || control_packet::Protocol::from(net_packet.transport_protocol())
+                != control_packet::Protocol::NatProbeRequest
+        {
+            return Ok(None);
+        }
+        let source = net_packet.source();
+        let mut packet = self.nat_probe(net_packet, addr, true).await?;
+        self.common_param(&mut packet, source);
+        Ok(Some(packet))
+    }
+    /// nat类型探测，probe表示是否是从探测端口收到的请求
+    async fn nat_probe<B: AsRef<[u8]>>(
+        &self,
+        net_packet: NetPacket<B>,
+        addr: SocketAddr,
+        probe: bool,
+    ) -> Result<NetPacket<Vec<u8>>> {
+        let request = control_packet::NatProbePacket::new(net_packet.payload())?;
+        let id = request.id();
+        let (mut main_port, mut probe_port) = self.cache.nat_probe.get(&id).unwrap_or_default();
+        let (port, other_port) = if probe {
+            probe_port = addr.port();
+            (probe_port, main_port)
+        } else {
+            main_port = addr.port();
+            (main_port, probe_port)
+        };
+        self.cache
+            .nat_probe
+            .insert(id, (main_port, probe_port), Duration::from_secs(10))
+            .await;
+        // 探测端口的请求通过主端口看到的端口找到设备，记录来源端口用于端口预测
+        if main_port != 0 {
+            let main_addr = SocketAddr::new(addr.ip(), main_port);
+            if let Some(context) = self.cache.get_context(&main_addr) {
+                if let Some(client_info) = context
+                    .network_info
+                    .write()
+                    .clients
+                    .get_mut(&context.virtual_ip)
+                {
+                    client_info.meta.record_port(addr.port());
+                }
+            }
+        }
+        let mut packet = NetPacket::new_encrypt(vec![0u8; 12 + 12 + ENCRYPTION_RESERVED])?;
+        packet.set_protocol(Protocol::Control);
+        packet.set_transport_protocol(control_packet::Protocol::NatProbeResponse.into());
+        let mut response = control_packet::NatProbePacket::new(packet.payload_mut())?;
+        response.set_id(id);
+        response.set_ipv4(public_ipv4(addr).unwrap_or(Ipv4Addr::UNSPECIFIED));
+        response.set_port(port);
+        response.set_other_port(other_port);
+        Ok(packet)
+    }
+    fn control_pong<B: AsRef<[u8]>>(
+        &self,
+        net_packet: NetPacket<B>,
+        context: &Context,
+    ) -> Result<()> {
+        let pong_packet = control_packet::PongPacket::new(net_packet.payload())?;
+        let rtt = probe_time().wrapping_sub(pong_packet.time()) as u32;
+        if let Some(client_info) = context
+            .network_info
+            .write()
+            .clients
+            .get_mut(&context.virtual_ip)
+        {
+            client_info.meta.rtt = Some(rtt);
+        }
+        Ok(())
+    }
+    /// 向所有在线设备发送ping，根据回应的pong计算延迟
+    fn probe_rtt(&self) {
+        let time = probe_time();
+        for (_, network_info) in self.cache.virtual_network.key_values() {
+            let guard = network_info.read();
+            let mut payload = [0u8; 4];
+            let mut ping_packet = control_packet::PingPacket::new(&mut payload[..]).unwrap();
+            ping_packet.set_time(time);
+            ping_packet.set_epoch(guard.pong_epoch());
+            for client_info in guard.clients.values().filter(|v| v.online) {
+                if let Err(e) = self.push_to_client(
+                    client_info,
+                    Protocol::Control,
+                    control_packet::Protocol::Ping.into(),
+                    &payload,
+                ) {
+                    log::warn!("ping {} {:?}", client_info.address, e);
+                }
+            }
+        }
+    }
+    pub async fn probe_rtt_task(self) {
+        loop {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            self.probe_rtt();
+        }
+    }
+    /// 收到路径mtu探测的回应，立即探测下一个大小
+    fn control_mtu_probe_reply<B: AsRef<[u8]>>(
+        &self,
+        net_packet: NetPacket<B>,
+        context: &Context,
+    ) -> Result<()> {
+        let id = control_packet::MtuProbePacket::new(net_packet.payload())?.id();
+        if self.cache.reach_probes.reply(id) {
+            return Ok(());
+        }
+        let mut guard = context.network_info.write();
+        let Some(client_info) = guard.clients.get_mut(&context.virtual_ip) else {
+            return Ok(());
+        };
+        if client_info.meta.mtu_probe.reply(id) {
+            self.probe_mtu(&mut guard, context.virtual_ip)?;
+        }
+        Ok(())
+    }
+    /// 向设备发送下一个路径mtu探测，查找结果变化时更新设备的路径mtu并推送推荐的mtu
+    fn probe_mtu(&self, network_info: &mut NetworkInfo, virtual_ip: u32) -> Result<()> {
+        let Some(client_info) = network_info.clients.get_mut(&virtual_ip) else {
+            return Ok(());
+        };
+        let id = rand::random();
+        let size = client_info.meta.mtu_probe.next_probe(id);
+        let changed = client_info.meta.mtu_probe.take_changed();
+        if changed {
+            client_info.path_mtu = client_info.meta.mtu_probe.confirmed();
+            log::info!(
+                "路径mtu变化 id={:?},virtual_ip={},addr={},path_mtu={:?}",
+                client_info.device_id,
+                Ipv4Addr::from(virtual_ip),
+                client_info.address,
+                client_info.path_mtu
+            );
+        }
+        let client_info = &network_info.clients[&virtual_ip];
+        if changed {
+            if let Some(config) = device_config(network_info, client_info) {
+                self.push_to_client(
+                    client_info,
+                    Protocol::Service,
+                    service_packet::Protocol::PushClientConfig.into(),
+                    &config.write_to_bytes()?,
+                )?;
+            }
+        }
+        if let Some(size) = size {
+            // 填充到探测的大小，和服务端加密时加上加密的开销
+            let mut overhead = HEAD_LEN;
+            if client_info.server_secret && self.cache.get_cipher(&client_info.address).is_some() {
+                overhead += AES_GCM_ENCRYPTION_RESERVED;
+            }
+            let mut payload = vec![0u8; size as usize - overhead];
+            control_packet::MtuProbePacket::new(&mut payload[..])?.set_id(id);
+            self.push_to_client(
+                client_info,
+                Protocol::Control,
+                control_packet::Protocol::MtuProbe.into(),
+                &payload,
+            )?;
+        }
+        Ok(())
+    }
+    /// 定时探测通过udp连接的在线设备的路径mtu
+    pub async fn probe_mtu_task(self) {
+        loop {
+            tokio::time::sleep(MTU_PROBE_INTERVAL).await;
+            for (_, network_info) in self.cache.virtual_network.key_values() {
+                let mut guard = network_info.write();
+                let clients: Vec<u32> = guard
+                    .clients
+                    .values()
+                    .filter(|v| v.online && v.tcp_sender.is_none())
+                    .map(|v| v.virtual_ip)
+                    .collect();
+                for virtual_ip in clients {
+                    if let Err(e) = self.probe_mtu(&mut guard, virtual_ip) {
+                        log::warn!("路径mtu探测 {},{:?}", Ipv4Addr::from(virtual_ip), e);
+                    }
+                }
+            }
+        }
+    }
+    /// 返回目标设备的端口预测，目标不在同一个组或者样本不足时samples为0
+    fn port_prediction<B: AsRef<[u8]>>(
+        &self,
+        net_packet: NetPacket<B>,
+        context: &Context,
+    ) -> Result<Option<NetPacket<Vec<u8>>>> {
+        let target = control_packet::PortPredictionPacket::new(net_packet.payload())?.target();
+        let mut packet = NetPacket::new_encrypt(vec![0u8; 12 + 16 + ENCRYPTION_RESERVED])?;
+        packet.set_protocol(Protocol::Control);
+        packet.set_transport_protocol(control_packet::Protocol::PortPredictionResponse.into());
+        let mut response = control_packet::PortPredictionPacket::new(packet.payload_mut())?;
+        response.set_target(target);
+        let guard = context.network_info.read();
+        if let Some(client_info) = guard.clients.get(&target.into()).filter(|v| v.online) {
+            if let Some(prediction) = client_info.meta.port_prediction() {
+                response
+                    .set_ipv4(public_ipv4(client_info.address).unwrap_or(Ipv4Addr::UNSPECIFIED));
+                response.set_last_port(prediction.last_port);
+                response.set_stride(prediction.stride);
+                response.set_matched(prediction.matched);
+                response.set_samples(prediction.samples);
+            }
+        }
+        Ok(Some(packet))
+    }
+    /// 返回服务端时间，打洞指令中的开始时间使用同一个时间基准
+    fn control_time_request<B: AsRef<[u8]>>(
+        &self,
+        net_packet: NetPacket<B>,
+        addr: SocketAddr,
+    ) -> Result<Option<NetPacket<Vec<u8>>>> {
+        let request = control_packet::TimePacket::new(net_packet.payload())?;
+        let server_time = Local::now().timestamp_micros();
+        self.record_clock_skew(addr, request.client_time(), server_time);
+        let mut packet = NetPacket::new_encrypt(vec![0u8; 12 + 16 + ENCRYPTION_RESERVED])?;
+        packet.set_protocol(Protocol::Control);
+        packet.set_transport_protocol(control_packet::Protocol::TimeResponse.into());
+        let mut response = control_packet::TimePacket::new(packet.payload_mut())?;
+        response.set_client_time(request.client_time());
+        response.set_server_time(server_time);
         Ok(Some(packet))
     }
+    /// 按时间同步请求里的设备时间估计设备的时钟偏差，设备时间是请求发出的时刻，用延迟的一半补偿单程耗时
+    fn record_clock_skew(&self, addr: SocketAddr, client_time: u64, server_time: i64) {
+        let Ok(client_time) = i64::try_from(client_time) else {
+            return;
+        };
+        // 不是unix时间戳(微秒)的设备时间无法比较
+        if client_time < MIN_SANE_TIME as i64 * 1_000_000 {
+            return;
+        }
+        let Some(context) = self.cache.get_context(&addr) else {
+            return;
+        };
+        let mut guard = context.network_info.write();
+        let Some(client_info) = guard.clients.get_mut(&context.virtual_ip) else {
+            return;
+        };
+        let one_way = client_info.meta.rtt.unwrap_or(0) as i64 / 2;
+        let skew = (client_time - server_time) / 1000 + one_way;
+        client_info.meta.clock_skew = Some(skew);
+        drop(guard);
+        if let Some(max) = self.config.max_clock_skew {
+            if skew.unsigned_abs() > max.as_millis() as u64
+                && self.cache.log_limiter.check(addr.ip(), CLOCK_SKEW)
+            {
+                log::warn!(
+                    "设备时钟和服务端相差{}毫秒,超过允许的{}秒,打洞时机可能不准,请检查设备或服务端的ntp同步 addr={},group={:?},ip={}",
+                    skew,
+                    max.as_secs(),
+                    addr,
+                    context.group,
+                    Ipv4Addr::from(context.virtual_ip)
+                );
+            }
+        }
+    }
     fn control_addr_request(&self, addr: SocketAddr) -> Result<Option<NetPacket<Vec<u8>>>> {
-        let ipv4 = match addr.ip() {
-            IpAddr::V4(ipv4) => ipv4,
-            IpAddr::V6(ip) => {
-                if let Some(ipv4) = ip.to_ipv4_mapped() {
-                    ipv4
-                } else {
-                    Ipv4Addr::UNSPECIFIED
-                }
-            }
-        };
+        let ipv4 = public_ipv4(addr).unwrap_or(Ipv4Addr::UNSPECIFIED);
         let mut packet = NetPacket::new_encrypt(vec![0u8; 12 + 6 + ENCRYPTION_RESERVED])?;
         packet.set_protocol(Protocol::Control);
         packet.set_transport_protocol(control_packet::Protocol::AddrResponse.into());
@@ -324,15 +1218,27 @@ impl ServerPacketHandler {
 impl ServerPacketHandler {
     async fn register<B: AsRef<[u8]>>(
         &self,
-        net_packet: NetPacket<B>,
+        net_packet: &NetPacket<B>,
         addr: SocketAddr,
         tcp_sender: &Option<Sender<Vec<u8>>>,
-        server_secret: bool,
+        aes: Option<&Aes256GcmCipher>,
     ) -> Result<Option<NetPacket<Vec<u8>>>> {
         let config = &self.config;
         let cache = &self.cache;
         let request = RegistrationRequest::parse_from_bytes(net_packet.payload())?;
         check_reg(&request)?;
+        let mut local_addrs = match parse_local_addrs(&request.local_addresses) {
+            Ok(local_addrs) => local_addrs,
+            Err(e) => {
+                log::warn!(
+                    "本地地址错误 addr={},id={:?},{}",
+                    addr,
+                    request.device_id,
+                    e
+                );
+                return Err(Error::InvalidRequest("invalid_local_addresses"));
+            }
+        };
         log::info!(
             "register,{},id={:?},name={:?},version={:?},virtual_ip={},client_secret={},allow_ip_change={},is_fast={},tcp={}",
             addr,
@@ -346,17 +1252,62 @@ impl ServerPacketHandler {
             tcp_sender.is_some()
         );
         let group_id = request.token.clone();
-        if let Some(white_token) = &config.white_token {
-            if !white_token.contains(&group_id) {
+        if cache.maintenance.read().is_some() {
+            // 维护期间只允许在线的设备重新注册，例如切换网络或tcp重连
+            let online = cache.virtual_network.get_val(&group_id).is_some_and(|v| {
+                v.read()
+                    .clients
+                    .values()
+                    .any(|x| x.online && x.device_id == request.device_id)
+            });
+            if !online {
                 log::info!(
-                    "token不在白名单，white_token={:?}，group_id={:?}",
-                    white_token,
-                    group_id
+                    "维护模式,拒绝注册 group_id={:?},id={:?},addr={}",
+                    group_id,
+                    request.device_id,
+                    addr
                 );
+                return Err(Error::Maintenance);
+            }
+        }
+        let migration = cache.virtual_network.get_val(&group_id).and_then(|v| {
+            v.read()
+                .migration
+                .as_ref()
+                .map(|migration| migration.address.clone())
+        });
+        if let Some(address) = migration {
+            log::info!(
+                "组迁移中,重定向到{} group_id={:?},id={:?},addr={}",
+                address,
+                group_id,
+                request.device_id,
+                addr
+            );
+            return redirect_packet(&address).map(Some);
+        }
+        {
+            let white_tokens = cache.white_tokens.read();
+            if white_tokens.is_enabled() && !white_tokens.contains(&group_id) {
+                log::info!("token不在白名单，group_id={:?}", group_id);
                 return Err(Error::TokenError);
             }
         }
+        if let Some(aes) = aes {
+            if let Err(e) = check_session_finger(aes, &Finger::new(&group_id)) {
+                log::warn!(
+                    "加密会话的token和注册的token不一致,group_id={:?},addr={}",
+                    group_id,
+                    addr
+                );
+                return Err(e);
+            }
+        }
+        let server_secret = aes.is_some();
+        // tcp不会丢包，只在udp上附带序号
+        let sequence = request.sequence && tcp_sender.is_none();
         let mut response = RegistrationResponse::new();
+        response.sequence = sequence;
         //公网地址
         response.public_port = addr.port() as u32;
         match addr.ip() {
@@ -371,119 +1322,322 @@ impl ServerPacketHandler {
                 }
             }
         }
+        let public_addr = *cache.public_addr.read();
+        if let Some(ipv4) = public_addr.ipv4 {
+            response.server_public_ip = ipv4.into();
+        }
+        if let Some(ipv6) = public_addr.ipv6 {
+            response.server_public_ipv6 = ipv6.octets().to_vec();
+        }
         //固定网段
         let gateway: u32 = config.gateway.into();
         let netmask: u32 = config.netmask.into();
         let network: u32 = gateway & netmask;
 
-        response.virtual_netmask = netmask;
-        response.virtual_gateway = gateway;
-
         let v = cache
             .virtual_network
             .optionally_get_with(group_id.clone(), || {
                 (
                     Duration::from_secs(7 * 24 * 3600),
                     Arc::new(parking_lot::const_rwlock(NetworkInfo::new(
-                        network, netmask, gateway,
+                        network,
+                        netmask,
+                        gateway,
+                        config.group_policy(&group_id),
                     ))),
                 )
             })
             .await;
+        {
+            let policy = &v.read().policy;
+            if policy.require_client_encryption && !request.client_secret {
+                log::warn!(
+                    "组要求客户端间加密,拒绝注册 group_id={:?},id={:?}",
+                    group_id,
+                    request.device_id
+                );
+                return Err(Error::EncryptionRequired("client_encryption_required"));
+            }
+            if policy.require_server_encryption && !server_secret {
+                log::warn!(
+                    "组要求和服务端加密,拒绝注册 group_id={:?},id={:?}",
+                    group_id,
+                    request.device_id
+                );
+                return Err(Error::EncryptionRequired("server_encryption_required"));
+            }
+        }
         let mut virtual_ip = request.virtual_ip;
-        // 可分配的ip段
-        let ip_range = network + 1..gateway | (!netmask);
-        let timestamp = Local::now().timestamp();
+        let device_id = request.device_id.clone();
+        let mut timestamp = Local::now().timestamp();
+        let mut local_path_changed = false;
         {
             let mut lock = v.write();
-            let mut insert = true;
-            if virtual_ip != 0 {
-                if u32::from(config.gateway) == virtual_ip
-                    || u32::from(config.broadcast) == virtual_ip
-                    || !ip_range.contains(&virtual_ip)
-                {
-                    log::warn!("手动指定的ip无效: {:?}", request);
-                    return Err(Error::InvalidIp);
+            let retry = lock.recent_registration(&request.device_id, request.virtual_ip);
+            if let Some(ip) = retry {
+                // 短时间内重复注册(例如udp重试的同时建立了tcp连接)，沿用刚分配的ip，不重复递增epoch
+                virtual_ip = ip;
+                let info = lock.clients.get_mut(&ip).unwrap();
+                if info.address == addr {
+                    timestamp = info.timestamp;
+                } else {
+                    // 以后到的注册为准，之前的地址在绑定会话时清理
+                    info.address = addr;
+                    info.tcp_sender = tcp_sender.clone();
+                    info.server_secret = server_secret;
+                    info.timestamp = timestamp;
+                    info.loss = sequence.then(Box::default);
+                    info.path_mtu = None;
+                    info.meta.mtu_probe = PathMtu::default();
+                }
+                info.online = true;
+                info.meta.offline_since = None;
+                log::info!(
+                    "重复注册,沿用刚分配的ip group_id={:?},id={:?},virtual_ip={},addr={}",
+                    group_id,
+                    request.device_id,
+                    Ipv4Addr::from(ip),
+                    addr
+                );
+            } else {
+                if let Some(max_clients) = lock.policy.max_clients {
+                    if lock.clients.len() >= max_clients
+                        && !lock
+                            .clients
+                            .values()
+                            .any(|x| x.device_id == request.device_id)
+                    {
+                        log::warn!(
+                            "组内设备数量达到上限 group_id={:?},max_clients={},id={:?}",
+                            group_id,
+                            max_clients,
+                            request.device_id
+                        );
+                        return Err(Error::GroupFull);
+                    }
+                }
+                if let Some(max_clients) = lock.policy.max_clients_per_ip {
+                    let ip = addr.ip().to_canonical();
+                    // 离线设备仍然占用ip，一起计算
+                    let count = lock
+                        .clients
+                        .values()
+                        .filter(|x| {
+                            x.address.ip().to_canonical() == ip && x.device_id != request.device_id
+                        })
+                        .count();
+                    if count >= max_clients
+                        && !config
+                            .client_limit_exempt
+                            .iter()
+                            .any(|net| net.contains(ip))
+                    {
+                        log::warn!(
+                            "同一公网ip的设备数量达到上限 group_id={:?},addr={},max_clients_per_ip={},id={:?}",
+                            group_id,
+                            addr,
+                            max_clients,
+                            request.device_id
+                        );
+                        return Err(Error::SourceLimit);
+                    }
                 }
-                //指定了ip
-                if let Some(info) = lock.clients.get_mut(&request.virtual_ip) {
-                    if info.device_id != request.device_id {
-                        //ip被占用了,并且不能更改ip
+                let mut insert = true;
+                let reassigned = lock
+                    .clients
+                    .values()
+                    .find(|x| x.meta.reassigned && x.device_id == request.device_id)
+                    .map(|x| x.virtual_ip);
+                if let Some(ip) = reassigned {
+                    // 管理员重新分配了ip
+                    virtual_ip = ip;
+                    insert = false;
+                    response.ip_change_reason = message::IpChangeReason::Reassigned.into();
+                } else if virtual_ip != 0 {
+                    if lock.pool_of(virtual_ip).is_none() {
+                        log::warn!("手动指定的ip无效: {:?}", request);
+                        return Err(Error::InvalidIp);
+                    }
+                    let conflict = lock
+                        .ip_conflicts
+                        .get(&virtual_ip)
+                        .is_some_and(|winner| winner != &request.device_id);
+                    if conflict {
+                        // 冲突的ip由另一个设备保留
                         if !request.allow_ip_change {
-                            log::warn!("手动指定的ip已经存在:{:?}", request);
+                            log::warn!("手动指定的ip存在冲突:{:?}", request);
                             return Err(Error::IpAlreadyExists);
                         }
-                        // 重新挑选ip
+                        response.ip_change_reason = message::IpChangeReason::Conflict.into();
                         virtual_ip = 0;
-                    } else {
-                        insert = false;
+                    } else if let Some(info) = lock.clients.get_mut(&request.virtual_ip) {
+                        //指定了ip
+                        if info.device_id != request.device_id {
+                            //ip被占用了,并且不能更改ip
+                            if !request.allow_ip_change {
+                                log::warn!("手动指定的ip已经存在:{:?}", request);
+                                return Err(Error::IpAlreadyExists);
+                            }
+                            // 重新挑选ip,并告知客户端原因
+                            response.ip_change_reason = if info.online {
+                                message::IpChangeReason::InUse
+                            } else {
+                                message::IpChangeReason::HeldByOffline
+                            }
+                            .into();
+                            virtual_ip = 0;
+                        } else {
+                            insert = false;
+                        }
                     }
                 }
-            }
-            let mut old_ip = 0;
-            if insert {
-                // 找到上一次用的ip
-                for (ip, x) in &lock.clients {
-                    if x.device_id == request.device_id {
-                        if virtual_ip == 0 {
-                            virtual_ip = *ip;
-                        } else {
-                            old_ip = *ip;
+                let partitioned = lock.secret_partition().is_some();
+                let mut old_ip = 0;
+                if insert {
+                    // 找到上一次用的ip
+                    for (ip, x) in &lock.clients {
+                        if x.device_id == request.device_id {
+                            if virtual_ip == 0 {
+                                virtual_ip = *ip;
+                            } else {
+                                old_ip = *ip;
+                            }
+                            break;
                         }
-                        break;
                     }
                 }
-            }
 
-            if virtual_ip == 0 {
-                // 从小到大找一个未使用的ip
-                for ip in ip_range {
-                    if ip == lock.gateway_ip {
-                        continue;
-                    }
-                    if !lock.clients.contains_key(&ip) {
+                if virtual_ip == 0 {
+                    // 按分配策略找一个未使用的ip
+                    if let Some(ip) = lock.allocate_ip(&request.device_id) {
                         virtual_ip = ip;
-                        break;
                     }
                 }
+                if virtual_ip == 0 {
+                    log::error!("地址使用完:{:?}", request);
+                    return Err(Error::AddressExhausted);
+                }
+                if lock.ip_conflicts.get(&virtual_ip) == Some(&request.device_id) {
+                    // 冲突已解决
+                    lock.ip_conflicts.remove(&virtual_ip);
+                }
+                // 虚拟网卡的地址不能用于直连，包括所有地址池
+                local_addrs.retain(|v| {
+                    let ip = u32::from(v.ip);
+                    lock.pools().all(|pool| ip & pool.netmask != pool.network())
+                });
+                let count = lock.clients.len();
+                let info = if old_ip == 0 {
+                    lock.clients
+                        .entry(virtual_ip)
+                        .or_insert_with(ClientInfo::default)
+                } else {
+                    let client_info = lock.clients.remove(&old_ip).unwrap();
+                    lock.clients
+                        .entry(virtual_ip)
+                        .or_insert_with(|| client_info)
+                };
+                // 新上线、公网ip或本地地址变化时才需要推送本地路径提示
+                local_path_changed = !info.online
+                    || info.address.ip() != addr.ip()
+                    || info.meta.local_addrs != local_addrs
+                    || info.meta.local_port != request.local_port as u16;
+                if !info.meta.name_assigned {
+                    info.name = request.name;
+                }
+                info.device_id = request.device_id;
+                info.meta.version = request.version;
+                info.client_secret = request.client_secret;
+                info.server_secret = server_secret;
+                info.address = addr;
+                info.online = true;
+                info.virtual_ip = virtual_ip;
+                info.tcp_sender = tcp_sender.clone();
+                info.loss = sequence.then(Box::default);
+                info.path_mtu = None;
+                info.meta.mtu_probe = PathMtu::default();
+                info.meta.last_join_time = Local::now();
+                info.timestamp = timestamp;
+                info.meta.reassigned = false;
+                info.meta.offline_since = None;
+                if !info.meta.tags_assigned {
+                    info.tags = request.tags;
+                }
+                info.meta.local_addrs = local_addrs;
+                info.meta.local_port = request.local_port as u16;
+                lock.bump_epoch();
+                if lock.clients.len() > count {
+                    lock.address_churn.record_allocation();
+                    lock.check_address_usage(&group_id);
+                }
+                if !partitioned {
+                    if let Some((secret, plaintext)) = lock.secret_partition() {
+                        let info = &lock.clients[&virtual_ip];
+                        log::warn!(
+                            "组内客户端加密设置不一致,加密和未加密的设备之间互相不可见 group_id={:?},secret={},plaintext={},id={:?},client_secret={}",
+                            group_id,
+                            secret,
+                            plaintext,
+                            info.device_id,
+                            info.client_secret
+                        );
+                    }
+                }
+                lock.recent_registrations
+                    .insert(device_id.clone(), (virtual_ip, Instant::now()));
             }
-            if virtual_ip == 0 {
-                log::error!("地址使用完:{:?}", request);
-                return Err(Error::AddressExhausted);
+            let pool = lock
+                .pool_of(virtual_ip)
+                .unwrap_or_else(|| lock.primary_pool());
+            response.virtual_netmask = pool.netmask;
+            response.virtual_gateway = pool.gateway;
+            response.client_config = effective_config(
+                lock.policy.client_defaults.as_ref(),
+                lock.client_config.as_ref(),
+                lock.clients[&virtual_ip].meta.client_config.as_ref(),
+            )
+            .into();
+            if virtual_ip != request.virtual_ip && request.virtual_ip != 0 {
+                log::info!(
+                    "请求的ip未被采用 id={:?},request_ip={},virtual_ip={},reason={:?}",
+                    lock.clients[&virtual_ip].device_id,
+                    Ipv4Addr::from(request.virtual_ip),
+                    Ipv4Addr::from(virtual_ip),
+                    response.ip_change_reason
+                );
             }
-            let info = if old_ip == 0 {
-                lock.clients
-                    .entry(virtual_ip)
-                    .or_insert_with(ClientInfo::default)
-            } else {
-                let client_info = lock.clients.remove(&old_ip).unwrap();
-                lock.clients
-                    .entry(virtual_ip)
-                    .or_insert_with(|| client_info)
-            };
-            info.name = request.name;
-            info.device_id = request.device_id;
-            info.version = request.version;
-            info.client_secret = request.client_secret;
-            info.server_secret = server_secret;
-            info.address = addr;
-            info.online = true;
-            info.virtual_ip = virtual_ip;
-            info.tcp_sender = tcp_sender.clone();
-            info.last_join_time = Local::now();
-            info.timestamp = timestamp;
-            lock.epoch += 1;
             response.virtual_ip = virtual_ip;
-            response.epoch = lock.epoch as u32;
-            response.device_info_list = Self::clients_info(&lock.clients, virtual_ip);
+            response.epoch = lock.epoch();
+            if !lock.policy.client_isolation {
+                response.device_info_list = Self::clients_info(&lock.clients, virtual_ip);
+            }
             drop(lock);
         }
-        cache
-            .insert_ip_session((group_id.clone(), virtual_ip), addr)
-            .await;
-        cache
-            .insert_addr_session(addr, (group_id, virtual_ip, timestamp))
-            .await;
+        cache.white_tokens.write().record_use(&group_id);
+        if local_path_changed {
+            cache
+                .local_path_pending
+                .lock()
+                .insert((group_id.clone(), virtual_ip), Instant::now());
+        }
+        // 同一设备的两次注册并发时，以设备最终记录的地址为准，之前的地址由绑定时清理
+        if !cache
+            .bind_session(group_id, virtual_ip, addr, timestamp, device_id, None)
+            .await
+        {
+            log::info!("同一设备的注册更晚,不绑定会话 addr={}", addr);
+        }
+        // tcp连接的来源地址不会变化，不需要会话id
+        if request.session_id && tcp_sender.is_none() {
+            let session_id = loop {
+                let session_id: u64 = rand::random();
+                if session_id != 0 {
+                    break session_id;
+                }
+            };
+            cache.insert_session_id(session_id, addr).await;
+            response.session_id = session_id;
+        }
         let bytes = response.write_to_bytes()?;
         let rs = vec![0u8; 12 + bytes.len() + ENCRYPTION_RESERVED];
         let mut packet = NetPacket::new_encrypt(rs)?;
@@ -494,15 +1648,165 @@ impl ServerPacketHandler {
     }
 }
 
+impl ServerPacketHandler {
+    /// 定时批量推送等待中的本地路径提示，设备加入等待后至少经过LOCAL_PATH_HINT_DELAY才推送
+    pub async fn local_path_hint_task(self) {
+        loop {
+            tokio::time::sleep(LOCAL_PATH_HINT_DELAY).await;
+            let now = Instant::now();
+            let mut due = Vec::new();
+            self.cache.local_path_pending.lock().retain(|key, queued| {
+                if now.duration_since(*queued) < LOCAL_PATH_HINT_DELAY {
+                    return true;
+                }
+                due.push(key.clone());
+                false
+            });
+            for (group, virtual_ip) in due {
+                if let Err(e) = self.push_local_path_hints(&group, virtual_ip) {
+                    log::warn!("推送本地路径提示 {},{:?}", Ipv4Addr::from(virtual_ip), e);
+                }
+            }
+        }
+    }
+    /// 检查刚注册的设备和组内其他在线设备是否可能在同一局域网或同一nat后，向双方推送本地路径提示，返回配对的设备数
+    ///
+    /// 任意一方的nat已知不支持回环时，提示双方不要尝试公网地址
+    fn push_local_path_hints(&self, group: &str, virtual_ip: u32) -> Result<usize> {
+        let Some(network_info) = self.cache.virtual_network.get_val(&group.to_string()) else {
+            return Ok(0);
+        };
+        let guard = network_info.read();
+        self.send_local_path_hints(&guard, group, virtual_ip)
+    }
+    fn send_local_path_hints(
+        &self,
+        guard: &NetworkInfo,
+        group: &str,
+        virtual_ip: u32,
+    ) -> Result<usize> {
+        if guard.policy.client_isolation {
+            return Ok(0);
+        }
+        let Some(client) = guard.clients.get(&virtual_ip).filter(|v| v.online) else {
+            return Ok(0);
+        };
+        let mut count = 0;
+        for peer in guard.clients.values() {
+            if !peer.online || peer.virtual_ip == virtual_ip {
+                continue;
+            }
+            let Some(reason) = local_path(client, peer) else {
+                continue;
+            };
+            let no_hairpin = no_hairpin(client, peer);
+            for (to, peer) in [(client, peer), (peer, client)] {
+                let mut hint = local_path_hint(peer, reason);
+                hint.no_hairpin = no_hairpin;
+                self.push_to_client(
+                    to,
+                    Protocol::Service,
+                    service_packet::Protocol::LocalPathHint.into(),
+                    &hint.write_to_bytes()?,
+                )?;
+            }
+            log::info!(
+                "本地路径提示 group={},virtual_ip={},peer={},reason={:?},no_hairpin={}",
+                group,
+                Ipv4Addr::from(virtual_ip),
+                Ipv4Addr::from(peer.virtual_ip),
+                reason,
+                no_hairpin
+            );
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// 发给设备的本地路径提示，包含对端上报的本地地址
+fn local_path_hint(peer: &ClientInfo, reason: message::LocalPathReason) -> message::LocalPathHint {
+    let mut hint = message::LocalPathHint::new();
+    hint.peer = peer.virtual_ip;
+    hint.reason = reason.into();
+    hint.peer_local_addresses = peer
+        .meta
+        .local_addrs
+        .iter()
+        .map(|v| v.to_address())
+        .collect();
+    hint.peer_local_port = peer.meta.local_port as u32;
+    hint
+}
+
+/// 设备实际生效的配置，加上按路径mtu推荐的mtu
+fn device_config(network_info: &NetworkInfo, client: &ClientInfo) -> Option<message::ClientConfig> {
+    let config = effective_config(
+        network_info.policy.client_defaults.as_ref(),
+        network_info.client_config.as_ref(),
+        client.meta.client_config.as_ref(),
+    );
+    recommend_mtu(config, client.relay_mtu(), client.meta.mtu_probe.version())
+}
+
+/// 延迟探测使用的时间，毫秒，只保留低16位
+fn probe_time() -> u16 {
+    Local::now().timestamp_millis() as u16
+}
+
+/// 来源地址的ipv4地址
+fn public_ipv4(addr: SocketAddr) -> Option<Ipv4Addr> {
+    match addr.ip() {
+        IpAddr::V4(ipv4) => Some(ipv4),
+        IpAddr::V6(ip) => ip.to_ipv4_mapped(),
+    }
+}
+
+/// 重定向到其他服务器
+fn redirect_packet(address: &str) -> Result<NetPacket<Vec<u8>>> {
+    let mut redirect = message::Redirect::new();
+    redirect.address = address.to_string();
+    let bytes = redirect.write_to_bytes()?;
+    let mut packet = NetPacket::new_encrypt(vec![0u8; 12 + bytes.len() + ENCRYPTION_RESERVED])?;
+    packet.set_protocol(Protocol::Service);
+    packet.set_transport_protocol(service_packet::Protocol::PushRedirect.into());
+    packet.set_payload(&bytes)?;
+    Ok(packet)
+}
+
+/// 网关生成的ipv4数据包
+fn ip_turn_packet(ipv4: &[u8]) -> Result<NetPacket<Vec<u8>>> {
+    let vec = vec![0u8; 12 + ipv4.len() + ENCRYPTION_RESERVED];
+    let mut packet = NetPacket::new_encrypt(vec)?;
+    packet.set_protocol(Protocol::IpTurn);
+    packet.set_transport_protocol(protocol::ip_turn_packet::Protocol::Ipv4.into());
+    packet.set_payload(ipv4)?;
+    Ok(packet)
+}
+
+/// 加密会话的指纹必须由组token计算得到，仅持有会话密钥无法向其他组注入数据
+fn check_session_finger(aes: &Aes256GcmCipher, finger: &Finger) -> Result<()> {
+    if aes.finger() != finger {
+        return Err(Error::TokenError);
+    }
+    Ok(())
+}
+
 fn check_reg(request: &RegistrationRequest) -> Result<()> {
     if request.token.is_empty() || request.token.len() > 128 {
-        return Err(Error::Other("group length error".into()));
+        return Err(Error::InvalidRequest("token_length"));
     }
     if request.device_id.is_empty() || request.device_id.len() > 128 {
-        return Err(Error::Other("device_id length error".into()));
+        return Err(Error::InvalidRequest("device_id_length"));
     }
     if request.name.is_empty() || request.name.len() > 128 {
-        return Err(Error::Other("name length error".into()));
+        return Err(Error::InvalidRequest("name_length"));
+    }
+    if check_tags(&request.tags).is_err() {
+        return Err(Error::InvalidRequest("invalid_tags"));
+    }
+    if request.local_port > u16::MAX as u32 {
+        return Err(Error::InvalidRequest("local_port"));
     }
     Ok(())
 }
@@ -516,8 +1820,23 @@ impl ServerPacketHandler {
         let req = message::HandshakeRequest::parse_from_bytes(net_packet.payload())?;
         log::info!("handshake:{},{}", addr, req);
         let mut res = message::HandshakeResponse::new();
-        res.version = env!("CARGO_PKG_VERSION").to_string();
-        if let Some(rsp_cipher) = &self.rsa_cipher {
+        res.version = match &self.config.handshake_version {
+            Some(version) => version.clone(),
+            None => env!("CARGO_PKG_VERSION").to_string(),
+        };
+        res.nat_probe_port = self.config.nat_probe_port.unwrap_or(0) as u32;
+        let public_addr = *self.cache.public_addr.read();
+        if let Some(ipv4) = public_addr.ipv4 {
+            res.server_public_ip = ipv4.into();
+        }
+        if let Some(ipv6) = public_addr.ipv6 {
+            res.server_public_ipv6 = ipv6.octets().to_vec();
+        }
+        let rsa_cipher = self
+            .rsa_cipher
+            .as_ref()
+            .filter(|_| !self.config.handshake_proof || self.check_proof(&req.token_proof));
+        if let Some(rsp_cipher) = rsa_cipher {
             res.key_finger = rsp_cipher.finger();
             if res.key_finger != req.key_finger {
                 //指纹不相同则回应公钥，这有助于重连减少数据传输
@@ -533,6 +1852,19 @@ impl ServerPacketHandler {
         packet.set_payload(&bytes)?;
         Ok(packet)
     }
+    /// token证明是否和白名单中的某个token对应
+    fn check_proof(&self, proof: &[u8]) -> bool {
+        let valid = self
+            .cache
+            .white_tokens
+            .read()
+            .tokens()
+            .any(|token| handshake_proof(token)[..] == *proof);
+        if !valid {
+            log::info!("握手的token证明无效，不返回公钥");
+        }
+        valid
+    }
     async fn secret_handshake<B: AsRef<[u8]>>(
         &self,
         net_packet: NetPacket<B>,
@@ -544,13 +1876,22 @@ impl ServerPacketHandler {
             let rsa_secret_body = rsp_cipher.decrypt(&net_packet)?;
             let sync_secret =
                 message::SecretHandshakeRequest::parse_from_bytes(rsa_secret_body.data())?;
-            let c = Aes256GcmCipher::new(
-                sync_secret
-                    .key
-                    .try_into()
-                    .map_err(|_| Error::Other("key err".into()))?,
-                Finger::new(&sync_secret.token),
-            );
+            let key = sync_secret
+                .key
+                .try_into()
+                .map_err(|_| Error::Other("key err".into()))?;
+            let finger = Finger::new(&sync_secret.token);
+            let c = if sync_secret.counter_nonce {
+                Aes256GcmCipher::new(key, finger)
+            } else if self.config.require_counter_nonce {
+                return Err(Error::Other("client does not support counter nonce".into()));
+            } else {
+                log::warn!(
+                    "客户端不支持计数器nonce，使用已弃用的协议头nonce，请升级客户端:{}",
+                    addr
+                );
+                Aes256GcmCipher::with_header_nonce(key, finger)
+            };
             let rs = vec![0u8; 12 + ENCRYPTION_RESERVED];
             let mut packet = NetPacket::new_encrypt(rs)?;
             packet.set_protocol(Protocol::Service);
@@ -572,13 +1913,14 @@ impl ServerPacketHandler {
         context: &Context,
     ) -> Result<Option<NetPacket<Vec<u8>>>> {
         let guard = context.network_info.read();
-        let ips = Self::clients_info(&guard.clients, context.virtual_ip);
-        let epoch = guard.epoch;
+        let bytes = guard.device_list.encode(
+            guard.epoch(),
+            guard.status_epoch(),
+            &guard.clients,
+            context.virtual_ip,
+            guard.policy.client_isolation,
+        )?;
         drop(guard);
-        let mut device_list = DeviceList::new();
-        device_list.epoch = epoch as u32;
-        device_list.device_info_list = ips;
-        let bytes = device_list.write_to_bytes()?;
         let vec = vec![0u8; 12 + bytes.len() + ENCRYPTION_RESERVED];
         let mut device_list_packet = NetPacket::new_encrypt(vec)?;
         device_list_packet.set_protocol(Protocol::Service);
@@ -586,6 +1928,102 @@ impl ServerPacketHandler {
         device_list_packet.set_payload(&bytes)?;
         Ok(Some(device_list_packet))
     }
+    /// 从组内设备中解析名称或者虚拟ip，名称不区分大小写
+    fn resolve(
+        &self,
+        request: message::ResolveRequest,
+        context: &Context,
+    ) -> Result<Option<NetPacket<Vec<u8>>>> {
+        let guard = context.network_info.read();
+        let current_rtt = guard
+            .clients
+            .get(&context.virtual_ip)
+            .and_then(|v| v.meta.rtt);
+        let name = request.name.trim();
+        let mut response = message::ResolveResponse::new();
+        for client in guard.clients.values() {
+            if guard.policy.client_isolation && client.virtual_ip != context.virtual_ip {
+                continue;
+            }
+            let matched = if name.is_empty() {
+                client.virtual_ip == request.virtual_ip
+            } else {
+                client.name.eq_ignore_ascii_case(name)
+            };
+            if !matched {
+                continue;
+            }
+            let mut dev = device_info_of(client);
+            if let (Some(current_rtt), Some(rtt)) = (current_rtt, client.meta.rtt) {
+                dev.relay_cost = current_rtt + rtt;
+            }
+            response.device_info_list.push(dev);
+        }
+        drop(guard);
+        response.name = request.name;
+        response.virtual_ip = request.virtual_ip;
+        let bytes = response.write_to_bytes()?;
+        let vec = vec![0u8; 12 + bytes.len() + ENCRYPTION_RESERVED];
+        let mut packet = NetPacket::new_encrypt(vec)?;
+        packet.set_protocol(Protocol::Service);
+        packet.set_transport_protocol(service_packet::Protocol::ResolveResponse.into());
+        packet.set_payload(&bytes)?;
+        Ok(Some(packet))
+    }
+    /// 替换设备登记的应用服务
+    fn service_register(&self, request: message::ServiceRegister, context: &Context) -> Result<()> {
+        let services = match parse_services(&request.services) {
+            Ok(services) => services,
+            Err(e) => {
+                log::warn!(
+                    "服务登记错误 group={},virtual_ip={},{}",
+                    context.group,
+                    Ipv4Addr::from(context.virtual_ip),
+                    e
+                );
+                return Err(Error::InvalidRequest("invalid_services"));
+            }
+        };
+        let mut guard = context.network_info.write();
+        if let Some(client_info) = guard.clients.get_mut(&context.virtual_ip) {
+            client_info.meta.services = services;
+        }
+        Ok(())
+    }
+    /// 按名称查询组内在线设备登记的服务，名称不区分大小写
+    fn service_query(
+        &self,
+        request: message::ServiceQueryRequest,
+        context: &Context,
+    ) -> Result<Option<NetPacket<Vec<u8>>>> {
+        let guard = context.network_info.read();
+        let name = request.name.trim();
+        let mut response = message::ServiceQueryResponse::new();
+        for client in guard.clients.values().filter(|v| v.online) {
+            if guard.policy.client_isolation && client.virtual_ip != context.virtual_ip {
+                continue;
+            }
+            for service in &client.meta.services {
+                if !name.is_empty() && !service.name.eq_ignore_ascii_case(name) {
+                    continue;
+                }
+                let mut record = message::ServiceRecord::new();
+                record.virtual_ip = client.virtual_ip;
+                record.device_name = client.name.clone();
+                record.service = Some(service.to_entry()).into();
+                response.records.push(record);
+            }
+        }
+        drop(guard);
+        response.name = request.name;
+        let bytes = response.write_to_bytes()?;
+        let vec = vec![0u8; 12 + bytes.len() + ENCRYPTION_RESERVED];
+        let mut packet = NetPacket::new_encrypt(vec)?;
+        packet.set_protocol(Protocol::Service);
+        packet.set_transport_protocol(service_packet::Protocol::ServiceQueryResponse.into());
+        packet.set_payload(&bytes)?;
+        Ok(Some(packet))
+    }
     fn up_client_status_info(
         &self,
         client_status_info: message::ClientStatusInfo,
@@ -603,53 +2041,458 @@ impl ServerPacketHandler {
         status_info.is_cone =
             client_status_info.nat_type.enum_value_or_default() == message::PunchNatType::Cone;
         status_info.update_time = Local::now();
-        if let Some(v) = context
-            .network_info
-            .write()
-            .clients
-            .get_mut(&client_status_info.source)
+        let source = client_status_info.source;
+        let mut guard = context.network_info.write();
+        let guard = &mut *guard;
+        let hairpin = guard.clients.get(&source).and_then(|client| {
+            infer_hairpin(
+                client,
+                guard.clients.values(),
+                &status_info.p2p_list,
+                &client_status_info.punch_failed,
+            )
+        });
+        let Some(v) = guard.clients.get_mut(&source) else {
+            return;
+        };
+        if let Some(loss) = &v.loss {
+            loss.report(
+                client_status_info.down_received,
+                client_status_info.down_lost,
+            );
+        }
+        guard.peer_stats.report(source, &status_info.p2p_list);
+        // NAT类型变化时所有设备都可能需要重新打洞，p2p断开时只有断开的一方需要
+        let (nat_changed, lost): (bool, Vec<Ipv4Addr>) = match &v.meta.client_status {
+            Some(old) => (
+                old.is_cone != status_info.is_cone,
+                old.p2p_list
+                    .iter()
+                    .filter(|ip| !status_info.p2p_list.contains(ip))
+                    .copied()
+                    .collect(),
+            ),
+            None => (status_info.is_cone, vec![]),
+        };
+        let first_report = v.meta.client_status.is_none();
+        let old_hairpin = v.meta.client_status.as_ref().and_then(|old| old.hairpin);
+        status_info.hairpin = hairpin.or(old_hairpin);
+        let hairpin_lost = status_info.hairpin == Some(false) && old_hairpin != Some(false);
+        v.meta.client_status = Some(status_info);
+        if hairpin_lost {
+            log::info!(
+                "nat不支持回环 group={},virtual_ip={},addr={}",
+                context.group,
+                Ipv4Addr::from(source),
+                v.address
+            );
+            // 同一nat后的设备改为只尝试本地地址或者使用中继
+            if let Err(e) = self.send_local_path_hints(guard, &context.group, source) {
+                log::warn!("推送本地路径提示 {},{:?}", Ipv4Addr::from(source), e);
+            }
+        }
+        if !nat_changed && lost.is_empty() {
+            return;
+        }
+        guard.bump_status_epoch();
+        for peer in guard.clients.values() {
+            let affected = if nat_changed && !first_report {
+                peer.virtual_ip != source
+            } else {
+                lost.contains(&peer.virtual_ip.into())
+            };
+            if !affected || !peer.online {
+                continue;
+            }
+            if let Err(e) = self.push_device_list(guard, peer) {
+                log::warn!("推送设备列表失败 {},{:?}", peer.address, e);
+            }
+        }
+    }
+    /// 向组内在线设备推送通知，virtual_ip为None时推送给组内所有在线设备，返回推送的设备数
+    #[cfg(feature = "web")]
+    pub fn push_notice(
+        &self,
+        group: &str,
+        virtual_ip: Option<u32>,
+        level: message::NoticeLevel,
+        text: &str,
+    ) -> Result<usize> {
+        let network_info = self
+            .cache
+            .virtual_network
+            .get_val(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        let mut notice = message::Notice::new();
+        notice.id = rand::random();
+        notice.level = level.into();
+        notice.text = text.to_string();
+        notice.time = Local::now().timestamp_millis();
+        let bytes = notice.write_to_bytes()?;
+        let guard = network_info.read();
+        let mut count = 0;
+        for client in guard.clients.values() {
+            if !client.online || virtual_ip.is_some_and(|ip| ip != client.virtual_ip) {
+                continue;
+            }
+            self.push_to_client(
+                client,
+                Protocol::Service,
+                service_packet::Protocol::PushNotice.into(),
+                &bytes,
+            )?;
+            count += 1;
+        }
+        if virtual_ip.is_some() && count == 0 {
+            return Err(Error::PeerOffline);
+        }
+        log::info!(
+            "推送通知 group={},virtual_ip={:?},count={},level={:?},text={:?}",
+            group,
+            virtual_ip.map(Ipv4Addr::from),
+            count,
+            level,
+            text
+        );
+        Ok(count)
+    }
+    /// 管理员修改配置后向受影响的在线设备推送实际生效的配置，virtual_ip为None时表示组的配置
+    #[cfg(feature = "web")]
+    pub fn push_client_config(&self, group: &str, virtual_ip: Option<u32>) -> Result<usize> {
+        let network_info = self
+            .cache
+            .virtual_network
+            .get_val(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        let guard = network_info.read();
+        let mut count = 0;
+        for client in guard.clients.values() {
+            if !client.online || virtual_ip.is_some_and(|ip| ip != client.virtual_ip) {
+                continue;
+            }
+            // 配置被全部清除时推送空的配置
+            let config = device_config(&guard, client).unwrap_or_else(|| {
+                let mut config = message::ClientConfig::new();
+                config.version = Local::now().timestamp_millis();
+                config
+            });
+            self.push_to_client(
+                client,
+                Protocol::Service,
+                service_packet::Protocol::PushClientConfig.into(),
+                &config.write_to_bytes()?,
+            )?;
+            count += 1;
+        }
+        Ok(count)
+    }
+    /// 把组迁移到其他服务器，通知组内在线设备连接新的服务器，等设备全部离开或超时后释放组
+    ///
+    /// address为None时取消迁移，返回通知的设备数
+    #[cfg(feature = "web")]
+    pub fn migrate_group(
+        &self,
+        group: &str,
+        address: Option<String>,
+        wait: Duration,
+    ) -> Result<usize> {
+        let network_info = self
+            .cache
+            .virtual_network
+            .get_val(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        let Some(address) = address else {
+            if network_info.write().migration.take().is_some() {
+                log::info!("取消迁移 group={}", group);
+            }
+            return Ok(0);
+        };
+        let running = {
+            let mut guard = network_info.write();
+            let running = guard.migration.is_some();
+            guard.migration = Some(crate::core::entity::Migration {
+                address: address.clone(),
+                since: guard
+                    .migration
+                    .as_ref()
+                    .map_or_else(Local::now, |migration| migration.since),
+                deadline: Instant::now() + wait,
+            });
+            running
+        };
+        let count = self.push_redirect(&network_info.read(), &address)?;
+        log::info!(
+            "开始迁移 group={},address={},count={},wait={}s",
+            group,
+            address,
+            count,
+            wait.as_secs()
+        );
+        if !running {
+            let handler = self.clone();
+            let group = group.to_string();
+            crate::core::task::spawn("migrate group", async move {
+                handler.migrate_task(group).await;
+            });
+        }
+        Ok(count)
+    }
+    #[cfg(feature = "web")]
+    fn push_redirect(&self, network_info: &NetworkInfo, address: &str) -> Result<usize> {
+        let mut redirect = message::Redirect::new();
+        redirect.address = address.to_string();
+        let bytes = redirect.write_to_bytes()?;
+        let mut count = 0;
+        for client in network_info.clients.values().filter(|x| x.online) {
+            self.push_to_client(
+                client,
+                Protocol::Service,
+                service_packet::Protocol::PushRedirect.into(),
+                &bytes,
+            )?;
+            count += 1;
+        }
+        Ok(count)
+    }
+    /// 等待组内设备迁移，没有收到重定向的设备会重复通知，全部离线或超时后释放组
+    #[cfg(feature = "web")]
+    async fn migrate_task(&self, group: String) {
+        loop {
+            tokio::time::sleep(MIGRATE_INTERVAL).await;
+            let Some(network_info) = self.cache.virtual_network.get_val(&group) else {
+                return;
+            };
+            let guard = network_info.read();
+            let Some(migration) = &guard.migration else {
+                return;
+            };
+            let online = guard.clients.values().filter(|x| x.online).count();
+            if online == 0 {
+                log::info!("组内设备已全部迁移 group={}", group);
+                break;
+            }
+            if migration.deadline <= Instant::now() {
+                log::warn!("迁移超时,剩余{}台设备未迁移 group={}", online, group);
+                break;
+            }
+            if let Err(e) = self.push_redirect(&guard, &migration.address) {
+                log::warn!("推送重定向失败 group={},{:?}", group, e);
+            }
+        }
+        if let Err(e) = self.cache.delete_group(&group) {
+            log::warn!("释放迁移的组失败 group={},{:?}", group, e);
+        }
+    }
+    /// tcp连接断开后设备离线，删除连接上下文和发送队列，客户端重连后重新注册
+    pub fn tcp_closed(&self, addr: SocketAddr) {
+        let Some(context) = self.cache.get_context(&addr) else {
+            return;
+        };
+        log::info!(
+            "tcp连接断开,设备离线 group={},virtual_ip={},addr={}",
+            context.group,
+            Ipv4Addr::from(context.virtual_ip),
+            addr
+        );
+        self.offline(&context, addr);
+    }
+    /// 设备立即离线，删除连接上下文、加密会话和发送队列，并通知组内其他在线设备
+    fn offline(&self, context: &Context, addr: SocketAddr) {
         {
-            v.client_status = Some(status_info);
+            let mut lock = context.network_info.write();
+            let Some(client) = lock.clients.get_mut(&context.virtual_ip) else {
+                return;
+            };
+            if client.address != addr || client.timestamp != context.timestamp {
+                return;
+            }
+            client.online = false;
+            client.meta.offline_since = Some(Instant::now());
+            client.tcp_sender = None;
+            // 离线后再注册按新的注册处理
+            lock.recent_registrations.remove(&context.device_id);
+            lock.bump_epoch();
+            for peer in lock.clients.values().filter(|x| x.online) {
+                if let Err(e) = self.push_device_list(&lock, peer) {
+                    log::warn!("推送设备列表失败 {},{:?}", peer.address, e);
+                }
+            }
+        }
+        self.cache.remove_addr_session(&addr);
+        self.cache.remove_cipher(&addr);
+    }
+    /// 客户端的来源地址变化时按会话id转移连接上下文
+    /// 设备的来源地址变化时按会话id转移会话
+    pub async fn rebind_session<B: AsRef<[u8]>>(
+        &self,
+        session_id: u64,
+        addr: SocketAddr,
+        net_packet: &NetPacket<B>,
+    ) {
+        let Some(old) = self.cache.session_addr(session_id) else {
+            return;
+        };
+        if old == addr {
+            return;
+        }
+        let rs = match self.check_rebind(old, addr, net_packet) {
+            Ok(true) => self.cache.rebind_session(session_id, old, addr).await,
+            // 等待可以验证的数据
+            Ok(false) => return,
+            Err(e) => Err(e),
+        };
+        match rs {
+            Ok(()) => log::info!("会话来源地址变化 {}->{}", old, addr),
+            Err(e) => {
+                if self
+                    .cache
+                    .log_limiter
+                    .check(addr.ip(), "session rebind refused")
+                {
+                    log::warn!("拒绝转移会话 {}->{},{}", old, addr, e);
+                }
+            }
+        }
+    }
+    /// 检查来源地址的变化是否允许转移会话，数据无法证明来自原设备时返回Ok(false)
+    ///
+    /// 和服务端建立了加密会话的设备，只有发给网关、能用会话密钥解密并且计数器大于之前所有数据的数据才能转移会话，
+    /// 防止截获了会话id或者数据的第三方抢占会话，无法这样验证的设备需要开启--addr-rebind-unverified
+    fn check_rebind<B: AsRef<[u8]>>(
+        &self,
+        old: SocketAddr,
+        addr: SocketAddr,
+        net_packet: &NetPacket<B>,
+    ) -> result::Result<bool, &'static str> {
+        match self.config.addr_rebind {
+            AddrRebind::Off => return Err("addr-rebind is off"),
+            AddrRebind::Port if old.ip().to_canonical() != addr.ip().to_canonical() => {
+                return Err("public ip changed")
+            }
+            _ => {}
+        }
+        let unverified = self.config.addr_rebind_unverified;
+        let Some(cipher) = self.cache.get_cipher(&old) else {
+            return if unverified {
+                Ok(true)
+            } else {
+                Err("session not encrypted")
+            };
+        };
+        if !cipher.counter_nonce() && !unverified {
+            return Err("session without counter nonce");
+        }
+        if !net_packet.is_gateway() || !net_packet.is_encrypt() {
+            return Ok(false);
+        }
+        let mut packet =
+            NetPacket::new(net_packet.buffer().to_vec()).map_err(|_| "invalid packet")?;
+        if cipher.counter_nonce() {
+            cipher
+                .decrypt_fresh(&mut packet)
+                .map_err(|_| "decrypt failed or replayed")?;
+        } else {
+            cipher
+                .decrypt_ipv4(&mut packet)
+                .map_err(|_| "decrypt failed")?;
         }
+        Ok(true)
+    }
+    /// 设备状态变化时立即向受影响的设备推送设备列表，不等下一次轮询
+    fn push_device_list(&self, network_info: &NetworkInfo, peer: &ClientInfo) -> Result<()> {
+        let bytes = network_info.device_list.encode(
+            network_info.epoch(),
+            network_info.status_epoch(),
+            &network_info.clients,
+            peer.virtual_ip,
+            network_info.policy.client_isolation,
+        )?;
+        self.push_to_client(
+            peer,
+            Protocol::Service,
+            service_packet::Protocol::PushDeviceList.into(),
+            &bytes,
+        )
     }
     fn clients_info(
         clients: &HashMap<u32, ClientInfo>,
         current_ip: u32,
     ) -> Vec<message::DeviceInfo> {
+        let current_rtt = clients.get(&current_ip).and_then(|v| v.meta.rtt);
         clients
             .iter()
             .filter(|&(_, dev)| dev.virtual_ip != current_ip)
             .map(|(_, device_info)| {
-                let mut dev = message::DeviceInfo::new();
-                dev.virtual_ip = device_info.virtual_ip;
-                dev.name = device_info.name.clone();
-                dev.device_status = if device_info.online { 0 } else { 1 };
-                dev.client_secret = device_info.client_secret;
+                let mut dev = device_info_of(device_info);
+                if let (Some(current_rtt), Some(rtt)) = (current_rtt, device_info.meta.rtt) {
+                    dev.relay_cost = current_rtt + rtt;
+                }
                 dev
             })
             .collect()
     }
+    /// 选择性广播中客户端声称已经通过p2p送达的设备，
+    /// 如果服务端掌握的状态表明p2p已经不可用，则仍然由服务端转发
+    ///
+    /// 发送方上报的p2p列表中没有该设备，或者该设备在上报之后重新上线过(地址可能已经变化)，都视为不可用，
+    /// 发送方还没有上报过状态时信任客户端的列表
+    fn p2p_exclude(&self, context: &Context, exclude: Vec<Ipv4Addr>) -> Vec<Ipv4Addr> {
+        let network_info = context.network_info.read();
+        let Some(status) = network_info
+            .clients
+            .get(&context.virtual_ip)
+            .and_then(|v| v.meta.client_status.as_ref())
+        else {
+            return exclude;
+        };
+        exclude
+            .into_iter()
+            .filter(|ip| {
+                let ip_u32 = u32::from(*ip);
+                if ip_u32 == context.virtual_ip {
+                    return true;
+                }
+                let Some(target) = network_info.clients.get(&ip_u32) else {
+                    return true;
+                };
+                let reachable = status.p2p_list.contains(ip)
+                    && target.meta.last_join_time <= status.update_time;
+                if !reachable {
+                    log::debug!(
+                        "广播包p2p不可达,由服务端转发 source={},target={}",
+                        Ipv4Addr::from(context.virtual_ip),
+                        ip
+                    );
+                }
+                reachable
+            })
+            .collect()
+    }
     fn broadcast<B: AsRef<[u8]>>(
         &self,
         context: &Context,
         net_packet: NetPacket<B>,
         exclude: &[Ipv4Addr],
     ) -> io::Result<()> {
-        let client_secret = net_packet.is_encrypt();
-        for (ip, client_info) in &context.network_info.read().clients {
-            if client_info.online
-                && !exclude.contains(&(*ip).into())
-                && client_info.client_secret == client_secret
-            {
-                if let Some(sender) = &client_info.tcp_sender {
-                    let _ = sender.try_send(net_packet.buffer().to_vec());
-                } else {
-                    let _ = self
-                        .udp
-                        .try_send_to(net_packet.buffer(), client_info.address);
-                }
-            }
+        let network_info = context.network_info.read();
+        let recipients =
+            client::broadcast_recipients(&network_info, context.virtual_ip, &net_packet, exclude);
+        if recipients.is_empty() || !client::broadcast_allowed(&network_info, recipients.len()) {
+            return Ok(());
         }
+        // 按实际接收的设备数计入中继带宽
+        if !client::relay_acquire(&network_info, net_packet.buffer().len() * recipients.len()) {
+            return Ok(());
+        }
+        let reencrypt = network_info.policy.relay_encryption.then_some(&self.cache);
+        client::broadcast(
+            &self.scheduler,
+            &context.network_info,
+            &network_info,
+            &recipients,
+            net_packet,
+            reencrypt,
+        );
         Ok(())
     }
 }
@@ -0,0 +1,749 @@
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use packet::ip::ipv4;
+use packet::ip::ipv4::packet::IpV4Packet;
+use packet::udp::udp::UdpPacket;
+use parking_lot::Mutex;
+
+use crate::core::entity::NetworkInfo;
+use crate::core::service::gateway;
+
+/// stun协议的magic cookie
+const MAGIC_COOKIE: u32 = 0x2112_A442;
+
+// 方法
+const BINDING: u16 = 0x001;
+const ALLOCATE: u16 = 0x003;
+const REFRESH: u16 = 0x004;
+const SEND: u16 = 0x006;
+const DATA: u16 = 0x007;
+const CREATE_PERMISSION: u16 = 0x008;
+const CHANNEL_BIND: u16 = 0x009;
+
+// 消息类别
+const REQUEST: u16 = 0;
+const INDICATION: u16 = 1;
+const SUCCESS: u16 = 2;
+const ERROR: u16 = 3;
+
+// 属性
+const ATTR_ERROR_CODE: u16 = 0x0009;
+const ATTR_CHANNEL_NUMBER: u16 = 0x000C;
+const ATTR_LIFETIME: u16 = 0x000D;
+const ATTR_XOR_PEER_ADDRESS: u16 = 0x0012;
+const ATTR_DATA: u16 = 0x0013;
+const ATTR_XOR_RELAYED_ADDRESS: u16 = 0x0016;
+const ATTR_REQUESTED_TRANSPORT: u16 = 0x0019;
+const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+/// 分配的默认有效期，客户端请求更短的有效期时也使用该值
+const DEFAULT_LIFETIME: Duration = Duration::from_secs(600);
+/// 分配的最大有效期
+const MAX_LIFETIME: Duration = Duration::from_secs(3600);
+/// 许可的有效期
+const PERMISSION_LIFETIME: Duration = Duration::from_secs(300);
+/// 通道绑定的有效期
+const CHANNEL_LIFETIME: Duration = Duration::from_secs(600);
+/// 所有组同时存在的分配数量上限
+const MAX_ALLOCATIONS: usize = 1024;
+/// 每台设备同时存在的分配数量上限
+const MAX_DEVICE_ALLOCATIONS: usize = 16;
+/// 网关一侧的中继端口范围
+const RELAY_PORTS: std::ops::RangeInclusive<u16> = 49152..=65535;
+/// 通道号范围
+const CHANNELS: std::ops::RangeInclusive<u16> = 0x4000..=0x7FFF;
+
+// (组，中继端口)
+type AllocationKey = (String, u16);
+// (设备ip，端口)
+type Address = (u32, u16);
+
+/// 设备在网关上申请的中继地址
+struct Allocation {
+    // 申请中继的设备地址
+    client: Address,
+    expire: Instant,
+    // 对端ip -> 过期时间
+    permissions: HashMap<u32, Instant>,
+    // 通道号 -> (对端地址，过期时间)
+    channels: HashMap<u16, (Address, Instant)>,
+}
+
+impl Allocation {
+    fn permitted(&self, peer: u32, now: Instant) -> bool {
+        self.permissions
+            .get(&peer)
+            .is_some_and(|expire| *expire > now)
+    }
+    fn channel_of(&self, peer: Address, now: Instant) -> Option<u16> {
+        self.channels
+            .iter()
+            .find(|(_, (address, expire))| *address == peer && *expire > now)
+            .map(|(channel, _)| *channel)
+    }
+}
+
+/// 网关上的turn服务(RFC 5766的子集)，组内设备可以把网关作为webrtc等应用的中继服务器
+///
+/// 只支持udp中继，对端只能是同组在线的设备，数据经服务端的中继转发
+/// 设备已经通过组的会话认证，因此不要求turn的长期凭证，客户端配置任意用户名和密码即可
+#[derive(Clone, Default)]
+pub struct TurnTable {
+    inner: Arc<Mutex<HashMap<AllocationKey, Allocation>>>,
+}
+
+impl TurnTable {
+    /// 处理设备发给网关的udp数据，port为turn服务端口
+    ///
+    /// 返回需要推送给组内设备的(设备ip，ipv4数据包)，不属于turn服务的数据返回None
+    pub fn handle(
+        &self,
+        network_info: &NetworkInfo,
+        group: &str,
+        port: u16,
+        ipv4: &[u8],
+    ) -> Option<Vec<(u32, Vec<u8>)>> {
+        let packet = IpV4Packet::new(ipv4).ok()?;
+        // 不处理分片
+        if packet.protocol() != ipv4::protocol::Protocol::Udp
+            || packet.offset() != 0
+            || packet.flags() & 0b001 != 0
+        {
+            return None;
+        }
+        let udp = UdpPacket::new(
+            packet.source_ip(),
+            packet.destination_ip(),
+            packet.payload(),
+        )
+        .ok()?;
+        let len = (udp.length() as usize).min(packet.payload().len());
+        if len < 8 || !udp.is_valid() {
+            return None;
+        }
+        let payload = &packet.payload()[8..len];
+        let source = (u32::from(packet.source_ip()), udp.source_port());
+        let now = Instant::now();
+        let mut inner = self.inner.lock();
+        if udp.destination_port() == port {
+            let mut session = Session {
+                inner: &mut inner,
+                network_info,
+                group,
+                gateway: (packet.destination_ip(), port),
+                client: source,
+                now,
+            };
+            return Some(session.client_message(payload).unwrap_or_default());
+        }
+        // 组内设备发往中继端口的数据
+        let relay_port = udp.destination_port();
+        let allocation = inner
+            .get(&(group.to_string(), relay_port))
+            .filter(|allocation| allocation.expire > now)?;
+        if !allocation.permitted(source.0, now) || !peer_allowed(network_info, source.0) {
+            return Some(Vec::new());
+        }
+        let message = match allocation.channel_of(source, now) {
+            Some(channel) => {
+                let mut message = Vec::with_capacity(4 + payload.len());
+                message.extend_from_slice(&channel.to_be_bytes());
+                message.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+                message.extend_from_slice(payload);
+                message
+            }
+            None => Builder::new(DATA, INDICATION, &rand::random::<[u8; 12]>())
+                .xor_address(ATTR_XOR_PEER_ADDRESS, source)
+                .attribute(ATTR_DATA, payload)
+                .finish(),
+        };
+        let reply = gateway::udp_ipv4(
+            (packet.destination_ip(), port),
+            (allocation.client.0.into(), allocation.client.1),
+            &message,
+        );
+        Some(vec![(allocation.client.0, reply)])
+    }
+}
+
+/// 对端必须是同组在线的设备，并且组没有开启设备隔离
+fn peer_allowed(network_info: &NetworkInfo, peer: u32) -> bool {
+    !network_info.policy.client_isolation
+        && network_info
+            .clients
+            .get(&peer)
+            .is_some_and(|client| client.online)
+}
+
+/// 处理一个设备发给turn服务端口的数据
+struct Session<'a> {
+    inner: &'a mut HashMap<AllocationKey, Allocation>,
+    network_info: &'a NetworkInfo,
+    group: &'a str,
+    // turn服务的地址
+    gateway: (Ipv4Addr, u16),
+    client: Address,
+    now: Instant,
+}
+
+impl Session<'_> {
+    fn client_message(&mut self, payload: &[u8]) -> Option<Vec<(u32, Vec<u8>)>> {
+        if payload.first()? & 0xC0 == 0x40 {
+            return self.channel_data(payload);
+        }
+        let message = Message::parse(payload)?;
+        let response = match (message.method, message.class) {
+            (BINDING, REQUEST) => Ok(Builder::new(BINDING, SUCCESS, message.transaction)
+                .xor_address(ATTR_XOR_MAPPED_ADDRESS, self.client)
+                .finish()),
+            (ALLOCATE, REQUEST) => self.allocate(&message),
+            (REFRESH, REQUEST) => self.refresh(&message),
+            (CREATE_PERMISSION, REQUEST) => self.create_permission(&message),
+            (CHANNEL_BIND, REQUEST) => self.channel_bind(&message),
+            (SEND, INDICATION) => return self.send(&message),
+            (_, REQUEST) => Err((400, "Bad Request")),
+            _ => return None,
+        };
+        let response = response.unwrap_or_else(|(code, reason)| {
+            Builder::new(message.method, ERROR, message.transaction)
+                .error_code(code, reason)
+                .finish()
+        });
+        let reply = gateway::udp_ipv4(
+            self.gateway,
+            (self.client.0.into(), self.client.1),
+            &response,
+        );
+        Some(vec![(self.client.0, reply)])
+    }
+    /// 设备当前的分配
+    fn allocation(&mut self) -> Option<(u16, &mut Allocation)> {
+        let (group, client, now) = (self.group, self.client, self.now);
+        self.inner
+            .iter_mut()
+            .find(|((g, _), allocation)| {
+                g == group && allocation.client == client && allocation.expire > now
+            })
+            .map(|((_, relay_port), allocation)| (*relay_port, allocation))
+    }
+    fn allocate(&mut self, message: &Message) -> Result<Vec<u8>, (u16, &'static str)> {
+        if self.allocation().is_some() {
+            return Err((437, "Allocation Mismatch"));
+        }
+        match message.attribute(ATTR_REQUESTED_TRANSPORT) {
+            // 只支持udp
+            Some(value) if value.first() == Some(&17) => {}
+            Some(_) => return Err((442, "Unsupported Transport Protocol")),
+            None => return Err((400, "Bad Request")),
+        }
+        let now = self.now;
+        self.inner.retain(|_, allocation| allocation.expire > now);
+        let device_allocations = self
+            .inner
+            .iter()
+            .filter(|((g, _), allocation)| g == self.group && allocation.client.0 == self.client.0)
+            .count();
+        if device_allocations >= MAX_DEVICE_ALLOCATIONS {
+            return Err((486, "Allocation Quota Reached"));
+        }
+        if self.inner.len() >= MAX_ALLOCATIONS {
+            return Err((508, "Insufficient Capacity"));
+        }
+        let span = RELAY_PORTS.end() - RELAY_PORTS.start() + 1;
+        let start = rand::random::<u16>() % span;
+        let relay_port = (0..span)
+            .map(|offset| RELAY_PORTS.start() + (start + offset) % span)
+            .find(|relay_port| {
+                *relay_port != self.gateway.1
+                    && !self
+                        .inner
+                        .contains_key(&(self.group.to_string(), *relay_port))
+            })
+            .ok_or((508, "Insufficient Capacity"))?;
+        let lifetime = lifetime(message).unwrap_or(DEFAULT_LIFETIME);
+        self.inner.insert(
+            (self.group.to_string(), relay_port),
+            Allocation {
+                client: self.client,
+                expire: now + lifetime,
+                permissions: HashMap::new(),
+                channels: HashMap::new(),
+            },
+        );
+        log::info!(
+            "turn分配 group={},client={}:{},relay_port={},lifetime={:?}",
+            self.group,
+            Ipv4Addr::from(self.client.0),
+            self.client.1,
+            relay_port,
+            lifetime
+        );
+        Ok(Builder::new(ALLOCATE, SUCCESS, message.transaction)
+            .xor_address(
+                ATTR_XOR_RELAYED_ADDRESS,
+                (self.gateway.0.into(), relay_port),
+            )
+            .attribute(ATTR_LIFETIME, &(lifetime.as_secs() as u32).to_be_bytes())
+            .xor_address(ATTR_XOR_MAPPED_ADDRESS, self.client)
+            .finish())
+    }
+    /// 刷新分配，有效期为0时删除
+    fn refresh(&mut self, message: &Message) -> Result<Vec<u8>, (u16, &'static str)> {
+        let now = self.now;
+        let group = self.group.to_string();
+        let (relay_port, allocation) = self.allocation().ok_or((437, "Allocation Mismatch"))?;
+        let lifetime = lifetime(message).unwrap_or(DEFAULT_LIFETIME);
+        if lifetime.is_zero() {
+            self.inner.remove(&(group, relay_port));
+        } else {
+            allocation.expire = now + lifetime;
+        }
+        Ok(Builder::new(REFRESH, SUCCESS, message.transaction)
+            .attribute(ATTR_LIFETIME, &(lifetime.as_secs() as u32).to_be_bytes())
+            .finish())
+    }
+    fn create_permission(&mut self, message: &Message) -> Result<Vec<u8>, (u16, &'static str)> {
+        let peers: Vec<Address> = message
+            .attributes(ATTR_XOR_PEER_ADDRESS)
+            .map(xor_address)
+            .collect::<Option<_>>()
+            .ok_or((400, "Bad Request"))?;
+        if peers.is_empty() {
+            return Err((400, "Bad Request"));
+        }
+        if !peers
+            .iter()
+            .all(|(peer, _)| peer_allowed(self.network_info, *peer))
+        {
+            return Err((403, "Forbidden"));
+        }
+        let now = self.now;
+        let (_, allocation) = self.allocation().ok_or((437, "Allocation Mismatch"))?;
+        for (peer, _) in peers {
+            allocation
+                .permissions
+                .insert(peer, now + PERMISSION_LIFETIME);
+        }
+        Ok(Builder::new(CREATE_PERMISSION, SUCCESS, message.transaction).finish())
+    }
+    fn channel_bind(&mut self, message: &Message) -> Result<Vec<u8>, (u16, &'static str)> {
+        let channel = message
+            .attribute(ATTR_CHANNEL_NUMBER)
+            .filter(|value| value.len() >= 2)
+            .map(|value| u16::from_be_bytes([value[0], value[1]]))
+            .filter(|channel| CHANNELS.contains(channel))
+            .ok_or((400, "Bad Request"))?;
+        let peer = message
+            .attribute(ATTR_XOR_PEER_ADDRESS)
+            .and_then(xor_address)
+            .ok_or((400, "Bad Request"))?;
+        if !peer_allowed(self.network_info, peer.0) {
+            return Err((403, "Forbidden"));
+        }
+        let now = self.now;
+        let (_, allocation) = self.allocation().ok_or((437, "Allocation Mismatch"))?;
+        // 通道和对端地址必须一一对应
+        let conflict = allocation
+            .channels
+            .iter()
+            .any(|(c, (address, expire))| *expire > now && ((*c == channel) != (*address == peer)));
+        if conflict {
+            return Err((400, "Bad Request"));
+        }
+        allocation
+            .channels
+            .insert(channel, (peer, now + CHANNEL_LIFETIME));
+        allocation
+            .permissions
+            .insert(peer.0, now + PERMISSION_LIFETIME);
+        Ok(Builder::new(CHANNEL_BIND, SUCCESS, message.transaction).finish())
+    }
+    /// Send指示，没有回应
+    fn send(&mut self, message: &Message) -> Option<Vec<(u32, Vec<u8>)>> {
+        let peer = message
+            .attribute(ATTR_XOR_PEER_ADDRESS)
+            .and_then(xor_address)?;
+        let data = message.attribute(ATTR_DATA)?;
+        self.relay(peer, data)
+    }
+    /// 通道数据：通道号(2)、长度(2)、数据
+    fn channel_data(&mut self, payload: &[u8]) -> Option<Vec<(u32, Vec<u8>)>> {
+        if payload.len() < 4 {
+            return None;
+        }
+        let channel = u16::from_be_bytes([payload[0], payload[1]]);
+        let len = u16::from_be_bytes([payload[2], payload[3]]) as usize;
+        let data = payload.get(4..4 + len)?;
+        let now = self.now;
+        let (_, allocation) = self.allocation()?;
+        let peer = allocation
+            .channels
+            .get(&channel)
+            .filter(|(_, expire)| *expire > now)
+            .map(|(peer, _)| *peer)?;
+        self.relay(peer, data)
+    }
+    /// 以中继地址把数据发给对端
+    fn relay(&mut self, peer: Address, data: &[u8]) -> Option<Vec<(u32, Vec<u8>)>> {
+        let (now, gateway, client) = (self.now, self.gateway.0, self.client.0);
+        let network_info = self.network_info;
+        let (relay_port, allocation) = self.allocation()?;
+        if !allocation.permitted(peer.0, now) || !peer_allowed(network_info, peer.0) {
+            return None;
+        }
+        let packet = gateway::udp_ipv4((gateway, relay_port), (peer.0.into(), peer.1), data);
+        if !network_info.send_allowed(client, peer.0.into(), Some(&packet)) {
+            return None;
+        }
+        Some(vec![(peer.0, packet)])
+    }
+}
+
+/// 请求中的有效期，限制在默认值和最大值之间，0表示删除
+fn lifetime(message: &Message) -> Option<Duration> {
+    let value = message.attribute(ATTR_LIFETIME)?;
+    let secs = u32::from_be_bytes(value.get(..4)?.try_into().ok()?);
+    if secs == 0 {
+        return Some(Duration::ZERO);
+    }
+    Some(Duration::from_secs(secs as u64).clamp(DEFAULT_LIFETIME, MAX_LIFETIME))
+}
+
+/// XOR地址属性，只支持ipv4
+fn xor_address(value: &[u8]) -> Option<Address> {
+    if value.len() < 8 || value[1] != 0x01 {
+        return None;
+    }
+    let port = u16::from_be_bytes([value[2], value[3]]) ^ (MAGIC_COOKIE >> 16) as u16;
+    let ip = u32::from_be_bytes([value[4], value[5], value[6], value[7]]) ^ MAGIC_COOKIE;
+    Some((ip, port))
+}
+
+/// stun消息
+struct Message<'a> {
+    method: u16,
+    class: u16,
+    transaction: &'a [u8],
+    attributes: Vec<(u16, &'a [u8])>,
+}
+
+impl<'a> Message<'a> {
+    fn parse(buf: &'a [u8]) -> Option<Self> {
+        if buf.len() < 20 || buf[0] & 0xC0 != 0 {
+            return None;
+        }
+        let kind = u16::from_be_bytes([buf[0], buf[1]]);
+        let len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        if u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]) != MAGIC_COOKIE {
+            return None;
+        }
+        let body = buf.get(20..20 + len)?;
+        let mut attributes = Vec::new();
+        let mut i = 0;
+        while i + 4 <= body.len() {
+            let attribute = u16::from_be_bytes([body[i], body[i + 1]]);
+            let len = u16::from_be_bytes([body[i + 2], body[i + 3]]) as usize;
+            attributes.push((attribute, body.get(i + 4..i + 4 + len)?));
+            // 属性按4字节对齐
+            i += 4 + len.div_ceil(4) * 4;
+        }
+        Some(Message {
+            method: (kind & 0x000F) | ((kind >> 1) & 0x0070) | ((kind >> 2) & 0x0F80),
+            class: ((kind >> 4) & 0x1) | ((kind >> 7) & 0x2),
+            transaction: &buf[8..20],
+            attributes,
+        })
+    }
+    fn attribute(&self, kind: u16) -> Option<&'a [u8]> {
+        self.attributes(kind).next()
+    }
+    fn attributes(&self, kind: u16) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.attributes
+            .iter()
+            .filter(move |(attribute, _)| *attribute == kind)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// 构造stun消息
+struct Builder {
+    buf: Vec<u8>,
+}
+
+impl Builder {
+    fn new(method: u16, class: u16, transaction: &[u8]) -> Self {
+        let kind = (method & 0x000F)
+            | ((method & 0x0070) << 1)
+            | ((method & 0x0F80) << 2)
+            | ((class & 0x1) << 4)
+            | ((class & 0x2) << 7);
+        let mut buf = Vec::with_capacity(128);
+        buf.extend_from_slice(&kind.to_be_bytes());
+        buf.extend_from_slice(&[0, 0]);
+        buf.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        buf.extend_from_slice(transaction);
+        Builder { buf }
+    }
+    fn attribute(mut self, kind: u16, value: &[u8]) -> Self {
+        self.buf.extend_from_slice(&kind.to_be_bytes());
+        self.buf
+            .extend_from_slice(&(value.len() as u16).to_be_bytes());
+        self.buf.extend_from_slice(value);
+        let padding = value.len().div_ceil(4) * 4 - value.len();
+        self.buf.extend_from_slice(&[0u8; 3][..padding]);
+        self
+    }
+    fn xor_address(self, kind: u16, (ip, port): Address) -> Self {
+        let mut value = [0u8; 8];
+        value[1] = 0x01;
+        value[2..4].copy_from_slice(&(port ^ (MAGIC_COOKIE >> 16) as u16).to_be_bytes());
+        value[4..8].copy_from_slice(&(ip ^ MAGIC_COOKIE).to_be_bytes());
+        self.attribute(kind, &value)
+    }
+    fn error_code(self, code: u16, reason: &str) -> Self {
+        let mut value = vec![0, 0, (code / 100) as u8, (code % 100) as u8];
+        value.extend_from_slice(reason.as_bytes());
+        self.attribute(ATTR_ERROR_CODE, &value)
+    }
+    fn finish(mut self) -> Vec<u8> {
+        let len = (self.buf.len() - 20) as u16;
+        self.buf[2..4].copy_from_slice(&len.to_be_bytes());
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::entity::{ClientInfo, GroupPolicy};
+
+    const GATEWAY: Ipv4Addr = Ipv4Addr::new(10, 26, 0, 1);
+    const CLIENT: Address = (0x0A1A_0002, 5000);
+    const PEER: Address = (0x0A1A_0003, 6000);
+    const TURN_PORT: u16 = 3478;
+    const TRANSACTION: [u8; 12] = [7; 12];
+
+    fn network_info() -> NetworkInfo {
+        let mut network_info = NetworkInfo::new(
+            0x0A1A_0000,
+            0xFFFF_FF00,
+            u32::from(GATEWAY),
+            GroupPolicy::default(),
+        );
+        for virtual_ip in [CLIENT.0, PEER.0] {
+            let client = ClientInfo {
+                virtual_ip,
+                online: true,
+                ..Default::default()
+            };
+            network_info.clients.insert(virtual_ip, client);
+        }
+        network_info
+    }
+
+    fn allocate_request(lifetime: Option<u32>) -> Vec<u8> {
+        let builder = Builder::new(ALLOCATE, REQUEST, &TRANSACTION)
+            .attribute(ATTR_REQUESTED_TRANSPORT, &[17, 0, 0, 0]);
+        match lifetime {
+            Some(secs) => builder.attribute(ATTR_LIFETIME, &secs.to_be_bytes()),
+            None => builder,
+        }
+        .finish()
+    }
+
+    /// 设备发给turn服务端口的消息，返回网关回应的stun消息
+    fn request(
+        inner: &mut HashMap<AllocationKey, Allocation>,
+        network_info: &NetworkInfo,
+        now: Instant,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut session = Session {
+            inner,
+            network_info,
+            group: "office",
+            gateway: (GATEWAY, TURN_PORT),
+            client: CLIENT,
+            now,
+        };
+        let mut replies = session.client_message(payload).unwrap();
+        assert_eq!(replies.len(), 1);
+        // 去掉ip头和udp头
+        replies.remove(0).1.split_off(28)
+    }
+
+    fn class_of(response: &[u8]) -> u16 {
+        Message::parse(response).unwrap().class
+    }
+
+    #[test]
+    fn message_round_trip() {
+        for method in [
+            BINDING,
+            ALLOCATE,
+            REFRESH,
+            SEND,
+            DATA,
+            CREATE_PERMISSION,
+            CHANNEL_BIND,
+        ] {
+            for class in [REQUEST, INDICATION, SUCCESS, ERROR] {
+                let buf = Builder::new(method, class, &TRANSACTION)
+                    .attribute(ATTR_DATA, b"hello")
+                    .xor_address(ATTR_XOR_PEER_ADDRESS, PEER)
+                    .attribute(ATTR_DATA, b"")
+                    .finish();
+                // 属性按4字节对齐
+                assert_eq!(buf.len(), 20 + (4 + 8) + (4 + 8) + 4);
+                let message = Message::parse(&buf).unwrap();
+                assert_eq!((message.method, message.class), (method, class));
+                assert_eq!(message.transaction, TRANSACTION);
+                assert_eq!(message.attribute(ATTR_DATA).unwrap(), b"hello");
+                assert_eq!(message.attributes(ATTR_DATA).count(), 2);
+                let peer = message.attribute(ATTR_XOR_PEER_ADDRESS).unwrap();
+                assert_eq!(xor_address(peer), Some(PEER));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let buf = Builder::new(BINDING, REQUEST, &TRANSACTION)
+            .attribute(ATTR_DATA, b"hello")
+            .finish();
+        assert!(Message::parse(&buf[..19]).is_none());
+        // 声明的长度超出数据
+        assert!(Message::parse(&buf[..buf.len() - 1]).is_none());
+        let mut bad_cookie = buf.clone();
+        bad_cookie[4] ^= 1;
+        assert!(Message::parse(&bad_cookie).is_none());
+        // 通道数据的前两位是01
+        let mut channel_data = buf.clone();
+        channel_data[0] = 0x40;
+        assert!(Message::parse(&channel_data).is_none());
+        // 属性长度超出消息
+        let mut bad_attribute = buf;
+        bad_attribute[22..24].copy_from_slice(&100u16.to_be_bytes());
+        assert!(Message::parse(&bad_attribute).is_none());
+        // ipv6地址
+        assert!(xor_address(&[0, 0x02, 0, 0, 0, 0, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn lifetime_is_clamped() {
+        let mut inner = HashMap::new();
+        let network_info = network_info();
+        let now = Instant::now();
+        let response = request(&mut inner, &network_info, now, &allocate_request(Some(60)));
+        let message = Message::parse(&response).unwrap();
+        assert_eq!(message.class, SUCCESS);
+        let lifetime = message.attribute(ATTR_LIFETIME).unwrap();
+        assert_eq!(lifetime, (DEFAULT_LIFETIME.as_secs() as u32).to_be_bytes());
+        let refresh = Builder::new(REFRESH, REQUEST, &TRANSACTION)
+            .attribute(ATTR_LIFETIME, &100_000u32.to_be_bytes())
+            .finish();
+        let response = request(&mut inner, &network_info, now, &refresh);
+        let message = Message::parse(&response).unwrap();
+        let lifetime = message.attribute(ATTR_LIFETIME).unwrap();
+        assert_eq!(lifetime, (MAX_LIFETIME.as_secs() as u32).to_be_bytes());
+        assert_eq!(inner.values().next().unwrap().expire, now + MAX_LIFETIME);
+    }
+
+    #[test]
+    fn allocation_expires() {
+        let mut inner = HashMap::new();
+        let network_info = network_info();
+        let now = Instant::now();
+        let response = request(&mut inner, &network_info, now, &allocate_request(None));
+        assert_eq!(class_of(&response), SUCCESS);
+        // 同一地址不能重复分配
+        let response = request(&mut inner, &network_info, now, &allocate_request(None));
+        assert_eq!(class_of(&response), ERROR);
+
+        // 过期后刷新失败，可以重新分配，过期的分配被清理
+        let expired = now + DEFAULT_LIFETIME;
+        let refresh = Builder::new(REFRESH, REQUEST, &TRANSACTION).finish();
+        let response = request(&mut inner, &network_info, expired, &refresh);
+        assert_eq!(class_of(&response), ERROR);
+        let response = request(&mut inner, &network_info, expired, &allocate_request(None));
+        assert_eq!(class_of(&response), SUCCESS);
+        assert_eq!(inner.len(), 1);
+
+        // 有效期为0时删除
+        let delete = Builder::new(REFRESH, REQUEST, &TRANSACTION)
+            .attribute(ATTR_LIFETIME, &0u32.to_be_bytes())
+            .finish();
+        let response = request(&mut inner, &network_info, expired, &delete);
+        assert_eq!(class_of(&response), SUCCESS);
+        assert!(inner.is_empty());
+    }
+
+    #[test]
+    fn expired_permissions_and_channels_stop_relaying() {
+        let mut inner = HashMap::new();
+        let network_info = network_info();
+        let now = Instant::now();
+        request(&mut inner, &network_info, now, &allocate_request(None));
+        let bind = Builder::new(CHANNEL_BIND, REQUEST, &TRANSACTION)
+            .attribute(ATTR_CHANNEL_NUMBER, &[0x40, 0x00, 0, 0])
+            .xor_address(ATTR_XOR_PEER_ADDRESS, PEER)
+            .finish();
+        let response = request(&mut inner, &network_info, now, &bind);
+        assert_eq!(class_of(&response), SUCCESS);
+
+        let channel_data = [0x40, 0x00, 0, 2, b'h', b'i'];
+        let relay = |inner: &mut HashMap<AllocationKey, Allocation>, now| {
+            let mut session = Session {
+                inner,
+                network_info: &network_info,
+                group: "office",
+                gateway: (GATEWAY, TURN_PORT),
+                client: CLIENT,
+                now,
+            };
+            session.client_message(&channel_data)
+        };
+        let packets = relay(&mut inner, now).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].0, PEER.0);
+        assert_eq!(&packets[0].1[28..], b"hi");
+        // 权限过期后不再转发
+        assert!(relay(&mut inner, now + PERMISSION_LIFETIME).is_none());
+    }
+
+    #[test]
+    fn peer_data_after_expiry_is_dropped() {
+        let table = TurnTable::default();
+        let network_info = network_info();
+        let allocate = gateway::udp_ipv4(
+            (CLIENT.0.into(), CLIENT.1),
+            (GATEWAY, TURN_PORT),
+            &allocate_request(None),
+        );
+        table
+            .handle(&network_info, "office", TURN_PORT, &allocate)
+            .unwrap();
+        let relay_port = {
+            let mut inner = table.inner.lock();
+            let ((_, relay_port), allocation) = inner.iter_mut().next().unwrap();
+            allocation
+                .permissions
+                .insert(PEER.0, Instant::now() + PERMISSION_LIFETIME);
+            *relay_port
+        };
+        let data = gateway::udp_ipv4((PEER.0.into(), PEER.1), (GATEWAY, relay_port), b"hi");
+        let packets = table
+            .handle(&network_info, "office", TURN_PORT, &data)
+            .unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].0, CLIENT.0);
+
+        table.inner.lock().values_mut().next().unwrap().expire = Instant::now();
+        assert!(table
+            .handle(&network_info, "office", TURN_PORT, &data)
+            .is_none());
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "web")]
+use sha2::Digest;
+
+/// web后台的角色，权限依次增加
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    // 只能查看
+    ReadOnly,
+    // 可以修改设备和组
+    Operator,
+    // 可以管理账号、api token和服务端配置
+    Admin,
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "read-only" | "readonly" => Ok(Role::ReadOnly),
+            "operator" => Ok(Role::Operator),
+            "admin" => Ok(Role::Admin),
+            _ => Err(format!("'{}' role must be read-only/operator/admin", s)),
+        }
+    }
+}
+
+/// web后台账号，密码只保存加盐后的哈希
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdminUser {
+    pub username: String,
+    pub role: Role,
+    salt: String,
+    password_hash: String,
+}
+
+/// 用于自动化调用的api token，只保存哈希，创建后不能再次查看
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub role: Role,
+    pub create_time: String,
+    token_hash: String,
+}
+
+/// 登录会话
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "web"), allow(dead_code))]
+pub struct Session {
+    // 会话id，用于查看和注销，和登录凭证不同
+    pub id: String,
+    pub username: String,
+    pub role: Role,
+    pub login_time: String,
+}
+
+/// web后台账号和api token，随设备注册信息一起保存
+#[derive(Default)]
+pub struct AdminStore {
+    // username -> 账号
+    users: HashMap<String, AdminUser>,
+    // id -> api token
+    tokens: HashMap<String, ApiToken>,
+}
+
+#[cfg(feature = "web")]
+fn hash(salt: &str, secret: &str) -> String {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(secret.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(feature = "web")]
+fn random_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+impl AdminStore {
+    pub fn restore(&mut self, users: Vec<AdminUser>, tokens: Vec<ApiToken>) {
+        self.users = users
+            .into_iter()
+            .map(|user| (user.username.clone(), user))
+            .collect();
+        self.tokens = tokens
+            .into_iter()
+            .map(|token| (token.id.clone(), token))
+            .collect();
+    }
+    pub fn users(&self) -> Vec<AdminUser> {
+        let mut users: Vec<AdminUser> = self.users.values().cloned().collect();
+        users.sort_by(|v1, v2| v1.username.cmp(&v2.username));
+        users
+    }
+    pub fn tokens(&self) -> Vec<ApiToken> {
+        let mut tokens: Vec<ApiToken> = self.tokens.values().cloned().collect();
+        tokens.sort_by(|v1, v2| v1.create_time.cmp(&v2.create_time));
+        tokens
+    }
+}
+
+#[cfg(feature = "web")]
+impl AdminStore {
+    /// 校验账号密码，返回角色
+    pub fn verify(&self, username: &str, password: &str) -> Option<Role> {
+        let user = self.users.get(username)?;
+        (hash(&user.salt, password) == user.password_hash).then_some(user.role)
+    }
+    /// 校验api token，返回角色
+    pub fn token_role(&self, token: &str) -> Option<Role> {
+        let token_hash = hash("", token);
+        self.tokens
+            .values()
+            .find(|v| v.token_hash == token_hash)
+            .map(|v| v.role)
+    }
+    /// 创建或修改账号，创建时必须指定密码，修改时不指定密码则保留原密码
+    pub fn save_user(
+        &mut self,
+        username: String,
+        password: Option<String>,
+        role: Role,
+    ) -> Result<(), String> {
+        if username.is_empty() || username.len() > 32 {
+            return Err("username length must be 1-32".into());
+        }
+        match (self.users.get_mut(&username), password) {
+            (Some(user), None) => user.role = role,
+            (_, Some(password)) => {
+                if password.len() < 6 {
+                    return Err("password length must be at least 6".into());
+                }
+                let salt = random_id();
+                let password_hash = hash(&salt, &password);
+                self.users.insert(
+                    username.clone(),
+                    AdminUser {
+                        username,
+                        role,
+                        salt,
+                        password_hash,
+                    },
+                );
+            }
+            (None, None) => return Err("password is required for a new user".into()),
+        }
+        Ok(())
+    }
+    pub fn delete_user(&mut self, username: &str) -> bool {
+        self.users.remove(username).is_some()
+    }
+    /// 创建api token，token只在创建时返回
+    pub fn create_token(&mut self, name: String, role: Role) -> (ApiToken, String) {
+        let token = format!("vnts_{}", random_id());
+        let api_token = ApiToken {
+            id: random_id()[..8].to_string(),
+            name,
+            role,
+            create_time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            token_hash: hash("", &token),
+        };
+        self.tokens.insert(api_token.id.clone(), api_token.clone());
+        (api_token, token)
+    }
+    pub fn delete_token(&mut self, id: &str) -> bool {
+        self.tokens.remove(id).is_some()
+    }
+}
+
+#[cfg(feature = "web")]
+impl Session {
+    pub fn new(username: String, role: Role) -> Session {
+        Session {
+            id: random_id()[..8].to_string(),
+            username,
+            role,
+            login_time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
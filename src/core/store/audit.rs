@@ -0,0 +1,183 @@
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use parking_lot::Mutex;
+
+use crate::core::store::cache::AppCache;
+
+/// 检查的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(300);
+/// 刚注册或刚握手的会话可能还没有写完所有映射，这段时间内不检查
+const GRACE: Duration = Duration::from_secs(60);
+
+/// 会话一致性检查的累计修复次数
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(not(feature = "web"), allow(dead_code))]
+pub struct AuditTotals {
+    // 检查次数
+    pub runs: u64,
+    pub last_run: Option<DateTime<Local>>,
+    // 删除的指向不存在设备的addr_session
+    pub addr_sessions: u64,
+    // 删除的指向不存在设备的ip_session
+    pub ip_sessions: u64,
+    // 补上的缺失的ip_session，缺失时ip永远不会回收
+    pub missing_ip_sessions: u64,
+    // 删除的没有绑定设备的加密会话
+    pub cipher_sessions: u64,
+    // 没有连接上下文但仍标记为在线的设备
+    pub online_flags: u64,
+}
+
+impl AuditTotals {
+    fn repairs(&self) -> u64 {
+        self.addr_sessions
+            + self.ip_sessions
+            + self.missing_ip_sessions
+            + self.cipher_sessions
+            + self.online_flags
+    }
+    fn add(&mut self, other: &AuditTotals) {
+        self.addr_sessions += other.addr_sessions;
+        self.ip_sessions += other.ip_sessions;
+        self.missing_ip_sessions += other.missing_ip_sessions;
+        self.cipher_sessions += other.cipher_sessions;
+        self.online_flags += other.online_flags;
+    }
+}
+
+/// 会话一致性检查的统计
+#[derive(Clone, Default)]
+pub struct AuditStats {
+    inner: Arc<Mutex<AuditTotals>>,
+}
+
+impl AuditStats {
+    #[cfg(feature = "web")]
+    pub fn totals(&self) -> AuditTotals {
+        self.inner.lock().clone()
+    }
+}
+
+/// 定时交叉检查ip_session、addr_session、cipher_session和组内设备，修复悬空的记录
+pub async fn audit_task(cache: AppCache) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    // 启动时立即执行的第一次跳过，状态文件恢复的设备需要时间重新注册
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let repairs = audit(&cache).await;
+        if repairs.repairs() > 0 {
+            log::warn!("会话一致性检查修复了悬空的记录 {:?}", repairs);
+        }
+        let mut totals = cache.audit.inner.lock();
+        totals.runs += 1;
+        totals.last_run = Some(Local::now());
+        totals.add(&repairs);
+    }
+}
+
+async fn audit(cache: &AppCache) -> AuditTotals {
+    let mut repairs = AuditTotals::default();
+    // addr_session指向的设备已不存在或已换了地址
+    for (addr, (group, virtual_ip, timestamp, _)) in cache.addr_session.key_values() {
+        let bound = cache.virtual_network.get_val(&group).is_some_and(|v| {
+            v.read()
+                .clients
+                .get(&virtual_ip)
+                .is_some_and(|x| x.address == addr && x.timestamp == timestamp)
+        });
+        if !bound {
+            log::info!(
+                "删除悬空的addr_session addr={},group={},virtual_ip={}",
+                addr,
+                group,
+                Ipv4Addr::from(virtual_ip)
+            );
+            cache.remove_addr_session(&addr);
+            repairs.addr_sessions += 1;
+        }
+    }
+    // ip_session指向的设备已不存在
+    for ((group, virtual_ip), addr) in cache.ip_session.key_values() {
+        let exists = cache
+            .virtual_network
+            .get_val(&group)
+            .is_some_and(|v| v.read().clients.contains_key(&virtual_ip));
+        if !exists {
+            log::info!(
+                "删除悬空的ip_session group={},virtual_ip={},addr={}",
+                group,
+                Ipv4Addr::from(virtual_ip),
+                addr
+            );
+            cache.ip_session.remove(&(group, virtual_ip));
+            repairs.ip_sessions += 1;
+        }
+    }
+    // 加密会话在握手后很快就会注册，长时间没有绑定设备的是残留的
+    for (addr, _) in cache.cipher_session.key_values() {
+        let idle = cache
+            .cipher_session
+            .last_access(&addr)
+            .is_some_and(|time| time.elapsed() >= GRACE);
+        if idle && cache.addr_session.get_val(&addr).is_none() {
+            cache.remove_cipher(&addr);
+            repairs.cipher_sessions += 1;
+        }
+    }
+    for (group, network_info) in cache.virtual_network.key_values() {
+        let mut missing = Vec::new();
+        {
+            let mut lock = network_info.write();
+            let mut changed = false;
+            for client in lock.clients.values_mut() {
+                if (Local::now() - client.meta.last_join_time)
+                    .to_std()
+                    .unwrap_or_default()
+                    < GRACE
+                {
+                    continue;
+                }
+                if cache
+                    .ip_session
+                    .get_val(&(group.clone(), client.virtual_ip))
+                    .is_none()
+                {
+                    missing.push((client.virtual_ip, client.address));
+                }
+                if client.online && cache.addr_session.get_val(&client.address).is_none() {
+                    log::info!(
+                        "设备没有连接上下文,标记为离线 group={},virtual_ip={},addr={}",
+                        group,
+                        Ipv4Addr::from(client.virtual_ip),
+                        client.address
+                    );
+                    client.online = false;
+                    client.meta.offline_since = Some(std::time::Instant::now());
+                    client.tcp_sender = None;
+                    changed = true;
+                    repairs.online_flags += 1;
+                }
+            }
+            if changed {
+                lock.bump_epoch();
+            }
+        }
+        for (virtual_ip, addr) in missing {
+            log::info!(
+                "补上缺失的ip_session group={},virtual_ip={},addr={}",
+                group,
+                Ipv4Addr::from(virtual_ip),
+                addr
+            );
+            cache
+                .insert_ip_session((group.clone(), virtual_ip), addr)
+                .await;
+            repairs.missing_ip_sessions += 1;
+        }
+    }
+    repairs
+}
@@ -1,12 +1,33 @@
+use std::collections::HashMap;
 use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 
-use crate::cipher::Aes256GcmCipher;
-use crate::core::entity::NetworkInfo;
+use crate::cipher::{Aes256GcmCipher, Finger};
+#[cfg(feature = "web")]
+use crate::core::entity::{check_tags, ClientConfig};
+use crate::core::entity::{LogLimiter, Maintenance, NetworkInfo, ReachProbes, SuspiciousSources};
+use crate::core::ingress::IngressTable;
+use crate::core::public_addr::PublicAddr;
+use crate::core::service::turn::TurnTable;
+use crate::core::store::admin::{AdminStore, Session};
+use crate::core::store::audit::AuditStats;
 use crate::core::store::expire_map::ExpireMap;
+use crate::core::store::read_view::ReadView;
+use crate::core::store::white_token::WhiteTokenStore;
+#[cfg(feature = "web")]
+use crate::error::{Error, Result};
+
+/// ip一天未使用则回收
+const IP_SESSION_EXPIRE: Duration = Duration::from_secs(24 * 3600);
+/// 连接上下文20秒没有数据则设备离线
+const ADDR_SESSION_EXPIRE: Duration = Duration::from_secs(20);
+/// 加密会话2分钟没有数据则需要重新握手
+const CIPHER_SESSION_EXPIRE: Duration = Duration::from_secs(120);
+/// 同一设备两次按会话id转移地址的最小间隔，防止两个来源交替抢占会话
+const MIN_REBIND_INTERVAL: Duration = Duration::from_secs(5);
 
 #[derive(Clone)]
 pub struct AppCache {
@@ -14,27 +35,69 @@ pub struct AppCache {
     pub virtual_network: ExpireMap<String, Arc<RwLock<NetworkInfo>>>,
     // (group,ip) -> addr
     pub ip_session: ExpireMap<(String, u32), SocketAddr>,
-    // addr -> (group，ip)
-    pub addr_session: ExpireMap<SocketAddr, (String, u32, i64)>,
+    // addr -> (group，ip，注册时间，设备id)
+    pub addr_session: ExpireMap<SocketAddr, (String, u32, i64, String)>,
     pub cipher_session: ExpireMap<SocketAddr, Arc<Aes256GcmCipher>>,
-    pub auth_map: ExpireMap<String, ()>,
+    // 登录凭证 -> 会话
+    pub auth_map: ExpireMap<String, Session>,
+    // web后台账号和api token
+    pub admin_store: Arc<RwLock<AdminStore>>,
+    // token白名单
+    pub white_tokens: Arc<RwLock<WhiteTokenStore>>,
+    // 服务端自己的公网地址
+    pub public_addr: Arc<RwLock<PublicAddr>>,
+    // nat探测id -> (主端口看到的来源端口，探测端口看到的来源端口)
+    pub nat_probe: ExpireMap<u32, (u16, u16)>,
+    // 异常流量的日志限流
+    pub log_limiter: LogLimiter,
+    // 发送异常数据的来源和临时封禁
+    pub suspicious: SuspiciousSources,
+    // 维护模式，为None时正常注册
+    pub maintenance: Arc<RwLock<Option<Maintenance>>>,
+    // 会话id -> addr，客户端经过负载均衡时来源地址可能变化
+    pub session_ids: ExpireMap<u64, SocketAddr>,
+    // 会话一致性检查的统计
+    pub audit: AuditStats,
+    // 管理接口发起的可达性探测
+    pub reach_probes: ReachProbes,
+    // socks5代理入口的连接
+    pub ingress: IngressTable,
+    // 网关turn服务的中继分配
+    pub turn: TurnTable,
+    // 等待推送本地路径提示的设备，(组，虚拟ip) -> 加入时间
+    pub local_path_pending: Arc<Mutex<HashMap<(String, u32), Instant>>>,
+    // 同时修改ip_session、addr_session和cipher_session时持有，保证多个映射一起更新
+    binding: Arc<Mutex<()>>,
+    // 数据转发路径使用的视图，addr -> 连接上下文
+    context_view: ReadView<SocketAddr, Arc<Context>>,
+    // addr -> 加密会话
+    cipher_view: ReadView<SocketAddr, Arc<Aes256GcmCipher>>,
 }
 
 pub struct Context {
     pub network_info: Arc<RwLock<NetworkInfo>>,
     pub group: String,
+    // 组token的指纹，校验加密会话时使用，避免每个数据包都计算一次
+    pub finger: Finger,
     pub virtual_ip: u32,
+    // 注册时间
+    pub timestamp: i64,
+    pub device_id: String,
 }
 
 impl AppCache {
     pub fn new() -> Self {
+        let context_view: ReadView<SocketAddr, Arc<Context>> = ReadView::new();
+        let cipher_view: ReadView<SocketAddr, Arc<Aes256GcmCipher>> = ReadView::new();
         // 网段7天未使用则回收
         let virtual_network: ExpireMap<String, Arc<RwLock<NetworkInfo>>> =
             ExpireMap::new(|_k, _v| {});
         let virtual_network_ = virtual_network.clone();
+        let context_view_ = context_view.clone();
         // ip一天未使用则回收
         let ip_session: ExpireMap<(String, u32), SocketAddr> =
             ExpireMap::new(move |(group_id, ip), addr: SocketAddr| {
+                context_view_.remove(&addr);
                 log::info!(
                     "ip_session eviction group_id={},ip={},addr={}",
                     group_id,
@@ -44,17 +107,26 @@ impl AppCache {
                 if let Some(v) = virtual_network_.get(&group_id) {
                     let mut lock = v.write();
                     if let Some(dev) = lock.clients.get(&ip) {
+                        // 受保护的设备不回收，配置了离线回收的组由回收任务按离线时长回收
+                        if dev.meta.protected || lock.policy.reclaim_offline.is_some() {
+                            return;
+                        }
                         if dev.address == addr {
                             lock.clients.remove(&ip);
-                            lock.epoch += 1;
+                            lock.peer_stats.remove(ip);
+                            lock.bump_epoch();
+                            lock.address_churn.record_release();
+                            lock.check_address_usage(&group_id);
                         }
                     }
                 }
             });
         let virtual_network_ = virtual_network.clone();
+        let context_view_ = context_view.clone();
         // 20秒钟没有收到消息则判定为掉线
         let addr_session = ExpireMap::new(
-            move |addr: SocketAddr, (group, virtual_ip, timestamp)| {
+            move |addr: SocketAddr, (group, virtual_ip, timestamp, _device_id)| {
+                context_view_.remove(&addr);
                 log::info!(
                     "addr_session eviction group={},virtual_ip={},addr={},timestamp={}",
                     group,
@@ -77,54 +149,453 @@ impl AppCache {
                             return;
                         }
                         item.online = false;
-                        lock.epoch += 1;
+                        item.meta.offline_since = Some(Instant::now());
+                        lock.bump_epoch();
                     }
                 }
             },
         );
-        let cipher_session = ExpireMap::new(|_k, _v| {});
+        let cipher_view_ = cipher_view.clone();
+        let cipher_session = ExpireMap::new(move |addr, _v| cipher_view_.remove(&addr));
         let auth_map = ExpireMap::new(|_k, _v| {});
+        let nat_probe = ExpireMap::new(|_k, _v| {});
+        let session_ids = ExpireMap::new(|_k, _v| {});
         Self {
             virtual_network,
             ip_session,
             addr_session,
             cipher_session,
             auth_map,
+            admin_store: Default::default(),
+            white_tokens: Default::default(),
+            public_addr: Default::default(),
+            nat_probe,
+            log_limiter: LogLimiter::new(),
+            suspicious: SuspiciousSources::new(),
+            maintenance: Arc::new(RwLock::new(None)),
+            session_ids,
+            audit: Default::default(),
+            reach_probes: Default::default(),
+            local_path_pending: Default::default(),
+            ingress: Default::default(),
+            turn: Default::default(),
+            binding: Default::default(),
+            context_view,
+            cipher_view,
         }
     }
 }
 
 impl AppCache {
-    pub fn get_context(&self, addr: &SocketAddr) -> Option<Context> {
-        if let Some((group, virtual_ip, _)) = self.addr_session.get(addr) {
-            let k = (group, virtual_ip);
-            self.ip_session.get(&k)?;
-            let (group, virtual_ip) = k;
-            return self
-                .virtual_network
-                .get(&group)
-                .map(|network_info| Context {
-                    network_info,
-                    group,
-                    virtual_ip,
-                });
+    /// 获取连接上下文，视图中没有时从addr_session、ip_session和virtual_network中查询后加入视图
+    pub fn get_context(&self, addr: &SocketAddr) -> Option<Arc<Context>> {
+        if let Some(context) = self.context_view.get(addr) {
+            return Some(context);
+        }
+        let version = self.context_view.version(addr);
+        let ((group, virtual_ip, timestamp, device_id), addr_deadline) =
+            self.addr_session.get_with_deadline(addr)?;
+        let k = (group, virtual_ip);
+        let (_, ip_deadline) = self.ip_session.get_with_deadline(&k)?;
+        let (group, virtual_ip) = k;
+        let (network_info, network_deadline) = self.virtual_network.get_with_deadline(&group)?;
+        let context = Arc::new(Context {
+            network_info,
+            finger: Finger::new(&group),
+            group,
+            virtual_ip,
+            timestamp,
+            device_id,
+        });
+        self.context_view.insert(
+            *addr,
+            context.clone(),
+            vec![addr_deadline, ip_deadline, network_deadline],
+            version,
+        );
+        Some(context)
+    }
+    /// 获取加密会话
+    pub fn get_cipher(&self, addr: &SocketAddr) -> Option<Arc<Aes256GcmCipher>> {
+        if let Some(cipher) = self.cipher_view.get(addr) {
+            return Some(cipher);
         }
-        None
+        let version = self.cipher_view.version(addr);
+        let (cipher, deadline) = self.cipher_session.get_with_deadline(addr)?;
+        self.cipher_view
+            .insert(*addr, cipher.clone(), vec![deadline], version);
+        Some(cipher)
     }
 
+    /// 删除会话密钥，客户端需要重新握手
+    pub fn remove_cipher(&self, addr: &SocketAddr) {
+        self.cipher_session.remove(addr);
+        self.cipher_view.remove(addr);
+    }
     pub async fn insert_cipher_session(&self, key: SocketAddr, value: Aes256GcmCipher) {
         self.cipher_session
-            .insert(key, Arc::new(value), Duration::from_secs(120))
-            .await
+            .insert(key, Arc::new(value), CIPHER_SESSION_EXPIRE)
+            .await;
+        self.cipher_view.remove(&key);
     }
     pub async fn insert_ip_session(&self, key: (String, u32), value: SocketAddr) {
-        self.ip_session
-            .insert(key, value, Duration::from_secs(24 * 3600))
-            .await
-    }
-    pub async fn insert_addr_session(&self, key: SocketAddr, value: (String, u32, i64)) {
-        self.addr_session
-            .insert(key, value, Duration::from_secs(20))
-            .await
+        self.ip_session.insert(key, value, IP_SESSION_EXPIRE).await;
+        self.context_view.remove(&value);
+    }
+    /// 删除连接上下文，该地址的下一个数据包会收到Disconnect
+    pub fn remove_addr_session(&self, addr: &SocketAddr) {
+        self.addr_session.remove(addr);
+        self.context_view.remove(addr);
+    }
+    pub async fn insert_session_id(&self, session_id: u64, addr: SocketAddr) {
+        // 和addr_session一样，设备持续发送数据时不会过期
+        self.session_ids
+            .insert(session_id, addr, ADDR_SESSION_EXPIRE)
+            .await;
+    }
+    /// 会话id当前绑定的来源地址
+    pub fn session_addr(&self, session_id: u64) -> Option<SocketAddr> {
+        self.session_ids.get(&session_id)
+    }
+    /// 按会话id把连接上下文和加密会话从old转移到新的来源地址，调用方已经检查过地址变化是否允许
+    ///
+    /// 会话已经不在old上或者距离上一次转移太近时返回Err
+    pub async fn rebind_session(
+        &self,
+        session_id: u64,
+        old: SocketAddr,
+        addr: SocketAddr,
+    ) -> std::result::Result<(), &'static str> {
+        if self.session_ids.get(&session_id) != Some(old) {
+            return Err("session moved");
+        }
+        let (group, virtual_ip, timestamp, device_id) =
+            self.addr_session.get_val(&old).ok_or("session expired")?;
+        let network_info = self
+            .virtual_network
+            .get_val(&group)
+            .ok_or("session expired")?;
+        {
+            let mut lock = network_info.write();
+            let client_info = lock.clients.get_mut(&virtual_ip).ok_or("session expired")?;
+            if client_info.address != old || client_info.timestamp != timestamp {
+                return Err("session moved");
+            }
+            if client_info
+                .meta
+                .last_rebind
+                .is_some_and(|last| last.elapsed() < MIN_REBIND_INTERVAL)
+            {
+                return Err("too frequent");
+            }
+            client_info.meta.last_rebind = Some(Instant::now());
+            client_info.address = addr;
+        }
+        let cipher = self.cipher_session.get_val(&old);
+        self.bind_session(group, virtual_ip, addr, timestamp, device_id, cipher)
+            .await;
+        self.insert_session_id(session_id, addr).await;
+        Ok(())
+    }
+    /// 绑定设备的会话，ip_session、addr_session和cipher_session一起更新，并清理同一设备之前的绑定
+    ///
+    /// 设备记录的地址和注册时间已经不是这次绑定的(同一设备的另一个注册更晚)时不绑定，返回false
+    pub async fn bind_session(
+        &self,
+        group: String,
+        virtual_ip: u32,
+        addr: SocketAddr,
+        timestamp: i64,
+        device_id: String,
+        cipher: Option<Arc<Aes256GcmCipher>>,
+    ) -> bool {
+        let tasks = {
+            let _guard = self.binding.lock();
+            let owner = self.virtual_network.get_val(&group).and_then(|v| {
+                v.read()
+                    .clients
+                    .get(&virtual_ip)
+                    .map(|x| (x.address, x.timestamp))
+            });
+            if owner != Some((addr, timestamp)) {
+                return false;
+            }
+            let key = (group, virtual_ip);
+            // 设备之前的地址
+            if let Some(old) = self.ip_session.get_val(&key).filter(|old| *old != addr) {
+                self.remove_addr_session(&old);
+                self.remove_cipher(&old);
+            }
+            // 该地址之前绑定的ip，例如设备换了ip
+            if let Some((group, virtual_ip, ..)) = self.addr_session.get_val(&addr) {
+                let old_key = (group, virtual_ip);
+                if old_key != key && self.ip_session.get_val(&old_key) == Some(addr) {
+                    self.ip_session.remove(&old_key);
+                }
+            }
+            let ip_task = self
+                .ip_session
+                .insert_unscheduled(key.clone(), addr, IP_SESSION_EXPIRE);
+            let addr_task = self.addr_session.insert_unscheduled(
+                addr,
+                (key.0, key.1, timestamp, device_id),
+                ADDR_SESSION_EXPIRE,
+            );
+            self.context_view.remove(&addr);
+            let cipher_task = cipher.map(|cipher| {
+                self.cipher_view.remove(&addr);
+                self.cipher_session
+                    .insert_unscheduled(addr, cipher, CIPHER_SESSION_EXPIRE)
+            });
+            (ip_task, addr_task, cipher_task)
+        };
+        let (ip_task, addr_task, cipher_task) = tasks;
+        ip_task.schedule().await;
+        addr_task.schedule().await;
+        if let Some(cipher_task) = cipher_task {
+            cipher_task.schedule().await;
+        }
+        true
+    }
+}
+
+#[cfg(feature = "web")]
+impl AppCache {
+    /// 管理员将设备移动到新的ip
+    ///
+    /// 删除设备的连接上下文，设备的下一个数据包会收到Disconnect，重新注册后获得新的ip
+    pub async fn reassign_ip(&self, group: &str, virtual_ip: u32, new_ip: u32) -> Result<()> {
+        let network_info = self
+            .virtual_network
+            .get_val(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        let addr = {
+            let mut lock = network_info.write();
+            // 和自动分配使用相同的检查，保留的ip只能由设备自己指定
+            let pool = match lock.pool_of(new_ip) {
+                Some(pool) if !lock.policy.is_reserved(new_ip) => pool,
+                _ => return Err(Error::InvalidIp),
+            };
+            if !lock.is_free(pool, new_ip) {
+                return Err(Error::IpAlreadyExists);
+            }
+            let mut client_info = lock
+                .clients
+                .remove(&virtual_ip)
+                .ok_or_else(|| Error::Other("device not found".into()))?;
+            client_info.virtual_ip = new_ip;
+            client_info.meta.reassigned = true;
+            let addr = client_info.address;
+            lock.clients.insert(new_ip, client_info);
+            lock.bump_epoch();
+            addr
+        };
+        log::info!(
+            "管理员重新分配ip group={},virtual_ip={},new_ip={},addr={}",
+            group,
+            Ipv4Addr::from(virtual_ip),
+            Ipv4Addr::from(new_ip),
+            addr
+        );
+        self.remove_addr_session(&addr);
+        self.ip_session.remove(&(group.to_string(), virtual_ip));
+        self.insert_ip_session((group.to_string(), new_ip), addr)
+            .await;
+        Ok(())
+    }
+    /// 管理员释放设备的ip，在线的设备会收到Disconnect，重新注册时重新分配ip
+    pub fn release_ip(&self, group: &str, virtual_ip: u32) -> Result<()> {
+        let network_info = self
+            .virtual_network
+            .get_val(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        let client_info = {
+            let mut lock = network_info.write();
+            let client_info = lock
+                .clients
+                .remove(&virtual_ip)
+                .ok_or_else(|| Error::Other("device not found".into()))?;
+            lock.peer_stats.remove(virtual_ip);
+            lock.address_churn.record_release();
+            lock.bump_epoch();
+            lock.check_address_usage(group);
+            client_info
+        };
+        log::info!(
+            "管理员释放ip group={},virtual_ip={},id={:?},addr={}",
+            group,
+            Ipv4Addr::from(virtual_ip),
+            client_info.device_id,
+            client_info.address
+        );
+        self.ip_session.remove(&(group.to_string(), virtual_ip));
+        if client_info.online {
+            self.remove_addr_session(&client_info.address);
+        }
+        Ok(())
+    }
+    /// 管理员设置设备是否受保护，受保护的设备离线多久都不回收ip
+    pub fn set_protected(&self, group: &str, virtual_ip: u32, protected: bool) -> Result<()> {
+        let network_info = self
+            .virtual_network
+            .get_val(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        let mut lock = network_info.write();
+        let client_info = lock
+            .clients
+            .get_mut(&virtual_ip)
+            .ok_or_else(|| Error::Other("device not found".into()))?;
+        log::info!(
+            "管理员设置设备保护 group={},virtual_ip={},protected={}",
+            group,
+            Ipv4Addr::from(virtual_ip),
+            protected
+        );
+        client_info.meta.protected = protected;
+        Ok(())
+    }
+    /// 管理员设置设备名称和备注，name为None时在设备下次注册时恢复使用客户端上报的名称，notes为None时不修改
+    pub fn set_device_info(
+        &self,
+        group: &str,
+        virtual_ip: u32,
+        name: Option<String>,
+        notes: Option<String>,
+    ) -> Result<()> {
+        if name
+            .as_ref()
+            .is_some_and(|name| name.is_empty() || name.len() > 128)
+        {
+            return Err(Error::Other("name length must be 1-128".into()));
+        }
+        if notes.as_ref().is_some_and(|notes| notes.len() > 1024) {
+            return Err(Error::Other("notes length must be at most 1024".into()));
+        }
+        let network_info = self
+            .virtual_network
+            .get_val(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        let mut lock = network_info.write();
+        let client_info = lock
+            .clients
+            .get_mut(&virtual_ip)
+            .ok_or_else(|| Error::Other("device not found".into()))?;
+        log::info!(
+            "管理员设置设备信息 group={},virtual_ip={},name={:?},notes={:?}",
+            group,
+            Ipv4Addr::from(virtual_ip),
+            name,
+            notes
+        );
+        client_info.meta.name_assigned = name.is_some();
+        if let Some(notes) = notes {
+            client_info.meta.notes = notes;
+        }
+        // 新名称通过设备列表推送给组内设备
+        if let Some(name) = name {
+            client_info.name = name;
+            lock.bump_epoch();
+        }
+        Ok(())
+    }
+    /// 管理员设置组或设备的配置，virtual_ip为None时设置组的配置，config为None时清除
+    pub fn set_client_config(
+        &self,
+        group: &str,
+        virtual_ip: Option<u32>,
+        config: Option<ClientConfig>,
+    ) -> Result<()> {
+        if let Some(config) = &config {
+            config.check().map_err(Error::Other)?;
+        }
+        let network_info = self
+            .virtual_network
+            .get_val(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        let mut lock = network_info.write();
+        log::info!(
+            "管理员设置配置 group={},virtual_ip={:?},config={:?}",
+            group,
+            virtual_ip.map(Ipv4Addr::from),
+            config
+        );
+        match virtual_ip {
+            None => lock.client_config = config,
+            Some(virtual_ip) => {
+                lock.clients
+                    .get_mut(&virtual_ip)
+                    .ok_or_else(|| Error::Other("device not found".into()))?
+                    .meta
+                    .client_config = config
+            }
+        }
+        Ok(())
+    }
+    /// 管理员设置设备标签，之后注册时不再使用客户端上报的标签，tags为None时恢复使用客户端上报的标签
+    pub fn set_tags(&self, group: &str, virtual_ip: u32, tags: Option<Vec<String>>) -> Result<()> {
+        if let Some(tags) = &tags {
+            check_tags(tags).map_err(Error::Other)?;
+        }
+        let network_info = self
+            .virtual_network
+            .get_val(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        let mut lock = network_info.write();
+        let client_info = lock
+            .clients
+            .get_mut(&virtual_ip)
+            .ok_or_else(|| Error::Other("device not found".into()))?;
+        log::info!(
+            "管理员设置标签 group={},virtual_ip={},tags={:?}",
+            group,
+            Ipv4Addr::from(virtual_ip),
+            tags
+        );
+        client_info.meta.tags_assigned = tags.is_some();
+        // 恢复使用客户端上报的标签时，在设备下次注册时生效
+        if let Some(tags) = tags {
+            client_info.tags = tags;
+        }
+        lock.bump_epoch();
+        Ok(())
+    }
+    /// 预先创建组，组已存在时返回错误
+    pub async fn create_group(&self, group: &str, network_info: NetworkInfo) -> Result<()> {
+        let mut created = false;
+        self.virtual_network
+            .optionally_get_with(group.to_string(), || {
+                created = true;
+                (
+                    Duration::from_secs(7 * 24 * 3600),
+                    Arc::new(parking_lot::const_rwlock(network_info)),
+                )
+            })
+            .await;
+        if !created {
+            return Err(Error::Other("group already exists".into()));
+        }
+        log::info!("管理员创建组 group={:?}", group);
+        Ok(())
+    }
+    /// 删除组，组内的设备会收到Disconnect
+    ///
+    /// 返回被断开的设备数量
+    pub fn delete_group(&self, group: &str) -> Result<usize> {
+        let network_info = self
+            .virtual_network
+            .remove(&group.to_string())
+            .ok_or_else(|| Error::Other("group not found".into()))?;
+        let lock = network_info.read();
+        for (ip, client_info) in &lock.clients {
+            self.ip_session.remove(&(group.to_string(), *ip));
+            self.remove_addr_session(&client_info.address);
+            self.remove_cipher(&client_info.address);
+        }
+        log::info!(
+            "管理员删除组 group={:?},clients={}",
+            group,
+            lock.clients.len()
+        );
+        Ok(lock.clients.len())
     }
 }
@@ -11,6 +11,8 @@ use parking_lot::RwLock;
 use tokio::sync::mpsc::error::TryRecvError;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
+use crate::core::task;
+
 #[derive(Clone)]
 pub struct ExpireMap<K, V> {
     base: Arc<RwLock<HashMap<K, Value<V>>>>,
@@ -19,10 +21,25 @@ pub struct ExpireMap<K, V> {
 
 struct Value<V> {
     val: V,
-    deadline: AtomicCell<Instant>,
+    deadline: Arc<AtomicCell<Instant>>,
+    expire: Duration,
+}
+
+/// 过期时间的句柄，不加锁延长过期时间
+///
+/// 对应的值被删除或者替换后再延长不会有任何效果
+#[derive(Clone)]
+pub struct Deadline {
+    deadline: Arc<AtomicCell<Instant>>,
     expire: Duration,
 }
 
+impl Deadline {
+    pub fn touch(&self) {
+        self.deadline.store(Instant::now().add(self.expire));
+    }
+}
+
 impl<K, V> ExpireMap<K, V> {
     pub fn new<F>(call: F) -> ExpireMap<K, V>
     where
@@ -36,7 +53,9 @@ impl<K, V> ExpireMap<K, V> {
             sender,
         };
         let map1 = map.clone();
-        tokio::spawn(async move { expire_task(receiver, map1, call).await });
+        task::spawn("expire map", async move {
+            expire_task(receiver, map1, call).await
+        });
         map
     }
 }
@@ -50,21 +69,26 @@ where
         self.base.read().len()
     }
     pub async fn insert(&self, k: K, val: V, expire: Duration) {
+        self.insert_unscheduled(k, val, expire).schedule().await
+    }
+    /// 立即写入，返回还未投入过期监听的任务
+    ///
+    /// 用于在不能等待的代码中(例如持有锁时)同时写入多个map，之后再调用schedule
+    pub fn insert_unscheduled(&self, k: K, val: V, expire: Duration) -> Unscheduled<K> {
         let instant = Instant::now().add(expire);
         {
             let mut write_guard = self.base.write();
             let value = Value {
                 val,
-                deadline: AtomicCell::new(instant),
+                deadline: Arc::new(AtomicCell::new(instant)),
                 expire,
             };
             write_guard.insert(k.clone(), value);
         }
-        //投入过期监听
-        self.sender
-            .send(DelayedTask { k, time: instant })
-            .await
-            .unwrap();
+        Unscheduled {
+            sender: self.sender.clone(),
+            task: DelayedTask { k, time: instant },
+        }
     }
     pub fn get(&self, k: &K) -> Option<V> {
         if let Some(v) = self.base.read().get(k) {
@@ -75,6 +99,30 @@ where
             None
         }
     }
+    /// 获取值和过期时间的句柄，同时延长过期时间
+    pub fn get_with_deadline(&self, k: &K) -> Option<(V, Deadline)> {
+        let guard = self.base.read();
+        let v = guard.get(k)?;
+        v.deadline.store(Instant::now().add(v.expire));
+        Some((
+            v.val.clone(),
+            Deadline {
+                deadline: v.deadline.clone(),
+                expire: v.expire,
+            },
+        ))
+    }
+    /// 直接删除，不会执行过期回调
+    pub fn remove(&self, k: &K) -> Option<V> {
+        self.base.write().remove(k).map(|v| v.val)
+    }
+    /// 最后一次访问的时间
+    pub fn last_access(&self, k: &K) -> Option<Instant> {
+        self.base
+            .read()
+            .get(k)
+            .and_then(|v| v.deadline.load().checked_sub(v.expire))
+    }
     pub fn get_val(&self, k: &K) -> Option<V> {
         self.base.read().get(k).map(|v| v.val.clone())
     }
@@ -111,7 +159,7 @@ where
                 let instant = Instant::now().add(expire);
                 let value = Value {
                     val: val.clone(),
-                    deadline: AtomicCell::new(instant),
+                    deadline: Arc::new(AtomicCell::new(instant)),
                     expire,
                 };
                 write_guard.insert(k.clone(), value);
@@ -208,6 +256,20 @@ where
     }
 }
 
+/// 已写入但还未投入过期监听的值，不调用schedule时值不会过期
+#[must_use]
+pub struct Unscheduled<K> {
+    sender: Sender<DelayedTask<K>>,
+    task: DelayedTask<K>,
+}
+
+impl<K> Unscheduled<K> {
+    /// 投入过期监听
+    pub async fn schedule(self) {
+        self.sender.send(self.task).await.unwrap();
+    }
+}
+
 struct DelayedTask<K> {
     k: K,
     time: Instant,
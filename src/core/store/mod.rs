@@ -1,2 +1,11 @@
+pub mod admin;
+pub mod audit;
 pub mod cache;
 pub mod expire_map;
+pub mod read_view;
+pub mod reclaim;
+pub mod snapshot;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod state;
+pub mod white_token;
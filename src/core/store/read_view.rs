@@ -0,0 +1,105 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+
+use crate::core::store::expire_map::Deadline;
+
+/// 分片数量，写入时只复制一个分片
+const SHARDS: usize = 16;
+
+/// 数据转发路径使用的只读视图
+///
+/// 读取不加锁，写入时复制所在的分片。过期仍由ExpireMap管理，
+/// 读取时通过Deadline延长ExpireMap中对应值的过期时间，ExpireMap中的值变化时需要调用remove
+#[derive(Clone)]
+pub struct ReadView<K, V> {
+    shards: Arc<[Shard<K, V>]>,
+}
+
+struct Shard<K, V> {
+    map: ArcSwap<HashMap<K, Entry<V>>>,
+    // 每次删除加1，查询期间发生过删除的值不会加入视图
+    version: AtomicU64,
+    write_lock: Mutex<()>,
+}
+
+struct Entry<V> {
+    val: V,
+    deadlines: Arc<[Deadline]>,
+}
+
+impl<V: Clone> Clone for Entry<V> {
+    fn clone(&self) -> Self {
+        Self {
+            val: self.val.clone(),
+            deadlines: self.deadlines.clone(),
+        }
+    }
+}
+
+impl<K, V> ReadView<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        let shards = (0..SHARDS)
+            .map(|_| Shard {
+                map: ArcSwap::from_pointee(HashMap::new()),
+                version: AtomicU64::new(0),
+                write_lock: Mutex::new(()),
+            })
+            .collect();
+        Self { shards }
+    }
+    fn shard(&self, k: &K) -> &Shard<K, V> {
+        let mut hasher = DefaultHasher::new();
+        k.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % SHARDS]
+    }
+    /// 获取值，同时延长ExpireMap中对应值的过期时间
+    pub fn get(&self, k: &K) -> Option<V> {
+        let map = self.shard(k).map.load();
+        let entry = map.get(k)?;
+        for deadline in entry.deadlines.iter() {
+            deadline.touch();
+        }
+        Some(entry.val.clone())
+    }
+    /// 在查询ExpireMap之前获取版本，用于insert
+    pub fn version(&self, k: &K) -> u64 {
+        self.shard(k).version.load(Ordering::Acquire)
+    }
+    /// 加入视图，获取版本之后发生过删除时放弃加入，避免加入已经失效的值
+    pub fn insert(&self, k: K, val: V, deadlines: Vec<Deadline>, version: u64) {
+        let shard = self.shard(&k);
+        let _guard = shard.write_lock.lock();
+        if shard.version.load(Ordering::Acquire) != version {
+            return;
+        }
+        let mut map = HashMap::clone(&shard.map.load());
+        map.insert(
+            k,
+            Entry {
+                val,
+                deadlines: deadlines.into(),
+            },
+        );
+        shard.map.store(Arc::new(map));
+    }
+    pub fn remove(&self, k: &K) {
+        let shard = self.shard(k);
+        let _guard = shard.write_lock.lock();
+        shard.version.fetch_add(1, Ordering::AcqRel);
+        if shard.map.load().contains_key(k) {
+            let mut map = HashMap::clone(&shard.map.load());
+            map.remove(k);
+            shard.map.store(Arc::new(map));
+        }
+    }
+}
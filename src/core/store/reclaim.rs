@@ -0,0 +1,57 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use crate::core::store::cache::AppCache;
+
+/// 检查离线设备的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// 定时回收离线超过组策略时长的设备的ip，受保护的设备不回收
+pub async fn reclaim_task(cache: AppCache) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        for (group, network_info) in cache.virtual_network.key_values() {
+            let reclaimed: Vec<u32> = {
+                let mut lock = network_info.write();
+                let Some(reclaim_offline) = lock.policy.reclaim_offline else {
+                    continue;
+                };
+                let reclaimed: Vec<u32> = lock
+                    .clients
+                    .values()
+                    .filter(|client| {
+                        !client.online
+                            && !client.meta.protected
+                            && client
+                                .meta
+                                .offline_since
+                                .is_some_and(|time| time.elapsed() >= reclaim_offline)
+                    })
+                    .map(|client| client.virtual_ip)
+                    .collect();
+                for ip in &reclaimed {
+                    if let Some(client) = lock.clients.remove(ip) {
+                        log::info!(
+                            "回收离线设备的ip group={},virtual_ip={},id={:?},name={:?}",
+                            group,
+                            Ipv4Addr::from(*ip),
+                            client.device_id,
+                            client.name
+                        );
+                    }
+                    lock.peer_stats.remove(*ip);
+                    lock.address_churn.record_release();
+                }
+                if !reclaimed.is_empty() {
+                    lock.bump_epoch();
+                    lock.check_address_usage(&group);
+                }
+                reclaimed
+            };
+            for ip in reclaimed {
+                cache.ip_session.remove(&(group.clone(), ip));
+            }
+        }
+    }
+}
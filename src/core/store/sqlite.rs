@@ -0,0 +1,498 @@
+use std::io;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::core::store::cache::AppCache;
+use crate::core::store::state::{self, ConfigState, DeviceState, GroupState, StateDump};
+
+/// 按顺序执行的表结构迁移，版本号记录在user_version中，只能追加不能修改已有的迁移
+const MIGRATIONS: &[&str] = &[
+    // 1: 组、设备、冲突ip的保留和后台凭证
+    "CREATE TABLE groups (
+        name TEXT PRIMARY KEY,
+        network_ip INTEGER NOT NULL,
+        mask_ip INTEGER NOT NULL,
+        gateway_ip INTEGER NOT NULL,
+        epoch INTEGER,
+        client_config TEXT
+    );
+    CREATE TABLE devices (
+        group_name TEXT NOT NULL REFERENCES groups(name) ON DELETE CASCADE,
+        device_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        virtual_ip INTEGER NOT NULL,
+        tags TEXT,
+        protected INTEGER NOT NULL,
+        name_assigned INTEGER NOT NULL,
+        notes TEXT NOT NULL,
+        client_config TEXT,
+        PRIMARY KEY (group_name, virtual_ip),
+        UNIQUE (group_name, device_id)
+    );
+    CREATE TABLE reservations (
+        group_name TEXT NOT NULL REFERENCES groups(name) ON DELETE CASCADE,
+        virtual_ip INTEGER NOT NULL,
+        device_id TEXT NOT NULL,
+        PRIMARY KEY (group_name, virtual_ip)
+    );
+    CREATE TABLE credentials (
+        kind TEXT NOT NULL,
+        id TEXT NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (kind, id)
+    );
+    CREATE TABLE settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );",
+];
+
+// credentials表中的凭证类型
+const USER: &str = "user";
+const API_TOKEN: &str = "api_token";
+const WHITE_TOKEN: &str = "white_token";
+// settings表中的项：是否开启了token白名单，未开启时不保存白名单
+const WHITE_TOKENS_ENABLED: &str = "white_tokens_enabled";
+// settings表中的项：最近一次保存的时间
+const EXPORT_TIME: &str = "export_time";
+
+/// sqlite存储的设备注册信息，内容和--state-file相同，每次保存整体替换
+#[derive(Clone)]
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    /// 打开数据库并执行未完成的迁移，数据库版本比程序新时返回错误
+    pub fn open(path: &Path) -> io::Result<SqliteStore> {
+        let conn = Connection::open(path).map_err(db_err)?;
+        Self::init(conn)
+    }
+    fn init(mut conn: Connection) -> io::Result<SqliteStore> {
+        conn.pragma_update(None, "foreign_keys", true)
+            .map_err(db_err)?;
+        migrate(&mut conn)?;
+        Ok(SqliteStore {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+    /// 读取保存的设备注册信息，从未保存过时返回None
+    pub fn load(&self) -> io::Result<Option<StateDump>> {
+        let conn = self.conn.lock();
+        let saved: Option<String> = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                [EXPORT_TIME],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_err)?;
+        let Some(export_time) = saved else {
+            return Ok(None);
+        };
+        let mut groups = Vec::new();
+        let mut stmt = conn
+            .prepare(
+                "SELECT name, network_ip, mask_ip, gateway_ip, epoch, client_config
+                 FROM groups ORDER BY name",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, u32>(2)?,
+                    row.get::<_, u32>(3)?,
+                    row.get::<_, Option<u32>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            })
+            .map_err(db_err)?;
+        for row in rows {
+            let (group, network_ip, mask_ip, gateway_ip, epoch, client_config) =
+                row.map_err(db_err)?;
+            groups.push(GroupState {
+                devices: load_devices(&conn, &group)?,
+                ip_conflicts: load_reservations(&conn, &group)?,
+                group,
+                network_ip: network_ip.into(),
+                mask_ip: mask_ip.into(),
+                gateway_ip: gateway_ip.into(),
+                epoch,
+                client_config: from_json_opt(client_config)?,
+            });
+        }
+        let white_tokens_enabled: Option<String> = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                [WHITE_TOKENS_ENABLED],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_err)?;
+        let dump = StateDump {
+            version: state::STATE_VERSION,
+            export_time,
+            groups,
+            users: load_credentials(&conn, USER)?,
+            api_tokens: load_credentials(&conn, API_TOKEN)?,
+            white_tokens: match white_tokens_enabled.as_deref() {
+                Some("1") => Some(load_credentials(&conn, WHITE_TOKEN)?),
+                _ => None,
+            },
+        };
+        dump.check()?;
+        Ok(Some(dump))
+    }
+    /// 在一个事务中整体替换保存的内容，写入失败时保持原来的内容
+    pub fn save(&self, dump: &StateDump) -> io::Result<()> {
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction().map_err(db_err)?;
+        save_dump(&tx, dump).map_err(db_err)?;
+        tx.commit().map_err(db_err)
+    }
+}
+
+fn migrate(conn: &mut Connection) -> io::Result<()> {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(db_err)?;
+    if version > MIGRATIONS.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "database version {} is newer than supported {}",
+                version,
+                MIGRATIONS.len()
+            ),
+        ));
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction().map_err(db_err)?;
+        tx.execute_batch(migration).map_err(db_err)?;
+        tx.pragma_update(None, "user_version", index + 1)
+            .map_err(db_err)?;
+        tx.commit().map_err(db_err)?;
+        log::info!("sqlite迁移到版本{}", index + 1);
+    }
+    Ok(())
+}
+
+fn save_dump(tx: &Transaction, dump: &StateDump) -> rusqlite::Result<()> {
+    // 设备和保留随组级联删除
+    tx.execute_batch("DELETE FROM groups; DELETE FROM credentials; DELETE FROM settings;")?;
+    for group in &dump.groups {
+        tx.execute(
+            "INSERT INTO groups (name, network_ip, mask_ip, gateway_ip, epoch, client_config)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                group.group,
+                u32::from(group.network_ip),
+                u32::from(group.mask_ip),
+                u32::from(group.gateway_ip),
+                group.epoch,
+                to_json_opt(group.client_config.as_ref())?,
+            ],
+        )?;
+        for device in &group.devices {
+            tx.execute(
+                "INSERT INTO devices (group_name, device_id, name, virtual_ip, tags, protected,
+                 name_assigned, notes, client_config)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    group.group,
+                    device.device_id,
+                    device.name,
+                    u32::from(device.virtual_ip),
+                    to_json_opt(device.tags.as_ref())?,
+                    device.protected,
+                    device.name_assigned,
+                    device.notes,
+                    to_json_opt(device.client_config.as_ref())?,
+                ],
+            )?;
+        }
+        for conflict in &group.ip_conflicts {
+            tx.execute(
+                "INSERT INTO reservations (group_name, virtual_ip, device_id) VALUES (?1, ?2, ?3)",
+                params![
+                    group.group,
+                    u32::from(conflict.virtual_ip),
+                    conflict.device_id
+                ],
+            )?;
+        }
+    }
+    for user in &dump.users {
+        insert_credential(tx, USER, &user.username, user)?;
+    }
+    for token in &dump.api_tokens {
+        insert_credential(tx, API_TOKEN, &token.id, token)?;
+    }
+    if let Some(white_tokens) = &dump.white_tokens {
+        for token in white_tokens {
+            insert_credential(tx, WHITE_TOKEN, &token.token, token)?;
+        }
+    }
+    let white_tokens_enabled = if dump.white_tokens.is_some() {
+        "1"
+    } else {
+        "0"
+    };
+    tx.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2), (?3, ?4)",
+        params![
+            WHITE_TOKENS_ENABLED,
+            white_tokens_enabled,
+            EXPORT_TIME,
+            dump.export_time
+        ],
+    )?;
+    Ok(())
+}
+
+fn load_devices(conn: &Connection, group: &str) -> io::Result<Vec<DeviceState>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, name, virtual_ip, tags, protected, name_assigned, notes, client_config
+             FROM devices WHERE group_name = ?1 ORDER BY virtual_ip",
+        )
+        .map_err(db_err)?;
+    let rows = stmt
+        .query_map([group], |row| {
+            Ok((
+                DeviceState {
+                    device_id: row.get(0)?,
+                    name: row.get(1)?,
+                    virtual_ip: Ipv4Addr::from(row.get::<_, u32>(2)?),
+                    tags: None,
+                    protected: row.get(4)?,
+                    name_assigned: row.get(5)?,
+                    notes: row.get(6)?,
+                    client_config: None,
+                },
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(7)?,
+            ))
+        })
+        .map_err(db_err)?;
+    let mut devices = Vec::new();
+    for row in rows {
+        let (mut device, tags, client_config) = row.map_err(db_err)?;
+        device.tags = from_json_opt(tags)?;
+        device.client_config = from_json_opt::<ConfigState>(client_config)?;
+        devices.push(device);
+    }
+    Ok(devices)
+}
+
+fn load_reservations(conn: &Connection, group: &str) -> io::Result<Vec<DeviceState>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, virtual_ip FROM reservations WHERE group_name = ?1
+             ORDER BY virtual_ip",
+        )
+        .map_err(db_err)?;
+    let rows = stmt
+        .query_map([group], |row| {
+            Ok(DeviceState {
+                device_id: row.get(0)?,
+                name: String::new(),
+                virtual_ip: Ipv4Addr::from(row.get::<_, u32>(1)?),
+                tags: None,
+                protected: false,
+                name_assigned: false,
+                notes: String::new(),
+                client_config: None,
+            })
+        })
+        .map_err(db_err)?;
+    rows.collect::<rusqlite::Result<_>>().map_err(db_err)
+}
+
+fn insert_credential<T: Serialize>(
+    tx: &Transaction,
+    kind: &str,
+    id: &str,
+    value: &T,
+) -> rusqlite::Result<()> {
+    let data = serde_json::to_string(value)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    tx.execute(
+        "INSERT INTO credentials (kind, id, data) VALUES (?1, ?2, ?3)",
+        params![kind, id, data],
+    )?;
+    Ok(())
+}
+
+fn load_credentials<T: DeserializeOwned>(conn: &Connection, kind: &str) -> io::Result<Vec<T>> {
+    let mut stmt = conn
+        .prepare("SELECT data FROM credentials WHERE kind = ?1 ORDER BY id")
+        .map_err(db_err)?;
+    let rows = stmt
+        .query_map([kind], |row| row.get::<_, String>(0))
+        .map_err(db_err)?;
+    let mut values = Vec::new();
+    for row in rows {
+        let data = row.map_err(db_err)?;
+        values.push(
+            serde_json::from_str(&data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        );
+    }
+    Ok(values)
+}
+
+fn to_json_opt<T: Serialize>(value: Option<&T>) -> rusqlite::Result<Option<String>> {
+    value
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn from_json_opt<T: DeserializeOwned>(value: Option<String>) -> io::Result<Option<T>> {
+    value
+        .map(|value| serde_json::from_str(&value))
+        .transpose()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn db_err(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+/// 定时保存设备注册信息
+pub async fn save_task(cache: AppCache, store: SqliteStore, path: PathBuf) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let dump = state::export(&cache);
+        let store = store.clone();
+        let rs = match tokio::task::spawn_blocking(move || store.save(&dump)).await {
+            Ok(rs) => rs,
+            Err(e) => Err(io::Error::other(e)),
+        };
+        if let Err(e) = rs {
+            log::error!("保存设备注册信息失败 path={:?},{:?}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+    use std::collections::BTreeMap;
+
+    fn store() -> SqliteStore {
+        SqliteStore::init(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn device(device_id: &str, last: u8) -> DeviceState {
+        DeviceState {
+            device_id: device_id.into(),
+            name: format!("{}-name", device_id),
+            virtual_ip: Ipv4Addr::new(10, 26, 0, last),
+            tags: None,
+            protected: false,
+            name_assigned: false,
+            notes: String::new(),
+            client_config: None,
+        }
+    }
+
+    fn dump() -> StateDump {
+        let mut tagged = device("b", 3);
+        tagged.tags = Some(vec!["iot".into()]);
+        tagged.protected = true;
+        tagged.name_assigned = true;
+        tagged.notes = "printer".into();
+        tagged.client_config = Some(ConfigState {
+            version: 2,
+            values: BTreeMap::from([("mtu".into(), "1400".into())]),
+            data: String::new(),
+        });
+        StateDump {
+            version: state::STATE_VERSION,
+            export_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            groups: vec![GroupState {
+                group: "office".into(),
+                network_ip: Ipv4Addr::new(10, 26, 0, 0),
+                mask_ip: Ipv4Addr::new(255, 255, 255, 0),
+                gateway_ip: Ipv4Addr::new(10, 26, 0, 1),
+                devices: vec![device("a", 2), tagged],
+                // 冲突的ip只保存设备id
+                ip_conflicts: vec![DeviceState {
+                    name: String::new(),
+                    ..device("c", 4)
+                }],
+                epoch: Some(7),
+                client_config: None,
+            }],
+            users: Vec::new(),
+            api_tokens: Vec::new(),
+            white_tokens: None,
+        }
+    }
+
+    #[test]
+    fn empty_store_loads_nothing() {
+        assert!(store().load().unwrap().is_none());
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let store = store();
+        let dump = dump();
+        store.save(&dump).unwrap();
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&dump).unwrap()
+        );
+    }
+
+    #[test]
+    fn save_replaces_previous_contents() {
+        let store = store();
+        store.save(&dump()).unwrap();
+        let mut dump = dump();
+        dump.groups[0].devices.truncate(1);
+        dump.groups[0].ip_conflicts.clear();
+        dump.white_tokens = Some(Vec::new());
+        store.save(&dump).unwrap();
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.device_count(), 1);
+        assert!(loaded.groups[0].ip_conflicts.is_empty());
+        assert_eq!(loaded.white_tokens.map(|v| v.len()), Some(0));
+    }
+
+    #[test]
+    fn failed_save_keeps_previous_contents() {
+        let store = store();
+        store.save(&dump()).unwrap();
+        // 重复的ip违反主键约束，整个事务回滚
+        let mut dump = dump();
+        dump.groups[0].devices[1].virtual_ip = dump.groups[0].devices[0].virtual_ip;
+        assert!(store.save(&dump).is_err());
+        assert_eq!(store.load().unwrap().unwrap().device_count(), 2);
+    }
+
+    #[test]
+    fn rejects_newer_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        let err = SqliteStore::init(conn).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::core::entity::{ClientConfig, ClientInfo, ClientMeta, NetworkInfo};
+use crate::core::store::admin::{AdminUser, ApiToken};
+use crate::core::store::cache::AppCache;
+use crate::core::store::white_token::WhiteToken;
+use crate::ConfigInfo;
+
+/// 导出格式的版本，格式不兼容时增加
+pub const STATE_VERSION: u32 = 1;
+
+/// 设备注册信息，用于迁移和灾难恢复
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateDump {
+    pub version: u32,
+    // 导出时间
+    pub export_time: String,
+    pub groups: Vec<GroupState>,
+    // web后台账号
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<AdminUser>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api_tokens: Vec<ApiToken>,
+    // token白名单和使用情况，未开启白名单时为None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub white_tokens: Option<Vec<WhiteToken>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupState {
+    pub group: String,
+    // 网段
+    pub network_ip: Ipv4Addr,
+    // 掩码
+    pub mask_ip: Ipv4Addr,
+    // 网关
+    pub gateway_ip: Ipv4Addr,
+    // 设备id和ip的对应关系
+    pub devices: Vec<DeviceState>,
+    // 冲突的ip由哪个设备保留
+    #[serde(default)]
+    pub ip_conflicts: Vec<DeviceState>,
+    // 纪元号，旧版本导出的文件没有
+    #[serde(default)]
+    pub epoch: Option<u32>,
+    // 管理员下发给组内所有设备的配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_config: Option<ConfigState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceState {
+    pub device_id: String,
+    #[serde(default)]
+    pub name: String,
+    pub virtual_ip: Ipv4Addr,
+    // 管理员设置的标签，客户端上报的标签重新注册时会再次上报，不需要保存
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    // 管理员设置了保护，离线多久都不回收ip
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub protected: bool,
+    // 名称是管理员设置的，重新注册时不使用客户端上报的名称
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub name_assigned: bool,
+    // 管理员填写的备注
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    // 管理员下发给该设备的配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_config: Option<ConfigState>,
+}
+
+/// 管理员下发的配置，原始数据使用base64编码
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigState {
+    pub version: i64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub data: String,
+}
+
+impl From<&ClientConfig> for ConfigState {
+    fn from(config: &ClientConfig) -> Self {
+        ConfigState {
+            version: config.version,
+            values: config.values.clone(),
+            data: STANDARD.encode(&config.data),
+        }
+    }
+}
+
+impl TryFrom<&ConfigState> for ClientConfig {
+    type Error = String;
+
+    fn try_from(state: &ConfigState) -> Result<Self, Self::Error> {
+        let config = ClientConfig {
+            version: state.version,
+            values: state.values.clone(),
+            data: STANDARD
+                .decode(&state.data)
+                .map_err(|e| format!("config data {}", e))?,
+        };
+        config.check()?;
+        Ok(config)
+    }
+}
+
+impl StateDump {
+    pub fn load(path: &Path) -> io::Result<StateDump> {
+        let data = std::fs::read(path)?;
+        let dump: StateDump = serde_json::from_slice(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        dump.check()?;
+        Ok(dump)
+    }
+    /// 先写临时文件再替换，避免写入中断后文件不完整
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let data = serde_json::to_vec_pretty(self)?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(tmp, path)
+    }
+    /// 检查版本，以及重复的组、设备id和ip
+    pub fn check(&self) -> io::Result<()> {
+        if self.version != STATE_VERSION {
+            return Err(invalid(format!(
+                "unsupported version {}, expected {}",
+                self.version, STATE_VERSION
+            )));
+        }
+        let mut groups = HashSet::new();
+        for group in &self.groups {
+            if group.group.is_empty() || group.group.len() > 128 {
+                return Err(invalid(format!("group length error {:?}", group.group)));
+            }
+            if !groups.insert(&group.group) {
+                return Err(invalid(format!("duplicate group {:?}", group.group)));
+            }
+            let configs = group.client_config.iter().chain(
+                group
+                    .devices
+                    .iter()
+                    .filter_map(|v| v.client_config.as_ref()),
+            );
+            for config in configs {
+                ClientConfig::try_from(config)
+                    .map_err(|e| invalid(format!("{} in group {:?}", e, group.group)))?;
+            }
+            let mut ips = HashSet::new();
+            let mut device_ids = HashSet::new();
+            for device in &group.devices {
+                if !ips.insert(device.virtual_ip) {
+                    return Err(invalid(format!(
+                        "duplicate ip {} in group {:?}",
+                        device.virtual_ip, group.group
+                    )));
+                }
+                if !device_ids.insert(&device.device_id) {
+                    return Err(invalid(format!(
+                        "duplicate device_id {:?} in group {:?}",
+                        device.device_id, group.group
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+    pub fn device_count(&self) -> usize {
+        self.groups.iter().map(|group| group.devices.len()).sum()
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// 导出当前缓存中的组和设备
+pub fn export(cache: &AppCache) -> StateDump {
+    let mut groups: Vec<GroupState> = cache
+        .virtual_network
+        .key_values()
+        .into_iter()
+        .map(|(group, info)| {
+            let guard = info.read();
+            let mut devices: Vec<DeviceState> = guard
+                .clients
+                .values()
+                .map(|client| DeviceState {
+                    device_id: client.device_id.clone(),
+                    name: client.name.clone(),
+                    virtual_ip: client.virtual_ip.into(),
+                    tags: client.meta.tags_assigned.then(|| client.tags.clone()),
+                    protected: client.meta.protected,
+                    name_assigned: client.meta.name_assigned,
+                    notes: client.meta.notes.clone(),
+                    client_config: client.meta.client_config.as_ref().map(ConfigState::from),
+                })
+                .collect();
+            devices.sort_by_key(|device| device.virtual_ip);
+            let mut ip_conflicts: Vec<DeviceState> = guard
+                .ip_conflicts
+                .iter()
+                .map(|(ip, device_id)| DeviceState {
+                    device_id: device_id.clone(),
+                    name: String::new(),
+                    virtual_ip: (*ip).into(),
+                    tags: None,
+                    protected: false,
+                    name_assigned: false,
+                    notes: String::new(),
+                    client_config: None,
+                })
+                .collect();
+            ip_conflicts.sort_by_key(|device| device.virtual_ip);
+            GroupState {
+                group,
+                network_ip: guard.network_ip.into(),
+                mask_ip: guard.mask_ip.into(),
+                gateway_ip: guard.gateway_ip.into(),
+                devices,
+                ip_conflicts,
+                epoch: Some(guard.epoch()),
+                client_config: guard.client_config.as_ref().map(ConfigState::from),
+            }
+        })
+        .collect();
+    groups.sort_by(|v1, v2| v1.group.cmp(&v2.group));
+    let admin_store = cache.admin_store.read();
+    StateDump {
+        version: STATE_VERSION,
+        export_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        groups,
+        users: admin_store.users(),
+        api_tokens: admin_store.tokens(),
+        white_tokens: cache.white_tokens.read().list(),
+    }
+}
+
+/// 恢复组和设备，设备恢复为离线状态，重新注册时沿用原来的ip，已存在的组不会覆盖
+pub async fn restore(cache: &AppCache, config: &ConfigInfo, dump: StateDump) -> usize {
+    cache
+        .admin_store
+        .write()
+        .restore(dump.users, dump.api_tokens);
+    cache.white_tokens.write().restore(dump.white_tokens);
+    let mut count = 0;
+    for group in dump.groups {
+        if cache.virtual_network.get_val(&group.group).is_some() {
+            log::warn!("组已存在,跳过恢复 group={:?}", group.group);
+            continue;
+        }
+        let mut network_info = NetworkInfo::new(
+            group.network_ip.into(),
+            group.mask_ip.into(),
+            group.gateway_ip.into(),
+            config.group_policy(&group.group),
+        );
+        for device in &group.devices {
+            let virtual_ip: u32 = device.virtual_ip.into();
+            network_info.clients.insert(
+                virtual_ip,
+                ClientInfo {
+                    device_id: device.device_id.clone(),
+                    name: device.name.clone(),
+                    virtual_ip,
+                    tags: device.tags.clone().unwrap_or_default(),
+                    meta: Box::new(ClientMeta {
+                        tags_assigned: device.tags.is_some(),
+                        // 离线时长从恢复时开始计算
+                        offline_since: Some(Instant::now()),
+                        protected: device.protected,
+                        name_assigned: device.name_assigned,
+                        notes: device.notes.clone(),
+                        // 加载时已经检查过
+                        client_config: device
+                            .client_config
+                            .as_ref()
+                            .and_then(|config| ClientConfig::try_from(config).ok()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            );
+        }
+        network_info.client_config = group
+            .client_config
+            .as_ref()
+            .and_then(|config| ClientConfig::try_from(config).ok());
+        if let Some(epoch) = group.epoch {
+            network_info.restore_epoch(epoch);
+        }
+        for conflict in group.ip_conflicts {
+            network_info
+                .ip_conflicts
+                .insert(conflict.virtual_ip.into(), conflict.device_id);
+        }
+        cache
+            .virtual_network
+            .insert(
+                group.group.clone(),
+                Arc::new(parking_lot::const_rwlock(network_info)),
+                Duration::from_secs(7 * 24 * 3600),
+            )
+            .await;
+        // 和注册时一样，一天内没有重新注册则回收ip
+        for device in group.devices {
+            let address = ClientInfo::default().address;
+            cache
+                .insert_ip_session((group.group.clone(), device.virtual_ip.into()), address)
+                .await;
+            count += 1;
+        }
+    }
+    count
+}
+
+/// 定时保存设备注册信息
+pub async fn save_task(cache: AppCache, path: PathBuf) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let dump = export(&cache);
+        if let Err(e) = dump.save(&path) {
+            log::error!("保存设备注册信息失败 path={:?},{:?}", path, e);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+/// 白名单中的token和使用情况
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WhiteToken {
+    pub token: String,
+    // 启动参数配置的token，不能通过管理接口删除
+    #[serde(skip)]
+    pub configured: bool,
+    pub create_time: String,
+    // 第一次和最近一次注册成功的时间，从未使用时为None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<String>,
+    // 注册成功的次数
+    #[serde(default)]
+    pub registrations: u64,
+}
+
+impl WhiteToken {
+    fn new(token: String, configured: bool) -> Self {
+        Self {
+            token,
+            configured,
+            create_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            first_seen: None,
+            last_used: None,
+            registrations: 0,
+        }
+    }
+}
+
+/// token白名单，启动参数配置的token加上管理接口添加的token，随设备注册信息一起保存
+#[derive(Default)]
+pub struct WhiteTokenStore {
+    // 开启白名单后只有白名单中的token可以注册，删除所有token后仍然开启
+    enabled: bool,
+    // token -> 使用情况
+    tokens: HashMap<String, WhiteToken>,
+}
+
+impl WhiteTokenStore {
+    /// 加载启动参数配置的白名单
+    pub fn configure(&mut self, tokens: Option<&HashSet<String>>) {
+        let Some(tokens) = tokens else {
+            return;
+        };
+        self.enabled = true;
+        for token in tokens {
+            self.tokens
+                .insert(token.clone(), WhiteToken::new(token.clone(), true));
+        }
+    }
+    /// 恢复保存的白名单和使用情况，启动参数配置的token保持不能删除
+    pub fn restore(&mut self, tokens: Option<Vec<WhiteToken>>) {
+        let Some(tokens) = tokens else {
+            return;
+        };
+        self.enabled = true;
+        for mut token in tokens {
+            token.configured = self.tokens.get(&token.token).is_some_and(|v| v.configured);
+            self.tokens.insert(token.token.clone(), token);
+        }
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    pub fn contains(&self, token: &str) -> bool {
+        self.tokens.contains_key(token)
+    }
+    pub fn tokens(&self) -> impl Iterator<Item = &String> {
+        self.tokens.keys()
+    }
+    /// 白名单，未开启时为None
+    pub fn list(&self) -> Option<Vec<WhiteToken>> {
+        if !self.enabled {
+            return None;
+        }
+        let mut tokens: Vec<WhiteToken> = self.tokens.values().cloned().collect();
+        tokens.sort_by(|v1, v2| v1.token.cmp(&v2.token));
+        Some(tokens)
+    }
+    /// 记录一次注册成功
+    pub fn record_use(&mut self, token: &str) {
+        if let Some(white_token) = self.tokens.get_mut(token) {
+            let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            white_token.first_seen.get_or_insert_with(|| now.clone());
+            white_token.last_used = Some(now);
+            white_token.registrations += 1;
+        }
+    }
+}
+
+#[cfg(feature = "web")]
+impl WhiteTokenStore {
+    /// 添加token，白名单未开启时同时开启白名单，返回token之前是否不存在
+    pub fn add(&mut self, token: String) -> Result<bool, String> {
+        if token.is_empty() || token.len() > 128 {
+            return Err("token length must be 1-128".into());
+        }
+        self.enabled = true;
+        if self.tokens.contains_key(&token) {
+            return Ok(false);
+        }
+        self.tokens
+            .insert(token.clone(), WhiteToken::new(token, false));
+        Ok(true)
+    }
+    pub fn remove(&mut self, token: &str) -> Result<(), String> {
+        match self.tokens.get(token) {
+            None => Err("token not found".into()),
+            Some(v) if v.configured => {
+                Err("token is configured by --white-token, cannot be removed".into())
+            }
+            Some(_) => {
+                self.tokens.remove(token);
+                Ok(())
+            }
+        }
+    }
+}
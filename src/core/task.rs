@@ -0,0 +1,26 @@
+use std::future::Future;
+
+use tokio::task::JoinHandle;
+
+/// 启动带名称的任务，使用tokio_unstable和console特性编译时可以在tokio-console中按名称查看任务
+#[cfg(all(tokio_unstable, feature = "console"))]
+pub fn spawn<F>(name: &str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::task::Builder::new()
+        .name(name)
+        .spawn(future)
+        .expect("spawn task")
+}
+
+/// 启动带名称的任务，使用tokio_unstable和console特性编译时可以在tokio-console中按名称查看任务
+#[cfg(not(all(tokio_unstable, feature = "console")))]
+pub fn spawn<F>(_name: &str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(future)
+}
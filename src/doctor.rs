@@ -0,0 +1,315 @@
+use std::fmt;
+use std::io;
+use std::net::Ipv4Addr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cipher::{RsaCipher, MIN_SAFE_BITS};
+use crate::logger::LogOptions;
+use crate::{
+    app_root, check_subnet, create_tcp, create_udp, parse_alert, parse_ddns, parse_group_policy,
+    parse_rsa, parse_runtime, StartArgs, GATEWAY, NETMASK,
+};
+
+/// 建议的udp缓冲区上限，中继流量较大时过小的缓冲区会丢包
+const RECOMMENDED_UDP_BUFFER: u64 = 4 * 1024 * 1024;
+/// 早于该时间(2024-01-01)说明系统时间没有同步
+pub const MIN_SANE_TIME: u64 = 1704067200;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Level {
+    Ok,
+    Warn,
+    Error,
+}
+
+/// 一项检查结果
+struct Finding {
+    level: Level,
+    item: &'static str,
+    message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            Level::Ok => "ok",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        };
+        write!(f, "[{}] {}: {}", level, self.item, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Report {
+    findings: Vec<Finding>,
+}
+
+impl Report {
+    fn ok(&mut self, item: &'static str, message: impl Into<String>) {
+        self.push(Level::Ok, item, message.into());
+    }
+    fn warn(&mut self, item: &'static str, message: impl Into<String>) {
+        self.push(Level::Warn, item, message.into());
+    }
+    fn error(&mut self, item: &'static str, message: impl Into<String>) {
+        self.push(Level::Error, item, message.into());
+    }
+    fn push(&mut self, level: Level, item: &'static str, message: String) {
+        self.findings.push(Finding {
+            level,
+            item,
+            message,
+        });
+    }
+    fn count(&self, level: Level) -> usize {
+        self.findings.iter().filter(|f| f.level == level).count()
+    }
+}
+
+/// 按启动参数检查运行环境和配置，输出发现的问题和处理建议，有错误时返回Err
+pub fn run(args: &StartArgs) -> Result<(), String> {
+    let mut report = Report::default();
+    check_config(args, &mut report);
+    check_network(args, &mut report);
+    check_ports(args, &mut report);
+    check_udp_buffer(&mut report);
+    check_clock(&mut report);
+    check_key(&mut report);
+    for finding in &report.findings {
+        println!("{}", finding);
+    }
+    let errors = report.count(Level::Error);
+    let warnings = report.count(Level::Warn);
+    println!();
+    if errors > 0 {
+        return Err(format!("发现{}个错误,{}个警告", errors, warnings));
+    }
+    println!("没有发现错误,{}个警告", warnings);
+    Ok(())
+}
+
+/// 用启动时相同的方式解析参数
+fn check_config(args: &StartArgs, report: &mut Report) {
+    let results = [
+        ("日志参数", LogOptions::parse(args).map(|_| ())),
+        ("组策略参数", parse_group_policy(args).map(|_| ())),
+        ("告警参数", parse_alert(args).map(|_| ())),
+        ("ddns参数", parse_ddns(args).map(|_| ())),
+        ("密钥参数", parse_rsa(args).map(|_| ())),
+        ("运行时参数", parse_runtime(args).map(|_| ())),
+    ];
+    let mut failed = false;
+    for (item, result) in results {
+        if let Err(e) = result {
+            report.error(item, e);
+            failed = true;
+        }
+    }
+    if args.handshake_proof && args.white_token.is_none() {
+        report.error("握手参数", "handshake-proof需要同时配置white-token");
+        failed = true;
+    }
+    if !failed {
+        report.ok("启动参数", "解析成功");
+    }
+}
+
+/// 检查网关和子网掩码，和启动时的计算方式相同
+fn check_network(args: &StartArgs, report: &mut Report) {
+    let gateway = match args.gateway.as_deref().map(str::parse::<Ipv4Addr>) {
+        None => GATEWAY,
+        Some(Ok(gateway)) => gateway,
+        Some(Err(e)) => {
+            report.error("网关", format!("必须为有效的ipv4地址 {}", e));
+            return;
+        }
+    };
+    let netmask = match args.netmask.as_deref().map(str::parse::<Ipv4Addr>) {
+        None => NETMASK,
+        Some(Ok(netmask)) => netmask,
+        Some(Err(e)) => {
+            report.error("子网掩码", format!("必须为有效的ipv4地址 {}", e));
+            return;
+        }
+    };
+    if gateway.is_unspecified() || gateway.is_broadcast() || gateway.is_multicast() {
+        report.error("网关", format!("{}不能作为网关", gateway));
+        return;
+    }
+    let broadcast = match check_subnet(gateway, netmask) {
+        Ok(broadcast) => broadcast,
+        Err(e) => {
+            report.error("网段", e);
+            return;
+        }
+    };
+    let mask = u32::from(netmask);
+    let network = Ipv4Addr::from(u32::from(gateway) & mask);
+    if !gateway.is_private() {
+        report.warn(
+            "网关",
+            format!("{}不是私有地址，可能和公网ip冲突，建议使用10.0.0.0/8、172.16.0.0/12或192.168.0.0/16内的地址", gateway),
+        );
+    }
+    report.ok(
+        "网段",
+        format!(
+            "{}/{} 网关{} 广播地址{} 除网关外可分配{}个ip",
+            network,
+            mask.count_ones(),
+            gateway,
+            broadcast,
+            !mask - 3
+        ),
+    );
+}
+
+/// 尝试绑定服务使用的端口
+fn check_ports(args: &StartArgs, report: &mut Report) {
+    let port = args.port.unwrap_or(29872);
+    let mut ports = vec![("udp", port, true), ("tcp", port, false)];
+    if let Some(nat_probe_port) = args.nat_probe_port {
+        if nat_probe_port == port {
+            report.error("端口", "nat-probe-port不能和port相同");
+        } else {
+            ports.push(("nat探测udp", nat_probe_port, true));
+        }
+    }
+    #[cfg(feature = "web")]
+    {
+        let web_port = args.web_port.unwrap_or(29870);
+        if web_port == port {
+            report.error("端口", "web-port不能和port相同");
+        } else if web_port != 0 {
+            ports.push(("web tcp", web_port, false));
+        }
+    }
+    for (name, port, udp) in ports {
+        let rs = if udp {
+            create_udp(port).map(|_| ())
+        } else {
+            create_tcp(port).map(|_| ())
+        };
+        match rs {
+            Ok(()) => report.ok("端口", format!("{} {} 可以绑定", name, port)),
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => report.error(
+                "端口",
+                format!(
+                    "{} {} 已被占用，如果vnts正在运行可以忽略，否则换一个端口",
+                    name, port
+                ),
+            ),
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => report.error(
+                "端口",
+                format!(
+                    "{} {} 没有权限绑定，1024以下的端口需要root权限或CAP_NET_BIND_SERVICE",
+                    name, port
+                ),
+            ),
+            Err(e) => report.error("端口", format!("{} {} 绑定失败 {}", name, port, e)),
+        }
+    }
+}
+
+/// 检查系统允许的udp缓冲区上限，只在linux上检查
+fn check_udp_buffer(report: &mut Report) {
+    if !cfg!(target_os = "linux") {
+        return;
+    }
+    for (name, sysctl) in [("接收", "rmem_max"), ("发送", "wmem_max")] {
+        let path = format!("/proc/sys/net/core/{}", sysctl);
+        let value = match std::fs::read_to_string(&path) {
+            Ok(value) => value,
+            Err(e) => {
+                report.warn("udp缓冲区", format!("读取{}失败 {}", path, e));
+                continue;
+            }
+        };
+        match value.trim().parse::<u64>() {
+            Ok(size) if size < RECOMMENDED_UDP_BUFFER => report.warn(
+                "udp缓冲区",
+                format!(
+                    "{}缓冲区上限{}字节，中继流量较大时可能丢包，建议执行 sysctl -w net.core.{}={}",
+                    name, size, sysctl, RECOMMENDED_UDP_BUFFER
+                ),
+            ),
+            Ok(size) => report.ok("udp缓冲区", format!("{}缓冲区上限{}字节", name, size)),
+            Err(_) => report.warn("udp缓冲区", format!("无法解析{} '{}'", path, value.trim())),
+        }
+    }
+}
+
+/// 启动时检查系统时间，明显没有同步时提示，不影响启动
+pub fn check_system_clock() {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or(0);
+    if now < MIN_SANE_TIME {
+        log::warn!("系统时间明显不正确，设备时钟偏差的估计和过期判断会异常，请开启ntp同步");
+        println!("warning: 系统时间明显不正确，请开启ntp同步，可以用'vnts doctor'检查");
+    }
+}
+
+/// 检查系统时间，时间不准会导致日志、过期判断和客户端的时间戳校验异常
+fn check_clock(report: &mut Report) {
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(now) => now,
+        Err(_) => {
+            report.error("系统时间", "早于1970年，请同步系统时间");
+            return;
+        }
+    };
+    if now.as_secs() < MIN_SANE_TIME {
+        report.error(
+            "系统时间",
+            format!(
+                "{}明显不正确，请开启ntp同步",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+            ),
+        );
+        return;
+    }
+    // 密钥文件的修改时间晚于当前时间说明系统时间曾经被调回
+    let key_path = app_root().join("key/private_key.pem");
+    if let Ok(modified) = std::fs::metadata(&key_path).and_then(|m| m.modified()) {
+        if let Ok(ahead) = modified.duration_since(SystemTime::now()) {
+            if ahead > Duration::from_secs(60) {
+                report.warn(
+                    "系统时间",
+                    format!(
+                        "密钥文件的修改时间比当前时间晚{}秒，系统时间可能被调回过，请开启ntp同步",
+                        ahead.as_secs()
+                    ),
+                );
+                return;
+            }
+        }
+    }
+    report.ok(
+        "系统时间",
+        chrono::Local::now()
+            .format("%Y-%m-%d %H:%M:%S %:z")
+            .to_string(),
+    );
+}
+
+/// 检查已有的密钥文件，不会生成新密钥
+fn check_key(report: &mut Report) {
+    match RsaCipher::inspect(&app_root()) {
+        Ok(None) => report.ok("密钥", "没有密钥文件，首次启动时生成"),
+        Ok(Some((bits, _))) if bits < MIN_SAFE_BITS => report.warn(
+            "密钥",
+            format!(
+                "rsa密钥长度{}位不安全，建议至少{}位，删除'key/'目录后重启可以重新生成，客户端需要重新确认指纹",
+                bits, MIN_SAFE_BITS
+            ),
+        ),
+        Ok(Some((bits, finger))) => report.ok("密钥", format!("rsa {}位 指纹 {}", bits, finger)),
+        Err(e) => report.error(
+            "密钥",
+            format!("{}，可以删除'key/'目录后重启重新生成，客户端需要重新确认指纹", e),
+        ),
+    }
+}
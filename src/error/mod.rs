@@ -25,6 +25,18 @@ pub enum Error {
     IpAlreadyExists,
     #[error("Invalid Ip")]
     InvalidIp,
+    #[error("Encryption Required")]
+    EncryptionRequired(&'static str),
+    #[error("Group Full")]
+    GroupFull,
+    #[error("Source Limit")]
+    SourceLimit,
+    #[error("Invalid Request")]
+    InvalidRequest(&'static str),
+    #[error("Peer Offline")]
+    PeerOffline,
+    #[error("Maintenance")]
+    Maintenance,
     #[error("Other")]
     Other(String),
 }
@@ -0,0 +1 @@
+pub const SERIAL_NUMBER: &str = "2610151421-439";
\ No newline at end of file
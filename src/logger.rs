@@ -0,0 +1,358 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use log::LevelFilter;
+use log4rs::config::{Logger, RawConfig, Root};
+use parking_lot::Mutex;
+
+use crate::core::parse_bytes;
+use crate::StartArgs;
+
+/// 日志文件轮转方式
+#[derive(Debug)]
+enum LogRotate {
+    // 文件大小，字节
+    Size(u64),
+    // 时间间隔，例如 1 day
+    Time(&'static str),
+}
+
+/// 命令行指定的日志配置，代替log4rs.yaml
+#[derive(Debug)]
+pub struct LogOptions {
+    rotate: LogRotate,
+    count: u32,
+    compress: bool,
+    file_level: LevelFilter,
+    console_level: LevelFilter,
+}
+
+impl LogOptions {
+    /// 没有指定任何日志参数时返回None，使用log4rs.yaml
+    pub fn parse(args: &StartArgs) -> Result<Option<LogOptions>, String> {
+        if args.log_rotate.is_none()
+            && args.log_count.is_none()
+            && !args.log_compress
+            && args.log_level.is_none()
+            && args.console_log_level.is_none()
+        {
+            return Ok(None);
+        }
+        let rotate = match args.log_rotate.as_deref() {
+            None => LogRotate::Size(10 * 1024 * 1024),
+            Some("hour") => LogRotate::Time("1 hour"),
+            Some("day") => LogRotate::Time("1 day"),
+            Some("week") => LogRotate::Time("1 week"),
+            Some(size) => {
+                LogRotate::Size(parse_bytes(size).map_err(|e| format!("log-rotate参数错误 {}", e))?)
+            }
+        };
+        let level = |level: &Option<String>, default| match level {
+            None => Ok(default),
+            Some(level) => parse_level(level),
+        };
+        Ok(Some(LogOptions {
+            rotate,
+            count: args.log_count.unwrap_or(5).max(1),
+            compress: args.log_compress,
+            file_level: level(&args.log_level, LevelFilter::Info)
+                .map_err(|e| format!("log-level参数错误 {}", e))?,
+            console_level: level(&args.console_log_level, LevelFilter::Off)
+                .map_err(|e| format!("console-log-level参数错误 {}", e))?,
+        }))
+    }
+    /// 生成log4rs配置，file_level是日志文件的级别
+    fn yaml(&self, log_path: &str, file_level: LevelFilter) -> String {
+        let trigger = match self.rotate {
+            LogRotate::Size(size) => format!("kind: size\n        limit: {}", size),
+            LogRotate::Time(interval) => format!("kind: time\n        interval: {}", interval),
+        };
+        let suffix = if self.compress { ".gz" } else { "" };
+        let mut appenders = vec!["rolling_file"];
+        let mut c = format!(
+            "appenders:
+  rolling_file:
+    kind: rolling_file
+    path: {}/vnts.log
+    append: true
+    encoder:
+      pattern: \"{{d}} [{{f}}:{{L}}] {{h({{l}})}} {{M}}:{{m}}{{n}}\"
+    filters:
+      - kind: threshold
+        level: {}
+    policy:
+      kind: compound
+      trigger:
+        {}
+      roller:
+        kind: fixed_window
+        pattern: {}/vnts.{{}}.log{}
+        base: 1
+        count: {}
+",
+            log_path,
+            level_str(file_level),
+            trigger,
+            log_path,
+            suffix,
+            self.count
+        );
+        if self.console_level != LevelFilter::Off {
+            appenders.push("console");
+            c.push_str(&format!(
+                "  console:
+    kind: console
+    encoder:
+      pattern: \"{{d}} {{h({{l}})}} {{M}}:{{m}}{{n}}\"
+    filters:
+      - kind: threshold
+        level: {}
+",
+                level_str(self.console_level)
+            ));
+        }
+        c.push_str(&format!(
+            "root:
+  level: {}
+  appenders: [{}]
+",
+            level_str(file_level.max(self.console_level)),
+            appenders.join(", ")
+        ));
+        c
+    }
+}
+
+fn level_str(level: LevelFilter) -> String {
+    level.as_str().to_lowercase()
+}
+
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level).map_err(|e| format!("'{}' {}", level, e))
+}
+
+/// 日志配置的来源
+enum LogSource {
+    // log4rs.yaml
+    File(PathBuf),
+    // 命令行参数
+    Options {
+        log_path: String,
+        options: LogOptions,
+    },
+}
+
+/// 运行中调整的日志级别
+#[derive(Clone, Debug, Default)]
+pub struct LogOverrides {
+    // 全局级别
+    pub root: Option<LevelFilter>,
+    // 模块 -> 级别，例如 vnts::core::service::client
+    pub modules: BTreeMap<String, LevelFilter>,
+}
+
+/// 运行中调整日志级别，不需要重启
+pub struct LogControl {
+    handle: log4rs::Handle,
+    source: LogSource,
+    overrides: Mutex<LogOverrides>,
+}
+
+impl Debug for LogControl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogControl")
+            .field("overrides", &*self.overrides.lock())
+            .finish()
+    }
+}
+
+impl LogControl {
+    fn new(source: LogSource) -> Result<LogControl, String> {
+        let config = build_config(&source, &LogOverrides::default())?;
+        let handle = log4rs::init_config(config).map_err(|e| e.to_string())?;
+        Ok(LogControl {
+            handle,
+            source,
+            overrides: Mutex::new(LogOverrides::default()),
+        })
+    }
+    /// 设置全局或者模块的级别，level为None时恢复配置文件中的级别
+    #[cfg(feature = "web")]
+    pub fn set_level(
+        &self,
+        module: Option<&str>,
+        level: Option<LevelFilter>,
+    ) -> Result<(), String> {
+        let mut overrides = self.overrides.lock();
+        let mut new_overrides = overrides.clone();
+        match (module, level) {
+            (None, level) => new_overrides.root = level,
+            (Some(module), Some(level)) => {
+                new_overrides.modules.insert(module.to_string(), level);
+            }
+            (Some(module), None) => {
+                new_overrides.modules.remove(module);
+            }
+        }
+        self.handle
+            .set_config(build_config(&self.source, &new_overrides)?);
+        log::warn!("调整日志级别 {:?}", new_overrides);
+        *overrides = new_overrides;
+        Ok(())
+    }
+    #[cfg(feature = "web")]
+    pub fn overrides(&self) -> LogOverrides {
+        self.overrides.lock().clone()
+    }
+    /// 配置文件修改后重新加载，保留运行中调整的级别
+    async fn reload_task(self: Arc<Self>, path: PathBuf, refresh_rate: Duration) {
+        let mut modified = modified_time(&path);
+        loop {
+            tokio::time::sleep(refresh_rate).await;
+            let time = modified_time(&path);
+            if time == modified {
+                continue;
+            }
+            modified = time;
+            let overrides = self.overrides.lock();
+            match build_config(&self.source, &overrides) {
+                Ok(config) => self.handle.set_config(config),
+                Err(e) => log::error!("重新加载日志配置失败 {:?},{}", path, e),
+            }
+        }
+    }
+}
+
+fn modified_time(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 生成log4rs配置，并加上运行中调整的级别
+fn build_config(source: &LogSource, overrides: &LogOverrides) -> Result<log4rs::Config, String> {
+    let yaml = match source {
+        LogSource::File(path) => std::fs::read_to_string(path).map_err(|e| e.to_string())?,
+        LogSource::Options { log_path, options } => {
+            // 调整后的级别比日志文件的级别更详细时，放开日志文件的过滤
+            let file_level = overrides
+                .modules
+                .values()
+                .chain(overrides.root.iter())
+                .fold(options.file_level, |a, b| a.max(*b));
+            options.yaml(log_path, file_level)
+        }
+    };
+    let raw: RawConfig = serde_yaml::from_str(&yaml).map_err(|e| e.to_string())?;
+    let (appenders, errors) = raw.appenders_lossy(&Default::default());
+    if !errors.is_empty() {
+        return Err(format!("{:?}", errors));
+    }
+    let root = raw.root();
+    let root = match overrides.root {
+        Some(level) => Root::builder()
+            .appenders(root.appenders().iter().cloned())
+            .build(level),
+        None => root,
+    };
+    let mut loggers = raw.loggers();
+    loggers.retain(|logger| !overrides.modules.contains_key(logger.name()));
+    for (module, level) in &overrides.modules {
+        loggers.push(Logger::builder().build(module, *level));
+    }
+    log4rs::Config::builder()
+        .appenders(appenders)
+        .loggers(loggers)
+        .build(root)
+        .map_err(|e| e.to_string())
+}
+
+/// 初始化日志，返回日志目录和日志控制，不输出日志时返回None
+pub fn log_init(
+    root_path: PathBuf,
+    log_path: Option<String>,
+    options: Option<LogOptions>,
+) -> Option<(PathBuf, Option<Arc<LogControl>>)> {
+    let log_path = match log_path {
+        None => root_path.join("log"),
+        Some(log_path) => {
+            if &log_path == "/dev/null" {
+                return None;
+            }
+            PathBuf::from(log_path)
+        }
+    };
+    if !log_path.exists() {
+        let _ = std::fs::create_dir(&log_path);
+    }
+    if let Some(options) = options {
+        let source = LogSource::Options {
+            log_path: log_path.to_str().unwrap().to_string(),
+            options,
+        };
+        return match LogControl::new(source) {
+            Ok(control) => Some((log_path, Some(Arc::new(control)))),
+            Err(e) => {
+                println!("日志初始化失败 {}", e);
+                Some((log_path, None))
+            }
+        };
+    }
+
+    let log_config = log_path.join("log4rs.yaml");
+    if !log_config.exists() {
+        if let Ok(mut f) = std::fs::File::create(&log_config) {
+            let log_path = log_path.to_str().unwrap();
+            let c = format!(
+                "refresh_rate: 30 seconds
+appenders:
+  rolling_file:
+    kind: rolling_file
+    path: {}/vnts.log
+    append: true
+    encoder:
+      pattern: \"{{d}} [{{f}}:{{L}}] {{h({{l}})}} {{M}}:{{m}}{{n}}\"
+    policy:
+      kind: compound
+      trigger:
+        kind: size
+        limit: 10 mb
+      roller:
+        kind: fixed_window
+        pattern: {}/vnts.{{}}.log
+        base: 1
+        count: 5
+
+root:
+  level: info
+  appenders:
+    - rolling_file",
+                log_path, log_path
+            );
+            let _ = f.write_all(c.as_bytes());
+        }
+    }
+    let refresh_rate = std::fs::read_to_string(&log_config)
+        .ok()
+        .and_then(|c| serde_yaml::from_str::<RawConfig>(&c).ok())
+        .and_then(|raw| raw.refresh_rate());
+    match LogControl::new(LogSource::File(log_config.clone())) {
+        Ok(control) => {
+            let control = Arc::new(control);
+            if let Some(refresh_rate) = refresh_rate {
+                crate::core::task::spawn(
+                    "log reload",
+                    control.clone().reload_task(log_config, refresh_rate),
+                );
+            }
+            Some((log_path, Some(control)))
+        }
+        Err(e) => {
+            println!("日志初始化失败 {:?},{}", log_config, e);
+            Some((log_path, None))
+        }
+    }
+}
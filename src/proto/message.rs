@@ -0,0 +1,5352 @@
+// This file is generated by rust-protobuf 3.4.0. Do not edit
+// .proto file is parsed by pure
+// @generated
+
+// https://github.com/rust-lang/rust-clippy/issues/702
+#![allow(unknown_lints)]
+#![allow(clippy::all)]
+
+#![allow(unused_attributes)]
+#![cfg_attr(rustfmt, rustfmt::skip)]
+
+#![allow(box_pointers)]
+#![allow(dead_code)]
+#![allow(missing_docs)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(trivial_casts)]
+#![allow(unused_results)]
+#![allow(unused_mut)]
+
+//! Generated file from `message.proto`
+
+/// Generated files are compatible only with the same version
+/// of protobuf runtime.
+const _PROTOBUF_VERSION_CHECK: () = ::protobuf::VERSION_3_4_0;
+
+// @@protoc_insertion_point(message:HandshakeRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct HandshakeRequest {
+    // message fields
+    // @@protoc_insertion_point(field:HandshakeRequest.version)
+    pub version: ::std::string::String,
+    // @@protoc_insertion_point(field:HandshakeRequest.secret)
+    pub secret: bool,
+    // @@protoc_insertion_point(field:HandshakeRequest.key_finger)
+    pub key_finger: ::std::string::String,
+    // @@protoc_insertion_point(field:HandshakeRequest.token_proof)
+    pub token_proof: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:HandshakeRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a HandshakeRequest {
+    fn default() -> &'a HandshakeRequest {
+        <HandshakeRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl HandshakeRequest {
+    pub fn new() -> HandshakeRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "version",
+            |m: &HandshakeRequest| { &m.version },
+            |m: &mut HandshakeRequest| { &mut m.version },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "secret",
+            |m: &HandshakeRequest| { &m.secret },
+            |m: &mut HandshakeRequest| { &mut m.secret },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "key_finger",
+            |m: &HandshakeRequest| { &m.key_finger },
+            |m: &mut HandshakeRequest| { &mut m.key_finger },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "token_proof",
+            |m: &HandshakeRequest| { &m.token_proof },
+            |m: &mut HandshakeRequest| { &mut m.token_proof },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<HandshakeRequest>(
+            "HandshakeRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for HandshakeRequest {
+    const NAME: &'static str = "HandshakeRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.version = is.read_string()?;
+                },
+                16 => {
+                    self.secret = is.read_bool()?;
+                },
+                26 => {
+                    self.key_finger = is.read_string()?;
+                },
+                34 => {
+                    self.token_proof = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.version.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.version);
+        }
+        if self.secret != false {
+            my_size += 1 + 1;
+        }
+        if !self.key_finger.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.key_finger);
+        }
+        if !self.token_proof.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(4, &self.token_proof);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.version.is_empty() {
+            os.write_string(1, &self.version)?;
+        }
+        if self.secret != false {
+            os.write_bool(2, self.secret)?;
+        }
+        if !self.key_finger.is_empty() {
+            os.write_string(3, &self.key_finger)?;
+        }
+        if !self.token_proof.is_empty() {
+            os.write_bytes(4, &self.token_proof)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> HandshakeRequest {
+        HandshakeRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.version.clear();
+        self.secret = false;
+        self.key_finger.clear();
+        self.token_proof.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static HandshakeRequest {
+        static instance: HandshakeRequest = HandshakeRequest {
+            version: ::std::string::String::new(),
+            secret: false,
+            key_finger: ::std::string::String::new(),
+            token_proof: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for HandshakeRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("HandshakeRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for HandshakeRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for HandshakeRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:HandshakeResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct HandshakeResponse {
+    // message fields
+    // @@protoc_insertion_point(field:HandshakeResponse.version)
+    pub version: ::std::string::String,
+    // @@protoc_insertion_point(field:HandshakeResponse.secret)
+    pub secret: bool,
+    // @@protoc_insertion_point(field:HandshakeResponse.public_key)
+    pub public_key: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:HandshakeResponse.key_finger)
+    pub key_finger: ::std::string::String,
+    // @@protoc_insertion_point(field:HandshakeResponse.nat_probe_port)
+    pub nat_probe_port: u32,
+    // @@protoc_insertion_point(field:HandshakeResponse.server_public_ip)
+    pub server_public_ip: u32,
+    // @@protoc_insertion_point(field:HandshakeResponse.server_public_ipv6)
+    pub server_public_ipv6: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:HandshakeResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a HandshakeResponse {
+    fn default() -> &'a HandshakeResponse {
+        <HandshakeResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl HandshakeResponse {
+    pub fn new() -> HandshakeResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(7);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "version",
+            |m: &HandshakeResponse| { &m.version },
+            |m: &mut HandshakeResponse| { &mut m.version },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "secret",
+            |m: &HandshakeResponse| { &m.secret },
+            |m: &mut HandshakeResponse| { &mut m.secret },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "public_key",
+            |m: &HandshakeResponse| { &m.public_key },
+            |m: &mut HandshakeResponse| { &mut m.public_key },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "key_finger",
+            |m: &HandshakeResponse| { &m.key_finger },
+            |m: &mut HandshakeResponse| { &mut m.key_finger },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "nat_probe_port",
+            |m: &HandshakeResponse| { &m.nat_probe_port },
+            |m: &mut HandshakeResponse| { &mut m.nat_probe_port },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "server_public_ip",
+            |m: &HandshakeResponse| { &m.server_public_ip },
+            |m: &mut HandshakeResponse| { &mut m.server_public_ip },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "server_public_ipv6",
+            |m: &HandshakeResponse| { &m.server_public_ipv6 },
+            |m: &mut HandshakeResponse| { &mut m.server_public_ipv6 },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<HandshakeResponse>(
+            "HandshakeResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for HandshakeResponse {
+    const NAME: &'static str = "HandshakeResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.version = is.read_string()?;
+                },
+                16 => {
+                    self.secret = is.read_bool()?;
+                },
+                26 => {
+                    self.public_key = is.read_bytes()?;
+                },
+                34 => {
+                    self.key_finger = is.read_string()?;
+                },
+                40 => {
+                    self.nat_probe_port = is.read_uint32()?;
+                },
+                53 => {
+                    self.server_public_ip = is.read_fixed32()?;
+                },
+                58 => {
+                    self.server_public_ipv6 = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.version.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.version);
+        }
+        if self.secret != false {
+            my_size += 1 + 1;
+        }
+        if !self.public_key.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(3, &self.public_key);
+        }
+        if !self.key_finger.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.key_finger);
+        }
+        if self.nat_probe_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(5, self.nat_probe_port);
+        }
+        if self.server_public_ip != 0 {
+            my_size += 1 + 4;
+        }
+        if !self.server_public_ipv6.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(7, &self.server_public_ipv6);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.version.is_empty() {
+            os.write_string(1, &self.version)?;
+        }
+        if self.secret != false {
+            os.write_bool(2, self.secret)?;
+        }
+        if !self.public_key.is_empty() {
+            os.write_bytes(3, &self.public_key)?;
+        }
+        if !self.key_finger.is_empty() {
+            os.write_string(4, &self.key_finger)?;
+        }
+        if self.nat_probe_port != 0 {
+            os.write_uint32(5, self.nat_probe_port)?;
+        }
+        if self.server_public_ip != 0 {
+            os.write_fixed32(6, self.server_public_ip)?;
+        }
+        if !self.server_public_ipv6.is_empty() {
+            os.write_bytes(7, &self.server_public_ipv6)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> HandshakeResponse {
+        HandshakeResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.version.clear();
+        self.secret = false;
+        self.public_key.clear();
+        self.key_finger.clear();
+        self.nat_probe_port = 0;
+        self.server_public_ip = 0;
+        self.server_public_ipv6.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static HandshakeResponse {
+        static instance: HandshakeResponse = HandshakeResponse {
+            version: ::std::string::String::new(),
+            secret: false,
+            public_key: ::std::vec::Vec::new(),
+            key_finger: ::std::string::String::new(),
+            nat_probe_port: 0,
+            server_public_ip: 0,
+            server_public_ipv6: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for HandshakeResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("HandshakeResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for HandshakeResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for HandshakeResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:SecretHandshakeRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct SecretHandshakeRequest {
+    // message fields
+    // @@protoc_insertion_point(field:SecretHandshakeRequest.token)
+    pub token: ::std::string::String,
+    // @@protoc_insertion_point(field:SecretHandshakeRequest.key)
+    pub key: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:SecretHandshakeRequest.counter_nonce)
+    pub counter_nonce: bool,
+    // special fields
+    // @@protoc_insertion_point(special_field:SecretHandshakeRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a SecretHandshakeRequest {
+    fn default() -> &'a SecretHandshakeRequest {
+        <SecretHandshakeRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SecretHandshakeRequest {
+    pub fn new() -> SecretHandshakeRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "token",
+            |m: &SecretHandshakeRequest| { &m.token },
+            |m: &mut SecretHandshakeRequest| { &mut m.token },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "key",
+            |m: &SecretHandshakeRequest| { &m.key },
+            |m: &mut SecretHandshakeRequest| { &mut m.key },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "counter_nonce",
+            |m: &SecretHandshakeRequest| { &m.counter_nonce },
+            |m: &mut SecretHandshakeRequest| { &mut m.counter_nonce },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<SecretHandshakeRequest>(
+            "SecretHandshakeRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for SecretHandshakeRequest {
+    const NAME: &'static str = "SecretHandshakeRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.token = is.read_string()?;
+                },
+                18 => {
+                    self.key = is.read_bytes()?;
+                },
+                24 => {
+                    self.counter_nonce = is.read_bool()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.token.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.token);
+        }
+        if !self.key.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(2, &self.key);
+        }
+        if self.counter_nonce != false {
+            my_size += 1 + 1;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.token.is_empty() {
+            os.write_string(1, &self.token)?;
+        }
+        if !self.key.is_empty() {
+            os.write_bytes(2, &self.key)?;
+        }
+        if self.counter_nonce != false {
+            os.write_bool(3, self.counter_nonce)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> SecretHandshakeRequest {
+        SecretHandshakeRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.token.clear();
+        self.key.clear();
+        self.counter_nonce = false;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static SecretHandshakeRequest {
+        static instance: SecretHandshakeRequest = SecretHandshakeRequest {
+            token: ::std::string::String::new(),
+            key: ::std::vec::Vec::new(),
+            counter_nonce: false,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for SecretHandshakeRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("SecretHandshakeRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for SecretHandshakeRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SecretHandshakeRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:RegistrationRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct RegistrationRequest {
+    // message fields
+    // @@protoc_insertion_point(field:RegistrationRequest.token)
+    pub token: ::std::string::String,
+    // @@protoc_insertion_point(field:RegistrationRequest.device_id)
+    pub device_id: ::std::string::String,
+    // @@protoc_insertion_point(field:RegistrationRequest.name)
+    pub name: ::std::string::String,
+    // @@protoc_insertion_point(field:RegistrationRequest.is_fast)
+    pub is_fast: bool,
+    // @@protoc_insertion_point(field:RegistrationRequest.version)
+    pub version: ::std::string::String,
+    // @@protoc_insertion_point(field:RegistrationRequest.virtual_ip)
+    pub virtual_ip: u32,
+    // @@protoc_insertion_point(field:RegistrationRequest.allow_ip_change)
+    pub allow_ip_change: bool,
+    // @@protoc_insertion_point(field:RegistrationRequest.client_secret)
+    pub client_secret: bool,
+    // @@protoc_insertion_point(field:RegistrationRequest.tags)
+    pub tags: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:RegistrationRequest.session_id)
+    pub session_id: bool,
+    // @@protoc_insertion_point(field:RegistrationRequest.sequence)
+    pub sequence: bool,
+    // @@protoc_insertion_point(field:RegistrationRequest.local_addresses)
+    pub local_addresses: ::std::vec::Vec<LocalAddress>,
+    // @@protoc_insertion_point(field:RegistrationRequest.local_port)
+    pub local_port: u32,
+    // special fields
+    // @@protoc_insertion_point(special_field:RegistrationRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RegistrationRequest {
+    fn default() -> &'a RegistrationRequest {
+        <RegistrationRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RegistrationRequest {
+    pub fn new() -> RegistrationRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(13);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "token",
+            |m: &RegistrationRequest| { &m.token },
+            |m: &mut RegistrationRequest| { &mut m.token },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "device_id",
+            |m: &RegistrationRequest| { &m.device_id },
+            |m: &mut RegistrationRequest| { &mut m.device_id },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "name",
+            |m: &RegistrationRequest| { &m.name },
+            |m: &mut RegistrationRequest| { &mut m.name },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "is_fast",
+            |m: &RegistrationRequest| { &m.is_fast },
+            |m: &mut RegistrationRequest| { &mut m.is_fast },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "version",
+            |m: &RegistrationRequest| { &m.version },
+            |m: &mut RegistrationRequest| { &mut m.version },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "virtual_ip",
+            |m: &RegistrationRequest| { &m.virtual_ip },
+            |m: &mut RegistrationRequest| { &mut m.virtual_ip },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "allow_ip_change",
+            |m: &RegistrationRequest| { &m.allow_ip_change },
+            |m: &mut RegistrationRequest| { &mut m.allow_ip_change },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "client_secret",
+            |m: &RegistrationRequest| { &m.client_secret },
+            |m: &mut RegistrationRequest| { &mut m.client_secret },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "tags",
+            |m: &RegistrationRequest| { &m.tags },
+            |m: &mut RegistrationRequest| { &mut m.tags },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "session_id",
+            |m: &RegistrationRequest| { &m.session_id },
+            |m: &mut RegistrationRequest| { &mut m.session_id },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "sequence",
+            |m: &RegistrationRequest| { &m.sequence },
+            |m: &mut RegistrationRequest| { &mut m.sequence },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "local_addresses",
+            |m: &RegistrationRequest| { &m.local_addresses },
+            |m: &mut RegistrationRequest| { &mut m.local_addresses },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "local_port",
+            |m: &RegistrationRequest| { &m.local_port },
+            |m: &mut RegistrationRequest| { &mut m.local_port },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RegistrationRequest>(
+            "RegistrationRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RegistrationRequest {
+    const NAME: &'static str = "RegistrationRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.token = is.read_string()?;
+                },
+                18 => {
+                    self.device_id = is.read_string()?;
+                },
+                26 => {
+                    self.name = is.read_string()?;
+                },
+                32 => {
+                    self.is_fast = is.read_bool()?;
+                },
+                42 => {
+                    self.version = is.read_string()?;
+                },
+                53 => {
+                    self.virtual_ip = is.read_fixed32()?;
+                },
+                56 => {
+                    self.allow_ip_change = is.read_bool()?;
+                },
+                64 => {
+                    self.client_secret = is.read_bool()?;
+                },
+                74 => {
+                    self.tags.push(is.read_string()?);
+                },
+                80 => {
+                    self.session_id = is.read_bool()?;
+                },
+                88 => {
+                    self.sequence = is.read_bool()?;
+                },
+                98 => {
+                    self.local_addresses.push(is.read_message()?);
+                },
+                104 => {
+                    self.local_port = is.read_uint32()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.token.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.token);
+        }
+        if !self.device_id.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.device_id);
+        }
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.name);
+        }
+        if self.is_fast != false {
+            my_size += 1 + 1;
+        }
+        if !self.version.is_empty() {
+            my_size += ::protobuf::rt::string_size(5, &self.version);
+        }
+        if self.virtual_ip != 0 {
+            my_size += 1 + 4;
+        }
+        if self.allow_ip_change != false {
+            my_size += 1 + 1;
+        }
+        if self.client_secret != false {
+            my_size += 1 + 1;
+        }
+        for value in &self.tags {
+            my_size += ::protobuf::rt::string_size(9, &value);
+        };
+        if self.session_id != false {
+            my_size += 1 + 1;
+        }
+        if self.sequence != false {
+            my_size += 1 + 1;
+        }
+        for value in &self.local_addresses {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        if self.local_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(13, self.local_port);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.token.is_empty() {
+            os.write_string(1, &self.token)?;
+        }
+        if !self.device_id.is_empty() {
+            os.write_string(2, &self.device_id)?;
+        }
+        if !self.name.is_empty() {
+            os.write_string(3, &self.name)?;
+        }
+        if self.is_fast != false {
+            os.write_bool(4, self.is_fast)?;
+        }
+        if !self.version.is_empty() {
+            os.write_string(5, &self.version)?;
+        }
+        if self.virtual_ip != 0 {
+            os.write_fixed32(6, self.virtual_ip)?;
+        }
+        if self.allow_ip_change != false {
+            os.write_bool(7, self.allow_ip_change)?;
+        }
+        if self.client_secret != false {
+            os.write_bool(8, self.client_secret)?;
+        }
+        for v in &self.tags {
+            os.write_string(9, &v)?;
+        };
+        if self.session_id != false {
+            os.write_bool(10, self.session_id)?;
+        }
+        if self.sequence != false {
+            os.write_bool(11, self.sequence)?;
+        }
+        for v in &self.local_addresses {
+            ::protobuf::rt::write_message_field_with_cached_size(12, v, os)?;
+        };
+        if self.local_port != 0 {
+            os.write_uint32(13, self.local_port)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RegistrationRequest {
+        RegistrationRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.token.clear();
+        self.device_id.clear();
+        self.name.clear();
+        self.is_fast = false;
+        self.version.clear();
+        self.virtual_ip = 0;
+        self.allow_ip_change = false;
+        self.client_secret = false;
+        self.tags.clear();
+        self.session_id = false;
+        self.sequence = false;
+        self.local_addresses.clear();
+        self.local_port = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RegistrationRequest {
+        static instance: RegistrationRequest = RegistrationRequest {
+            token: ::std::string::String::new(),
+            device_id: ::std::string::String::new(),
+            name: ::std::string::String::new(),
+            is_fast: false,
+            version: ::std::string::String::new(),
+            virtual_ip: 0,
+            allow_ip_change: false,
+            client_secret: false,
+            tags: ::std::vec::Vec::new(),
+            session_id: false,
+            sequence: false,
+            local_addresses: ::std::vec::Vec::new(),
+            local_port: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RegistrationRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RegistrationRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RegistrationRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RegistrationRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:LocalAddress)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct LocalAddress {
+    // message fields
+    // @@protoc_insertion_point(field:LocalAddress.ip)
+    pub ip: u32,
+    // @@protoc_insertion_point(field:LocalAddress.prefix_len)
+    pub prefix_len: u32,
+    // special fields
+    // @@protoc_insertion_point(special_field:LocalAddress.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a LocalAddress {
+    fn default() -> &'a LocalAddress {
+        <LocalAddress as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl LocalAddress {
+    pub fn new() -> LocalAddress {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "ip",
+            |m: &LocalAddress| { &m.ip },
+            |m: &mut LocalAddress| { &mut m.ip },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "prefix_len",
+            |m: &LocalAddress| { &m.prefix_len },
+            |m: &mut LocalAddress| { &mut m.prefix_len },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<LocalAddress>(
+            "LocalAddress",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for LocalAddress {
+    const NAME: &'static str = "LocalAddress";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                13 => {
+                    self.ip = is.read_fixed32()?;
+                },
+                16 => {
+                    self.prefix_len = is.read_uint32()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.ip != 0 {
+            my_size += 1 + 4;
+        }
+        if self.prefix_len != 0 {
+            my_size += ::protobuf::rt::uint32_size(2, self.prefix_len);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.ip != 0 {
+            os.write_fixed32(1, self.ip)?;
+        }
+        if self.prefix_len != 0 {
+            os.write_uint32(2, self.prefix_len)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> LocalAddress {
+        LocalAddress::new()
+    }
+
+    fn clear(&mut self) {
+        self.ip = 0;
+        self.prefix_len = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static LocalAddress {
+        static instance: LocalAddress = LocalAddress {
+            ip: 0,
+            prefix_len: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for LocalAddress {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("LocalAddress").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for LocalAddress {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for LocalAddress {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:LocalPathHint)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct LocalPathHint {
+    // message fields
+    // @@protoc_insertion_point(field:LocalPathHint.peer)
+    pub peer: u32,
+    // @@protoc_insertion_point(field:LocalPathHint.reason)
+    pub reason: ::protobuf::EnumOrUnknown<LocalPathReason>,
+    // @@protoc_insertion_point(field:LocalPathHint.peer_local_addresses)
+    pub peer_local_addresses: ::std::vec::Vec<LocalAddress>,
+    // @@protoc_insertion_point(field:LocalPathHint.peer_local_port)
+    pub peer_local_port: u32,
+    // @@protoc_insertion_point(field:LocalPathHint.no_hairpin)
+    pub no_hairpin: bool,
+    // special fields
+    // @@protoc_insertion_point(special_field:LocalPathHint.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a LocalPathHint {
+    fn default() -> &'a LocalPathHint {
+        <LocalPathHint as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl LocalPathHint {
+    pub fn new() -> LocalPathHint {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(5);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "peer",
+            |m: &LocalPathHint| { &m.peer },
+            |m: &mut LocalPathHint| { &mut m.peer },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "reason",
+            |m: &LocalPathHint| { &m.reason },
+            |m: &mut LocalPathHint| { &mut m.reason },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "peer_local_addresses",
+            |m: &LocalPathHint| { &m.peer_local_addresses },
+            |m: &mut LocalPathHint| { &mut m.peer_local_addresses },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "peer_local_port",
+            |m: &LocalPathHint| { &m.peer_local_port },
+            |m: &mut LocalPathHint| { &mut m.peer_local_port },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "no_hairpin",
+            |m: &LocalPathHint| { &m.no_hairpin },
+            |m: &mut LocalPathHint| { &mut m.no_hairpin },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<LocalPathHint>(
+            "LocalPathHint",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for LocalPathHint {
+    const NAME: &'static str = "LocalPathHint";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                13 => {
+                    self.peer = is.read_fixed32()?;
+                },
+                16 => {
+                    self.reason = is.read_enum_or_unknown()?;
+                },
+                26 => {
+                    self.peer_local_addresses.push(is.read_message()?);
+                },
+                32 => {
+                    self.peer_local_port = is.read_uint32()?;
+                },
+                40 => {
+                    self.no_hairpin = is.read_bool()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.peer != 0 {
+            my_size += 1 + 4;
+        }
+        if self.reason != ::protobuf::EnumOrUnknown::new(LocalPathReason::SameLan) {
+            my_size += ::protobuf::rt::int32_size(2, self.reason.value());
+        }
+        for value in &self.peer_local_addresses {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        if self.peer_local_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(4, self.peer_local_port);
+        }
+        if self.no_hairpin != false {
+            my_size += 1 + 1;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.peer != 0 {
+            os.write_fixed32(1, self.peer)?;
+        }
+        if self.reason != ::protobuf::EnumOrUnknown::new(LocalPathReason::SameLan) {
+            os.write_enum(2, ::protobuf::EnumOrUnknown::value(&self.reason))?;
+        }
+        for v in &self.peer_local_addresses {
+            ::protobuf::rt::write_message_field_with_cached_size(3, v, os)?;
+        };
+        if self.peer_local_port != 0 {
+            os.write_uint32(4, self.peer_local_port)?;
+        }
+        if self.no_hairpin != false {
+            os.write_bool(5, self.no_hairpin)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> LocalPathHint {
+        LocalPathHint::new()
+    }
+
+    fn clear(&mut self) {
+        self.peer = 0;
+        self.reason = ::protobuf::EnumOrUnknown::new(LocalPathReason::SameLan);
+        self.peer_local_addresses.clear();
+        self.peer_local_port = 0;
+        self.no_hairpin = false;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static LocalPathHint {
+        static instance: LocalPathHint = LocalPathHint {
+            peer: 0,
+            reason: ::protobuf::EnumOrUnknown::from_i32(0),
+            peer_local_addresses: ::std::vec::Vec::new(),
+            peer_local_port: 0,
+            no_hairpin: false,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for LocalPathHint {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("LocalPathHint").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for LocalPathHint {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for LocalPathHint {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:RegistrationResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct RegistrationResponse {
+    // message fields
+    // @@protoc_insertion_point(field:RegistrationResponse.virtual_ip)
+    pub virtual_ip: u32,
+    // @@protoc_insertion_point(field:RegistrationResponse.virtual_gateway)
+    pub virtual_gateway: u32,
+    // @@protoc_insertion_point(field:RegistrationResponse.virtual_netmask)
+    pub virtual_netmask: u32,
+    // @@protoc_insertion_point(field:RegistrationResponse.epoch)
+    pub epoch: u32,
+    // @@protoc_insertion_point(field:RegistrationResponse.device_info_list)
+    pub device_info_list: ::std::vec::Vec<DeviceInfo>,
+    // @@protoc_insertion_point(field:RegistrationResponse.public_ip)
+    pub public_ip: u32,
+    // @@protoc_insertion_point(field:RegistrationResponse.public_port)
+    pub public_port: u32,
+    // @@protoc_insertion_point(field:RegistrationResponse.public_ipv6)
+    pub public_ipv6: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:RegistrationResponse.ip_change_reason)
+    pub ip_change_reason: ::protobuf::EnumOrUnknown<IpChangeReason>,
+    // @@protoc_insertion_point(field:RegistrationResponse.server_public_ip)
+    pub server_public_ip: u32,
+    // @@protoc_insertion_point(field:RegistrationResponse.server_public_ipv6)
+    pub server_public_ipv6: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:RegistrationResponse.client_config)
+    pub client_config: ::protobuf::MessageField<ClientConfig>,
+    // @@protoc_insertion_point(field:RegistrationResponse.session_id)
+    pub session_id: u64,
+    // @@protoc_insertion_point(field:RegistrationResponse.sequence)
+    pub sequence: bool,
+    // special fields
+    // @@protoc_insertion_point(special_field:RegistrationResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RegistrationResponse {
+    fn default() -> &'a RegistrationResponse {
+        <RegistrationResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RegistrationResponse {
+    pub fn new() -> RegistrationResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(14);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "virtual_ip",
+            |m: &RegistrationResponse| { &m.virtual_ip },
+            |m: &mut RegistrationResponse| { &mut m.virtual_ip },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "virtual_gateway",
+            |m: &RegistrationResponse| { &m.virtual_gateway },
+            |m: &mut RegistrationResponse| { &mut m.virtual_gateway },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "virtual_netmask",
+            |m: &RegistrationResponse| { &m.virtual_netmask },
+            |m: &mut RegistrationResponse| { &mut m.virtual_netmask },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "epoch",
+            |m: &RegistrationResponse| { &m.epoch },
+            |m: &mut RegistrationResponse| { &mut m.epoch },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "device_info_list",
+            |m: &RegistrationResponse| { &m.device_info_list },
+            |m: &mut RegistrationResponse| { &mut m.device_info_list },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "public_ip",
+            |m: &RegistrationResponse| { &m.public_ip },
+            |m: &mut RegistrationResponse| { &mut m.public_ip },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "public_port",
+            |m: &RegistrationResponse| { &m.public_port },
+            |m: &mut RegistrationResponse| { &mut m.public_port },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "public_ipv6",
+            |m: &RegistrationResponse| { &m.public_ipv6 },
+            |m: &mut RegistrationResponse| { &mut m.public_ipv6 },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "ip_change_reason",
+            |m: &RegistrationResponse| { &m.ip_change_reason },
+            |m: &mut RegistrationResponse| { &mut m.ip_change_reason },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "server_public_ip",
+            |m: &RegistrationResponse| { &m.server_public_ip },
+            |m: &mut RegistrationResponse| { &mut m.server_public_ip },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "server_public_ipv6",
+            |m: &RegistrationResponse| { &m.server_public_ipv6 },
+            |m: &mut RegistrationResponse| { &mut m.server_public_ipv6 },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, ClientConfig>(
+            "client_config",
+            |m: &RegistrationResponse| { &m.client_config },
+            |m: &mut RegistrationResponse| { &mut m.client_config },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "session_id",
+            |m: &RegistrationResponse| { &m.session_id },
+            |m: &mut RegistrationResponse| { &mut m.session_id },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "sequence",
+            |m: &RegistrationResponse| { &m.sequence },
+            |m: &mut RegistrationResponse| { &mut m.sequence },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RegistrationResponse>(
+            "RegistrationResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RegistrationResponse {
+    const NAME: &'static str = "RegistrationResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                13 => {
+                    self.virtual_ip = is.read_fixed32()?;
+                },
+                21 => {
+                    self.virtual_gateway = is.read_fixed32()?;
+                },
+                29 => {
+                    self.virtual_netmask = is.read_fixed32()?;
+                },
+                32 => {
+                    self.epoch = is.read_uint32()?;
+                },
+                42 => {
+                    self.device_info_list.push(is.read_message()?);
+                },
+                53 => {
+                    self.public_ip = is.read_fixed32()?;
+                },
+                56 => {
+                    self.public_port = is.read_uint32()?;
+                },
+                66 => {
+                    self.public_ipv6 = is.read_bytes()?;
+                },
+                72 => {
+                    self.ip_change_reason = is.read_enum_or_unknown()?;
+                },
+                85 => {
+                    self.server_public_ip = is.read_fixed32()?;
+                },
+                90 => {
+                    self.server_public_ipv6 = is.read_bytes()?;
+                },
+                98 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.client_config)?;
+                },
+                105 => {
+                    self.session_id = is.read_fixed64()?;
+                },
+                112 => {
+                    self.sequence = is.read_bool()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.virtual_ip != 0 {
+            my_size += 1 + 4;
+        }
+        if self.virtual_gateway != 0 {
+            my_size += 1 + 4;
+        }
+        if self.virtual_netmask != 0 {
+            my_size += 1 + 4;
+        }
+        if self.epoch != 0 {
+            my_size += ::protobuf::rt::uint32_size(4, self.epoch);
+        }
+        for value in &self.device_info_list {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        if self.public_ip != 0 {
+            my_size += 1 + 4;
+        }
+        if self.public_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(7, self.public_port);
+        }
+        if !self.public_ipv6.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(8, &self.public_ipv6);
+        }
+        if self.ip_change_reason != ::protobuf::EnumOrUnknown::new(IpChangeReason::Unchanged) {
+            my_size += ::protobuf::rt::int32_size(9, self.ip_change_reason.value());
+        }
+        if self.server_public_ip != 0 {
+            my_size += 1 + 4;
+        }
+        if !self.server_public_ipv6.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(11, &self.server_public_ipv6);
+        }
+        if let Some(v) = self.client_config.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        if self.session_id != 0 {
+            my_size += 1 + 8;
+        }
+        if self.sequence != false {
+            my_size += 1 + 1;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.virtual_ip != 0 {
+            os.write_fixed32(1, self.virtual_ip)?;
+        }
+        if self.virtual_gateway != 0 {
+            os.write_fixed32(2, self.virtual_gateway)?;
+        }
+        if self.virtual_netmask != 0 {
+            os.write_fixed32(3, self.virtual_netmask)?;
+        }
+        if self.epoch != 0 {
+            os.write_uint32(4, self.epoch)?;
+        }
+        for v in &self.device_info_list {
+            ::protobuf::rt::write_message_field_with_cached_size(5, v, os)?;
+        };
+        if self.public_ip != 0 {
+            os.write_fixed32(6, self.public_ip)?;
+        }
+        if self.public_port != 0 {
+            os.write_uint32(7, self.public_port)?;
+        }
+        if !self.public_ipv6.is_empty() {
+            os.write_bytes(8, &self.public_ipv6)?;
+        }
+        if self.ip_change_reason != ::protobuf::EnumOrUnknown::new(IpChangeReason::Unchanged) {
+            os.write_enum(9, ::protobuf::EnumOrUnknown::value(&self.ip_change_reason))?;
+        }
+        if self.server_public_ip != 0 {
+            os.write_fixed32(10, self.server_public_ip)?;
+        }
+        if !self.server_public_ipv6.is_empty() {
+            os.write_bytes(11, &self.server_public_ipv6)?;
+        }
+        if let Some(v) = self.client_config.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(12, v, os)?;
+        }
+        if self.session_id != 0 {
+            os.write_fixed64(13, self.session_id)?;
+        }
+        if self.sequence != false {
+            os.write_bool(14, self.sequence)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RegistrationResponse {
+        RegistrationResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.virtual_ip = 0;
+        self.virtual_gateway = 0;
+        self.virtual_netmask = 0;
+        self.epoch = 0;
+        self.device_info_list.clear();
+        self.public_ip = 0;
+        self.public_port = 0;
+        self.public_ipv6.clear();
+        self.ip_change_reason = ::protobuf::EnumOrUnknown::new(IpChangeReason::Unchanged);
+        self.server_public_ip = 0;
+        self.server_public_ipv6.clear();
+        self.client_config.clear();
+        self.session_id = 0;
+        self.sequence = false;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RegistrationResponse {
+        static instance: RegistrationResponse = RegistrationResponse {
+            virtual_ip: 0,
+            virtual_gateway: 0,
+            virtual_netmask: 0,
+            epoch: 0,
+            device_info_list: ::std::vec::Vec::new(),
+            public_ip: 0,
+            public_port: 0,
+            public_ipv6: ::std::vec::Vec::new(),
+            ip_change_reason: ::protobuf::EnumOrUnknown::from_i32(0),
+            server_public_ip: 0,
+            server_public_ipv6: ::std::vec::Vec::new(),
+            client_config: ::protobuf::MessageField::none(),
+            session_id: 0,
+            sequence: false,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RegistrationResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RegistrationResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RegistrationResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RegistrationResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:ClientConfig)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ClientConfig {
+    // message fields
+    // @@protoc_insertion_point(field:ClientConfig.version)
+    pub version: i64,
+    // @@protoc_insertion_point(field:ClientConfig.values)
+    pub values: ::std::collections::HashMap<::std::string::String, ::std::string::String>,
+    // @@protoc_insertion_point(field:ClientConfig.data)
+    pub data: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:ClientConfig.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ClientConfig {
+    fn default() -> &'a ClientConfig {
+        <ClientConfig as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ClientConfig {
+    pub fn new() -> ClientConfig {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "version",
+            |m: &ClientConfig| { &m.version },
+            |m: &mut ClientConfig| { &mut m.version },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "values",
+            |m: &ClientConfig| { &m.values },
+            |m: &mut ClientConfig| { &mut m.values },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "data",
+            |m: &ClientConfig| { &m.data },
+            |m: &mut ClientConfig| { &mut m.data },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ClientConfig>(
+            "ClientConfig",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ClientConfig {
+    const NAME: &'static str = "ClientConfig";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.version = is.read_int64()?;
+                },
+                18 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            10 => key = is.read_string()?,
+                            18 => value = is.read_string()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.values.insert(key, value);
+                },
+                26 => {
+                    self.data = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.version != 0 {
+            my_size += ::protobuf::rt::int64_size(1, self.version);
+        }
+        for (k, v) in &self.values {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::string_size(1, &k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        if !self.data.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(3, &self.data);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.version != 0 {
+            os.write_int64(1, self.version)?;
+        }
+        for (k, v) in &self.values {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::string_size(1, &k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            os.write_raw_varint32(18)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_string(1, &k)?;
+            os.write_string(2, &v)?;
+        };
+        if !self.data.is_empty() {
+            os.write_bytes(3, &self.data)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ClientConfig {
+        ClientConfig::new()
+    }
+
+    fn clear(&mut self) {
+        self.version = 0;
+        self.values.clear();
+        self.data.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ClientConfig {
+        static instance: ::protobuf::rt::Lazy<ClientConfig> = ::protobuf::rt::Lazy::new();
+        instance.get(ClientConfig::new)
+    }
+}
+
+impl ::protobuf::MessageFull for ClientConfig {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ClientConfig").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ClientConfig {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ClientConfig {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:DeviceInfo)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct DeviceInfo {
+    // message fields
+    // @@protoc_insertion_point(field:DeviceInfo.name)
+    pub name: ::std::string::String,
+    // @@protoc_insertion_point(field:DeviceInfo.virtual_ip)
+    pub virtual_ip: u32,
+    // @@protoc_insertion_point(field:DeviceInfo.device_status)
+    pub device_status: u32,
+    // @@protoc_insertion_point(field:DeviceInfo.client_secret)
+    pub client_secret: bool,
+    // @@protoc_insertion_point(field:DeviceInfo.relay_cost)
+    pub relay_cost: u32,
+    // @@protoc_insertion_point(field:DeviceInfo.is_cone)
+    pub is_cone: bool,
+    // @@protoc_insertion_point(field:DeviceInfo.tags)
+    pub tags: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:DeviceInfo.local_addresses)
+    pub local_addresses: ::std::vec::Vec<LocalAddress>,
+    // @@protoc_insertion_point(field:DeviceInfo.local_port)
+    pub local_port: u32,
+    // special fields
+    // @@protoc_insertion_point(special_field:DeviceInfo.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a DeviceInfo {
+    fn default() -> &'a DeviceInfo {
+        <DeviceInfo as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl DeviceInfo {
+    pub fn new() -> DeviceInfo {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(9);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "name",
+            |m: &DeviceInfo| { &m.name },
+            |m: &mut DeviceInfo| { &mut m.name },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "virtual_ip",
+            |m: &DeviceInfo| { &m.virtual_ip },
+            |m: &mut DeviceInfo| { &mut m.virtual_ip },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "device_status",
+            |m: &DeviceInfo| { &m.device_status },
+            |m: &mut DeviceInfo| { &mut m.device_status },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "client_secret",
+            |m: &DeviceInfo| { &m.client_secret },
+            |m: &mut DeviceInfo| { &mut m.client_secret },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "relay_cost",
+            |m: &DeviceInfo| { &m.relay_cost },
+            |m: &mut DeviceInfo| { &mut m.relay_cost },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "is_cone",
+            |m: &DeviceInfo| { &m.is_cone },
+            |m: &mut DeviceInfo| { &mut m.is_cone },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "tags",
+            |m: &DeviceInfo| { &m.tags },
+            |m: &mut DeviceInfo| { &mut m.tags },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "local_addresses",
+            |m: &DeviceInfo| { &m.local_addresses },
+            |m: &mut DeviceInfo| { &mut m.local_addresses },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "local_port",
+            |m: &DeviceInfo| { &m.local_port },
+            |m: &mut DeviceInfo| { &mut m.local_port },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<DeviceInfo>(
+            "DeviceInfo",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for DeviceInfo {
+    const NAME: &'static str = "DeviceInfo";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.name = is.read_string()?;
+                },
+                21 => {
+                    self.virtual_ip = is.read_fixed32()?;
+                },
+                24 => {
+                    self.device_status = is.read_uint32()?;
+                },
+                32 => {
+                    self.client_secret = is.read_bool()?;
+                },
+                40 => {
+                    self.relay_cost = is.read_uint32()?;
+                },
+                48 => {
+                    self.is_cone = is.read_bool()?;
+                },
+                58 => {
+                    self.tags.push(is.read_string()?);
+                },
+                66 => {
+                    self.local_addresses.push(is.read_message()?);
+                },
+                72 => {
+                    self.local_port = is.read_uint32()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.name);
+        }
+        if self.virtual_ip != 0 {
+            my_size += 1 + 4;
+        }
+        if self.device_status != 0 {
+            my_size += ::protobuf::rt::uint32_size(3, self.device_status);
+        }
+        if self.client_secret != false {
+            my_size += 1 + 1;
+        }
+        if self.relay_cost != 0 {
+            my_size += ::protobuf::rt::uint32_size(5, self.relay_cost);
+        }
+        if self.is_cone != false {
+            my_size += 1 + 1;
+        }
+        for value in &self.tags {
+            my_size += ::protobuf::rt::string_size(7, &value);
+        };
+        for value in &self.local_addresses {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        if self.local_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(9, self.local_port);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.name.is_empty() {
+            os.write_string(1, &self.name)?;
+        }
+        if self.virtual_ip != 0 {
+            os.write_fixed32(2, self.virtual_ip)?;
+        }
+        if self.device_status != 0 {
+            os.write_uint32(3, self.device_status)?;
+        }
+        if self.client_secret != false {
+            os.write_bool(4, self.client_secret)?;
+        }
+        if self.relay_cost != 0 {
+            os.write_uint32(5, self.relay_cost)?;
+        }
+        if self.is_cone != false {
+            os.write_bool(6, self.is_cone)?;
+        }
+        for v in &self.tags {
+            os.write_string(7, &v)?;
+        };
+        for v in &self.local_addresses {
+            ::protobuf::rt::write_message_field_with_cached_size(8, v, os)?;
+        };
+        if self.local_port != 0 {
+            os.write_uint32(9, self.local_port)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> DeviceInfo {
+        DeviceInfo::new()
+    }
+
+    fn clear(&mut self) {
+        self.name.clear();
+        self.virtual_ip = 0;
+        self.device_status = 0;
+        self.client_secret = false;
+        self.relay_cost = 0;
+        self.is_cone = false;
+        self.tags.clear();
+        self.local_addresses.clear();
+        self.local_port = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static DeviceInfo {
+        static instance: DeviceInfo = DeviceInfo {
+            name: ::std::string::String::new(),
+            virtual_ip: 0,
+            device_status: 0,
+            client_secret: false,
+            relay_cost: 0,
+            is_cone: false,
+            tags: ::std::vec::Vec::new(),
+            local_addresses: ::std::vec::Vec::new(),
+            local_port: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for DeviceInfo {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("DeviceInfo").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for DeviceInfo {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for DeviceInfo {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:DeviceList)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct DeviceList {
+    // message fields
+    // @@protoc_insertion_point(field:DeviceList.epoch)
+    pub epoch: u32,
+    // @@protoc_insertion_point(field:DeviceList.device_info_list)
+    pub device_info_list: ::std::vec::Vec<DeviceInfo>,
+    // @@protoc_insertion_point(field:DeviceList.status_epoch)
+    pub status_epoch: u32,
+    // special fields
+    // @@protoc_insertion_point(special_field:DeviceList.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a DeviceList {
+    fn default() -> &'a DeviceList {
+        <DeviceList as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl DeviceList {
+    pub fn new() -> DeviceList {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "epoch",
+            |m: &DeviceList| { &m.epoch },
+            |m: &mut DeviceList| { &mut m.epoch },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "device_info_list",
+            |m: &DeviceList| { &m.device_info_list },
+            |m: &mut DeviceList| { &mut m.device_info_list },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "status_epoch",
+            |m: &DeviceList| { &m.status_epoch },
+            |m: &mut DeviceList| { &mut m.status_epoch },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<DeviceList>(
+            "DeviceList",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for DeviceList {
+    const NAME: &'static str = "DeviceList";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.epoch = is.read_uint32()?;
+                },
+                18 => {
+                    self.device_info_list.push(is.read_message()?);
+                },
+                24 => {
+                    self.status_epoch = is.read_uint32()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.epoch != 0 {
+            my_size += ::protobuf::rt::uint32_size(1, self.epoch);
+        }
+        for value in &self.device_info_list {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        if self.status_epoch != 0 {
+            my_size += ::protobuf::rt::uint32_size(3, self.status_epoch);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.epoch != 0 {
+            os.write_uint32(1, self.epoch)?;
+        }
+        for v in &self.device_info_list {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        };
+        if self.status_epoch != 0 {
+            os.write_uint32(3, self.status_epoch)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> DeviceList {
+        DeviceList::new()
+    }
+
+    fn clear(&mut self) {
+        self.epoch = 0;
+        self.device_info_list.clear();
+        self.status_epoch = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static DeviceList {
+        static instance: DeviceList = DeviceList {
+            epoch: 0,
+            device_info_list: ::std::vec::Vec::new(),
+            status_epoch: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for DeviceList {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("DeviceList").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for DeviceList {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for DeviceList {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:PunchInfo)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct PunchInfo {
+    // message fields
+    // @@protoc_insertion_point(field:PunchInfo.public_ip_list)
+    pub public_ip_list: ::std::vec::Vec<u32>,
+    // @@protoc_insertion_point(field:PunchInfo.public_port)
+    pub public_port: u32,
+    // @@protoc_insertion_point(field:PunchInfo.public_port_range)
+    pub public_port_range: u32,
+    // @@protoc_insertion_point(field:PunchInfo.nat_type)
+    pub nat_type: ::protobuf::EnumOrUnknown<PunchNatType>,
+    // @@protoc_insertion_point(field:PunchInfo.reply)
+    pub reply: bool,
+    // @@protoc_insertion_point(field:PunchInfo.local_ip)
+    pub local_ip: u32,
+    // @@protoc_insertion_point(field:PunchInfo.local_port)
+    pub local_port: u32,
+    // @@protoc_insertion_point(field:PunchInfo.ipv6)
+    pub ipv6: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:PunchInfo.ipv6_port)
+    pub ipv6_port: u32,
+    // @@protoc_insertion_point(field:PunchInfo.tcp_port)
+    pub tcp_port: u32,
+    // @@protoc_insertion_point(field:PunchInfo.udp_ports)
+    pub udp_ports: ::std::vec::Vec<u32>,
+    // @@protoc_insertion_point(field:PunchInfo.public_ports)
+    pub public_ports: ::std::vec::Vec<u32>,
+    // special fields
+    // @@protoc_insertion_point(special_field:PunchInfo.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a PunchInfo {
+    fn default() -> &'a PunchInfo {
+        <PunchInfo as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl PunchInfo {
+    pub fn new() -> PunchInfo {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(12);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "public_ip_list",
+            |m: &PunchInfo| { &m.public_ip_list },
+            |m: &mut PunchInfo| { &mut m.public_ip_list },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "public_port",
+            |m: &PunchInfo| { &m.public_port },
+            |m: &mut PunchInfo| { &mut m.public_port },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "public_port_range",
+            |m: &PunchInfo| { &m.public_port_range },
+            |m: &mut PunchInfo| { &mut m.public_port_range },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "nat_type",
+            |m: &PunchInfo| { &m.nat_type },
+            |m: &mut PunchInfo| { &mut m.nat_type },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "reply",
+            |m: &PunchInfo| { &m.reply },
+            |m: &mut PunchInfo| { &mut m.reply },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "local_ip",
+            |m: &PunchInfo| { &m.local_ip },
+            |m: &mut PunchInfo| { &mut m.local_ip },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "local_port",
+            |m: &PunchInfo| { &m.local_port },
+            |m: &mut PunchInfo| { &mut m.local_port },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "ipv6",
+            |m: &PunchInfo| { &m.ipv6 },
+            |m: &mut PunchInfo| { &mut m.ipv6 },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "ipv6_port",
+            |m: &PunchInfo| { &m.ipv6_port },
+            |m: &mut PunchInfo| { &mut m.ipv6_port },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "tcp_port",
+            |m: &PunchInfo| { &m.tcp_port },
+            |m: &mut PunchInfo| { &mut m.tcp_port },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "udp_ports",
+            |m: &PunchInfo| { &m.udp_ports },
+            |m: &mut PunchInfo| { &mut m.udp_ports },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "public_ports",
+            |m: &PunchInfo| { &m.public_ports },
+            |m: &mut PunchInfo| { &mut m.public_ports },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<PunchInfo>(
+            "PunchInfo",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for PunchInfo {
+    const NAME: &'static str = "PunchInfo";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                18 => {
+                    is.read_repeated_packed_fixed32_into(&mut self.public_ip_list)?;
+                },
+                21 => {
+                    self.public_ip_list.push(is.read_fixed32()?);
+                },
+                24 => {
+                    self.public_port = is.read_uint32()?;
+                },
+                32 => {
+                    self.public_port_range = is.read_uint32()?;
+                },
+                40 => {
+                    self.nat_type = is.read_enum_or_unknown()?;
+                },
+                48 => {
+                    self.reply = is.read_bool()?;
+                },
+                61 => {
+                    self.local_ip = is.read_fixed32()?;
+                },
+                64 => {
+                    self.local_port = is.read_uint32()?;
+                },
+                74 => {
+                    self.ipv6 = is.read_bytes()?;
+                },
+                80 => {
+                    self.ipv6_port = is.read_uint32()?;
+                },
+                88 => {
+                    self.tcp_port = is.read_uint32()?;
+                },
+                98 => {
+                    is.read_repeated_packed_uint32_into(&mut self.udp_ports)?;
+                },
+                96 => {
+                    self.udp_ports.push(is.read_uint32()?);
+                },
+                106 => {
+                    is.read_repeated_packed_uint32_into(&mut self.public_ports)?;
+                },
+                104 => {
+                    self.public_ports.push(is.read_uint32()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        my_size += 5 * self.public_ip_list.len() as u64;
+        if self.public_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(3, self.public_port);
+        }
+        if self.public_port_range != 0 {
+            my_size += ::protobuf::rt::uint32_size(4, self.public_port_range);
+        }
+        if self.nat_type != ::protobuf::EnumOrUnknown::new(PunchNatType::Symmetric) {
+            my_size += ::protobuf::rt::int32_size(5, self.nat_type.value());
+        }
+        if self.reply != false {
+            my_size += 1 + 1;
+        }
+        if self.local_ip != 0 {
+            my_size += 1 + 4;
+        }
+        if self.local_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(8, self.local_port);
+        }
+        if !self.ipv6.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(9, &self.ipv6);
+        }
+        if self.ipv6_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(10, self.ipv6_port);
+        }
+        if self.tcp_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(11, self.tcp_port);
+        }
+        for value in &self.udp_ports {
+            my_size += ::protobuf::rt::uint32_size(12, *value);
+        };
+        for value in &self.public_ports {
+            my_size += ::protobuf::rt::uint32_size(13, *value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.public_ip_list {
+            os.write_fixed32(2, *v)?;
+        };
+        if self.public_port != 0 {
+            os.write_uint32(3, self.public_port)?;
+        }
+        if self.public_port_range != 0 {
+            os.write_uint32(4, self.public_port_range)?;
+        }
+        if self.nat_type != ::protobuf::EnumOrUnknown::new(PunchNatType::Symmetric) {
+            os.write_enum(5, ::protobuf::EnumOrUnknown::value(&self.nat_type))?;
+        }
+        if self.reply != false {
+            os.write_bool(6, self.reply)?;
+        }
+        if self.local_ip != 0 {
+            os.write_fixed32(7, self.local_ip)?;
+        }
+        if self.local_port != 0 {
+            os.write_uint32(8, self.local_port)?;
+        }
+        if !self.ipv6.is_empty() {
+            os.write_bytes(9, &self.ipv6)?;
+        }
+        if self.ipv6_port != 0 {
+            os.write_uint32(10, self.ipv6_port)?;
+        }
+        if self.tcp_port != 0 {
+            os.write_uint32(11, self.tcp_port)?;
+        }
+        for v in &self.udp_ports {
+            os.write_uint32(12, *v)?;
+        };
+        for v in &self.public_ports {
+            os.write_uint32(13, *v)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> PunchInfo {
+        PunchInfo::new()
+    }
+
+    fn clear(&mut self) {
+        self.public_ip_list.clear();
+        self.public_port = 0;
+        self.public_port_range = 0;
+        self.nat_type = ::protobuf::EnumOrUnknown::new(PunchNatType::Symmetric);
+        self.reply = false;
+        self.local_ip = 0;
+        self.local_port = 0;
+        self.ipv6.clear();
+        self.ipv6_port = 0;
+        self.tcp_port = 0;
+        self.udp_ports.clear();
+        self.public_ports.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static PunchInfo {
+        static instance: PunchInfo = PunchInfo {
+            public_ip_list: ::std::vec::Vec::new(),
+            public_port: 0,
+            public_port_range: 0,
+            nat_type: ::protobuf::EnumOrUnknown::from_i32(0),
+            reply: false,
+            local_ip: 0,
+            local_port: 0,
+            ipv6: ::std::vec::Vec::new(),
+            ipv6_port: 0,
+            tcp_port: 0,
+            udp_ports: ::std::vec::Vec::new(),
+            public_ports: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for PunchInfo {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("PunchInfo").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for PunchInfo {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for PunchInfo {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:ClientStatusInfo)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ClientStatusInfo {
+    // message fields
+    // @@protoc_insertion_point(field:ClientStatusInfo.source)
+    pub source: u32,
+    // @@protoc_insertion_point(field:ClientStatusInfo.p2p_list)
+    pub p2p_list: ::std::vec::Vec<RouteItem>,
+    // @@protoc_insertion_point(field:ClientStatusInfo.up_stream)
+    pub up_stream: u64,
+    // @@protoc_insertion_point(field:ClientStatusInfo.down_stream)
+    pub down_stream: u64,
+    // @@protoc_insertion_point(field:ClientStatusInfo.nat_type)
+    pub nat_type: ::protobuf::EnumOrUnknown<PunchNatType>,
+    // @@protoc_insertion_point(field:ClientStatusInfo.down_received)
+    pub down_received: u64,
+    // @@protoc_insertion_point(field:ClientStatusInfo.down_lost)
+    pub down_lost: u64,
+    // @@protoc_insertion_point(field:ClientStatusInfo.punch_failed)
+    pub punch_failed: ::std::vec::Vec<u32>,
+    // special fields
+    // @@protoc_insertion_point(special_field:ClientStatusInfo.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ClientStatusInfo {
+    fn default() -> &'a ClientStatusInfo {
+        <ClientStatusInfo as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ClientStatusInfo {
+    pub fn new() -> ClientStatusInfo {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(8);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "source",
+            |m: &ClientStatusInfo| { &m.source },
+            |m: &mut ClientStatusInfo| { &mut m.source },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "p2p_list",
+            |m: &ClientStatusInfo| { &m.p2p_list },
+            |m: &mut ClientStatusInfo| { &mut m.p2p_list },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "up_stream",
+            |m: &ClientStatusInfo| { &m.up_stream },
+            |m: &mut ClientStatusInfo| { &mut m.up_stream },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "down_stream",
+            |m: &ClientStatusInfo| { &m.down_stream },
+            |m: &mut ClientStatusInfo| { &mut m.down_stream },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "nat_type",
+            |m: &ClientStatusInfo| { &m.nat_type },
+            |m: &mut ClientStatusInfo| { &mut m.nat_type },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "down_received",
+            |m: &ClientStatusInfo| { &m.down_received },
+            |m: &mut ClientStatusInfo| { &mut m.down_received },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "down_lost",
+            |m: &ClientStatusInfo| { &m.down_lost },
+            |m: &mut ClientStatusInfo| { &mut m.down_lost },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "punch_failed",
+            |m: &ClientStatusInfo| { &m.punch_failed },
+            |m: &mut ClientStatusInfo| { &mut m.punch_failed },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ClientStatusInfo>(
+            "ClientStatusInfo",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ClientStatusInfo {
+    const NAME: &'static str = "ClientStatusInfo";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                13 => {
+                    self.source = is.read_fixed32()?;
+                },
+                18 => {
+                    self.p2p_list.push(is.read_message()?);
+                },
+                24 => {
+                    self.up_stream = is.read_uint64()?;
+                },
+                32 => {
+                    self.down_stream = is.read_uint64()?;
+                },
+                40 => {
+                    self.nat_type = is.read_enum_or_unknown()?;
+                },
+                48 => {
+                    self.down_received = is.read_uint64()?;
+                },
+                56 => {
+                    self.down_lost = is.read_uint64()?;
+                },
+                66 => {
+                    is.read_repeated_packed_fixed32_into(&mut self.punch_failed)?;
+                },
+                69 => {
+                    self.punch_failed.push(is.read_fixed32()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.source != 0 {
+            my_size += 1 + 4;
+        }
+        for value in &self.p2p_list {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        if self.up_stream != 0 {
+            my_size += ::protobuf::rt::uint64_size(3, self.up_stream);
+        }
+        if self.down_stream != 0 {
+            my_size += ::protobuf::rt::uint64_size(4, self.down_stream);
+        }
+        if self.nat_type != ::protobuf::EnumOrUnknown::new(PunchNatType::Symmetric) {
+            my_size += ::protobuf::rt::int32_size(5, self.nat_type.value());
+        }
+        if self.down_received != 0 {
+            my_size += ::protobuf::rt::uint64_size(6, self.down_received);
+        }
+        if self.down_lost != 0 {
+            my_size += ::protobuf::rt::uint64_size(7, self.down_lost);
+        }
+        my_size += 5 * self.punch_failed.len() as u64;
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.source != 0 {
+            os.write_fixed32(1, self.source)?;
+        }
+        for v in &self.p2p_list {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        };
+        if self.up_stream != 0 {
+            os.write_uint64(3, self.up_stream)?;
+        }
+        if self.down_stream != 0 {
+            os.write_uint64(4, self.down_stream)?;
+        }
+        if self.nat_type != ::protobuf::EnumOrUnknown::new(PunchNatType::Symmetric) {
+            os.write_enum(5, ::protobuf::EnumOrUnknown::value(&self.nat_type))?;
+        }
+        if self.down_received != 0 {
+            os.write_uint64(6, self.down_received)?;
+        }
+        if self.down_lost != 0 {
+            os.write_uint64(7, self.down_lost)?;
+        }
+        for v in &self.punch_failed {
+            os.write_fixed32(8, *v)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ClientStatusInfo {
+        ClientStatusInfo::new()
+    }
+
+    fn clear(&mut self) {
+        self.source = 0;
+        self.p2p_list.clear();
+        self.up_stream = 0;
+        self.down_stream = 0;
+        self.nat_type = ::protobuf::EnumOrUnknown::new(PunchNatType::Symmetric);
+        self.down_received = 0;
+        self.down_lost = 0;
+        self.punch_failed.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ClientStatusInfo {
+        static instance: ClientStatusInfo = ClientStatusInfo {
+            source: 0,
+            p2p_list: ::std::vec::Vec::new(),
+            up_stream: 0,
+            down_stream: 0,
+            nat_type: ::protobuf::EnumOrUnknown::from_i32(0),
+            down_received: 0,
+            down_lost: 0,
+            punch_failed: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ClientStatusInfo {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ClientStatusInfo").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ClientStatusInfo {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ClientStatusInfo {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:RouteItem)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct RouteItem {
+    // message fields
+    // @@protoc_insertion_point(field:RouteItem.next_ip)
+    pub next_ip: u32,
+    // special fields
+    // @@protoc_insertion_point(special_field:RouteItem.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RouteItem {
+    fn default() -> &'a RouteItem {
+        <RouteItem as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RouteItem {
+    pub fn new() -> RouteItem {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "next_ip",
+            |m: &RouteItem| { &m.next_ip },
+            |m: &mut RouteItem| { &mut m.next_ip },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RouteItem>(
+            "RouteItem",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RouteItem {
+    const NAME: &'static str = "RouteItem";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                13 => {
+                    self.next_ip = is.read_fixed32()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.next_ip != 0 {
+            my_size += 1 + 4;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.next_ip != 0 {
+            os.write_fixed32(1, self.next_ip)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RouteItem {
+        RouteItem::new()
+    }
+
+    fn clear(&mut self) {
+        self.next_ip = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RouteItem {
+        static instance: RouteItem = RouteItem {
+            next_ip: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RouteItem {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RouteItem").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RouteItem {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RouteItem {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:ResolveRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ResolveRequest {
+    // message fields
+    // @@protoc_insertion_point(field:ResolveRequest.name)
+    pub name: ::std::string::String,
+    // @@protoc_insertion_point(field:ResolveRequest.virtual_ip)
+    pub virtual_ip: u32,
+    // special fields
+    // @@protoc_insertion_point(special_field:ResolveRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ResolveRequest {
+    fn default() -> &'a ResolveRequest {
+        <ResolveRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ResolveRequest {
+    pub fn new() -> ResolveRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "name",
+            |m: &ResolveRequest| { &m.name },
+            |m: &mut ResolveRequest| { &mut m.name },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "virtual_ip",
+            |m: &ResolveRequest| { &m.virtual_ip },
+            |m: &mut ResolveRequest| { &mut m.virtual_ip },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ResolveRequest>(
+            "ResolveRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ResolveRequest {
+    const NAME: &'static str = "ResolveRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.name = is.read_string()?;
+                },
+                21 => {
+                    self.virtual_ip = is.read_fixed32()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.name);
+        }
+        if self.virtual_ip != 0 {
+            my_size += 1 + 4;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.name.is_empty() {
+            os.write_string(1, &self.name)?;
+        }
+        if self.virtual_ip != 0 {
+            os.write_fixed32(2, self.virtual_ip)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ResolveRequest {
+        ResolveRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.name.clear();
+        self.virtual_ip = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ResolveRequest {
+        static instance: ResolveRequest = ResolveRequest {
+            name: ::std::string::String::new(),
+            virtual_ip: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ResolveRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ResolveRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ResolveRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ResolveRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:ResolveResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ResolveResponse {
+    // message fields
+    // @@protoc_insertion_point(field:ResolveResponse.name)
+    pub name: ::std::string::String,
+    // @@protoc_insertion_point(field:ResolveResponse.virtual_ip)
+    pub virtual_ip: u32,
+    // @@protoc_insertion_point(field:ResolveResponse.device_info_list)
+    pub device_info_list: ::std::vec::Vec<DeviceInfo>,
+    // special fields
+    // @@protoc_insertion_point(special_field:ResolveResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ResolveResponse {
+    fn default() -> &'a ResolveResponse {
+        <ResolveResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ResolveResponse {
+    pub fn new() -> ResolveResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "name",
+            |m: &ResolveResponse| { &m.name },
+            |m: &mut ResolveResponse| { &mut m.name },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "virtual_ip",
+            |m: &ResolveResponse| { &m.virtual_ip },
+            |m: &mut ResolveResponse| { &mut m.virtual_ip },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "device_info_list",
+            |m: &ResolveResponse| { &m.device_info_list },
+            |m: &mut ResolveResponse| { &mut m.device_info_list },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ResolveResponse>(
+            "ResolveResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ResolveResponse {
+    const NAME: &'static str = "ResolveResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.name = is.read_string()?;
+                },
+                21 => {
+                    self.virtual_ip = is.read_fixed32()?;
+                },
+                26 => {
+                    self.device_info_list.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.name);
+        }
+        if self.virtual_ip != 0 {
+            my_size += 1 + 4;
+        }
+        for value in &self.device_info_list {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.name.is_empty() {
+            os.write_string(1, &self.name)?;
+        }
+        if self.virtual_ip != 0 {
+            os.write_fixed32(2, self.virtual_ip)?;
+        }
+        for v in &self.device_info_list {
+            ::protobuf::rt::write_message_field_with_cached_size(3, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ResolveResponse {
+        ResolveResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.name.clear();
+        self.virtual_ip = 0;
+        self.device_info_list.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ResolveResponse {
+        static instance: ResolveResponse = ResolveResponse {
+            name: ::std::string::String::new(),
+            virtual_ip: 0,
+            device_info_list: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ResolveResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ResolveResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ResolveResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ResolveResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:KeyExchange)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct KeyExchange {
+    // message fields
+    // @@protoc_insertion_point(field:KeyExchange.target)
+    pub target: u32,
+    // @@protoc_insertion_point(field:KeyExchange.session_id)
+    pub session_id: u64,
+    // @@protoc_insertion_point(field:KeyExchange.algorithm)
+    pub algorithm: ::std::string::String,
+    // @@protoc_insertion_point(field:KeyExchange.public_key)
+    pub public_key: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:KeyExchange.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a KeyExchange {
+    fn default() -> &'a KeyExchange {
+        <KeyExchange as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl KeyExchange {
+    pub fn new() -> KeyExchange {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "target",
+            |m: &KeyExchange| { &m.target },
+            |m: &mut KeyExchange| { &mut m.target },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "session_id",
+            |m: &KeyExchange| { &m.session_id },
+            |m: &mut KeyExchange| { &mut m.session_id },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "algorithm",
+            |m: &KeyExchange| { &m.algorithm },
+            |m: &mut KeyExchange| { &mut m.algorithm },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "public_key",
+            |m: &KeyExchange| { &m.public_key },
+            |m: &mut KeyExchange| { &mut m.public_key },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<KeyExchange>(
+            "KeyExchange",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for KeyExchange {
+    const NAME: &'static str = "KeyExchange";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                13 => {
+                    self.target = is.read_fixed32()?;
+                },
+                16 => {
+                    self.session_id = is.read_uint64()?;
+                },
+                26 => {
+                    self.algorithm = is.read_string()?;
+                },
+                34 => {
+                    self.public_key = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.target != 0 {
+            my_size += 1 + 4;
+        }
+        if self.session_id != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.session_id);
+        }
+        if !self.algorithm.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.algorithm);
+        }
+        if !self.public_key.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(4, &self.public_key);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.target != 0 {
+            os.write_fixed32(1, self.target)?;
+        }
+        if self.session_id != 0 {
+            os.write_uint64(2, self.session_id)?;
+        }
+        if !self.algorithm.is_empty() {
+            os.write_string(3, &self.algorithm)?;
+        }
+        if !self.public_key.is_empty() {
+            os.write_bytes(4, &self.public_key)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> KeyExchange {
+        KeyExchange::new()
+    }
+
+    fn clear(&mut self) {
+        self.target = 0;
+        self.session_id = 0;
+        self.algorithm.clear();
+        self.public_key.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static KeyExchange {
+        static instance: KeyExchange = KeyExchange {
+            target: 0,
+            session_id: 0,
+            algorithm: ::std::string::String::new(),
+            public_key: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for KeyExchange {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("KeyExchange").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for KeyExchange {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for KeyExchange {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:Notice)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct Notice {
+    // message fields
+    // @@protoc_insertion_point(field:Notice.id)
+    pub id: u64,
+    // @@protoc_insertion_point(field:Notice.level)
+    pub level: ::protobuf::EnumOrUnknown<NoticeLevel>,
+    // @@protoc_insertion_point(field:Notice.text)
+    pub text: ::std::string::String,
+    // @@protoc_insertion_point(field:Notice.time)
+    pub time: i64,
+    // special fields
+    // @@protoc_insertion_point(special_field:Notice.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a Notice {
+    fn default() -> &'a Notice {
+        <Notice as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl Notice {
+    pub fn new() -> Notice {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "id",
+            |m: &Notice| { &m.id },
+            |m: &mut Notice| { &mut m.id },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "level",
+            |m: &Notice| { &m.level },
+            |m: &mut Notice| { &mut m.level },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "text",
+            |m: &Notice| { &m.text },
+            |m: &mut Notice| { &mut m.text },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "time",
+            |m: &Notice| { &m.time },
+            |m: &mut Notice| { &mut m.time },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<Notice>(
+            "Notice",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for Notice {
+    const NAME: &'static str = "Notice";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.id = is.read_uint64()?;
+                },
+                16 => {
+                    self.level = is.read_enum_or_unknown()?;
+                },
+                26 => {
+                    self.text = is.read_string()?;
+                },
+                32 => {
+                    self.time = is.read_int64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.id != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.id);
+        }
+        if self.level != ::protobuf::EnumOrUnknown::new(NoticeLevel::Info) {
+            my_size += ::protobuf::rt::int32_size(2, self.level.value());
+        }
+        if !self.text.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.text);
+        }
+        if self.time != 0 {
+            my_size += ::protobuf::rt::int64_size(4, self.time);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.id != 0 {
+            os.write_uint64(1, self.id)?;
+        }
+        if self.level != ::protobuf::EnumOrUnknown::new(NoticeLevel::Info) {
+            os.write_enum(2, ::protobuf::EnumOrUnknown::value(&self.level))?;
+        }
+        if !self.text.is_empty() {
+            os.write_string(3, &self.text)?;
+        }
+        if self.time != 0 {
+            os.write_int64(4, self.time)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> Notice {
+        Notice::new()
+    }
+
+    fn clear(&mut self) {
+        self.id = 0;
+        self.level = ::protobuf::EnumOrUnknown::new(NoticeLevel::Info);
+        self.text.clear();
+        self.time = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static Notice {
+        static instance: Notice = Notice {
+            id: 0,
+            level: ::protobuf::EnumOrUnknown::from_i32(0),
+            text: ::std::string::String::new(),
+            time: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for Notice {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("Notice").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for Notice {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for Notice {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:Redirect)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct Redirect {
+    // message fields
+    // @@protoc_insertion_point(field:Redirect.address)
+    pub address: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:Redirect.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a Redirect {
+    fn default() -> &'a Redirect {
+        <Redirect as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl Redirect {
+    pub fn new() -> Redirect {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "address",
+            |m: &Redirect| { &m.address },
+            |m: &mut Redirect| { &mut m.address },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<Redirect>(
+            "Redirect",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for Redirect {
+    const NAME: &'static str = "Redirect";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.address = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> Redirect {
+        Redirect::new()
+    }
+
+    fn clear(&mut self) {
+        self.address.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static Redirect {
+        static instance: Redirect = Redirect {
+            address: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for Redirect {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("Redirect").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for Redirect {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for Redirect {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MaintenanceInfo)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct MaintenanceInfo {
+    // message fields
+    // @@protoc_insertion_point(field:MaintenanceInfo.retry_after)
+    pub retry_after: u32,
+    // @@protoc_insertion_point(field:MaintenanceInfo.message)
+    pub message: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:MaintenanceInfo.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a MaintenanceInfo {
+    fn default() -> &'a MaintenanceInfo {
+        <MaintenanceInfo as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl MaintenanceInfo {
+    pub fn new() -> MaintenanceInfo {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "retry_after",
+            |m: &MaintenanceInfo| { &m.retry_after },
+            |m: &mut MaintenanceInfo| { &mut m.retry_after },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "message",
+            |m: &MaintenanceInfo| { &m.message },
+            |m: &mut MaintenanceInfo| { &mut m.message },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<MaintenanceInfo>(
+            "MaintenanceInfo",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for MaintenanceInfo {
+    const NAME: &'static str = "MaintenanceInfo";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.retry_after = is.read_uint32()?;
+                },
+                18 => {
+                    self.message = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.retry_after != 0 {
+            my_size += ::protobuf::rt::uint32_size(1, self.retry_after);
+        }
+        if !self.message.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.message);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.retry_after != 0 {
+            os.write_uint32(1, self.retry_after)?;
+        }
+        if !self.message.is_empty() {
+            os.write_string(2, &self.message)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> MaintenanceInfo {
+        MaintenanceInfo::new()
+    }
+
+    fn clear(&mut self) {
+        self.retry_after = 0;
+        self.message.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static MaintenanceInfo {
+        static instance: MaintenanceInfo = MaintenanceInfo {
+            retry_after: 0,
+            message: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for MaintenanceInfo {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("MaintenanceInfo").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for MaintenanceInfo {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for MaintenanceInfo {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:TcpPunchRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct TcpPunchRequest {
+    // message fields
+    // @@protoc_insertion_point(field:TcpPunchRequest.target)
+    pub target: u32,
+    // @@protoc_insertion_point(field:TcpPunchRequest.tcp_port)
+    pub tcp_port: u32,
+    // @@protoc_insertion_point(field:TcpPunchRequest.public_ports)
+    pub public_ports: ::std::vec::Vec<u32>,
+    // @@protoc_insertion_point(field:TcpPunchRequest.public_ip_list)
+    pub public_ip_list: ::std::vec::Vec<u32>,
+    // special fields
+    // @@protoc_insertion_point(special_field:TcpPunchRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a TcpPunchRequest {
+    fn default() -> &'a TcpPunchRequest {
+        <TcpPunchRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl TcpPunchRequest {
+    pub fn new() -> TcpPunchRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "target",
+            |m: &TcpPunchRequest| { &m.target },
+            |m: &mut TcpPunchRequest| { &mut m.target },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "tcp_port",
+            |m: &TcpPunchRequest| { &m.tcp_port },
+            |m: &mut TcpPunchRequest| { &mut m.tcp_port },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "public_ports",
+            |m: &TcpPunchRequest| { &m.public_ports },
+            |m: &mut TcpPunchRequest| { &mut m.public_ports },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "public_ip_list",
+            |m: &TcpPunchRequest| { &m.public_ip_list },
+            |m: &mut TcpPunchRequest| { &mut m.public_ip_list },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<TcpPunchRequest>(
+            "TcpPunchRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for TcpPunchRequest {
+    const NAME: &'static str = "TcpPunchRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                13 => {
+                    self.target = is.read_fixed32()?;
+                },
+                16 => {
+                    self.tcp_port = is.read_uint32()?;
+                },
+                26 => {
+                    is.read_repeated_packed_uint32_into(&mut self.public_ports)?;
+                },
+                24 => {
+                    self.public_ports.push(is.read_uint32()?);
+                },
+                34 => {
+                    is.read_repeated_packed_fixed32_into(&mut self.public_ip_list)?;
+                },
+                37 => {
+                    self.public_ip_list.push(is.read_fixed32()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.target != 0 {
+            my_size += 1 + 4;
+        }
+        if self.tcp_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(2, self.tcp_port);
+        }
+        for value in &self.public_ports {
+            my_size += ::protobuf::rt::uint32_size(3, *value);
+        };
+        my_size += 5 * self.public_ip_list.len() as u64;
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.target != 0 {
+            os.write_fixed32(1, self.target)?;
+        }
+        if self.tcp_port != 0 {
+            os.write_uint32(2, self.tcp_port)?;
+        }
+        for v in &self.public_ports {
+            os.write_uint32(3, *v)?;
+        };
+        for v in &self.public_ip_list {
+            os.write_fixed32(4, *v)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> TcpPunchRequest {
+        TcpPunchRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.target = 0;
+        self.tcp_port = 0;
+        self.public_ports.clear();
+        self.public_ip_list.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static TcpPunchRequest {
+        static instance: TcpPunchRequest = TcpPunchRequest {
+            target: 0,
+            tcp_port: 0,
+            public_ports: ::std::vec::Vec::new(),
+            public_ip_list: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for TcpPunchRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("TcpPunchRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for TcpPunchRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for TcpPunchRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:TcpPunchStart)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct TcpPunchStart {
+    // message fields
+    // @@protoc_insertion_point(field:TcpPunchStart.peer)
+    pub peer: u32,
+    // @@protoc_insertion_point(field:TcpPunchStart.peer_public_ip_list)
+    pub peer_public_ip_list: ::std::vec::Vec<u32>,
+    // @@protoc_insertion_point(field:TcpPunchStart.peer_tcp_port)
+    pub peer_tcp_port: u32,
+    // @@protoc_insertion_point(field:TcpPunchStart.peer_public_ports)
+    pub peer_public_ports: ::std::vec::Vec<u32>,
+    // @@protoc_insertion_point(field:TcpPunchStart.start_time)
+    pub start_time: i64,
+    // special fields
+    // @@protoc_insertion_point(special_field:TcpPunchStart.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a TcpPunchStart {
+    fn default() -> &'a TcpPunchStart {
+        <TcpPunchStart as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl TcpPunchStart {
+    pub fn new() -> TcpPunchStart {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(5);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "peer",
+            |m: &TcpPunchStart| { &m.peer },
+            |m: &mut TcpPunchStart| { &mut m.peer },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "peer_public_ip_list",
+            |m: &TcpPunchStart| { &m.peer_public_ip_list },
+            |m: &mut TcpPunchStart| { &mut m.peer_public_ip_list },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "peer_tcp_port",
+            |m: &TcpPunchStart| { &m.peer_tcp_port },
+            |m: &mut TcpPunchStart| { &mut m.peer_tcp_port },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "peer_public_ports",
+            |m: &TcpPunchStart| { &m.peer_public_ports },
+            |m: &mut TcpPunchStart| { &mut m.peer_public_ports },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "start_time",
+            |m: &TcpPunchStart| { &m.start_time },
+            |m: &mut TcpPunchStart| { &mut m.start_time },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<TcpPunchStart>(
+            "TcpPunchStart",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for TcpPunchStart {
+    const NAME: &'static str = "TcpPunchStart";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                13 => {
+                    self.peer = is.read_fixed32()?;
+                },
+                18 => {
+                    is.read_repeated_packed_fixed32_into(&mut self.peer_public_ip_list)?;
+                },
+                21 => {
+                    self.peer_public_ip_list.push(is.read_fixed32()?);
+                },
+                24 => {
+                    self.peer_tcp_port = is.read_uint32()?;
+                },
+                34 => {
+                    is.read_repeated_packed_uint32_into(&mut self.peer_public_ports)?;
+                },
+                32 => {
+                    self.peer_public_ports.push(is.read_uint32()?);
+                },
+                40 => {
+                    self.start_time = is.read_int64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.peer != 0 {
+            my_size += 1 + 4;
+        }
+        my_size += 5 * self.peer_public_ip_list.len() as u64;
+        if self.peer_tcp_port != 0 {
+            my_size += ::protobuf::rt::uint32_size(3, self.peer_tcp_port);
+        }
+        for value in &self.peer_public_ports {
+            my_size += ::protobuf::rt::uint32_size(4, *value);
+        };
+        if self.start_time != 0 {
+            my_size += ::protobuf::rt::int64_size(5, self.start_time);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.peer != 0 {
+            os.write_fixed32(1, self.peer)?;
+        }
+        for v in &self.peer_public_ip_list {
+            os.write_fixed32(2, *v)?;
+        };
+        if self.peer_tcp_port != 0 {
+            os.write_uint32(3, self.peer_tcp_port)?;
+        }
+        for v in &self.peer_public_ports {
+            os.write_uint32(4, *v)?;
+        };
+        if self.start_time != 0 {
+            os.write_int64(5, self.start_time)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> TcpPunchStart {
+        TcpPunchStart::new()
+    }
+
+    fn clear(&mut self) {
+        self.peer = 0;
+        self.peer_public_ip_list.clear();
+        self.peer_tcp_port = 0;
+        self.peer_public_ports.clear();
+        self.start_time = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static TcpPunchStart {
+        static instance: TcpPunchStart = TcpPunchStart {
+            peer: 0,
+            peer_public_ip_list: ::std::vec::Vec::new(),
+            peer_tcp_port: 0,
+            peer_public_ports: ::std::vec::Vec::new(),
+            start_time: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for TcpPunchStart {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("TcpPunchStart").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for TcpPunchStart {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for TcpPunchStart {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:ServiceEntry)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ServiceEntry {
+    // message fields
+    // @@protoc_insertion_point(field:ServiceEntry.name)
+    pub name: ::std::string::String,
+    // @@protoc_insertion_point(field:ServiceEntry.port)
+    pub port: u32,
+    // @@protoc_insertion_point(field:ServiceEntry.protocol)
+    pub protocol: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:ServiceEntry.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ServiceEntry {
+    fn default() -> &'a ServiceEntry {
+        <ServiceEntry as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ServiceEntry {
+    pub fn new() -> ServiceEntry {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "name",
+            |m: &ServiceEntry| { &m.name },
+            |m: &mut ServiceEntry| { &mut m.name },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "port",
+            |m: &ServiceEntry| { &m.port },
+            |m: &mut ServiceEntry| { &mut m.port },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "protocol",
+            |m: &ServiceEntry| { &m.protocol },
+            |m: &mut ServiceEntry| { &mut m.protocol },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ServiceEntry>(
+            "ServiceEntry",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ServiceEntry {
+    const NAME: &'static str = "ServiceEntry";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.name = is.read_string()?;
+                },
+                16 => {
+                    self.port = is.read_uint32()?;
+                },
+                26 => {
+                    self.protocol = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.name);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::uint32_size(2, self.port);
+        }
+        if !self.protocol.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.protocol);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.name.is_empty() {
+            os.write_string(1, &self.name)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.protocol.is_empty() {
+            os.write_string(3, &self.protocol)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ServiceEntry {
+        ServiceEntry::new()
+    }
+
+    fn clear(&mut self) {
+        self.name.clear();
+        self.port = 0;
+        self.protocol.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ServiceEntry {
+        static instance: ServiceEntry = ServiceEntry {
+            name: ::std::string::String::new(),
+            port: 0,
+            protocol: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ServiceEntry {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ServiceEntry").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ServiceEntry {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ServiceEntry {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:ServiceRegister)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ServiceRegister {
+    // message fields
+    // @@protoc_insertion_point(field:ServiceRegister.services)
+    pub services: ::std::vec::Vec<ServiceEntry>,
+    // special fields
+    // @@protoc_insertion_point(special_field:ServiceRegister.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ServiceRegister {
+    fn default() -> &'a ServiceRegister {
+        <ServiceRegister as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ServiceRegister {
+    pub fn new() -> ServiceRegister {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "services",
+            |m: &ServiceRegister| { &m.services },
+            |m: &mut ServiceRegister| { &mut m.services },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ServiceRegister>(
+            "ServiceRegister",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ServiceRegister {
+    const NAME: &'static str = "ServiceRegister";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.services.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        for value in &self.services {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.services {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ServiceRegister {
+        ServiceRegister::new()
+    }
+
+    fn clear(&mut self) {
+        self.services.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ServiceRegister {
+        static instance: ServiceRegister = ServiceRegister {
+            services: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ServiceRegister {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ServiceRegister").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ServiceRegister {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ServiceRegister {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:ServiceQueryRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ServiceQueryRequest {
+    // message fields
+    // @@protoc_insertion_point(field:ServiceQueryRequest.name)
+    pub name: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:ServiceQueryRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ServiceQueryRequest {
+    fn default() -> &'a ServiceQueryRequest {
+        <ServiceQueryRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ServiceQueryRequest {
+    pub fn new() -> ServiceQueryRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "name",
+            |m: &ServiceQueryRequest| { &m.name },
+            |m: &mut ServiceQueryRequest| { &mut m.name },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ServiceQueryRequest>(
+            "ServiceQueryRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ServiceQueryRequest {
+    const NAME: &'static str = "ServiceQueryRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.name = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.name);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.name.is_empty() {
+            os.write_string(1, &self.name)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ServiceQueryRequest {
+        ServiceQueryRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.name.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ServiceQueryRequest {
+        static instance: ServiceQueryRequest = ServiceQueryRequest {
+            name: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ServiceQueryRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ServiceQueryRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ServiceQueryRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ServiceQueryRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:ServiceRecord)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ServiceRecord {
+    // message fields
+    // @@protoc_insertion_point(field:ServiceRecord.virtual_ip)
+    pub virtual_ip: u32,
+    // @@protoc_insertion_point(field:ServiceRecord.device_name)
+    pub device_name: ::std::string::String,
+    // @@protoc_insertion_point(field:ServiceRecord.service)
+    pub service: ::protobuf::MessageField<ServiceEntry>,
+    // special fields
+    // @@protoc_insertion_point(special_field:ServiceRecord.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ServiceRecord {
+    fn default() -> &'a ServiceRecord {
+        <ServiceRecord as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ServiceRecord {
+    pub fn new() -> ServiceRecord {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "virtual_ip",
+            |m: &ServiceRecord| { &m.virtual_ip },
+            |m: &mut ServiceRecord| { &mut m.virtual_ip },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "device_name",
+            |m: &ServiceRecord| { &m.device_name },
+            |m: &mut ServiceRecord| { &mut m.device_name },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, ServiceEntry>(
+            "service",
+            |m: &ServiceRecord| { &m.service },
+            |m: &mut ServiceRecord| { &mut m.service },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ServiceRecord>(
+            "ServiceRecord",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ServiceRecord {
+    const NAME: &'static str = "ServiceRecord";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                13 => {
+                    self.virtual_ip = is.read_fixed32()?;
+                },
+                18 => {
+                    self.device_name = is.read_string()?;
+                },
+                26 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.service)?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.virtual_ip != 0 {
+            my_size += 1 + 4;
+        }
+        if !self.device_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.device_name);
+        }
+        if let Some(v) = self.service.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.virtual_ip != 0 {
+            os.write_fixed32(1, self.virtual_ip)?;
+        }
+        if !self.device_name.is_empty() {
+            os.write_string(2, &self.device_name)?;
+        }
+        if let Some(v) = self.service.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(3, v, os)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ServiceRecord {
+        ServiceRecord::new()
+    }
+
+    fn clear(&mut self) {
+        self.virtual_ip = 0;
+        self.device_name.clear();
+        self.service.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ServiceRecord {
+        static instance: ServiceRecord = ServiceRecord {
+            virtual_ip: 0,
+            device_name: ::std::string::String::new(),
+            service: ::protobuf::MessageField::none(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ServiceRecord {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ServiceRecord").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ServiceRecord {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ServiceRecord {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:ServiceQueryResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ServiceQueryResponse {
+    // message fields
+    // @@protoc_insertion_point(field:ServiceQueryResponse.name)
+    pub name: ::std::string::String,
+    // @@protoc_insertion_point(field:ServiceQueryResponse.records)
+    pub records: ::std::vec::Vec<ServiceRecord>,
+    // special fields
+    // @@protoc_insertion_point(special_field:ServiceQueryResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ServiceQueryResponse {
+    fn default() -> &'a ServiceQueryResponse {
+        <ServiceQueryResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ServiceQueryResponse {
+    pub fn new() -> ServiceQueryResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "name",
+            |m: &ServiceQueryResponse| { &m.name },
+            |m: &mut ServiceQueryResponse| { &mut m.name },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "records",
+            |m: &ServiceQueryResponse| { &m.records },
+            |m: &mut ServiceQueryResponse| { &mut m.records },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ServiceQueryResponse>(
+            "ServiceQueryResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ServiceQueryResponse {
+    const NAME: &'static str = "ServiceQueryResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.name = is.read_string()?;
+                },
+                18 => {
+                    self.records.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.name);
+        }
+        for value in &self.records {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.name.is_empty() {
+            os.write_string(1, &self.name)?;
+        }
+        for v in &self.records {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ServiceQueryResponse {
+        ServiceQueryResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.name.clear();
+        self.records.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ServiceQueryResponse {
+        static instance: ServiceQueryResponse = ServiceQueryResponse {
+            name: ::std::string::String::new(),
+            records: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ServiceQueryResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ServiceQueryResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ServiceQueryResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ServiceQueryResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+// @@protoc_insertion_point(enum:LocalPathReason)
+pub enum LocalPathReason {
+    // @@protoc_insertion_point(enum_value:LocalPathReason.SameLan)
+    SameLan = 0,
+    // @@protoc_insertion_point(enum_value:LocalPathReason.SameNat)
+    SameNat = 1,
+}
+
+impl ::protobuf::Enum for LocalPathReason {
+    const NAME: &'static str = "LocalPathReason";
+
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<LocalPathReason> {
+        match value {
+            0 => ::std::option::Option::Some(LocalPathReason::SameLan),
+            1 => ::std::option::Option::Some(LocalPathReason::SameNat),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn from_str(str: &str) -> ::std::option::Option<LocalPathReason> {
+        match str {
+            "SameLan" => ::std::option::Option::Some(LocalPathReason::SameLan),
+            "SameNat" => ::std::option::Option::Some(LocalPathReason::SameNat),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    const VALUES: &'static [LocalPathReason] = &[
+        LocalPathReason::SameLan,
+        LocalPathReason::SameNat,
+    ];
+}
+
+impl ::protobuf::EnumFull for LocalPathReason {
+    fn enum_descriptor() -> ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().enum_by_package_relative_name("LocalPathReason").unwrap()).clone()
+    }
+
+    fn descriptor(&self) -> ::protobuf::reflect::EnumValueDescriptor {
+        let index = *self as usize;
+        Self::enum_descriptor().value_by_index(index)
+    }
+}
+
+impl ::std::default::Default for LocalPathReason {
+    fn default() -> Self {
+        LocalPathReason::SameLan
+    }
+}
+
+impl LocalPathReason {
+    fn generated_enum_descriptor_data() -> ::protobuf::reflect::GeneratedEnumDescriptorData {
+        ::protobuf::reflect::GeneratedEnumDescriptorData::new::<LocalPathReason>("LocalPathReason")
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+// @@protoc_insertion_point(enum:IpChangeReason)
+pub enum IpChangeReason {
+    // @@protoc_insertion_point(enum_value:IpChangeReason.Unchanged)
+    Unchanged = 0,
+    // @@protoc_insertion_point(enum_value:IpChangeReason.InUse)
+    InUse = 1,
+    // @@protoc_insertion_point(enum_value:IpChangeReason.HeldByOffline)
+    HeldByOffline = 2,
+    // @@protoc_insertion_point(enum_value:IpChangeReason.Reassigned)
+    Reassigned = 3,
+    // @@protoc_insertion_point(enum_value:IpChangeReason.Conflict)
+    Conflict = 4,
+}
+
+impl ::protobuf::Enum for IpChangeReason {
+    const NAME: &'static str = "IpChangeReason";
+
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<IpChangeReason> {
+        match value {
+            0 => ::std::option::Option::Some(IpChangeReason::Unchanged),
+            1 => ::std::option::Option::Some(IpChangeReason::InUse),
+            2 => ::std::option::Option::Some(IpChangeReason::HeldByOffline),
+            3 => ::std::option::Option::Some(IpChangeReason::Reassigned),
+            4 => ::std::option::Option::Some(IpChangeReason::Conflict),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn from_str(str: &str) -> ::std::option::Option<IpChangeReason> {
+        match str {
+            "Unchanged" => ::std::option::Option::Some(IpChangeReason::Unchanged),
+            "InUse" => ::std::option::Option::Some(IpChangeReason::InUse),
+            "HeldByOffline" => ::std::option::Option::Some(IpChangeReason::HeldByOffline),
+            "Reassigned" => ::std::option::Option::Some(IpChangeReason::Reassigned),
+            "Conflict" => ::std::option::Option::Some(IpChangeReason::Conflict),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    const VALUES: &'static [IpChangeReason] = &[
+        IpChangeReason::Unchanged,
+        IpChangeReason::InUse,
+        IpChangeReason::HeldByOffline,
+        IpChangeReason::Reassigned,
+        IpChangeReason::Conflict,
+    ];
+}
+
+impl ::protobuf::EnumFull for IpChangeReason {
+    fn enum_descriptor() -> ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().enum_by_package_relative_name("IpChangeReason").unwrap()).clone()
+    }
+
+    fn descriptor(&self) -> ::protobuf::reflect::EnumValueDescriptor {
+        let index = *self as usize;
+        Self::enum_descriptor().value_by_index(index)
+    }
+}
+
+impl ::std::default::Default for IpChangeReason {
+    fn default() -> Self {
+        IpChangeReason::Unchanged
+    }
+}
+
+impl IpChangeReason {
+    fn generated_enum_descriptor_data() -> ::protobuf::reflect::GeneratedEnumDescriptorData {
+        ::protobuf::reflect::GeneratedEnumDescriptorData::new::<IpChangeReason>("IpChangeReason")
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+// @@protoc_insertion_point(enum:PunchNatType)
+pub enum PunchNatType {
+    // @@protoc_insertion_point(enum_value:PunchNatType.Symmetric)
+    Symmetric = 0,
+    // @@protoc_insertion_point(enum_value:PunchNatType.Cone)
+    Cone = 1,
+}
+
+impl ::protobuf::Enum for PunchNatType {
+    const NAME: &'static str = "PunchNatType";
+
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<PunchNatType> {
+        match value {
+            0 => ::std::option::Option::Some(PunchNatType::Symmetric),
+            1 => ::std::option::Option::Some(PunchNatType::Cone),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn from_str(str: &str) -> ::std::option::Option<PunchNatType> {
+        match str {
+            "Symmetric" => ::std::option::Option::Some(PunchNatType::Symmetric),
+            "Cone" => ::std::option::Option::Some(PunchNatType::Cone),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    const VALUES: &'static [PunchNatType] = &[
+        PunchNatType::Symmetric,
+        PunchNatType::Cone,
+    ];
+}
+
+impl ::protobuf::EnumFull for PunchNatType {
+    fn enum_descriptor() -> ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().enum_by_package_relative_name("PunchNatType").unwrap()).clone()
+    }
+
+    fn descriptor(&self) -> ::protobuf::reflect::EnumValueDescriptor {
+        let index = *self as usize;
+        Self::enum_descriptor().value_by_index(index)
+    }
+}
+
+impl ::std::default::Default for PunchNatType {
+    fn default() -> Self {
+        PunchNatType::Symmetric
+    }
+}
+
+impl PunchNatType {
+    fn generated_enum_descriptor_data() -> ::protobuf::reflect::GeneratedEnumDescriptorData {
+        ::protobuf::reflect::GeneratedEnumDescriptorData::new::<PunchNatType>("PunchNatType")
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Hash)]
+// @@protoc_insertion_point(enum:NoticeLevel)
+pub enum NoticeLevel {
+    // @@protoc_insertion_point(enum_value:NoticeLevel.Info)
+    Info = 0,
+    // @@protoc_insertion_point(enum_value:NoticeLevel.Warning)
+    Warning = 1,
+}
+
+impl ::protobuf::Enum for NoticeLevel {
+    const NAME: &'static str = "NoticeLevel";
+
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<NoticeLevel> {
+        match value {
+            0 => ::std::option::Option::Some(NoticeLevel::Info),
+            1 => ::std::option::Option::Some(NoticeLevel::Warning),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn from_str(str: &str) -> ::std::option::Option<NoticeLevel> {
+        match str {
+            "Info" => ::std::option::Option::Some(NoticeLevel::Info),
+            "Warning" => ::std::option::Option::Some(NoticeLevel::Warning),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    const VALUES: &'static [NoticeLevel] = &[
+        NoticeLevel::Info,
+        NoticeLevel::Warning,
+    ];
+}
+
+impl ::protobuf::EnumFull for NoticeLevel {
+    fn enum_descriptor() -> ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().enum_by_package_relative_name("NoticeLevel").unwrap()).clone()
+    }
+
+    fn descriptor(&self) -> ::protobuf::reflect::EnumValueDescriptor {
+        let index = *self as usize;
+        Self::enum_descriptor().value_by_index(index)
+    }
+}
+
+impl ::std::default::Default for NoticeLevel {
+    fn default() -> Self {
+        NoticeLevel::Info
+    }
+}
+
+impl NoticeLevel {
+    fn generated_enum_descriptor_data() -> ::protobuf::reflect::GeneratedEnumDescriptorData {
+        ::protobuf::reflect::GeneratedEnumDescriptorData::new::<NoticeLevel>("NoticeLevel")
+    }
+}
+
+static file_descriptor_proto_data: &'static [u8] = b"\
+    \n\rmessage.proto\"\x84\x01\n\x10HandshakeRequest\x12\x18\n\x07version\
+    \x18\x01\x20\x01(\tR\x07version\x12\x16\n\x06secret\x18\x02\x20\x01(\x08\
+    R\x06secret\x12\x1d\n\nkey_finger\x18\x03\x20\x01(\tR\tkeyFinger\x12\x1f\
+    \n\x0btoken_proof\x18\x04\x20\x01(\x0cR\ntokenProof\"\x81\x02\n\x11Hands\
+    hakeResponse\x12\x18\n\x07version\x18\x01\x20\x01(\tR\x07version\x12\x16\
+    \n\x06secret\x18\x02\x20\x01(\x08R\x06secret\x12\x1d\n\npublic_key\x18\
+    \x03\x20\x01(\x0cR\tpublicKey\x12\x1d\n\nkey_finger\x18\x04\x20\x01(\tR\
+    \tkeyFinger\x12$\n\x0enat_probe_port\x18\x05\x20\x01(\rR\x0cnatProbePort\
+    \x12(\n\x10server_public_ip\x18\x06\x20\x01(\x07R\x0eserverPublicIp\x12,\
+    \n\x12server_public_ipv6\x18\x07\x20\x01(\x0cR\x10serverPublicIpv6\"e\n\
+    \x16SecretHandshakeRequest\x12\x14\n\x05token\x18\x01\x20\x01(\tR\x05tok\
+    en\x12\x10\n\x03key\x18\x02\x20\x01(\x0cR\x03key\x12#\n\rcounter_nonce\
+    \x18\x03\x20\x01(\x08R\x0ccounterNonce\"\xa1\x03\n\x13RegistrationReques\
+    t\x12\x14\n\x05token\x18\x01\x20\x01(\tR\x05token\x12\x1b\n\tdevice_id\
+    \x18\x02\x20\x01(\tR\x08deviceId\x12\x12\n\x04name\x18\x03\x20\x01(\tR\
+    \x04name\x12\x17\n\x07is_fast\x18\x04\x20\x01(\x08R\x06isFast\x12\x18\n\
+    \x07version\x18\x05\x20\x01(\tR\x07version\x12\x1d\n\nvirtual_ip\x18\x06\
+    \x20\x01(\x07R\tvirtualIp\x12&\n\x0fallow_ip_change\x18\x07\x20\x01(\x08\
+    R\rallowIpChange\x12#\n\rclient_secret\x18\x08\x20\x01(\x08R\x0cclientSe\
+    cret\x12\x12\n\x04tags\x18\t\x20\x03(\tR\x04tags\x12\x1d\n\nsession_id\
+    \x18\n\x20\x01(\x08R\tsessionId\x12\x1a\n\x08sequence\x18\x0b\x20\x01(\
+    \x08R\x08sequence\x126\n\x0flocal_addresses\x18\x0c\x20\x03(\x0b2\r.Loca\
+    lAddressR\x0elocalAddresses\x12\x1d\n\nlocal_port\x18\r\x20\x01(\rR\tloc\
+    alPort\"=\n\x0cLocalAddress\x12\x0e\n\x02ip\x18\x01\x20\x01(\x07R\x02ip\
+    \x12\x1d\n\nprefix_len\x18\x02\x20\x01(\rR\tprefixLen\"\xd5\x01\n\rLocal\
+    PathHint\x12\x12\n\x04peer\x18\x01\x20\x01(\x07R\x04peer\x12(\n\x06reaso\
+    n\x18\x02\x20\x01(\x0e2\x10.LocalPathReasonR\x06reason\x12?\n\x14peer_lo\
+    cal_addresses\x18\x03\x20\x03(\x0b2\r.LocalAddressR\x12peerLocalAddresse\
+    s\x12&\n\x0fpeer_local_port\x18\x04\x20\x01(\rR\rpeerLocalPort\x12\x1d\n\
+    \nno_hairpin\x18\x05\x20\x01(\x08R\tnoHairpin\"\xb5\x04\n\x14Registratio\
+    nResponse\x12\x1d\n\nvirtual_ip\x18\x01\x20\x01(\x07R\tvirtualIp\x12'\n\
+    \x0fvirtual_gateway\x18\x02\x20\x01(\x07R\x0evirtualGateway\x12'\n\x0fvi\
+    rtual_netmask\x18\x03\x20\x01(\x07R\x0evirtualNetmask\x12\x14\n\x05epoch\
+    \x18\x04\x20\x01(\rR\x05epoch\x125\n\x10device_info_list\x18\x05\x20\x03\
+    (\x0b2\x0b.DeviceInfoR\x0edeviceInfoList\x12\x1b\n\tpublic_ip\x18\x06\
+    \x20\x01(\x07R\x08publicIp\x12\x1f\n\x0bpublic_port\x18\x07\x20\x01(\rR\
+    \npublicPort\x12\x1f\n\x0bpublic_ipv6\x18\x08\x20\x01(\x0cR\npublicIpv6\
+    \x129\n\x10ip_change_reason\x18\t\x20\x01(\x0e2\x0f.IpChangeReasonR\x0ei\
+    pChangeReason\x12(\n\x10server_public_ip\x18\n\x20\x01(\x07R\x0eserverPu\
+    blicIp\x12,\n\x12server_public_ipv6\x18\x0b\x20\x01(\x0cR\x10serverPubli\
+    cIpv6\x122\n\rclient_config\x18\x0c\x20\x01(\x0b2\r.ClientConfigR\x0ccli\
+    entConfig\x12\x1d\n\nsession_id\x18\r\x20\x01(\x06R\tsessionId\x12\x1a\n\
+    \x08sequence\x18\x0e\x20\x01(\x08R\x08sequence\"\xaa\x01\n\x0cClientConf\
+    ig\x12\x18\n\x07version\x18\x01\x20\x01(\x03R\x07version\x121\n\x06value\
+    s\x18\x02\x20\x03(\x0b2\x19.ClientConfig.ValuesEntryR\x06values\x12\x12\
+    \n\x04data\x18\x03\x20\x01(\x0cR\x04data\x1a9\n\x0bValuesEntry\x12\x10\n\
+    \x03key\x18\x01\x20\x01(\tR\x03key\x12\x14\n\x05value\x18\x02\x20\x01(\t\
+    R\x05value:\x028\x01\"\xac\x02\n\nDeviceInfo\x12\x12\n\x04name\x18\x01\
+    \x20\x01(\tR\x04name\x12\x1d\n\nvirtual_ip\x18\x02\x20\x01(\x07R\tvirtua\
+    lIp\x12#\n\rdevice_status\x18\x03\x20\x01(\rR\x0cdeviceStatus\x12#\n\rcl\
+    ient_secret\x18\x04\x20\x01(\x08R\x0cclientSecret\x12\x1d\n\nrelay_cost\
+    \x18\x05\x20\x01(\rR\trelayCost\x12\x17\n\x07is_cone\x18\x06\x20\x01(\
+    \x08R\x06isCone\x12\x12\n\x04tags\x18\x07\x20\x03(\tR\x04tags\x126\n\x0f\
+    local_addresses\x18\x08\x20\x03(\x0b2\r.LocalAddressR\x0elocalAddresses\
+    \x12\x1d\n\nlocal_port\x18\t\x20\x01(\rR\tlocalPort\"|\n\nDeviceList\x12\
+    \x14\n\x05epoch\x18\x01\x20\x01(\rR\x05epoch\x125\n\x10device_info_list\
+    \x18\x02\x20\x03(\x0b2\x0b.DeviceInfoR\x0edeviceInfoList\x12!\n\x0cstatu\
+    s_epoch\x18\x03\x20\x01(\rR\x0bstatusEpoch\"\x84\x03\n\tPunchInfo\x12$\n\
+    \x0epublic_ip_list\x18\x02\x20\x03(\x07R\x0cpublicIpList\x12\x1f\n\x0bpu\
+    blic_port\x18\x03\x20\x01(\rR\npublicPort\x12*\n\x11public_port_range\
+    \x18\x04\x20\x01(\rR\x0fpublicPortRange\x12(\n\x08nat_type\x18\x05\x20\
+    \x01(\x0e2\r.PunchNatTypeR\x07natType\x12\x14\n\x05reply\x18\x06\x20\x01\
+    (\x08R\x05reply\x12\x19\n\x08local_ip\x18\x07\x20\x01(\x07R\x07localIp\
+    \x12\x1d\n\nlocal_port\x18\x08\x20\x01(\rR\tlocalPort\x12\x12\n\x04ipv6\
+    \x18\t\x20\x01(\x0cR\x04ipv6\x12\x1b\n\tipv6_port\x18\n\x20\x01(\rR\x08i\
+    pv6Port\x12\x19\n\x08tcp_port\x18\x0b\x20\x01(\rR\x07tcpPort\x12\x1b\n\t\
+    udp_ports\x18\x0c\x20\x03(\rR\x08udpPorts\x12!\n\x0cpublic_ports\x18\r\
+    \x20\x03(\rR\x0bpublicPorts\"\x9e\x02\n\x10ClientStatusInfo\x12\x16\n\
+    \x06source\x18\x01\x20\x01(\x07R\x06source\x12%\n\x08p2p_list\x18\x02\
+    \x20\x03(\x0b2\n.RouteItemR\x07p2pList\x12\x1b\n\tup_stream\x18\x03\x20\
+    \x01(\x04R\x08upStream\x12\x1f\n\x0bdown_stream\x18\x04\x20\x01(\x04R\nd\
+    ownStream\x12(\n\x08nat_type\x18\x05\x20\x01(\x0e2\r.PunchNatTypeR\x07na\
+    tType\x12#\n\rdown_received\x18\x06\x20\x01(\x04R\x0cdownReceived\x12\
+    \x1b\n\tdown_lost\x18\x07\x20\x01(\x04R\x08downLost\x12!\n\x0cpunch_fail\
+    ed\x18\x08\x20\x03(\x07R\x0bpunchFailed\"$\n\tRouteItem\x12\x17\n\x07nex\
+    t_ip\x18\x01\x20\x01(\x07R\x06nextIp\"C\n\x0eResolveRequest\x12\x12\n\
+    \x04name\x18\x01\x20\x01(\tR\x04name\x12\x1d\n\nvirtual_ip\x18\x02\x20\
+    \x01(\x07R\tvirtualIp\"{\n\x0fResolveResponse\x12\x12\n\x04name\x18\x01\
+    \x20\x01(\tR\x04name\x12\x1d\n\nvirtual_ip\x18\x02\x20\x01(\x07R\tvirtua\
+    lIp\x125\n\x10device_info_list\x18\x03\x20\x03(\x0b2\x0b.DeviceInfoR\x0e\
+    deviceInfoList\"\x81\x01\n\x0bKeyExchange\x12\x16\n\x06target\x18\x01\
+    \x20\x01(\x07R\x06target\x12\x1d\n\nsession_id\x18\x02\x20\x01(\x04R\tse\
+    ssionId\x12\x1c\n\talgorithm\x18\x03\x20\x01(\tR\talgorithm\x12\x1d\n\np\
+    ublic_key\x18\x04\x20\x01(\x0cR\tpublicKey\"d\n\x06Notice\x12\x0e\n\x02i\
+    d\x18\x01\x20\x01(\x04R\x02id\x12\"\n\x05level\x18\x02\x20\x01(\x0e2\x0c\
+    .NoticeLevelR\x05level\x12\x12\n\x04text\x18\x03\x20\x01(\tR\x04text\x12\
+    \x12\n\x04time\x18\x04\x20\x01(\x03R\x04time\"$\n\x08Redirect\x12\x18\n\
+    \x07address\x18\x01\x20\x01(\tR\x07address\"L\n\x0fMaintenanceInfo\x12\
+    \x1f\n\x0bretry_after\x18\x01\x20\x01(\rR\nretryAfter\x12\x18\n\x07messa\
+    ge\x18\x02\x20\x01(\tR\x07message\"\x8d\x01\n\x0fTcpPunchRequest\x12\x16\
+    \n\x06target\x18\x01\x20\x01(\x07R\x06target\x12\x19\n\x08tcp_port\x18\
+    \x02\x20\x01(\rR\x07tcpPort\x12!\n\x0cpublic_ports\x18\x03\x20\x03(\rR\
+    \x0bpublicPorts\x12$\n\x0epublic_ip_list\x18\x04\x20\x03(\x07R\x0cpublic\
+    IpList\"\xc1\x01\n\rTcpPunchStart\x12\x12\n\x04peer\x18\x01\x20\x01(\x07\
+    R\x04peer\x12-\n\x13peer_public_ip_list\x18\x02\x20\x03(\x07R\x10peerPub\
+    licIpList\x12\"\n\rpeer_tcp_port\x18\x03\x20\x01(\rR\x0bpeerTcpPort\x12*\
+    \n\x11peer_public_ports\x18\x04\x20\x03(\rR\x0fpeerPublicPorts\x12\x1d\n\
+    \nstart_time\x18\x05\x20\x01(\x03R\tstartTime\"R\n\x0cServiceEntry\x12\
+    \x12\n\x04name\x18\x01\x20\x01(\tR\x04name\x12\x12\n\x04port\x18\x02\x20\
+    \x01(\rR\x04port\x12\x1a\n\x08protocol\x18\x03\x20\x01(\tR\x08protocol\"\
+    <\n\x0fServiceRegister\x12)\n\x08services\x18\x01\x20\x03(\x0b2\r.Servic\
+    eEntryR\x08services\")\n\x13ServiceQueryRequest\x12\x12\n\x04name\x18\
+    \x01\x20\x01(\tR\x04name\"x\n\rServiceRecord\x12\x1d\n\nvirtual_ip\x18\
+    \x01\x20\x01(\x07R\tvirtualIp\x12\x1f\n\x0bdevice_name\x18\x02\x20\x01(\
+    \tR\ndeviceName\x12'\n\x07service\x18\x03\x20\x01(\x0b2\r.ServiceEntryR\
+    \x07service\"T\n\x14ServiceQueryResponse\x12\x12\n\x04name\x18\x01\x20\
+    \x01(\tR\x04name\x12(\n\x07records\x18\x02\x20\x03(\x0b2\x0e.ServiceReco\
+    rdR\x07records*+\n\x0fLocalPathReason\x12\x0b\n\x07SameLan\x10\0\x12\x0b\
+    \n\x07SameNat\x10\x01*[\n\x0eIpChangeReason\x12\r\n\tUnchanged\x10\0\x12\
+    \t\n\x05InUse\x10\x01\x12\x11\n\rHeldByOffline\x10\x02\x12\x0e\n\nReassi\
+    gned\x10\x03\x12\x0c\n\x08Conflict\x10\x04*'\n\x0cPunchNatType\x12\r\n\t\
+    Symmetric\x10\0\x12\x08\n\x04Cone\x10\x01*$\n\x0bNoticeLevel\x12\x08\n\
+    \x04Info\x10\0\x12\x0b\n\x07Warning\x10\x01b\x06proto3\
+";
+
+/// `FileDescriptorProto` object which was a source for this generated file
+fn file_descriptor_proto() -> &'static ::protobuf::descriptor::FileDescriptorProto {
+    static file_descriptor_proto_lazy: ::protobuf::rt::Lazy<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::rt::Lazy::new();
+    file_descriptor_proto_lazy.get(|| {
+        ::protobuf::Message::parse_from_bytes(file_descriptor_proto_data).unwrap()
+    })
+}
+
+/// `FileDescriptor` object which allows dynamic access to files
+pub fn file_descriptor() -> &'static ::protobuf::reflect::FileDescriptor {
+    static generated_file_descriptor_lazy: ::protobuf::rt::Lazy<::protobuf::reflect::GeneratedFileDescriptor> = ::protobuf::rt::Lazy::new();
+    static file_descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::FileDescriptor> = ::protobuf::rt::Lazy::new();
+    file_descriptor.get(|| {
+        let generated_file_descriptor = generated_file_descriptor_lazy.get(|| {
+            let mut deps = ::std::vec::Vec::with_capacity(0);
+            let mut messages = ::std::vec::Vec::with_capacity(26);
+            messages.push(HandshakeRequest::generated_message_descriptor_data());
+            messages.push(HandshakeResponse::generated_message_descriptor_data());
+            messages.push(SecretHandshakeRequest::generated_message_descriptor_data());
+            messages.push(RegistrationRequest::generated_message_descriptor_data());
+            messages.push(LocalAddress::generated_message_descriptor_data());
+            messages.push(LocalPathHint::generated_message_descriptor_data());
+            messages.push(RegistrationResponse::generated_message_descriptor_data());
+            messages.push(ClientConfig::generated_message_descriptor_data());
+            messages.push(DeviceInfo::generated_message_descriptor_data());
+            messages.push(DeviceList::generated_message_descriptor_data());
+            messages.push(PunchInfo::generated_message_descriptor_data());
+            messages.push(ClientStatusInfo::generated_message_descriptor_data());
+            messages.push(RouteItem::generated_message_descriptor_data());
+            messages.push(ResolveRequest::generated_message_descriptor_data());
+            messages.push(ResolveResponse::generated_message_descriptor_data());
+            messages.push(KeyExchange::generated_message_descriptor_data());
+            messages.push(Notice::generated_message_descriptor_data());
+            messages.push(Redirect::generated_message_descriptor_data());
+            messages.push(MaintenanceInfo::generated_message_descriptor_data());
+            messages.push(TcpPunchRequest::generated_message_descriptor_data());
+            messages.push(TcpPunchStart::generated_message_descriptor_data());
+            messages.push(ServiceEntry::generated_message_descriptor_data());
+            messages.push(ServiceRegister::generated_message_descriptor_data());
+            messages.push(ServiceQueryRequest::generated_message_descriptor_data());
+            messages.push(ServiceRecord::generated_message_descriptor_data());
+            messages.push(ServiceQueryResponse::generated_message_descriptor_data());
+            let mut enums = ::std::vec::Vec::with_capacity(4);
+            enums.push(LocalPathReason::generated_enum_descriptor_data());
+            enums.push(IpChangeReason::generated_enum_descriptor_data());
+            enums.push(PunchNatType::generated_enum_descriptor_data());
+            enums.push(NoticeLevel::generated_enum_descriptor_data());
+            ::protobuf::reflect::GeneratedFileDescriptor::new_generated(
+                file_descriptor_proto(),
+                deps,
+                messages,
+                enums,
+            )
+        });
+        ::protobuf::reflect::FileDescriptor::new_generated_2(generated_file_descriptor)
+    })
+}
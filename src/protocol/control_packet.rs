@@ -22,6 +22,68 @@ pub enum Protocol {
     ///获取对端看到的地址
     AddrRequest,
     AddrResponse,
+    /// nat类型探测，客户端向服务器的主端口和探测端口都发送请求，比较服务器看到的来源端口
+    /*
+     0                   1                   2                   3
+     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |                               id                              |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |                              ipv4                             |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |              port             |           other_port          |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    请求只需要填写id，响应中port为当前端口看到的来源端口，other_port为另一个端口看到的来源端口，未收到时为0
+    */
+    NatProbeRequest,
+    NatProbeResponse,
+    /// 时间同步，客户端据此换算服务端下发的打洞时间
+    /*
+     0                   1                   2                   3
+     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |                          client_time                          |
+    |                                                               |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |                          server_time                          |
+    |                                                               |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    请求填写client_time，响应原样返回client_time，server_time为服务端的unix时间戳(微秒)，
+    客户端用 server_time - (发送时间 + 接收时间) / 2 估计和服务端的时钟偏差
+    */
+    TimeRequest,
+    TimeResponse,
+    /// 对称nat的端口预测，由服务端根据nat类型探测时依次看到的来源端口计算
+    /*
+     0                   1                   2                   3
+     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |                             target                            |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |                              ipv4                             |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |           last_port           |             stride            |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |    matched    |    samples    |            reserved           |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    请求只需要填写target(目标设备的虚拟ip)，响应中ipv4为目标的公网ip，下一个端口约为last_port+stride，
+    stride为有符号数，matched为符合该步长的样本间隔数，samples为样本数，samples为0表示没有预测
+    */
+    PortPredictionRequest,
+    PortPredictionResponse,
+    /// 路径mtu探测，服务端发送不同大小的探测包，客户端收到后回应id
+    /*
+     0                   1                   2                   3
+     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |                               id                              |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    |                          padding ...                          |
+    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    探测包用padding填充到要探测的大小，回应只包含id，不需要填充
+    */
+    MtuProbe,
+    MtuProbeReply,
     Unknown(u8),
 }
 
@@ -34,6 +96,14 @@ impl From<u8> for Protocol {
             4 => Protocol::PunchResponse,
             5 => Protocol::AddrRequest,
             6 => Protocol::AddrResponse,
+            7 => Protocol::NatProbeRequest,
+            8 => Protocol::NatProbeResponse,
+            9 => Protocol::TimeRequest,
+            10 => Protocol::TimeResponse,
+            11 => Protocol::PortPredictionRequest,
+            12 => Protocol::PortPredictionResponse,
+            13 => Protocol::MtuProbe,
+            14 => Protocol::MtuProbeReply,
             val => Protocol::Unknown(val),
         }
     }
@@ -48,6 +118,14 @@ impl From<Protocol> for u8 {
             Protocol::PunchResponse => 4,
             Protocol::AddrRequest => 5,
             Protocol::AddrResponse => 6,
+            Protocol::NatProbeRequest => 7,
+            Protocol::NatProbeResponse => 8,
+            Protocol::TimeRequest => 9,
+            Protocol::TimeResponse => 10,
+            Protocol::PortPredictionRequest => 11,
+            Protocol::PortPredictionResponse => 12,
+            Protocol::MtuProbe => 13,
+            Protocol::MtuProbeReply => 14,
             Protocol::Unknown(val) => val,
         }
     }
@@ -60,6 +138,14 @@ pub enum ControlPacket<B> {
     PunchResponse,
     AddrRequest,
     AddrResponse(AddrPacket<B>),
+    NatProbeRequest(NatProbePacket<B>),
+    NatProbeResponse(NatProbePacket<B>),
+    TimeRequest(TimePacket<B>),
+    TimeResponse(TimePacket<B>),
+    PortPredictionRequest(PortPredictionPacket<B>),
+    PortPredictionResponse(PortPredictionPacket<B>),
+    MtuProbe(MtuProbePacket<B>),
+    MtuProbeReply(MtuProbePacket<B>),
 }
 
 impl<B: AsRef<[u8]>> ControlPacket<B> {
@@ -71,6 +157,24 @@ impl<B: AsRef<[u8]>> ControlPacket<B> {
             Protocol::PunchResponse => Ok(ControlPacket::PunchResponse),
             Protocol::AddrRequest => Ok(ControlPacket::AddrRequest),
             Protocol::AddrResponse => Ok(ControlPacket::AddrResponse(AddrPacket::new(buffer)?)),
+            Protocol::NatProbeRequest => {
+                Ok(ControlPacket::NatProbeRequest(NatProbePacket::new(buffer)?))
+            }
+            Protocol::NatProbeResponse => Ok(ControlPacket::NatProbeResponse(NatProbePacket::new(
+                buffer,
+            )?)),
+            Protocol::TimeRequest => Ok(ControlPacket::TimeRequest(TimePacket::new(buffer)?)),
+            Protocol::TimeResponse => Ok(ControlPacket::TimeResponse(TimePacket::new(buffer)?)),
+            Protocol::PortPredictionRequest => Ok(ControlPacket::PortPredictionRequest(
+                PortPredictionPacket::new(buffer)?,
+            )),
+            Protocol::PortPredictionResponse => Ok(ControlPacket::PortPredictionResponse(
+                PortPredictionPacket::new(buffer)?,
+            )),
+            Protocol::MtuProbe => Ok(ControlPacket::MtuProbe(MtuProbePacket::new(buffer)?)),
+            Protocol::MtuProbeReply => {
+                Ok(ControlPacket::MtuProbeReply(MtuProbePacket::new(buffer)?))
+            }
             Protocol::Unknown(_) => Err(io::Error::new(io::ErrorKind::InvalidData, "Unsupported")),
         }
     }
@@ -158,3 +262,195 @@ impl<B: AsRef<[u8]>> fmt::Debug for AddrPacket<B> {
             .finish()
     }
 }
+
+pub struct NatProbePacket<B> {
+    buffer: B,
+}
+
+impl<B: AsRef<[u8]>> NatProbePacket<B> {
+    pub fn new(buffer: B) -> io::Result<NatProbePacket<B>> {
+        let len = buffer.as_ref().len();
+        if len != 12 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "len != 12"));
+        }
+        Ok(NatProbePacket { buffer })
+    }
+    pub fn id(&self) -> u32 {
+        u32::from_be_bytes(self.buffer.as_ref()[..4].try_into().unwrap())
+    }
+    pub fn ipv4(&self) -> Ipv4Addr {
+        let buf = self.buffer.as_ref();
+        Ipv4Addr::new(buf[4], buf[5], buf[6], buf[7])
+    }
+    pub fn port(&self) -> u16 {
+        u16::from_be_bytes(self.buffer.as_ref()[8..10].try_into().unwrap())
+    }
+    pub fn other_port(&self) -> u16 {
+        u16::from_be_bytes(self.buffer.as_ref()[10..12].try_into().unwrap())
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> NatProbePacket<B> {
+    pub fn set_id(&mut self, id: u32) {
+        self.buffer.as_mut()[..4].copy_from_slice(&id.to_be_bytes())
+    }
+    pub fn set_ipv4(&mut self, ip: Ipv4Addr) {
+        self.buffer.as_mut()[4..8].copy_from_slice(&ip.octets())
+    }
+    pub fn set_port(&mut self, port: u16) {
+        self.buffer.as_mut()[8..10].copy_from_slice(&port.to_be_bytes())
+    }
+    pub fn set_other_port(&mut self, port: u16) {
+        self.buffer.as_mut()[10..12].copy_from_slice(&port.to_be_bytes())
+    }
+}
+
+impl<B: AsRef<[u8]>> fmt::Debug for NatProbePacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NatProbePacket")
+            .field("id", &self.id())
+            .field("ipv4", &self.ipv4())
+            .field("port", &self.port())
+            .field("other_port", &self.other_port())
+            .finish()
+    }
+}
+
+pub struct TimePacket<B> {
+    buffer: B,
+}
+
+impl<B: AsRef<[u8]>> TimePacket<B> {
+    pub fn new(buffer: B) -> io::Result<TimePacket<B>> {
+        let len = buffer.as_ref().len();
+        if len != 16 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "len != 16"));
+        }
+        Ok(TimePacket { buffer })
+    }
+    pub fn client_time(&self) -> u64 {
+        u64::from_be_bytes(self.buffer.as_ref()[..8].try_into().unwrap())
+    }
+    pub fn server_time(&self) -> i64 {
+        i64::from_be_bytes(self.buffer.as_ref()[8..16].try_into().unwrap())
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> TimePacket<B> {
+    pub fn set_client_time(&mut self, time: u64) {
+        self.buffer.as_mut()[..8].copy_from_slice(&time.to_be_bytes())
+    }
+    pub fn set_server_time(&mut self, time: i64) {
+        self.buffer.as_mut()[8..16].copy_from_slice(&time.to_be_bytes())
+    }
+}
+
+impl<B: AsRef<[u8]>> fmt::Debug for TimePacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimePacket")
+            .field("client_time", &self.client_time())
+            .field("server_time", &self.server_time())
+            .finish()
+    }
+}
+
+pub struct PortPredictionPacket<B> {
+    buffer: B,
+}
+
+impl<B: AsRef<[u8]>> PortPredictionPacket<B> {
+    pub fn new(buffer: B) -> io::Result<PortPredictionPacket<B>> {
+        let len = buffer.as_ref().len();
+        if len != 16 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "len != 16"));
+        }
+        Ok(PortPredictionPacket { buffer })
+    }
+    pub fn target(&self) -> Ipv4Addr {
+        let buf = self.buffer.as_ref();
+        Ipv4Addr::new(buf[0], buf[1], buf[2], buf[3])
+    }
+    pub fn ipv4(&self) -> Ipv4Addr {
+        let buf = self.buffer.as_ref();
+        Ipv4Addr::new(buf[4], buf[5], buf[6], buf[7])
+    }
+    pub fn last_port(&self) -> u16 {
+        u16::from_be_bytes(self.buffer.as_ref()[8..10].try_into().unwrap())
+    }
+    pub fn stride(&self) -> i16 {
+        i16::from_be_bytes(self.buffer.as_ref()[10..12].try_into().unwrap())
+    }
+    pub fn matched(&self) -> u8 {
+        self.buffer.as_ref()[12]
+    }
+    pub fn samples(&self) -> u8 {
+        self.buffer.as_ref()[13]
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> PortPredictionPacket<B> {
+    pub fn set_target(&mut self, ip: Ipv4Addr) {
+        self.buffer.as_mut()[..4].copy_from_slice(&ip.octets())
+    }
+    pub fn set_ipv4(&mut self, ip: Ipv4Addr) {
+        self.buffer.as_mut()[4..8].copy_from_slice(&ip.octets())
+    }
+    pub fn set_last_port(&mut self, port: u16) {
+        self.buffer.as_mut()[8..10].copy_from_slice(&port.to_be_bytes())
+    }
+    pub fn set_stride(&mut self, stride: i16) {
+        self.buffer.as_mut()[10..12].copy_from_slice(&stride.to_be_bytes())
+    }
+    pub fn set_matched(&mut self, matched: u8) {
+        self.buffer.as_mut()[12] = matched
+    }
+    pub fn set_samples(&mut self, samples: u8) {
+        self.buffer.as_mut()[13] = samples
+    }
+}
+
+impl<B: AsRef<[u8]>> fmt::Debug for PortPredictionPacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PortPredictionPacket")
+            .field("target", &self.target())
+            .field("ipv4", &self.ipv4())
+            .field("last_port", &self.last_port())
+            .field("stride", &self.stride())
+            .field("matched", &self.matched())
+            .field("samples", &self.samples())
+            .finish()
+    }
+}
+
+/// 路径mtu探测
+pub struct MtuProbePacket<B> {
+    buffer: B,
+}
+
+impl<B: AsRef<[u8]>> MtuProbePacket<B> {
+    pub fn new(buffer: B) -> io::Result<MtuProbePacket<B>> {
+        let len = buffer.as_ref().len();
+        if len < 4 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "len < 4"));
+        }
+        Ok(MtuProbePacket { buffer })
+    }
+    pub fn id(&self) -> u32 {
+        u32::from_be_bytes(self.buffer.as_ref()[..4].try_into().unwrap())
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> MtuProbePacket<B> {
+    pub fn set_id(&mut self, id: u32) {
+        self.buffer.as_mut()[..4].copy_from_slice(&id.to_be_bytes())
+    }
+}
+
+impl<B: AsRef<[u8]>> fmt::Debug for MtuProbePacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MtuProbePacket")
+            .field("id", &self.id())
+            .field("len", &self.buffer.as_ref().len())
+            .finish()
+    }
+}
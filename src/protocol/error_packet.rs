@@ -10,6 +10,16 @@ pub enum Protocol {
     IpAlreadyExists,
     InvalidIp,
     NoKey,
+    // 组要求加密
+    EncryptionRequired,
+    // 组内设备数量达到上限
+    GroupFull,
+    // 请求参数错误
+    InvalidRequest,
+    // 对方不在线
+    PeerOffline,
+    // 服务端维护中，内容为MaintenanceInfo，客户端按其中的间隔重试注册
+    Maintenance,
     Other(u8),
 }
 
@@ -22,6 +32,11 @@ impl From<u8> for Protocol {
             4 => Self::IpAlreadyExists,
             5 => Self::InvalidIp,
             6 => Self::NoKey,
+            7 => Self::EncryptionRequired,
+            8 => Self::GroupFull,
+            9 => Self::InvalidRequest,
+            10 => Self::PeerOffline,
+            11 => Self::Maintenance,
             val => Self::Other(val),
         }
     }
@@ -36,6 +51,11 @@ impl From<Protocol> for u8 {
             Protocol::IpAlreadyExists => 4,
             Protocol::InvalidIp => 5,
             Protocol::NoKey => 6,
+            Protocol::EncryptionRequired => 7,
+            Protocol::GroupFull => 8,
+            Protocol::InvalidRequest => 9,
+            Protocol::PeerOffline => 10,
+            Protocol::Maintenance => 11,
             Protocol::Other(val) => val,
         }
     }
@@ -60,7 +80,12 @@ impl<B: AsRef<[u8]>> InErrorPacket<B> {
             Protocol::IpAlreadyExists => Ok(InErrorPacket::IpAlreadyExists),
             Protocol::InvalidIp => Ok(InErrorPacket::InvalidIp),
             Protocol::NoKey => Ok(InErrorPacket::NoKey),
-            Protocol::Other(_) => Ok(InErrorPacket::OtherError(ErrorPacket::new(buffer)?)),
+            Protocol::EncryptionRequired
+            | Protocol::GroupFull
+            | Protocol::InvalidRequest
+            | Protocol::PeerOffline
+            | Protocol::Maintenance
+            | Protocol::Other(_) => Ok(InErrorPacket::OtherError(ErrorPacket::new(buffer)?)),
         }
     }
 }
@@ -9,7 +9,7 @@ use std::{fmt, io};
    0                                            15                                              31
    0  1  2  3  4  5  6  7  8  9  0  1  2  3  4  5  6  7  8  9  0  1  2  3  4  5  6  7  8  9  0  1
   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-  |e |s |u |u|   版本(4) |      协议(8)          |      上层协议(8)        | 初始ttl(4) | 生存时间(4) |
+  |e |s |i |u|   版本(4) |      协议(8)          |      上层协议(8)        | 初始ttl(4) | 生存时间(4) |
   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
   |                                          源ip地址(32)                                         |
   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
@@ -17,9 +17,16 @@ use std::{fmt, io};
   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
   |                                           数据体                                              |
   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-  注：e为是否加密标志，s为服务端通信包标志，u未使用
+  注：e为是否加密标志，s为服务端通信包标志，i为会话id标志，u未使用
+  i置位时数据末尾附带8字节的会话id，由注册时协商，只在客户端发往服务端的udp数据中使用
+  注册时协商了序号的客户端，通过udp收发的中继数据(非服务端通信包)末尾附带4字节的序号，
+  在会话id之前，客户端和服务端各自按发送顺序编号，接收方据此统计丢包
 */
 pub const HEAD_LEN: usize = 12;
+/// 数据末尾附带的会话id长度
+pub const SESSION_ID_LEN: usize = 8;
+/// 中继数据末尾附带的序号长度
+pub const SEQUENCE_LEN: usize = 4;
 
 pub mod body;
 pub mod control_packet;
@@ -160,6 +167,10 @@ impl<B: AsRef<[u8]>> NetPacket<B> {
     pub fn is_gateway(&self) -> bool {
         self.buffer.as_ref()[0] & 0x40 == 0x40
     }
+    /// 数据末尾附带会话id
+    pub fn has_session_id(&self) -> bool {
+        self.buffer.as_ref()[0] & 0x20 == 0x20
+    }
     pub fn version(&self) -> Version {
         Version::from(self.buffer.as_ref()[0] & 0x0F)
     }
@@ -210,6 +221,35 @@ impl<B: AsRef<[u8]> + AsMut<[u8]>> NetPacket<B> {
             self.buffer.as_mut()[0] = self.buffer.as_ref()[0] & 0xBF
         };
     }
+    /// 取出并去掉数据末尾的会话id，没有会话id时返回None
+    pub fn take_session_id(&mut self) -> Option<u64> {
+        if !self.has_session_id() || self.data_len < HEAD_LEN + SESSION_ID_LEN {
+            return None;
+        }
+        let end = self.data_len;
+        let session_id = u64::from_be_bytes(
+            self.buffer.as_ref()[end - SESSION_ID_LEN..end]
+                .try_into()
+                .unwrap(),
+        );
+        self.data_len -= SESSION_ID_LEN;
+        self.buffer.as_mut()[0] &= 0xDF;
+        Some(session_id)
+    }
+    /// 取出并去掉数据末尾的序号，是否附带序号由注册时协商，调用方确认附带了序号时才调用
+    pub fn take_sequence(&mut self) -> Option<u32> {
+        if self.data_len < HEAD_LEN + SEQUENCE_LEN {
+            return None;
+        }
+        let end = self.data_len;
+        let sequence = u32::from_be_bytes(
+            self.buffer.as_ref()[end - SEQUENCE_LEN..end]
+                .try_into()
+                .unwrap(),
+        );
+        self.data_len -= SEQUENCE_LEN;
+        Some(sequence)
+    }
     pub fn set_default_version(&mut self) {
         let v: u8 = Version::V2.into();
         self.buffer.as_mut()[0] = (self.buffer.as_ref()[0] & 0xF0) | (0x0F & v);
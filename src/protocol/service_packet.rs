@@ -15,6 +15,31 @@ pub enum Protocol {
     SecretHandshakeResponse,
     /// 客户端上报状态
     ClientStatusInfo,
+    /// tcp打洞协调请求
+    TcpPunchRequest,
+    /// tcp打洞指令
+    TcpPunchStart,
+    /// 名称和虚拟ip互相解析，比拉取整个设备列表开销小
+    ResolveRequest,
+    ResolveResponse,
+    /// 端到端密钥交换，由服务端在两个设备间转发
+    KeyExchangeRequest,
+    KeyExchangeResponse,
+    /// 服务端主动推送的通知，由客户端界面展示
+    PushNotice,
+    /// 管理员修改配置后推送给受影响的设备
+    PushClientConfig,
+    /// 组迁移到其他服务器，推送给在线设备，也作为迁移期间注册请求的回应
+    PushRedirect,
+    /// 客户端正常退出时注销，服务端立即将设备标记为离线
+    Unregister,
+    /// 设备登记提供的应用服务
+    ServiceRegister,
+    /// 查询组内设备登记的服务，比扫描端口开销小
+    ServiceQueryRequest,
+    ServiceQueryResponse,
+    /// 同组设备可能在同一局域网或同一nat后时推送给双方
+    LocalPathHint,
     Unknown(u8),
 }
 
@@ -30,6 +55,20 @@ impl From<u8> for Protocol {
             7 => Self::SecretHandshakeRequest,
             8 => Self::SecretHandshakeResponse,
             9 => Self::ClientStatusInfo,
+            10 => Self::TcpPunchRequest,
+            11 => Self::TcpPunchStart,
+            12 => Self::ResolveRequest,
+            13 => Self::ResolveResponse,
+            14 => Self::KeyExchangeRequest,
+            15 => Self::KeyExchangeResponse,
+            16 => Self::PushNotice,
+            17 => Self::PushClientConfig,
+            18 => Self::PushRedirect,
+            19 => Self::Unregister,
+            20 => Self::ServiceRegister,
+            21 => Self::ServiceQueryRequest,
+            22 => Self::ServiceQueryResponse,
+            23 => Self::LocalPathHint,
             val => Self::Unknown(val),
         }
     }
@@ -47,6 +86,20 @@ impl From<Protocol> for u8 {
             Protocol::SecretHandshakeRequest => 7,
             Protocol::SecretHandshakeResponse => 8,
             Protocol::ClientStatusInfo => 9,
+            Protocol::TcpPunchRequest => 10,
+            Protocol::TcpPunchStart => 11,
+            Protocol::ResolveRequest => 12,
+            Protocol::ResolveResponse => 13,
+            Protocol::KeyExchangeRequest => 14,
+            Protocol::KeyExchangeResponse => 15,
+            Protocol::PushNotice => 16,
+            Protocol::PushClientConfig => 17,
+            Protocol::PushRedirect => 18,
+            Protocol::Unregister => 19,
+            Protocol::ServiceRegister => 20,
+            Protocol::ServiceQueryRequest => 21,
+            Protocol::ServiceQueryResponse => 22,
+            Protocol::LocalPathHint => 23,
             Protocol::Unknown(val) => val,
         }
     }